@@ -0,0 +1,381 @@
+use crate::instance::Instance;
+use anyhow::{Result, bail, ensure};
+use common::{
+    fetch::ProveBlockByNumberParams,
+    ids::JobId,
+    report::{BlockProvingReport, ReportStore},
+};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::{
+        Arc, Mutex, MutexGuard,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+use tokio::time::{Duration, sleep};
+use tracing::{error, info, warn};
+
+// maximum number of blocks distributed by a request
+pub const MAX_DISTRIBUTED_RANGE_BLOCKS: u64 = 100_000;
+
+// interval of reconnecting the report watcher of an instance
+const WATCH_RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+
+// state of a block assigned to an instance
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum AssignmentState {
+    // requested and not reported yet
+    InFlight,
+
+    // reported as proved
+    Proved,
+
+    // reported as failed, it could be assigned again
+    Failed,
+}
+
+// assignment of a block to an instance
+#[derive(Clone, Copy, Debug)]
+struct Assignment {
+    // index of the assigned instance
+    instance: usize,
+
+    // state of the block
+    state: AssignmentState,
+}
+
+// chunk of consecutive blocks requested from an instance
+#[derive(Clone, Debug, Serialize)]
+pub struct ChunkAssignment {
+    // name of the assigned instance
+    pub instance: String,
+
+    // first block number of the chunk
+    pub start_block_num: u64,
+
+    // number of blocks of the chunk
+    pub count: u64,
+
+    // job id assigned by the instance
+    pub job_id: JobId,
+}
+
+// status of the blocks assigned to an instance
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct InstanceStatus {
+    // name of the instance
+    pub name: String,
+
+    // number of the blocks requested and not reported yet
+    pub in_flight_blocks: u64,
+
+    // number of the blocks reported as proved
+    pub proved_blocks: u64,
+
+    // number of the blocks reported as failed
+    pub failed_blocks: u64,
+
+    // number of the dropped reports of the blocks assigned to another instance or already proved
+    pub duplicate_reports: u64,
+}
+
+// coordinator distributing the block ranges across several eth-proofs deployments, each block is
+// assigned to one instance at a time and the reports of all instances are merged into one store
+#[derive(Debug)]
+pub struct Coordinator {
+    // eth-proofs deployments
+    instances: Vec<Instance>,
+
+    // chain id of the distributed blocks
+    chain_id: u64,
+
+    // maximum number of blocks of a chunk requested from an instance
+    chunk_size: u64,
+
+    // store of the merged reports
+    report_store: Arc<dyn ReportStore>,
+
+    // assignments of the distributed blocks by block number
+    assignments: Mutex<BTreeMap<u64, Assignment>>,
+
+    // number of the dropped duplicate reports of each instance
+    duplicate_reports: Mutex<Vec<u64>>,
+
+    // index of the instance assigned with the next chunk
+    next_instance: AtomicUsize,
+}
+
+impl Coordinator {
+    pub fn new(
+        instances: Vec<Instance>,
+        chain_id: u64,
+        chunk_size: u64,
+        report_store: Arc<dyn ReportStore>,
+    ) -> Self {
+        let duplicate_reports = vec![0; instances.len()];
+        Self {
+            instances,
+            chain_id,
+            chunk_size: chunk_size.max(1),
+            report_store,
+            assignments: Mutex::default(),
+            duplicate_reports: Mutex::new(duplicate_reports),
+            next_instance: AtomicUsize::new(0),
+        }
+    }
+
+    // return the store of the merged reports
+    pub fn report_store(&self) -> &Arc<dyn ReportStore> {
+        &self.report_store
+    }
+
+    // distribute a block range (inclusive) across the instances in chunks, the blocks in flight or
+    // already proved are skipped so no block is proved twice; a chunk is requested from the next
+    // instance if the assigned one is unreachable
+    pub async fn prove_block_range(
+        &self,
+        start_block_num: u64,
+        end_block_num: u64,
+    ) -> Result<Vec<ChunkAssignment>> {
+        ensure!(
+            start_block_num <= end_block_num,
+            "start block {start_block_num} is after end block {end_block_num}",
+        );
+        ensure!(
+            end_block_num - start_block_num < MAX_DISTRIBUTED_RANGE_BLOCKS,
+            "range exceeds {MAX_DISTRIBUTED_RANGE_BLOCKS} blocks",
+        );
+
+        // skip the blocks proved before, e.g. before the coordinator restarted
+        let proved_blocks: HashSet<u64> = self
+            .report_store
+            .range(self.chain_id, start_block_num, end_block_num)
+            .await?
+            .into_iter()
+            .filter(|report| report.success)
+            .map(|report| report.block_number)
+            .collect();
+
+        // reserve the unassigned blocks, the reservation is released if no instance accepts them
+        let reserved_blocks: Vec<u64> = {
+            let mut assignments = self.lock_assignments();
+            let instance = self.next_instance.load(Ordering::Relaxed) % self.instances.len();
+            (start_block_num..=end_block_num)
+                .filter(|block_number| !proved_blocks.contains(block_number))
+                .filter(|block_number| {
+                    let is_assignable = assignments
+                        .get(block_number)
+                        .is_none_or(|assignment| assignment.state == AssignmentState::Failed);
+                    if is_assignable {
+                        assignments.insert(
+                            *block_number,
+                            Assignment {
+                                instance,
+                                state: AssignmentState::InFlight,
+                            },
+                        );
+                    }
+                    is_assignable
+                })
+                .collect()
+        };
+
+        // request the chunks of the consecutive reserved blocks from the instances in turn
+        let mut chunks = vec![];
+        let mut failure = None;
+        for (start_block_num, count) in split_chunks(&reserved_blocks, self.chunk_size) {
+            match self.request_chunk(start_block_num, count).await {
+                Ok(chunk) => chunks.push(chunk),
+                Err(e) => {
+                    let mut assignments = self.lock_assignments();
+                    for block_number in start_block_num..start_block_num + count {
+                        assignments.remove(&block_number);
+                    }
+                    drop(assignments);
+                    error!(
+                        "coordinator: failed to request {count} blocks from {start_block_num} from any instance {e:?}",
+                    );
+                    failure = Some(e);
+                }
+            }
+        }
+
+        // it fails only if no chunk is accepted, the released blocks could be requested again
+        match failure {
+            Some(e) if chunks.is_empty() => Err(e),
+            _ => Ok(chunks),
+        }
+    }
+
+    // return the status of the blocks assigned to each instance
+    pub fn status(&self) -> Vec<InstanceStatus> {
+        let mut status: Vec<InstanceStatus> = self
+            .instances
+            .iter()
+            .map(|instance| InstanceStatus {
+                name: instance.name.clone(),
+                ..Default::default()
+            })
+            .collect();
+
+        for assignment in self.lock_assignments().values() {
+            let status = &mut status[assignment.instance];
+            match assignment.state {
+                AssignmentState::InFlight => status.in_flight_blocks += 1,
+                AssignmentState::Proved => status.proved_blocks += 1,
+                AssignmentState::Failed => status.failed_blocks += 1,
+            }
+        }
+        let duplicate_reports = self
+            .duplicate_reports
+            .lock()
+            .expect("coordinator: failed to lock the duplicate reports");
+        for (status, duplicate_reports) in status.iter_mut().zip(duplicate_reports.iter()) {
+            status.duplicate_reports = *duplicate_reports;
+        }
+
+        status
+    }
+
+    // watch the reports of an instance and merge them, the watcher is reconnected if closed
+    pub async fn watch_instance(self: Arc<Self>, instance: usize) {
+        let name = &self.instances[instance].name;
+        loop {
+            match self.instances[instance].client.watch_reports().await {
+                Ok(mut watcher) => {
+                    info!("coordinator: watching the reports of instance {name}");
+                    loop {
+                        match watcher.next_report().await {
+                            Ok(Some(report)) => self.merge_report(instance, report).await,
+                            Ok(None) => {
+                                warn!("coordinator: report watcher of instance {name} is closed");
+                                break;
+                            }
+                            Err(e) => {
+                                warn!(
+                                    "coordinator: failed to receive the reports of instance {name} {e:?}"
+                                );
+                                break;
+                            }
+                        }
+                    }
+                    watcher.close().await;
+                }
+                Err(e) => {
+                    warn!("coordinator: failed to watch the reports of instance {name} {e:?}");
+                }
+            }
+            sleep(WATCH_RECONNECT_INTERVAL).await;
+        }
+    }
+
+    // merge a report of an instance, the report of a block assigned to another instance or already
+    // proved is dropped as a duplicate; the reports of the blocks requested from the instance
+    // directly are merged and assign the blocks to it
+    async fn merge_report(&self, instance: usize, mut report: BlockProvingReport) {
+        if report.is_estimation || report.chain_id != self.chain_id {
+            return;
+        }
+
+        let block_number = report.block_number;
+        let state = if report.success {
+            AssignmentState::Proved
+        } else {
+            AssignmentState::Failed
+        };
+        let is_duplicate = {
+            let mut assignments = self.lock_assignments();
+            let assignment = assignments.entry(block_number).or_insert(Assignment {
+                instance,
+                state: AssignmentState::InFlight,
+            });
+            if assignment.instance != instance || assignment.state == AssignmentState::Proved {
+                true
+            } else {
+                assignment.state = state;
+                false
+            }
+        };
+        let name = &self.instances[instance].name;
+        if is_duplicate {
+            warn!(
+                "coordinator: dropped a duplicate report of block {block_number} from instance {name}"
+            );
+            self.duplicate_reports
+                .lock()
+                .expect("coordinator: failed to lock the duplicate reports")[instance] += 1;
+            return;
+        }
+
+        info!(
+            "coordinator: merged the report of block {block_number} from instance {name}, success {}",
+            report.success,
+        );
+        report.proof = None;
+        if let Err(e) = self.report_store.insert(report).await {
+            error!("coordinator: failed to store the report of block {block_number} {e:?}");
+        }
+    }
+
+    // request a chunk of blocks from the next instance, the following instances are tried in turn
+    // if it fails; the reserved blocks are assigned to the accepting instance
+    async fn request_chunk(&self, start_block_num: u64, count: u64) -> Result<ChunkAssignment> {
+        let params = ProveBlockByNumberParams::new(start_block_num, Some(count));
+        let first_instance = self.next_instance.fetch_add(1, Ordering::Relaxed);
+        for i in 0..self.instances.len() {
+            let index = (first_instance + i) % self.instances.len();
+            let instance = &self.instances[index];
+            match instance.client.prove_block_by_number(&params).await {
+                Ok(job_id) => {
+                    info!(
+                        "coordinator: requested {count} blocks from {start_block_num} from instance {} by job {job_id}",
+                        instance.name,
+                    );
+                    let mut assignments = self.lock_assignments();
+                    for block_number in start_block_num..start_block_num + count {
+                        if let Some(assignment) = assignments.get_mut(&block_number) {
+                            assignment.instance = index;
+                        }
+                    }
+
+                    return Ok(ChunkAssignment {
+                        instance: instance.name.clone(),
+                        start_block_num,
+                        count,
+                        job_id,
+                    });
+                }
+                Err(e) => warn!(
+                    "coordinator: failed to request blocks from instance {} {e:?}",
+                    instance.name,
+                ),
+            }
+        }
+
+        bail!("no instance accepted the request")
+    }
+
+    // lock the block assignments
+    fn lock_assignments(&self) -> MutexGuard<'_, BTreeMap<u64, Assignment>> {
+        self.assignments
+            .lock()
+            .expect("coordinator: failed to lock the assignments")
+    }
+}
+
+// split the sorted block numbers into the chunks of consecutive blocks of the maximum size, each
+// chunk is returned as the first block number and the number of blocks
+fn split_chunks(block_numbers: &[u64], chunk_size: u64) -> Vec<(u64, u64)> {
+    let mut chunks: Vec<(u64, u64)> = vec![];
+    for &block_number in block_numbers {
+        match chunks.last_mut() {
+            Some((start, count)) if *start + *count == block_number && *count < chunk_size => {
+                *count += 1;
+            }
+            _ => chunks.push((block_number, 1)),
+        }
+    }
+
+    chunks
+}
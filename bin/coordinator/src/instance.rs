@@ -0,0 +1,65 @@
+use anyhow::{Result, ensure};
+use fetch_client::client::FetchClient;
+use reqwest::Url;
+use serde::Deserialize;
+use std::{collections::HashSet, fs, path::Path};
+
+// eth-proofs deployment distributed with the block ranges, loaded from the instances config file
+#[derive(Clone, Debug, Deserialize)]
+pub struct InstanceConfig {
+    // unique name of the deployment, e.g. the data center
+    pub name: String,
+
+    // fetch service HTTP URL of the deployment
+    pub http_url: Url,
+
+    // fetch service websocket URL of the deployment
+    pub ws_url: Url,
+
+    // key of the `authorization: Bearer <key>` header; no header if not specified
+    pub auth_key: Option<String>,
+}
+
+// eth-proofs deployment with its fetch client
+#[derive(Debug)]
+pub struct Instance {
+    // unique name of the deployment
+    pub name: String,
+
+    // client of the fetch service of the deployment
+    pub client: FetchClient,
+}
+
+impl Instance {
+    // create an instance by its config
+    pub fn new(config: InstanceConfig) -> Result<Self> {
+        let mut builder = FetchClient::builder()
+            .http_url(config.http_url)
+            .ws_url(config.ws_url);
+        if let Some(auth_key) = config.auth_key {
+            builder = builder.auth_key(auth_key);
+        }
+
+        Ok(Self {
+            name: config.name,
+            client: builder.build()?,
+        })
+    }
+}
+
+// load the instances config file of a JSON array, the instance names should be unique
+pub fn load_instance_configs(file_path: &Path) -> Result<Vec<InstanceConfig>> {
+    let configs: Vec<InstanceConfig> = serde_json::from_slice(&fs::read(file_path)?)?;
+    ensure!(!configs.is_empty(), "no eth-proofs instance is configured");
+
+    let mut names = HashSet::new();
+    for config in &configs {
+        ensure!(
+            names.insert(config.name.as_str()),
+            "duplicate instance name {}",
+            config.name,
+        );
+    }
+
+    Ok(configs)
+}
@@ -0,0 +1,204 @@
+mod coordinator;
+mod instance;
+
+use anyhow::Result;
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use clap::Parser;
+use common::{
+    fetch::{
+        BackfillBlockRangeParams, HTTP_REPORT_PATH, HTTP_REPORTS_PATH, ReportParams,
+        ReportRangeParams,
+    },
+    ids::{BlockKey, DEFAULT_CHAIN_ID},
+    logger::setup_logger,
+    report::open_report_store,
+};
+use coordinator::{Coordinator, MAX_DISTRIBUTED_RANGE_BLOCKS};
+use dotenvy::dotenv;
+use instance::{Instance, load_instance_configs};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tokio::{net::TcpListener, signal::ctrl_c, spawn};
+use tracing::info;
+
+// HTTP path of distributing a block range across the instances:
+// `http://HTTP_URL/prove_block_range?start_block_num=START_BLOCK_NUM&end_block_num=END_BLOCK_NUM`
+const HTTP_PROVE_BLOCK_RANGE_PATH: &str = "/prove_block_range";
+
+// HTTP path of the status of the blocks assigned to each instance
+const HTTP_STATUS_PATH: &str = "/status";
+
+#[derive(Parser)]
+struct Args {
+    #[clap(
+        long,
+        env = "COORDINATOR_ADDR",
+        default_value = "[::]:8090",
+        help = "Coordinator HTTP address"
+    )]
+    addr: SocketAddr,
+
+    #[clap(
+        long,
+        env = "COORDINATOR_INSTANCES_PATH",
+        help = "JSON file of the eth-proofs instances, e.g. `[{\"name\": \"dc-1\", \"http_url\": \"http://172.1.1.1:8080\", \"ws_url\": \"ws://172.1.1.1:8080\"}]`, with an optional `auth_key` of each instance"
+    )]
+    instances_path: PathBuf,
+
+    #[clap(
+        long,
+        env = "COORDINATOR_CHUNK_SIZE",
+        default_value = "100",
+        help = "Maximum number of consecutive blocks requested from an instance at a time"
+    )]
+    chunk_size: u64,
+
+    #[clap(
+        long,
+        default_value_t = DEFAULT_CHAIN_ID,
+        help = "Chain id of the distributed blocks, the instances should prove the same chain"
+    )]
+    chain_id: u64,
+
+    #[clap(
+        long,
+        env = "COORDINATOR_REPORT_STORE_URL",
+        default_value = "sqlite://coordinator_reports.db",
+        help = "Report store URL of the merged reports, `memory://` or `sqlite://PATH`; the proved blocks in it are not distributed again"
+    )]
+    report_store_url: String,
+}
+
+// distribute the block ranges across several eth-proofs deployments and merge their reports
+#[tokio::main]
+async fn main() {
+    // setup env and logger
+    dotenv().ok();
+    setup_logger();
+
+    // parse the cli arguments
+    let args = Args::parse();
+
+    let instances = load_instance_configs(&args.instances_path)
+        .and_then(|configs| {
+            configs
+                .into_iter()
+                .map(Instance::new)
+                .collect::<Result<Vec<_>>>()
+        })
+        .unwrap_or_else(|e| {
+            panic!(
+                "coordinator: failed to load the instances {:?}: {e}",
+                args.instances_path,
+            )
+        });
+    let report_store = open_report_store(&args.report_store_url)
+        .unwrap_or_else(|e| panic!("coordinator: failed to open the report store: {e}"));
+    let instance_count = instances.len();
+    let coordinator = Arc::new(Coordinator::new(
+        instances,
+        args.chain_id,
+        args.chunk_size,
+        report_store,
+    ));
+
+    // watch and merge the reports of each instance
+    for instance in 0..instance_count {
+        spawn(coordinator.clone().watch_instance(instance));
+    }
+
+    let router = Router::new()
+        .route(HTTP_PROVE_BLOCK_RANGE_PATH, get(prove_block_range))
+        .route(HTTP_STATUS_PATH, get(status))
+        .route(HTTP_REPORT_PATH, get(report))
+        .route(HTTP_REPORTS_PATH, get(reports))
+        .with_state(coordinator);
+
+    let listener = TcpListener::bind(args.addr)
+        .await
+        .unwrap_or_else(|e| panic!("coordinator: failed to listen on {}: {e}", args.addr));
+    info!("coordinator: listening on {}", args.addr);
+    axum::serve(listener, router)
+        .with_graceful_shutdown(async {
+            ctrl_c().await.expect("failed to install Ctrl+C handler");
+            info!("Ctrl+C signal received");
+        })
+        .await
+        .expect("coordinator: failed to start");
+}
+
+// handle `prove_block_range` HTTP Get request
+async fn prove_block_range(
+    State(coordinator): State<Arc<Coordinator>>,
+    Query(params): Query<BackfillBlockRangeParams>,
+) -> Response {
+    info!("coordinator: received prove_block_range with params {params:?}");
+
+    match coordinator
+        .prove_block_range(params.start_block_num, params.end_block_num)
+        .await
+    {
+        Ok(chunks) => Json(chunks).into_response(),
+        Err(e) => (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response(),
+    }
+}
+
+// handle `status` HTTP Get request
+async fn status(State(coordinator): State<Arc<Coordinator>>) -> impl IntoResponse {
+    Json(coordinator.status())
+}
+
+// handle `report` HTTP Get request of the merged reports
+async fn report(
+    State(coordinator): State<Arc<Coordinator>>,
+    Query(params): Query<ReportParams>,
+) -> Response {
+    let block_key = BlockKey::new(
+        params.chain_id.unwrap_or(DEFAULT_CHAIN_ID),
+        params.block_number,
+    );
+    match coordinator.report_store().get(block_key).await {
+        Ok(Some(report)) => Json(report).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "block is not reported".to_string()).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+// handle `reports` HTTP Get request of the merged reports
+async fn reports(
+    State(coordinator): State<Arc<Coordinator>>,
+    Query(params): Query<ReportRangeParams>,
+) -> Response {
+    let ReportRangeParams {
+        from_block_num,
+        to_block_num,
+        chain_id,
+    } = params;
+    if from_block_num > to_block_num
+        || to_block_num - from_block_num >= MAX_DISTRIBUTED_RANGE_BLOCKS
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("invalid range or exceeding {MAX_DISTRIBUTED_RANGE_BLOCKS} blocks"),
+        )
+            .into_response();
+    }
+
+    match coordinator
+        .report_store()
+        .range(
+            chain_id.unwrap_or(DEFAULT_CHAIN_ID),
+            from_block_num,
+            to_block_num,
+        )
+        .await
+    {
+        Ok(reports) => Json(reports).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
@@ -0,0 +1,61 @@
+use anyhow::Result;
+use std::{env, fs, path::PathBuf};
+use toml::Value;
+use vergen_gitcl::{BuildBuilder, Emitter, GitclBuilder};
+
+// locked packages whose versions are embedded, keyed by the env variable names
+const LOCKED_PACKAGES: [(&str, &str); 2] = [
+    ("PICO_SDK_VERSION", "pico-sdk"),
+    ("RSP_VERSION", "rsp-client-executor"),
+];
+
+// emit the git commit and build timestamp by vergen, and the versions of the pico-sdk and rsp
+// packages locked in the workspace lock file
+fn main() -> Result<()> {
+    let build = BuildBuilder::default().build_timestamp(true).build()?;
+    let git = GitclBuilder::default().sha(false).dirty(true).build()?;
+    Emitter::default()
+        .add_instructions(&build)?
+        .add_instructions(&git)?
+        .emit()?;
+
+    let lock_path = PathBuf::from(env::var("CARGO_MANIFEST_DIR")?).join("../../Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lock_path.display());
+    let lock: Value = toml::from_str(&fs::read_to_string(&lock_path)?)?;
+    for (env_name, package) in LOCKED_PACKAGES {
+        println!(
+            "cargo:rustc-env={env_name}={}",
+            locked_version(&lock, package)
+        );
+    }
+
+    Ok(())
+}
+
+// return the version of a locked package with its git revision if any, e.g. `1.1.6 (d5a3fef2f55f)`
+fn locked_version(lock: &Value, name: &str) -> String {
+    let package = lock
+        .get("package")
+        .and_then(Value::as_array)
+        .and_then(|packages| {
+            packages
+                .iter()
+                .find(|package| package.get("name").and_then(Value::as_str) == Some(name))
+        });
+    let Some(package) = package else {
+        return "unknown".to_string();
+    };
+
+    let version = package
+        .get("version")
+        .and_then(Value::as_str)
+        .unwrap_or("unknown");
+    match package
+        .get("source")
+        .and_then(Value::as_str)
+        .and_then(|source| source.rsplit_once('#'))
+    {
+        Some((_, revision)) => format!("{version} ({})", &revision[..revision.len().min(12)]),
+        None => version.to_string(),
+    }
+}
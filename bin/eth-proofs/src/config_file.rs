@@ -0,0 +1,146 @@
+use anyhow::{Result, anyhow, bail, ensure};
+use clap::{ArgAction, CommandFactory, FromArgMatches, parser::ValueSource};
+use serde_json::{Map, Value};
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+// id of the argument of the config file path
+const CONFIG_PATH_ARG: &str = "config_path";
+
+// ids of the arguments applied before the config file is read, e.g. loading the env files
+const PRE_CONFIG_ARGS: [&str; 2] = ["env_file", "profile"];
+
+// parse the cli arguments merged with the settings of the config file if specified, the settings
+// are keyed by the argument names (e.g. `max_num_subblocks` or `max-num-subblocks`) and could be
+// grouped in any tables; the cli arguments and env variables take precedence over the file, and
+// the file over the defaults; it exits with the usage on the invalid cli arguments or `--help`
+pub fn parse_args<A: CommandFactory + FromArgMatches>() -> Result<A> {
+    try_parse_args().map_err(|e| match e.downcast::<clap::Error>() {
+        Ok(e) => e.exit(),
+        Err(e) => e,
+    })
+}
+
+// parse the cli arguments merged with the config file like `parse_args`, but return the errors
+// instead of exiting, e.g. for reloading the config file at runtime
+pub fn try_parse_args<A: CommandFactory + FromArgMatches>() -> Result<A> {
+    // the errors are ignored when looking for the config file, since the required arguments and
+    // their conflicts could be set by the file, e.g. `reproduce = true` without the RPC URLs
+    let argv: Vec<OsString> = env::args_os().collect();
+    let command = A::command();
+    let matches = command
+        .clone()
+        .ignore_errors(true)
+        .try_get_matches_from(&argv)?;
+    let Some(config_path) = matches.get_one::<PathBuf>(CONFIG_PATH_ARG) else {
+        let matches = command.try_get_matches_from(&argv)?;
+        return Ok(A::from_arg_matches(&matches)?);
+    };
+
+    // convert the settings not specified by the cli or env to the cli arguments
+    let mut file_args = vec![];
+    for (key, value) in load_settings(config_path)? {
+        let id = key.replace('-', "_");
+        let arg = command
+            .get_arguments()
+            .find(|arg| arg.get_id() == id.as_str())
+            .ok_or_else(|| anyhow!("unknown setting `{key}` in config file {config_path:?}"))?;
+        ensure!(
+            id != CONFIG_PATH_ARG,
+            "config file {config_path:?} can't include another one",
+        );
+        ensure!(
+            !PRE_CONFIG_ARGS.contains(&id.as_str()),
+            "setting `{key}` can't be set in config file {config_path:?}, it's applied before the file is read",
+        );
+        if matches!(
+            matches.value_source(&id),
+            Some(ValueSource::CommandLine | ValueSource::EnvVariable),
+        ) {
+            continue;
+        }
+
+        let long = arg
+            .get_long()
+            .ok_or_else(|| anyhow!("setting `{key}` is not a named argument"))?;
+        match (arg.get_action(), &value, arg.get_value_delimiter()) {
+            (ArgAction::SetTrue, Value::Bool(true), _) => file_args.push(format!("--{long}")),
+            (ArgAction::SetTrue, Value::Bool(false), _) => {}
+            (ArgAction::SetTrue, _, _) => bail!("setting `{key}` must be a boolean"),
+            (_, Value::Array(values), Some(delimiter)) => {
+                let values: Vec<String> = values
+                    .iter()
+                    .map(|value| setting_value(&key, value))
+                    .collect::<Result<_>>()?;
+                file_args.push(format!("--{long}={}", values.join(&delimiter.to_string())));
+            }
+            (ArgAction::Append, Value::Array(values), None) => {
+                for value in values {
+                    file_args.push(format!("--{long}={}", setting_value(&key, value)?));
+                }
+            }
+            _ => file_args.push(format!("--{long}={}", setting_value(&key, &value)?)),
+        }
+    }
+
+    // parse again with the settings of the file inserted before the cli arguments
+    let argv: Vec<OsString> = argv[..1]
+        .iter()
+        .cloned()
+        .chain(file_args.into_iter().map(OsString::from))
+        .chain(argv[1..].iter().cloned())
+        .collect();
+    let matches = command.try_get_matches_from(argv)?;
+
+    Ok(A::from_arg_matches(&matches)?)
+}
+
+// load the settings of a toml, yaml or json file by its extension, the tables are flattened
+fn load_settings(config_path: &Path) -> Result<Map<String, Value>> {
+    let data = fs::read_to_string(config_path)?;
+    let value: Value = match config_path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => toml::from_str(&data)?,
+        Some("yaml" | "yml") => serde_yaml::from_str(&data)?,
+        Some("json") => serde_json::from_str(&data)?,
+        _ => bail!("unsupported config file {config_path:?}, expected toml, yaml or json"),
+    };
+    let Value::Object(table) = value else {
+        bail!("config file {config_path:?} is not a table of settings");
+    };
+
+    let mut settings = Map::new();
+    flatten_settings(table, &mut settings)?;
+
+    Ok(settings)
+}
+
+// flatten the nested tables into the settings, the table names are only for grouping
+fn flatten_settings(table: Map<String, Value>, settings: &mut Map<String, Value>) -> Result<()> {
+    for (key, value) in table {
+        match value {
+            Value::Object(table) => flatten_settings(table, settings)?,
+            value => {
+                ensure!(
+                    settings.insert(key.clone(), value).is_none(),
+                    "duplicate setting `{key}` in config file",
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// return a scalar setting value as a cli argument value
+fn setting_value(key: &str, value: &Value) -> Result<String> {
+    match value {
+        Value::String(value) => Ok(value.clone()),
+        Value::Number(value) => Ok(value.to_string()),
+        Value::Bool(value) => Ok(value.to_string()),
+        _ => bail!("setting `{key}` must be a string, number or boolean"),
+    }
+}
@@ -1,32 +1,233 @@
+mod config_file;
+mod mode;
+mod pipeline;
+mod profile;
+mod prove;
+mod shutdown;
+
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use common::{
-    channel::{DuplexUnboundedChannel, SingleUnboundedChannel},
-    logger::setup_logger,
+    bus::open_message_bus,
+    channel::{
+        BroadcastChannel, ChannelMetrics, DuplexBoundedChannel, DuplexUnboundedChannel,
+        SingleBoundedChannel, SingleUnboundedChannel, WatchChannel, WatchReceiver,
+    },
+    fetch::FOLLOW_LATEST_COUNT,
+    in_flight::InFlightBlocks,
+    inputs::{DEFAULT_INPUT_LAYOUT, DEFAULT_SUBBLOCK_GAS_THRESHOLD, InputLayout},
+    logger::{check_log_filter, setup_logger, watch_log_filter},
+    metrics::MetricsRegistry,
+    pipeline_error::PipelineErrorLog,
+    proof_store::ProofStore,
+    readiness::{ReadinessCheck, notify_systemd_ready, wait_ready},
+    report::{ReportStore, open_report_store},
+    runtime::{ProvingUrls, RuntimeState, config_reload_requested},
+    utils::{DEFAULT_MAX_NUM_SUBBLOCKS, DEFAULT_PIPELINE_ID, PipelineId, TaskStarter},
+    version::{BuildInfo, file_digest, set_build_info},
+};
+use config_file::{parse_args, try_parse_args};
+use docker_control::{config::load_docker_control_config, control::DockerControl};
+use ethproofs::{config::load_ethproofs_config, submitter::EthproofsSubmitter};
+use fetch_service::{
+    config::FetchServiceConfig,
+    job_queue::{
+        DEFAULT_JOB_QUEUE_CLAIM_TIMEOUT_SECONDS, DEFAULT_JOB_QUEUE_GROUP,
+        DEFAULT_JOB_QUEUE_MAX_CLAIMS, DEFAULT_JOB_QUEUE_STREAM, JobQueueConfig, JobQueueConsumer,
+        RedisJobQueue,
+    },
+    service::FetchService,
+};
+use fetcher::{
+    config::BlockFetcherConfig, dump_retention::DumpRetentionPolicy, fetcher::BlockFetcher,
+    rpc_fixture::RpcFixtureMode,
 };
-use dotenvy::dotenv;
-use fetch_service::{config::FetchServiceConfig, service::FetchService};
-use fetcher::{config::BlockFetcherConfig, fetcher::BlockFetcher};
 use futures::future::join_all;
-use messages::{BlockMsgEndpoint, BlockMsgReceiver, BlockMsgSender};
-use proof_service::{config::ProofServiceConfig, service::ProofService};
-use proving_client::{client::ProvingClient, config::ProvingClientConfig};
-use proving_mock::{config::MockProvingServiceConfig, service::MockProvingService};
+use messages::{
+    BlockMsgEndpoint, BlockMsgKind, BlockMsgReceiver, BlockMsgSender, BoundedBlockMsgEndpoint,
+    BoundedBlockMsgSender, PipelineErrorSender,
+};
+use mode::Mode;
+use pipeline::{Pipeline, load_pipeline_configs};
+use profile::{Profile, load_env};
+use proof_service::{
+    config::{ProofServiceConfig, ProofServiceTls},
+    quarantine_store::QuarantineStore,
+    service::ProofService,
+    verifier::ProofVerifier,
+};
+use prove::{ProveArgs, run_prove};
+use proving_client::{
+    client::ProvingClient,
+    config::{
+        DEFAULT_MAX_PROVING_REQUEST_RETRIES, DEFAULT_PROVING_REQUEST_RETRY_SECONDS,
+        DEFAULT_PROVING_TIMEOUT_SECONDS, ProvingClientConfig,
+    },
+    discovery::{DEFAULT_DISCOVERY_INTERVAL_SECONDS, SubblockDiscovery},
+    nats::{DEFAULT_NATS_STREAM, DEFAULT_NATS_SUBJECT_PREFIX, NatsDispatchConfig},
+};
+use proving_mock::{
+    config::{
+        DEFAULT_MOCK_BASE_PORT, DEFAULT_MOCK_BIND_IP, MockProvingBehavior, MockProvingServiceConfig,
+    },
+    emulator::MockEmulator,
+    recorder::RequestRecorder,
+    scenario::MockScenario,
+    service::MockProvingService,
+};
 use reporter::BlockReporter;
 use reqwest::Url;
-use scheduler::Scheduler;
-use std::{net::SocketAddr, path::PathBuf, sync::Arc};
-use tokio::sync::Mutex;
+use scheduler::{
+    Scheduler,
+    error_handler::PipelineErrorHandler,
+    journal::MessageJournal,
+    routing::{RoutingTable, Subsystem},
+    supervisor::Supervisor,
+};
+use shutdown::shutdown;
+use std::{
+    env,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    process,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    select,
+    signal::unix::{SignalKind, signal},
+    spawn,
+    sync::Mutex,
+    task::JoinHandle,
+};
+use tracing::{error, info, warn};
 
 #[derive(Parser)]
 struct Args {
+    #[clap(
+        long = "config",
+        env = "CONFIG_PATH",
+        help = "TOML, YAML or JSON file of the settings keyed by the argument names (e.g. `max_num_subblocks = 4`), optionally grouped in tables; the cli arguments and env variables take precedence over the file"
+    )]
+    config_path: Option<PathBuf>,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+
     #[clap(
         long,
+        requires = "input_load_dir",
+        conflicts_with_all = [
+            "input_dump_dir",
+            "is_input_emulated",
+            "rpc_fixture_mode",
+            "block_cache_dir",
+            "prove_latest_on_start",
+            "follow_on_start",
+        ],
+        help = "Only reproduce the dumped inputs of `--input-load-dir` without the RPC node, the RPC fetchers and the proving, backfilling and estimating requests are disabled; the blocks fetched from the RPC node are proved if not set, with the dumped inputs also reproducible if `--input-load-dir` is specified"
+    )]
+    reproduce: bool,
+
+    #[clap(
+        long,
+        env = "ENV_FILE",
+        help = "Env file loaded before `.env`, its variables take precedence over `.env.<profile>`, `.env` and the profile defaults but not over the variables already set; it's only read from the cli or env variables"
+    )]
+    env_file: Option<PathBuf>,
+
+    #[clap(
+        long,
+        env = "PROFILE",
+        value_enum,
+        help = "Deployment profile loading `.env.<profile>` before `.env` if it exists: `dev` defaults to the mock proving service and a local RPC node at `127.0.0.1:8545`/`8546`, `staging` and `prod` have no built-in defaults and `prod` rejects `--is-mock-proving`; it's only read from the cli, env variables or env files"
+    )]
+    profile: Option<Profile>,
+
+    #[clap(
+        long,
+        env = "IS_MOCK_PROVING",
         default_value = "false",
         help = "identify if enable mock proving service (only used for testing)"
     )]
     is_mock_proving: bool,
 
+    #[clap(
+        long,
+        env = "MOCK_PROVING_MILLISECONDS",
+        default_value = "0",
+        help = "Simulated proving duration of the mock proving service before returning the result (in milliseconds), the result is returned immediately if zero"
+    )]
+    mock_proving_milliseconds: u64,
+
+    #[clap(
+        long,
+        env = "MOCK_SUCCESS_RATE",
+        default_value = "1.0",
+        help = "Probability of a block proved successfully by the mock proving service, in the range of [0, 1]"
+    )]
+    mock_success_rate: f64,
+
+    #[clap(
+        long,
+        env = "MOCK_FAILED_BLOCKS",
+        value_delimiter = ',',
+        help = "Block numbers separated by comma always failed to prove by the mock proving service, e.g. `23264565,23264570`"
+    )]
+    mock_failed_blocks: Vec<u64>,
+
+    #[clap(
+        long,
+        env = "MOCK_REPLAY_PROOF_DIR",
+        help = "Directory of the proofs recorded from the real proving runs, e.g. a `PROOF_STORE_DIR`, the mock proving service returns `block{n}/proof.bin` or the latest `block{n}/job{id}.bin` with the cycles and timing of an optional `block{n}/result.json` instead of the mock proof"
+    )]
+    mock_replay_proof_dir: Option<PathBuf>,
+
+    #[clap(
+        long,
+        env = "MOCK_SCENARIO_PATH",
+        help = "JSON file of the per-block behaviors of the mock proving service, e.g. `{\"23264565\": [{\"no_response\": true}, {\"fail\": \"oom\"}]}`, the n-th request of a block takes the n-th step and the last step is repeated"
+    )]
+    mock_scenario_path: Option<PathBuf>,
+
+    #[clap(
+        long,
+        env = "MOCK_EMULATION",
+        default_value = "false",
+        help = "identify if the mock proving service emulates the received inputs by the subblock and aggregator ELFs, and reports the true cycles as proved without a proof"
+    )]
+    mock_emulation: bool,
+
+    #[clap(
+        long,
+        env = "MOCK_NUM_SUBBLOCKS",
+        help = "Number of the mock subblock proving services, it's `MAX_NUM_SUBBLOCKS` if not specified"
+    )]
+    mock_num_subblocks: Option<usize>,
+
+    #[clap(
+        long,
+        env = "MOCK_BIND_IP",
+        default_value_t = DEFAULT_MOCK_BIND_IP,
+        help = "Bind address of the mock proving services"
+    )]
+    mock_bind_ip: IpAddr,
+
+    #[clap(
+        long,
+        env = "MOCK_BASE_PORT",
+        default_value_t = DEFAULT_MOCK_BASE_PORT,
+        help = "Port of the mock aggregator service, the mock subblock services listen on the following ports by subblock index"
+    )]
+    mock_base_port: u16,
+
+    #[clap(
+        long,
+        env = "MOCK_REQUEST_LOG_PATH",
+        help = "JSON lines file recording every proving request received by the mock proving services, with the inputs by length and digest, for asserting what the proving-client sent"
+    )]
+    mock_request_log_path: Option<PathBuf>,
+
     #[clap(
         long,
         default_value = "false",
@@ -36,21 +237,112 @@ struct Args {
 
     #[clap(
         long,
-        help = "Base directory for saving input files; nothing will be saved if not specified"
+        help = "Base directory or object store URL (`s3://BUCKET/PREFIX` or `gs://BUCKET/PREFIX`) for saving input files; nothing will be saved if not specified"
     )]
     input_dump_dir: Option<PathBuf>,
 
     #[clap(
         long,
-        help = "Base directory for reproducing blocks by loading input files; it could be the same directory as `input_dump_dir`"
+        env = "DUMP_RETENTION_MAX_AGE_SECONDS",
+        help = "Maximum age of the dumped blocks in seconds, the older blocks are removed; unlimited if not specified"
+    )]
+    dump_retention_max_age_seconds: Option<u64>,
+
+    #[clap(
+        long,
+        env = "DUMP_RETENTION_MAX_TOTAL_BYTES",
+        help = "Maximum total bytes of the dumped blocks, the oldest blocks are removed when exceeding it; unlimited if not specified"
+    )]
+    dump_retention_max_total_bytes: Option<u64>,
+
+    #[clap(
+        long,
+        env = "DUMP_RETENTION_KEEP_LATEST_COUNT",
+        default_value = "100",
+        help = "Number of the most recent dumped blocks which are always kept by the retention policy"
+    )]
+    dump_retention_keep_latest_count: usize,
+
+    #[clap(
+        long,
+        help = "Base directory or object store URL for reproducing blocks by loading input files; it could be the same location as `input_dump_dir`"
     )]
     input_load_dir: Option<PathBuf>,
 
-    #[clap(long, env = "RPC_HTTP_URL", help = "RPC node HTTP URL")]
-    rpc_http_url: Url,
+    #[clap(
+        long,
+        env = "INPUT_LOAD_MMAP",
+        help = "Memory-map the input files in a local `input_load_dir` when loading, so the compressed files of large reproduction campaigns are not read into memory; the files must not be rewritten while loading"
+    )]
+    input_load_mmap: bool,
 
-    #[clap(long, env = "RPC_WS_URL", help = "RPC node websocket URL")]
-    rpc_ws_url: Url,
+    #[clap(
+        long,
+        env = "INPUT_LAYOUT",
+        default_value = DEFAULT_INPUT_LAYOUT,
+        help = "Template of the input directory under `block{n}/` for dumping and loading input files, the `{chain_id}` and `{gas_threshold}` placeholders are replaced, e.g. `chain{chain_id}/gas{gas_threshold}`"
+    )]
+    input_layout: String,
+
+    #[clap(
+        long,
+        env = "SUBBLOCK_GAS_THRESHOLD",
+        default_value_t = DEFAULT_SUBBLOCK_GAS_THRESHOLD,
+        help = "Subblock gas threshold of the generated inputs for the `{gas_threshold}` placeholder of the input layout"
+    )]
+    subblock_gas_threshold: u64,
+
+    #[clap(
+        long,
+        help = "Base directory for caching the fetched block data and execution witnesses keyed by block hash; nothing will be cached if not specified"
+    )]
+    block_cache_dir: Option<PathBuf>,
+
+    #[clap(
+        long,
+        env = "RPC_HTTP_URL",
+        required_unless_present = "reproduce",
+        help = "RPC node HTTP URL, it's not used by `--reproduce`"
+    )]
+    rpc_http_url: Option<Url>,
+
+    #[clap(
+        long,
+        env = "RPC_WS_URL",
+        required_unless_present = "reproduce",
+        help = "RPC node websocket URL, it's not used by `--reproduce`"
+    )]
+    rpc_ws_url: Option<Url>,
+
+    #[clap(
+        long,
+        env = "CHAIN_ID",
+        default_value = "1",
+        help = "Chain id of the blocks fetched by the RPC node, it's carried by the block reports for correlating the blocks across the chains"
+    )]
+    chain_id: u64,
+
+    #[clap(
+        long,
+        env = "WITNESS_RPC_URL",
+        help = "RPC node HTTP URL for fetching the block execution witnesses; RPC_HTTP_URL is used if not specified"
+    )]
+    witness_rpc_url: Option<Url>,
+
+    #[clap(
+        long,
+        env = "RPC_FIXTURE_MODE",
+        help = "Record the HTTP RPC responses of each block to fixture files (`record`, requires `--prefetch-block-count 1`), or serve them from the fixture files offline (`replay`); disabled if not specified"
+    )]
+    rpc_fixture_mode: Option<RpcFixtureMode>,
+
+    #[clap(
+        long,
+        env = "RPC_FIXTURE_DIR",
+        default_value = "rpc-fixtures",
+        help = "Directory of the RPC fixture files"
+    )]
+    rpc_fixture_dir: PathBuf,
 
     #[clap(
         long,
@@ -68,6 +360,177 @@ struct Args {
     )]
     agg_elf_path: PathBuf,
 
+    #[clap(
+        long,
+        env = "PREFETCH_BLOCK_COUNT",
+        default_value = "2",
+        help = "Maximum number of blocks whose inputs are prepared ahead of proving when proving by block number"
+    )]
+    prefetch_block_count: usize,
+
+    #[clap(
+        long,
+        env = "PREFETCH_PERMIT_TIMEOUT_SECONDS",
+        default_value = "3600",
+        help = "Maximum seconds of a prefetched block waiting for its report or failure when proving by block number, its prefetch slot is released after it"
+    )]
+    prefetch_permit_timeout_seconds: u64,
+
+    #[clap(
+        long,
+        env = "MAX_BLOCKS_PER_BATCH",
+        default_value = "10",
+        help = "Maximum number of latest blocks fetched in each subscription batch when proving the latest blocks; batching is disabled if it's zero"
+    )]
+    max_blocks_per_batch: usize,
+
+    #[clap(
+        long,
+        env = "CONFIRMATIONS",
+        default_value = "0",
+        help = "Number of blocks to wait behind the chain head before proving the latest blocks, avoiding wasted work on reorged blocks"
+    )]
+    confirmations: u64,
+
+    #[clap(
+        long,
+        env = "MAX_PROVING_QUEUE_DEPTH",
+        default_value = "8",
+        help = "Maximum number of blocks submitted for proving but not reported, new blocks are delayed when the backlog exceeds it; it's disabled if zero"
+    )]
+    max_proving_queue_depth: usize,
+
+    #[clap(
+        long,
+        env = "MAX_IN_FLIGHT_BLOCKS",
+        default_value = "8",
+        help = "Maximum number of blocks dispatched for proving but not reported in a pipeline, the scheduler holds the new fetch requests of the pipeline when it's reached; it's disabled if zero"
+    )]
+    max_in_flight_blocks: usize,
+
+    #[clap(
+        long,
+        env = "LOG_FILTER",
+        help = "Log filter directives replacing the `RUST_LOG` ones, e.g. `info,scheduler=debug`; the `RUST_LOG` filter is kept if not specified"
+    )]
+    log_filter: Option<String>,
+
+    #[clap(
+        long,
+        env = "CHANNEL_CAPACITY",
+        default_value = "1024",
+        help = "Capacity of the bounded channels of the proving-clients and reporter, the scheduler waits for the capacity when delivering to a slow consumer; it's also the number of messages buffered for each websocket watcher before skipping the oldest"
+    )]
+    channel_capacity: usize,
+
+    #[clap(
+        long,
+        env = "CHANNEL_METRICS",
+        default_value = "false",
+        help = "Record the queue depth, send rate and receive latency of the channels between the components and the scheduler to the metrics endpoint"
+    )]
+    channel_metrics: bool,
+
+    #[clap(
+        long,
+        env = "MESSAGE_BUS_URL",
+        default_value = "memory://",
+        help = "Message bus URL connecting the components run on different hosts, `memory://` (in-process), `nats://HOST:4222` or `redis://HOST:6379`"
+    )]
+    message_bus_url: String,
+
+    #[clap(
+        long,
+        env = "REMOTE_COMPONENTS",
+        value_delimiter = ',',
+        help = "Components separated by comma run on the other hosts by `--serve-components` and connected by the message bus, e.g. `proof-service,proving-client`; the all components run in this process if not specified"
+    )]
+    remote_components: Option<Vec<String>>,
+
+    #[clap(
+        long,
+        env = "SERVE_COMPONENTS",
+        value_delimiter = ',',
+        help = "Only run the components separated by comma for the scheduler on another host, e.g. `proof-service,proving-client`; the scheduler runs in this process if not specified"
+    )]
+    serve_components: Option<Vec<String>>,
+
+    #[clap(
+        long,
+        env = "MESSAGE_JOURNAL_PATH",
+        help = "File path of the journal appending every message routed by the scheduler for debugging and replay; disabled if not specified"
+    )]
+    message_journal_path: Option<PathBuf>,
+
+    #[clap(
+        long,
+        env = "REPORT_NDJSON_PATH",
+        help = "File path of the NDJSON appending every block report emitted by the reporter as a JSON object line; disabled if not specified"
+    )]
+    report_ndjson_path: Option<PathBuf>,
+
+    #[clap(
+        long,
+        env = "REPORT_STORE_URL",
+        default_value = "memory://",
+        help = "URL of the store of the block reports for the history requests, `memory://` or a SQLite database file like `sqlite://proving_reports.db`"
+    )]
+    report_store_url: String,
+
+    #[clap(
+        long,
+        env = "REPORT_SUMMARY_INTERVAL_SECONDS",
+        default_value = "3600",
+        help = "Interval seconds of logging the summary of the blocks reported since the last summary by the reporter; disabled if zero"
+    )]
+    report_summary_interval_seconds: u64,
+
+    #[clap(
+        long,
+        env = "ETHPROOFS_CONFIG_PATH",
+        requires = "ethproofs_api_key",
+        help = "JSON file of the ethproofs.org cluster and submission settings, the reporter submits the queued, proving, proved and failed statuses of the blocks through a durable outbox with retries; disabled if not specified"
+    )]
+    ethproofs_config_path: Option<PathBuf>,
+
+    #[clap(
+        long,
+        env = "ETHPROOFS_API_KEY",
+        help = "API key of ethproofs.org sent as the `authorization: Bearer <key>` header, it's required by `--ethproofs-config-path`"
+    )]
+    ethproofs_api_key: Option<String>,
+
+    #[clap(
+        long,
+        env = "MAX_INPUT_BYTES",
+        help = "Maximum bytes of each serialized subblock or aggregator input, the blocks exceeding it are rejected before proving; it's the maximum gRPC message bytes if not specified"
+    )]
+    max_input_bytes: Option<usize>,
+
+    #[clap(
+        long,
+        env = "MAX_NUM_SUBBLOCKS",
+        default_value_t = DEFAULT_MAX_NUM_SUBBLOCKS,
+        help = "Maximum number of subblocks of a block, it should not exceed the number of subblock provers in the cluster"
+    )]
+    max_num_subblocks: usize,
+
+    #[clap(
+        long,
+        env = "BACKFILL_CHECKPOINT_PATH",
+        default_value = "backfill_checkpoint.json",
+        help = "Checkpoint file path for resuming the backfill campaign after a restart"
+    )]
+    backfill_checkpoint_path: PathBuf,
+
+    #[clap(
+        long,
+        env = "MAX_TASK_RESTARTS",
+        default_value = "10",
+        help = "Maximum number of consecutive restarts of a crashed pipeline task before giving up; unlimited if zero"
+    )]
+    max_task_restarts: usize,
+
     #[clap(
         long,
         env = "FETCH_SERVICE_ADDR",
@@ -76,6 +539,68 @@ struct Args {
     )]
     fetch_service_addr: SocketAddr,
 
+    #[clap(
+        long,
+        env = "PROVE_LATEST_ON_START",
+        help = "Number of the latest blocks of the default pipeline to prove once the fetch service is ready, like a `prove_latest_block` request on launch; nothing is requested if not specified"
+    )]
+    prove_latest_on_start: Option<u64>,
+
+    #[clap(
+        long,
+        env = "FOLLOW_ON_START",
+        default_value = "false",
+        conflicts_with = "prove_latest_on_start",
+        help = "Follow the chain of the default pipeline by proving every new block once the fetch service is ready"
+    )]
+    follow_on_start: bool,
+
+    #[clap(
+        long,
+        env = "JOB_QUEUE_URL",
+        help = "Redis URL of the job queue shared by the orchestrator replicas, the fetch requests of any replica are queued and claimed by one of them, e.g. `redis://127.0.0.1:6379`; the requests are sent to the local pipelines if not specified"
+    )]
+    job_queue_url: Option<String>,
+
+    #[clap(
+        long,
+        env = "JOB_QUEUE_STREAM",
+        default_value = DEFAULT_JOB_QUEUE_STREAM,
+        help = "Redis stream of the shared job queue, it's created with the consumer group if not existing"
+    )]
+    job_queue_stream: String,
+
+    #[clap(
+        long,
+        env = "JOB_QUEUE_GROUP",
+        default_value = DEFAULT_JOB_QUEUE_GROUP,
+        help = "Consumer group of the replicas sharing the job queue"
+    )]
+    job_queue_group: String,
+
+    #[clap(
+        long,
+        env = "JOB_QUEUE_CONSUMER",
+        help = "Consumer name of this replica in the job queue group, it must be unique among the replicas; `HOSTNAME` or `eth-proofs-<pid>` if not specified"
+    )]
+    job_queue_consumer: Option<String>,
+
+    #[clap(
+        long,
+        env = "JOB_QUEUE_MAX_CLAIMS",
+        default_value_t = DEFAULT_JOB_QUEUE_MAX_CLAIMS,
+        help = "Maximum number of the queued jobs claimed by this replica at the same time, a single block request is a job"
+    )]
+    job_queue_max_claims: usize,
+
+    #[clap(
+        long,
+        env = "JOB_QUEUE_CLAIM_TIMEOUT_SECONDS",
+        default_value_t = DEFAULT_JOB_QUEUE_CLAIM_TIMEOUT_SECONDS,
+        help = "Seconds of a claimed job not renewed before it's claimed by another replica, e.g. the claiming replica crashed"
+    )]
+    job_queue_claim_timeout_seconds: u64,
+
     #[clap(
         long,
         env = "PROOF_SERVICE_ADDR",
@@ -84,6 +609,51 @@ struct Args {
     )]
     pub proof_service_addr: SocketAddr,
 
+    #[clap(
+        long,
+        env = "PROOF_SERVICE_TOKEN",
+        help = "Shared token required from the proving cluster callbacks as the `authorization: Bearer <token>` gRPC metadata; the callbacks are not authenticated if not specified"
+    )]
+    proof_service_token: Option<String>,
+
+    #[clap(
+        long,
+        env = "PROOF_SERVICE_TLS_CERT_PATH",
+        requires = "proof_service_tls_key_path",
+        help = "PEM encoded certificate chain file path for serving the proof service over TLS; the proof service is plaintext if not specified"
+    )]
+    proof_service_tls_cert_path: Option<PathBuf>,
+
+    #[clap(
+        long,
+        env = "PROOF_SERVICE_TLS_KEY_PATH",
+        requires = "proof_service_tls_cert_path",
+        help = "PEM encoded private key file path for serving the proof service over TLS"
+    )]
+    proof_service_tls_key_path: Option<PathBuf>,
+
+    #[clap(
+        long,
+        env = "VERIFY_PROOFS",
+        default_value = "false",
+        help = "Verify the received proofs against the verification key of AGG_ELF_PATH, the invalid proofs are recorded as proving failures"
+    )]
+    verify_proofs: bool,
+
+    #[clap(
+        long,
+        env = "PROOF_STORE_DIR",
+        help = "Base directory or object store URL (`s3://BUCKET/PREFIX` or `gs://BUCKET/PREFIX`) for persisting the received proofs as `block{n}/job{id}.bin`, the reports carry the storage reference and SHA-256 hash instead of the proof bytes, which are served by the `/proof` endpoint; the proofs are carried by the reports if not specified"
+    )]
+    proof_store_dir: Option<PathBuf>,
+
+    #[clap(
+        long,
+        env = "PROOF_QUARANTINE_DIR",
+        help = "Base directory or object store URL (`s3://BUCKET/PREFIX` or `gs://BUCKET/PREFIX`) for quarantining the proof results of the blocks not in flight, e.g. a late result after the block is completed; the results are dropped with a warning if not specified"
+    )]
+    proof_quarantine_dir: Option<PathBuf>,
+
     #[clap(
         long,
         env = "MAX_GRPC_MSG_BYTES",
@@ -92,6 +662,14 @@ struct Args {
     )]
     pub max_grpc_msg_bytes: usize,
 
+    #[clap(
+        long,
+        env = "MAX_PROOF_BYTES",
+        default_value = "4294967296",
+        help = "Maximum proof bytes uploaded in chunks by `CompleteProvingStream`, a stream exceeding it is rejected"
+    )]
+    max_proof_bytes: usize,
+
     #[clap(
         long,
         env = "PROVING_AGG_URL",
@@ -106,16 +684,174 @@ struct Args {
         help = "Subbblock proving GRPC URLs separated by comma, e.g. `http://172.1.1.1:50052,http://172.2.2.2:50052`"
     )]
     pub proving_subblock_urls: Option<Vec<Url>>,
+
+    #[clap(
+        long,
+        env = "PROVING_SUBBLOCK_SERVICE",
+        conflicts_with_all = ["proving_subblock_urls", "is_mock_proving"],
+        help = "Headless Kubernetes service `<host>:<port>` of the subblock provers, e.g. `subblock-prover.pico.svc.cluster.local:50052`; it's resolved by DNS to the ready pods, which replace `--proving-subblock-urls` and are followed as they scale"
+    )]
+    pub proving_subblock_service: Option<String>,
+
+    #[clap(
+        long,
+        env = "PROVING_DISCOVERY_INTERVAL_SECONDS",
+        default_value_t = DEFAULT_DISCOVERY_INTERVAL_SECONDS,
+        help = "Seconds between the resolutions of `--proving-subblock-service`"
+    )]
+    pub proving_discovery_interval_seconds: u64,
+
+    #[clap(
+        long,
+        env = "PROVING_NATS_URL",
+        conflicts_with_all = ["proving_agg_url", "proving_subblock_urls", "proving_subblock_service", "is_mock_proving"],
+        help = "NATS server URL publishing the proving inputs to the JetStream subjects consumed by the prover workers instead of requesting the GRPC services, e.g. `nats://127.0.0.1:4222`"
+    )]
+    pub proving_nats_url: Option<String>,
+
+    #[clap(
+        long,
+        env = "PROVING_NATS_STREAM",
+        default_value = DEFAULT_NATS_STREAM,
+        help = "JetStream stream of the proving inputs published by `--proving-nats-url`, it's created as a work queue if not existing"
+    )]
+    pub proving_nats_stream: String,
+
+    #[clap(
+        long,
+        env = "PROVING_NATS_SUBJECT_PREFIX",
+        default_value = DEFAULT_NATS_SUBJECT_PREFIX,
+        help = "Subject prefix of the proving inputs published by `--proving-nats-url`, the inputs of a pipeline are published to `<prefix>.<pipeline id>.aggregation` and `<prefix>.<pipeline id>.subblock`"
+    )]
+    pub proving_nats_subject_prefix: String,
+
+    #[clap(
+        long,
+        env = "PROVING_TIMEOUT_SECONDS",
+        default_value_t = DEFAULT_PROVING_TIMEOUT_SECONDS,
+        help = "Maximum seconds of waiting for the proving result or any progress of the proving block, the block is resent to the proving cluster after it"
+    )]
+    pub proving_timeout_seconds: u64,
+
+    #[clap(
+        long,
+        env = "MAX_PROVING_REQUEST_RETRIES",
+        default_value_t = DEFAULT_MAX_PROVING_REQUEST_RETRIES,
+        help = "Maximum number of retries of sending a proving request to the proving cluster"
+    )]
+    pub max_proving_request_retries: u32,
+
+    #[clap(
+        long,
+        env = "PROVING_REQUEST_RETRY_SECONDS",
+        default_value_t = DEFAULT_PROVING_REQUEST_RETRY_SECONDS,
+        help = "Seconds between the retries of sending a proving request"
+    )]
+    pub proving_request_retry_seconds: u64,
+
+    #[clap(
+        long,
+        env = "DOCKER_CONTROL_CONFIG_PATH",
+        help = "JSON file of the docker hosts and prover containers restarted by the docker engine api on a proving timeout, e.g. `{\"hosts\": [{\"url\": \"tcp://172.1.1.1:2375\", \"containers\": [\"pico-aggregator\"]}]}`; the block is resent without restarting if not specified"
+    )]
+    pub docker_control_path: Option<PathBuf>,
+
+    #[clap(
+        long,
+        env = "SHUTDOWN_DRAIN_SECONDS",
+        default_value = "300",
+        help = "Maximum seconds of waiting for the blocks in flight to be proved on `Ctrl+C` or `SIGTERM`, the new requests and blocks are no longer accepted meanwhile; a second signal stops waiting"
+    )]
+    pub shutdown_drain_seconds: u64,
+
+    #[clap(
+        long,
+        env = "PIPELINES_CONFIG_PATH",
+        help = "JSON file of the additional pipelines of a fetcher and a proving-client (e.g. one per chain or proving cluster) driven by the same scheduler; only the default pipeline runs if not specified"
+    )]
+    pub pipelines_config_path: Option<PathBuf>,
+}
+
+// subcommand of the binary, the whole instance runs as a service if not specified
+#[derive(Clone, Debug, Subcommand)]
+enum Command {
+    #[clap(flatten)]
+    Component(Component),
+
+    #[clap(
+        about = "Prove a single block by the whole pipeline in this process, write the proof and the JSON report to files and exit"
+    )]
+    Prove(ProveArgs),
+}
+
+// pipeline component run as a separate process, it's served for the scheduler on another host by
+// the message bus like `--serve-components`; the components sharing the in-process state are
+// served together
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Subcommand)]
+enum Component {
+    #[clap(
+        about = "Only run the fetch-service with the reporter for the websocket watchers, for the scheduler on another host"
+    )]
+    FetchService,
+
+    #[clap(about = "Only run the fetchers of the all pipelines for the scheduler on another host")]
+    Fetcher,
+
+    #[clap(
+        about = "Only run the proving-clients of the all pipelines with the proof-service for the blocks in flight, for the scheduler on another host"
+    )]
+    ProvingClient,
+
+    #[clap(
+        about = "Only run the reporter without the websocket watchers for the scheduler on another host"
+    )]
+    Reporter,
+}
+
+impl Component {
+    // return the served component names, the pipeline components are named by each pipeline
+    fn names(&self, pipelines: &[Pipeline]) -> Vec<String> {
+        let pipeline_names = |component| {
+            pipelines
+                .iter()
+                .map(|pipeline| pipeline.component_name(component))
+                .collect::<Vec<_>>()
+        };
+        match self {
+            Self::FetchService => vec![
+                "fetch-service".to_string(),
+                "job-queue".to_string(),
+                "reporter".to_string(),
+            ],
+            Self::Fetcher => pipeline_names("fetcher"),
+            Self::ProvingClient => [
+                pipeline_names("proving-client"),
+                vec!["proof-service".to_string()],
+            ]
+            .concat(),
+            Self::Reporter => vec!["reporter".to_string()],
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // setup env and logger
-    dotenv().ok();
+    // setup env and logger, the env files are loaded before parsing the cli arguments
+    load_env()?;
     setup_logger();
 
-    // parse the cli arguments
-    let mut args = Args::parse();
+    // parse the cli arguments merged with the config file
+    let mut args: Args = parse_args()?;
+    args.validate().unwrap_or_else(|e| e.exit());
+
+    // log the build info for comparing the proving numbers across the deployments, it's also
+    // served by the `/version` request
+    let build_info = build_info(&args);
+    info!("eth-proofs: {build_info}");
+    set_build_info(build_info);
+    if let Some(profile) = args.profile {
+        info!("eth-proofs: running with profile {}", profile.name());
+    }
 
     // collect the thread handles
     let mut handles = vec![];
@@ -126,59 +862,507 @@ async fn main() -> Result<()> {
         handles.extend(mock_proving_service.run());
     }
 
-    // initialize fetch service
-    let (fetch_service, fetch_service_receiver) = init_fetch_service(&args);
+    // create the metrics registry and the pipeline error log shared by the threads
+    let metrics = Arc::new(MetricsRegistry::default());
+    let pipeline_error_log = Arc::new(PipelineErrorLog::default());
+
+    // create the runtime configuration and status changed by the admin requests or the reloaded
+    // config file
+    let runtime_state = WatchChannel::new(RuntimeState::default());
+
+    // apply the log filter changed by the admin requests
+    handles.push(watch_log_filter(runtime_state.subscribe()));
+
+    // open the store of the block reports shared by the reporter and fetch-service
+    let report_store = open_report_store(&args.report_store_url)?;
+
+    // open the store of the persisted proofs shared by the proof-service and fetch-service
+    let proof_store = init_proof_store(&args);
+
+    // create the registry of the in-flight blocks shared by the proving-clients and proof-service
+    let in_flight_blocks = Arc::new(InFlightBlocks::default());
+
+    // collect the default pipeline and the additional pipelines
+    let pipelines = init_pipelines(&args)?;
+    let pipeline_ids: Vec<PipelineId> = pipelines
+        .iter()
+        .map(|pipeline| pipeline.pipeline_id)
+        .collect();
+
+    // apply the tunable settings to the runtime state, and reload them on `SIGHUP` or the admin
+    // requests
+    apply_runtime_settings(&args, &pipelines, &runtime_state)?;
+    handles.push(watch_config_reload(&args, runtime_state.clone()));
+
+    // only serve the component of the subcommand if specified
+    if let Some(Command::Component(component)) = args.command {
+        args.serve_components = Some(component.names(&pipelines));
+    }
+
+    // connect the job queue shared by the replicas if enabled
+    let job_queue = init_job_queue(&args).await?;
+
+    // initialize the consumer of the shared job queue for the local pipelines
+    let job_queue_consumer = job_queue
+        .clone()
+        .map(|job_queue| init_job_queue_consumer(&args, job_queue, pipeline_ids.clone(), &metrics));
 
-    // initialize proof service
-    let (proof_service, proof_service_receiver) = init_proof_service(&args);
+    // initialize fetch service, only the reproducing requests are served in the reproduce mode
+    let (fetch_service, fetch_service_receiver) = init_fetch_service(
+        &args,
+        FetchServiceConfig::new(
+            args.fetch_service_addr,
+            pipeline_ids,
+            prove_latest_on_start(&args),
+            args.mode() == Mode::Reproduce,
+            job_queue,
+        ),
+        metrics.clone(),
+        pipeline_error_log.clone(),
+        runtime_state.clone(),
+        report_store.clone(),
+        proof_store.clone(),
+    );
 
-    // initialize fetcher implementation thread
-    let (fetcher, fetcher_endpoint) = init_fetcher(&args);
+    // initialize the ethproofs submitter of the reporter, it loads the proofs persisted in the
+    // proof store
+    let ethproofs_submitter = init_ethproofs_submitter(&args, proof_store.clone());
 
-    // initialize proving client thread
-    let (proving_client, proving_client_endpoint) = init_proving_client(&args);
+    // initialize proof service, the proof store is also kept for writing the proof of the `prove`
+    // subcommand
+    let prove_proof_store = proof_store.clone();
+    let (proof_service, proof_service_receiver) = init_proof_service(
+        &args,
+        metrics.clone(),
+        in_flight_blocks.clone(),
+        proof_store,
+    );
 
     // initialize reporter thread
-    let (reporter, reporter_sender) = init_reporter(&args);
+    let (reporter, reporter_sender) =
+        init_reporter(&args, &metrics, report_store, ethproofs_submitter.clone());
 
-    // initialize main scheduler
-    let scheduler = Arc::new(Scheduler::new(
+    // initialize pipeline error handler thread
+    let (error_handler, error_sender) = init_error_handler(metrics.clone(), pipeline_error_log);
+
+    // declare the subsystems with the message kinds they produce and consume
+    let mut subsystems = vec![];
+    subsystems.push(Subsystem::new("fetch-service").producing(
         fetch_service_receiver,
+        &[
+            BlockMsgKind::Fetch,
+            BlockMsgKind::Watch,
+            BlockMsgKind::Control,
+        ],
+    ));
+    subsystems.push(Subsystem::new("proof-service").producing(
         proof_service_receiver,
-        fetcher_endpoint,
-        proving_client_endpoint,
+        &[
+            BlockMsgKind::Proved,
+            BlockMsgKind::Status,
+            BlockMsgKind::Error,
+        ],
+    ));
+
+    let job_queue_consumer = job_queue_consumer.map(|(consumer, comm_receiver, comm_sender)| {
+        subsystems.push(
+            Subsystem::new("job-queue")
+                .producing(comm_receiver, &[BlockMsgKind::Fetch])
+                .consuming(comm_sender, &[BlockMsgKind::Report]),
+        );
+        consumer
+    });
+
+    // initialize the fetcher and proving-client threads of each pipeline
+    let mut pipeline_tasks: Vec<(String, TaskStarter)> = vec![];
+    for pipeline in &pipelines {
+        let pipeline_id = pipeline.pipeline_id;
+
+        // initialize fetcher implementation thread, the readiness check is only pending if the
+        // fetcher runs in this process
+        let fetcher_name = pipeline.component_name("fetcher");
+        let fetcher_readiness = ReadinessCheck::new(fetcher_name.clone(), runtime_state.clone());
+        if is_local_component(&args, &fetcher_name) {
+            fetcher_readiness.set_pending();
+        }
+        let (fetcher, fetcher_endpoint) =
+            init_fetcher(&args, pipeline, &metrics, fetcher_readiness);
+
+        // initialize proving client thread, the channel of a remote proving-client is replaced by
+        // the message bus
+        let proving_client_name = pipeline.component_name("proving-client");
+        let proving_client_endpoint = if is_local_component(&args, &proving_client_name) {
+            let proving_client_readiness =
+                ReadinessCheck::new(proving_client_name.clone(), runtime_state.clone());
+            proving_client_readiness.set_pending();
+            let (proving_client, proving_client_endpoint) = init_proving_client(
+                &args,
+                pipeline,
+                in_flight_blocks.clone(),
+                runtime_state.subscribe(),
+                proving_client_readiness,
+                &metrics,
+            );
+            pipeline_tasks.push((
+                proving_client_name.clone(),
+                Box::new(move || proving_client.clone().run()),
+            ));
+            proving_client_endpoint
+        } else {
+            DuplexBoundedChannel::new(args.channel_capacity).endpoint2()
+        };
+
+        subsystems.push(
+            Subsystem::new(fetcher_name.clone())
+                .in_pipeline(pipeline_id)
+                .producing(
+                    fetcher_endpoint.receiver(),
+                    &[
+                        BlockMsgKind::Proving,
+                        BlockMsgKind::Report,
+                        BlockMsgKind::Status,
+                        BlockMsgKind::Error,
+                    ],
+                )
+                .consuming(
+                    fetcher_endpoint.sender(),
+                    &[
+                        BlockMsgKind::Fetch,
+                        BlockMsgKind::Report,
+                        BlockMsgKind::Control,
+                        BlockMsgKind::Error,
+                    ],
+                ),
+        );
+        subsystems.push(
+            Subsystem::new(proving_client_name)
+                .in_pipeline(pipeline_id)
+                .producing(
+                    proving_client_endpoint.receiver(),
+                    &[
+                        BlockMsgKind::Report,
+                        BlockMsgKind::Status,
+                        BlockMsgKind::Error,
+                    ],
+                )
+                .consuming(
+                    proving_client_endpoint.sender(),
+                    &[
+                        BlockMsgKind::Proving,
+                        BlockMsgKind::Proved,
+                        BlockMsgKind::Control,
+                        BlockMsgKind::Status,
+                    ],
+                ),
+        );
+
+        if is_local_component(&args, &fetcher_name) {
+            pipeline_tasks.extend(
+                fetcher
+                    .tasks()
+                    .into_iter()
+                    .map(|(name, start)| (pipeline.component_name(name), start)),
+            );
+        }
+    }
+    subsystems.push(Subsystem::new("reporter").consuming(
         reporter_sender,
+        &[
+            BlockMsgKind::Watch,
+            BlockMsgKind::Report,
+            BlockMsgKind::Status,
+            BlockMsgKind::Error,
+        ],
     ));
 
-    // start scheduler
-    handles.push(scheduler.run());
+    // supervise the pipeline tasks for restarting them after a panic
+    let mut supervisor = Supervisor::new(args.max_task_restarts);
+
+    // connect the components run on different hosts by the message bus
+    let bus = open_message_bus(&args.message_bus_url).await?;
+    if args.serve_components.is_some() {
+        // serve the local components for the scheduler on another host
+        for subsystem in &subsystems {
+            if is_local_component(&args, subsystem.name()) {
+                subsystem.serve_remote(&bus).await?;
+            }
+        }
+    } else {
+        // register the subsystems, the remote ones are connected by the message bus
+        let mut routing_table = RoutingTable::default();
+        for subsystem in subsystems {
+            if is_local_component(&args, subsystem.name()) {
+                routing_table.register(subsystem);
+            } else {
+                routing_table.register(subsystem.connect_remote(&bus).await?);
+            }
+        }
+
+        // initialize main scheduler
+        let journal = args.message_journal_path.as_ref().map(|journal_path| {
+            MessageJournal::open(journal_path).unwrap_or_else(|e| {
+                panic!("eth-proofs: failed to open the message journal {journal_path:?}: {e}")
+            })
+        });
+        let scheduler = Arc::new(Scheduler::new(
+            routing_table,
+            error_sender,
+            metrics,
+            runtime_state.subscribe(),
+            journal,
+        ));
+
+        // start scheduler, no message is routed without it
+        supervisor.supervise_critical("scheduler", Box::new(move || scheduler.clone().run()));
+
+        // start the pipeline error handler thread
+        supervisor.supervise(
+            "pipeline-error-handler",
+            Box::new(move || error_handler.clone().run()),
+        );
+    }
 
     // start the reporter thread
-    handles.push(reporter.run());
+    if is_local_component(&args, "reporter") {
+        supervisor.supervise("reporter", Box::new(move || reporter.clone().run()));
+    }
 
-    // start the proving-client thread
-    handles.push(proving_client.run());
+    // start the ethproofs submitter sending the statuses queued by the reporter
+    if let Some(ethproofs_submitter) = ethproofs_submitter {
+        supervisor.supervise(
+            "ethproofs-submitter",
+            Box::new(move || ethproofs_submitter.clone().run()),
+        );
+    }
+
+    // start the proving-client and fetcher threads of each pipeline
+    supervisor.supervise_all(pipeline_tasks);
 
-    // start the fetcher thread
-    handles.extend(fetcher.run());
+    // start claiming the shared jobs for the local pipelines
+    if let Some(job_queue_consumer) = job_queue_consumer
+        && is_local_component(&args, "job-queue")
+    {
+        supervisor.supervise(
+            "job-queue",
+            Box::new(move || job_queue_consumer.clone().run()),
+        );
+    }
 
     // start the proof-service
-    handles.push(proof_service.run());
+    if is_local_component(&args, "proof-service") {
+        supervisor.supervise(
+            "proof-service",
+            Box::new(move || proof_service.clone().run()),
+        );
+    }
 
-    // start the fetch-service
-    handles.push(fetch_service.run());
+    // keep the fetch-service for pausing the pipelines on shutdown if it runs in this process
+    let shutdown_fetch_service =
+        is_local_component(&args, "fetch-service").then(|| fetch_service.clone());
 
-    // wait for the all threads exit
-    join_all(handles).await;
+    // start the fetch-service, or prove the block of the `prove` subcommand by the fetch-service
+    // without listening
+    match args.command.clone() {
+        Some(Command::Prove(prove_args)) => handles.push(run_prove(
+            prove_args,
+            fetch_service,
+            prove_proof_store,
+            runtime_state.subscribe(),
+        )),
+        _ if is_local_component(&args, "fetch-service") => {
+            supervisor.supervise(
+                "fetch-service",
+                Box::new(move || fetch_service.clone().run()),
+            );
+        }
+        _ => {}
+    }
+
+    handles.extend(supervisor.run());
+
+    // notify systemd once the components are initialized, the fetch-service notifies after
+    // listening if it runs in this process
+    if !is_local_component(&args, "fetch-service") {
+        handles.push(notify_ready(runtime_state.subscribe()));
+    }
+
+    // wait for the all threads exit, or the ordered shutdown on `Ctrl+C` or `SIGTERM`
+    select! {
+        _ = join_all(handles) => {}
+        () = shutdown(
+            Duration::from_secs(args.shutdown_drain_seconds),
+            shutdown_fetch_service,
+            in_flight_blocks,
+        ) => {}
+    }
+
+    Ok(())
+}
+
+// collect the build info embedded at compile time with the digests of the ELFs in use
+fn build_info(args: &Args) -> BuildInfo {
+    let elf_digest = |elf_path: &PathBuf| {
+        file_digest(elf_path)
+            .inspect_err(|e| warn!("eth-proofs: failed to digest the ELF {elf_path:?} {e:?}"))
+            .ok()
+    };
+    let git_commit = if env!("VERGEN_GIT_DIRTY") == "true" {
+        format!("{}-dirty", env!("VERGEN_GIT_SHA"))
+    } else {
+        env!("VERGEN_GIT_SHA").to_string()
+    };
+
+    BuildInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        git_commit,
+        build_timestamp: env!("VERGEN_BUILD_TIMESTAMP").to_string(),
+        pico_sdk_version: env!("PICO_SDK_VERSION").to_string(),
+        rsp_version: env!("RSP_VERSION").to_string(),
+        subblock_elf_digest: elf_digest(&args.subblock_elf_path),
+        agg_elf_digest: elf_digest(&args.agg_elf_path),
+    }
+}
+
+// identify if a component runs in this process, only the served components run if serving for the
+// scheduler on another host, otherwise the all components except the remote ones run
+fn is_local_component(args: &Args, name: &str) -> bool {
+    let contains = |components: &Vec<String>| components.iter().any(|component| component == name);
+    match &args.serve_components {
+        Some(serve_components) => contains(serve_components),
+        None => !args.remote_components.as_ref().is_some_and(contains),
+    }
+}
+
+// apply the tunable settings of the arguments and pipelines to the runtime state, the current log
+// filter is kept if not specified
+fn apply_runtime_settings(
+    args: &Args,
+    pipelines: &[Pipeline],
+    runtime_state: &WatchChannel<RuntimeState>,
+) -> Result<()> {
+    if let Some(log_filter) = &args.log_filter {
+        check_log_filter(log_filter)?;
+    }
+    let proving_urls = pipelines
+        .iter()
+        .filter_map(|pipeline| {
+            // the subblock urls of a discovered prover service are replaced by the proving-client
+            let agg_url = pipeline.proving_agg_url.as_ref()?;
+            let subblock_urls = match &pipeline.proving_subblock_service {
+                Some(_) => Vec::new(),
+                None => pipeline.proving_subblock_urls.as_ref()?.clone(),
+            };
+            let urls = ProvingUrls::new(
+                agg_url.to_string(),
+                subblock_urls.iter().map(Url::to_string).collect(),
+            );
+            Some((pipeline.pipeline_id, urls))
+        })
+        .collect();
+
+    runtime_state.send_modify(|state| {
+        state.max_in_flight_blocks = args.max_in_flight_blocks;
+        if args.log_filter.is_some() {
+            state.log_filter = args.log_filter.clone();
+        }
+        state.proving_timeout_seconds = Some(args.proving_timeout_seconds);
+        state.proving_urls = proving_urls;
+    });
 
     Ok(())
 }
 
+// reload the cli arguments merged with the config file on `SIGHUP` or the `reload_config` admin
+// requests and apply the tunable settings to the runtime state, the urls of the mock proving
+// service are kept
+fn watch_config_reload(args: &Args, runtime_state: WatchChannel<RuntimeState>) -> JoinHandle<()> {
+    let mock_proving_urls = args.is_mock_proving.then(|| {
+        (
+            args.proving_agg_url.clone(),
+            args.proving_subblock_urls.clone(),
+        )
+    });
+
+    spawn(async move {
+        let mut hangup = signal(SignalKind::hangup())
+            .unwrap_or_else(|e| panic!("eth-proofs: failed to listen for SIGHUP: {e}"));
+        loop {
+            select! {
+                Some(()) = hangup.recv() => info!("eth-proofs: reload the config file on SIGHUP"),
+                () = config_reload_requested() => {
+                    info!("eth-proofs: reload the config file on the admin request");
+                }
+            }
+
+            let result = try_parse_args::<Args>().and_then(|mut args| {
+                if let Some((agg_url, subblock_urls)) = mock_proving_urls.clone() {
+                    args.proving_agg_url = agg_url;
+                    args.proving_subblock_urls = subblock_urls;
+                }
+                let pipelines = init_pipelines(&args)?;
+                apply_runtime_settings(&args, &pipelines, &runtime_state)
+            });
+            match result {
+                Ok(()) => info!("eth-proofs: applied the reloaded settings"),
+                Err(e) => error!("eth-proofs: failed to reload the config file {e:?}"),
+            }
+        }
+    })
+}
+
+// wait for the all readiness checks passed and notify systemd
+fn notify_ready(mut runtime_state: WatchReceiver<RuntimeState>) -> JoinHandle<()> {
+    spawn(async move {
+        wait_ready(&mut runtime_state).await;
+        info!("eth-proofs: passed the all readiness checks");
+        if let Err(e) = notify_systemd_ready() {
+            error!("eth-proofs: failed to notify systemd of the readiness {e:?}");
+        }
+    })
+}
+
 // initialize mock proving service
 fn init_mock_proving_service(args: &mut Args) -> Arc<MockProvingService> {
+    // load the scenario of the per-block behaviors if specified
+    let scenario = args.mock_scenario_path.as_deref().map(|file_path| {
+        MockScenario::load(file_path).unwrap_or_else(|e| {
+            panic!("eth-proofs: failed to load the mock scenario {file_path:?}: {e}")
+        })
+    });
+
+    // create the emulator of the received inputs if enabled
+    let emulator = args.mock_emulation.then(|| {
+        let emulator = MockEmulator::new(&args.subblock_elf_path, &args.agg_elf_path)
+            .unwrap_or_else(|e| panic!("eth-proofs: failed to create the mock emulator: {e}"));
+        Arc::new(emulator)
+    });
+
     // create mock proving service
-    let config = MockProvingServiceConfig::new(args.max_grpc_msg_bytes, &args.proof_service_addr);
-    let service = MockProvingService::new(config);
+    let behavior = MockProvingBehavior::new(
+        Duration::from_millis(args.mock_proving_milliseconds),
+        args.mock_success_rate,
+        args.mock_failed_blocks.iter().copied().collect(),
+        args.mock_replay_proof_dir.clone(),
+        scenario,
+        emulator,
+    );
+    let config = MockProvingServiceConfig::new(
+        args.max_grpc_msg_bytes,
+        args.mock_num_subblocks.unwrap_or(args.max_num_subblocks),
+        args.mock_bind_ip,
+        args.mock_base_port,
+        &args.proof_service_addr,
+        args.proof_service_token.clone(),
+        behavior,
+    );
+    let recorder =
+        RequestRecorder::new(args.mock_request_log_path.as_deref()).unwrap_or_else(|e| {
+            panic!(
+                "eth-proofs: failed to open the mock request log {:?}: {e}",
+                args.mock_request_log_path,
+            )
+        });
+    let service = MockProvingService::new(config, recorder);
 
     // reset the mock proving urls to the arguments
     args.proving_agg_url = Some(service.aggregator_url());
@@ -187,78 +1371,399 @@ fn init_mock_proving_service(args: &mut Args) -> Arc<MockProvingService> {
     service.into()
 }
 
+// initialize the default pipeline by the cli arguments and the additional pipelines by the
+// pipelines config file
+fn init_pipelines(args: &Args) -> Result<Vec<Pipeline>> {
+    let mut pipelines = vec![Pipeline {
+        pipeline_id: DEFAULT_PIPELINE_ID,
+        chain_id: args.chain_id,
+        rpc_http_url: args.rpc_http_url.clone(),
+        rpc_ws_url: args.rpc_ws_url.clone(),
+        witness_rpc_url: args.witness_rpc_url.clone(),
+        rpc_fixture_dir: args.rpc_fixture_dir.clone(),
+        input_dump_dir: args.input_dump_dir.clone(),
+        input_load_dir: args.input_load_dir.clone(),
+        backfill_checkpoint_path: args.backfill_checkpoint_path.clone(),
+        proving_agg_url: args.proving_agg_url.clone(),
+        proving_subblock_urls: args.proving_subblock_urls.clone(),
+        proving_subblock_service: args.proving_subblock_service.clone(),
+        proving_nats_url: args.proving_nats_url.clone(),
+        docker_control_path: args.docker_control_path.clone(),
+    }];
+
+    if let Some(config_path) = &args.pipelines_config_path {
+        for config in load_pipeline_configs(config_path)? {
+            pipelines
+                .push(config.into_pipeline(&args.rpc_fixture_dir, &args.backfill_checkpoint_path)?);
+        }
+    }
+
+    Ok(pipelines)
+}
+
 // initialize fetch-service
-fn init_fetch_service(args: &Args) -> (Arc<FetchService>, Arc<Mutex<BlockMsgReceiver>>) {
+fn init_fetch_service(
+    args: &Args,
+    config: FetchServiceConfig,
+    metrics: Arc<MetricsRegistry>,
+    pipeline_error_log: Arc<PipelineErrorLog>,
+    runtime_state: WatchChannel<RuntimeState>,
+    report_store: Arc<dyn ReportStore>,
+    proof_store: Option<Arc<ProofStore>>,
+) -> (Arc<FetchService>, Arc<Mutex<BlockMsgReceiver>>) {
     // create communication channel
-    let comm_channel = SingleUnboundedChannel::default();
+    let comm_channel = SingleUnboundedChannel::instrumented(channel_metrics(
+        args,
+        &metrics,
+        "fetch-service-outbox",
+    ));
 
     // create fetch service
-    let config = FetchServiceConfig::new(args.fetch_service_addr);
-    let service = FetchService::new(config, comm_channel.sender()).into();
+    let service = FetchService::new(
+        config,
+        comm_channel.sender(),
+        metrics,
+        pipeline_error_log,
+        runtime_state,
+        report_store,
+        proof_store,
+    )
+    .into();
 
     (service, comm_channel.receiver())
 }
 
+// return the number of the latest blocks proved once the fetch service is ready
+fn prove_latest_on_start(args: &Args) -> Option<u64> {
+    if args.follow_on_start {
+        Some(FOLLOW_LATEST_COUNT)
+    } else {
+        args.prove_latest_on_start
+    }
+}
+
+// connect the job queue shared by the replicas if enabled, it's not used by the `prove` subcommand
+// since the block must be proved by this process, nor in the reproduce mode since the jobs of the
+// other replicas can't be fetched without the rpc node
+async fn init_job_queue(args: &Args) -> Result<Option<Arc<RedisJobQueue>>> {
+    let Some(url) = &args.job_queue_url else {
+        return Ok(None);
+    };
+    if matches!(args.command, Some(Command::Prove(_))) || args.mode() == Mode::Reproduce {
+        return Ok(None);
+    }
+
+    let consumer = args.job_queue_consumer.clone().unwrap_or_else(|| {
+        env::var("HOSTNAME").unwrap_or_else(|_| format!("eth-proofs-{}", process::id()))
+    });
+    let config = JobQueueConfig::new(
+        url.clone(),
+        args.job_queue_stream.clone(),
+        args.job_queue_group.clone(),
+        consumer,
+        args.job_queue_max_claims,
+        Duration::from_secs(args.job_queue_claim_timeout_seconds),
+    );
+    info!(
+        "eth-proofs: sharing the jobs by stream {} of group {} as consumer {}",
+        config.stream, config.group, config.consumer,
+    );
+    let job_queue = RedisJobQueue::connect(config).await?;
+
+    Ok(Some(Arc::new(job_queue)))
+}
+
+// initialize the consumer of the shared job queue, it returns the consumer with the receiver of
+// the claimed jobs and the sender of the block reports
+fn init_job_queue_consumer(
+    args: &Args,
+    job_queue: Arc<RedisJobQueue>,
+    pipeline_ids: Vec<PipelineId>,
+    metrics: &Arc<MetricsRegistry>,
+) -> (
+    Arc<JobQueueConsumer>,
+    Arc<Mutex<BlockMsgReceiver>>,
+    Arc<BlockMsgSender>,
+) {
+    // create the communication channels of the claimed jobs and the block reports
+    let outbox =
+        SingleUnboundedChannel::instrumented(channel_metrics(args, metrics, "job-queue-outbox"));
+    let inbox =
+        SingleUnboundedChannel::instrumented(channel_metrics(args, metrics, "job-queue-inbox"));
+
+    let consumer =
+        JobQueueConsumer::new(job_queue, pipeline_ids, outbox.sender(), inbox.receiver()).into();
+
+    (consumer, outbox.receiver(), inbox.sender())
+}
+
+// open the proof store if the proofs are persisted
+fn init_proof_store(args: &Args) -> Option<Arc<ProofStore>> {
+    args.proof_store_dir.as_ref().map(|location| {
+        let proof_store = ProofStore::open(location).unwrap_or_else(|e| {
+            panic!("eth-proofs: failed to open the proof store {location:?}: {e}")
+        });
+        Arc::new(proof_store)
+    })
+}
+
 // initialize proof-service
-fn init_proof_service(args: &Args) -> (ProofService, Arc<Mutex<BlockMsgReceiver>>) {
+fn init_proof_service(
+    args: &Args,
+    metrics: Arc<MetricsRegistry>,
+    in_flight_blocks: Arc<InFlightBlocks>,
+    proof_store: Option<Arc<ProofStore>>,
+) -> (ProofService, Arc<Mutex<BlockMsgReceiver>>) {
     // create communication channel
-    let comm_channel = SingleUnboundedChannel::default();
+    let comm_channel = SingleUnboundedChannel::instrumented(channel_metrics(
+        args,
+        &metrics,
+        "proof-service-outbox",
+    ));
 
     // create proof service
-    let config = ProofServiceConfig::new(args.proof_service_addr, args.max_grpc_msg_bytes);
-    let service = ProofService::new(config, comm_channel.sender());
+    let config = ProofServiceConfig::new(
+        args.proof_service_addr,
+        args.max_grpc_msg_bytes,
+        args.max_proof_bytes,
+        args.proof_service_token.clone(),
+        args.proof_service_tls_cert_path
+            .clone()
+            .zip(args.proof_service_tls_key_path.clone())
+            .map(|(cert_path, key_path)| ProofServiceTls::new(cert_path, key_path)),
+    );
+    let verifier = args.verify_proofs.then(|| {
+        let verifier = ProofVerifier::new(&args.agg_elf_path).unwrap_or_else(|e| {
+            panic!(
+                "eth-proofs: failed to create the proof verifier by {:?}: {e}",
+                args.agg_elf_path,
+            )
+        });
+        Arc::new(verifier)
+    });
+    let quarantine_store = args.proof_quarantine_dir.as_ref().map(|location| {
+        let quarantine_store = QuarantineStore::open(location).unwrap_or_else(|e| {
+            panic!("eth-proofs: failed to open the quarantine store {location:?}: {e}")
+        });
+        Arc::new(quarantine_store)
+    });
+    let service = ProofService::new(
+        config,
+        comm_channel.sender(),
+        verifier,
+        proof_store,
+        quarantine_store,
+        in_flight_blocks,
+        metrics,
+    );
 
     (service, comm_channel.receiver())
 }
 
 // initialize fetcher implementation thread
-fn init_fetcher(args: &Args) -> (Arc<BlockFetcher>, Arc<BlockMsgEndpoint>) {
-    // create communication channel
-    let comm_channel = DuplexUnboundedChannel::default();
+fn init_fetcher(
+    args: &Args,
+    pipeline: &Pipeline,
+    metrics: &Arc<MetricsRegistry>,
+    readiness: ReadinessCheck,
+) -> (Arc<BlockFetcher>, Arc<BlockMsgEndpoint>) {
+    // create communication channel, endpoint1 is of the fetcher
+    let name = pipeline.component_name("fetcher");
+    let comm_channel = DuplexUnboundedChannel::instrumented(
+        channel_metrics(args, metrics, &format!("{name}-outbox")),
+        channel_metrics(args, metrics, &format!("{name}-inbox")),
+    );
 
-    // create fetcher instance
+    // create the layout of the input files of the pipeline chain
+    let input_layout = InputLayout::new(
+        args.input_layout.clone(),
+        pipeline.chain_id,
+        args.subblock_gas_threshold,
+    )
+    .unwrap_or_else(|e| panic!("eth-proofs: invalid input layout: {e}"));
+
+    // create fetcher instance, the rpc node is not connected in the reproduce mode so the rpc
+    // fetchers are not started for any pipeline
+    let (rpc_http_url, rpc_ws_url) = match args.mode() {
+        Mode::Prove => (pipeline.rpc_http_url.clone(), pipeline.rpc_ws_url.clone()),
+        Mode::Reproduce => (None, None),
+    };
     let config = BlockFetcherConfig::new(
         args.is_input_emulated,
-        args.input_dump_dir.clone(),
-        args.input_load_dir.clone(),
-        args.rpc_http_url.clone(),
-        args.rpc_ws_url.clone(),
+        pipeline.input_dump_dir.clone(),
+        DumpRetentionPolicy::new(
+            args.dump_retention_max_age_seconds,
+            args.dump_retention_max_total_bytes,
+            args.dump_retention_keep_latest_count,
+        ),
+        pipeline.input_load_dir.clone(),
+        args.input_load_mmap,
+        input_layout,
+        rpc_http_url,
+        rpc_ws_url,
+        pipeline.witness_rpc_url.clone(),
+        args.rpc_fixture_mode,
+        pipeline.rpc_fixture_dir.clone(),
         args.subblock_elf_path.clone(),
         args.agg_elf_path.clone(),
+        args.block_cache_dir.clone(),
+        args.prefetch_block_count,
+        Duration::from_secs(args.prefetch_permit_timeout_seconds),
+        args.max_blocks_per_batch,
+        args.confirmations,
+        args.max_proving_queue_depth,
+        args.max_input_bytes.unwrap_or(args.max_grpc_msg_bytes),
+        args.max_num_subblocks,
+        pipeline.backfill_checkpoint_path.clone(),
+        pipeline.chain_id,
     )
     .into();
-    let fetcher = BlockFetcher::new(config, comm_channel.endpoint1());
+    let fetcher = BlockFetcher::new(config, comm_channel.endpoint1(), readiness);
 
     (fetcher, comm_channel.endpoint2())
 }
 
 // initialize proving-client thread
-fn init_proving_client(args: &Args) -> (Arc<ProvingClient>, Arc<BlockMsgEndpoint>) {
-    // create communication channel
-    let comm_channel = DuplexUnboundedChannel::default();
+fn init_proving_client(
+    args: &Args,
+    pipeline: &Pipeline,
+    in_flight_blocks: Arc<InFlightBlocks>,
+    runtime_state: WatchReceiver<RuntimeState>,
+    readiness: ReadinessCheck,
+    metrics: &Arc<MetricsRegistry>,
+) -> (Arc<ProvingClient>, Arc<BoundedBlockMsgEndpoint>) {
+    // create bounded communication channel, endpoint1 is of the proving-client
+    let name = pipeline.component_name("proving-client");
+    let comm_channel = DuplexBoundedChannel::instrumented(
+        args.channel_capacity,
+        channel_metrics(args, metrics, &format!("{name}-outbox")),
+        channel_metrics(args, metrics, &format!("{name}-inbox")),
+    );
 
     // create proving-client instance
+    let has_proving_urls = pipeline.proving_agg_url.is_some()
+        && (pipeline.proving_subblock_urls.is_some()
+            || pipeline.proving_subblock_service.is_some());
+    assert!(
+        has_proving_urls || pipeline.proving_nats_url.is_some(),
+        "eth-proofs: must set the proving urls, `proving_nats_url` or enable `is_mock_proving`",
+    );
     let config = ProvingClientConfig::new(
+        pipeline.pipeline_id,
         args.max_grpc_msg_bytes,
-        args.proving_agg_url
-            .clone()
-            .expect("eth-proofs: must set `proving_agg_url` or enable `is_mock_proving`"),
-        args.proving_subblock_urls
-            .clone()
-            .expect("eth-proofs: must set `proving_subblock_urls` or enable `is_mock_proving`"),
+        pipeline.proving_agg_url.clone(),
+        pipeline.proving_subblock_urls.clone().unwrap_or_default(),
+        pipeline.proving_subblock_service.clone().map(|service| {
+            SubblockDiscovery::new(
+                service,
+                Duration::from_secs(args.proving_discovery_interval_seconds),
+            )
+        }),
+        pipeline.proving_nats_url.clone().map(|url| {
+            NatsDispatchConfig::new(
+                url,
+                args.proving_nats_stream.clone(),
+                args.proving_nats_subject_prefix.clone(),
+            )
+        }),
+        Duration::from_secs(args.proving_timeout_seconds),
+        args.max_proving_request_retries,
+        Duration::from_secs(args.proving_request_retry_seconds),
     );
-    let proving_client = ProvingClient::new(config, comm_channel.endpoint1()).into();
+    let docker_control = pipeline.docker_control_path.as_ref().map(|config_path| {
+        load_docker_control_config(config_path)
+            .and_then(DockerControl::connect)
+            .map(Arc::new)
+            .unwrap_or_else(|e| {
+                panic!("eth-proofs: failed to initialize docker control by {config_path:?}: {e}")
+            })
+    });
+    let proving_client = ProvingClient::new(
+        config,
+        comm_channel.endpoint1(),
+        in_flight_blocks,
+        runtime_state,
+        readiness,
+        docker_control,
+    )
+    .into();
 
     (proving_client, comm_channel.endpoint2())
 }
 
 // initialize reporter thread
-fn init_reporter(_args: &Args) -> (Arc<BlockReporter>, Arc<BlockMsgSender>) {
+fn init_reporter(
+    args: &Args,
+    metrics: &Arc<MetricsRegistry>,
+    report_store: Arc<dyn ReportStore>,
+    ethproofs_submitter: Option<Arc<EthproofsSubmitter>>,
+) -> (Arc<BlockReporter>, Arc<BoundedBlockMsgSender>) {
+    // create bounded communication channel
+    let comm_channel = SingleBoundedChannel::instrumented(
+        args.channel_capacity,
+        channel_metrics(args, metrics, "reporter-inbox"),
+    );
+
+    // create reporter instance, the watchers are broadcast by the same capacity
+    let reporter = BlockReporter::new(
+        comm_channel.receiver(),
+        BroadcastChannel::new(args.channel_capacity),
+        args.report_ndjson_path.clone(),
+        report_store,
+        (args.report_summary_interval_seconds > 0)
+            .then(|| Duration::from_secs(args.report_summary_interval_seconds)),
+        ethproofs_submitter,
+    )
+    .into();
+
+    (reporter, comm_channel.sender())
+}
+
+// initialize the ethproofs submitter if configured and the reporter runs in this process
+fn init_ethproofs_submitter(
+    args: &Args,
+    proof_store: Option<Arc<ProofStore>>,
+) -> Option<Arc<EthproofsSubmitter>> {
+    let config_path = args.ethproofs_config_path.as_ref()?;
+    if !is_local_component(args, "reporter") {
+        return None;
+    }
+
+    let api_key = args
+        .ethproofs_api_key
+        .clone()
+        .expect("eth-proofs: `ethproofs_api_key` is required by `ethproofs_config_path`");
+    let submitter = load_ethproofs_config(config_path)
+        .and_then(|config| EthproofsSubmitter::open(config, api_key, proof_store))
+        .unwrap_or_else(|e| {
+            panic!("eth-proofs: failed to initialize ethproofs submitter by {config_path:?}: {e}")
+        });
+
+    Some(Arc::new(submitter))
+}
+
+// return the metrics of a named channel between a component and the scheduler if the channels are
+// instrumented, the channel is named by the component and the direction, e.g. `reporter-inbox`
+fn channel_metrics(
+    args: &Args,
+    metrics: &Arc<MetricsRegistry>,
+    name: &str,
+) -> Option<Arc<ChannelMetrics>> {
+    args.channel_metrics
+        .then(|| Arc::new(ChannelMetrics::new(name, metrics.clone())))
+}
+
+// initialize pipeline error handler thread
+fn init_error_handler(
+    metrics: Arc<MetricsRegistry>,
+    pipeline_error_log: Arc<PipelineErrorLog>,
+) -> (Arc<PipelineErrorHandler>, Arc<PipelineErrorSender>) {
     // create communication channel
     let comm_channel = SingleUnboundedChannel::default();
 
-    // create reporter instance
-    let reporter = BlockReporter::new(comm_channel.receiver()).into();
+    // create pipeline error handler instance
+    let error_handler =
+        PipelineErrorHandler::new(comm_channel.receiver(), pipeline_error_log, metrics).into();
 
-    (reporter, comm_channel.sender())
+    (error_handler, comm_channel.sender())
 }
@@ -0,0 +1,98 @@
+use crate::{Args, Command, Component, is_local_component, profile::Profile};
+use clap::{CommandFactory, error::ErrorKind};
+use fetcher::rpc_fixture::RpcFixtureMode;
+use std::fmt::Display;
+
+// mode of the service deciding the started fetchers and the served requests, the flags of each
+// mode are checked by clap when parsing
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    // prove the blocks fetched from the RPC node, the dumped inputs could also be reproduced if
+    // `input_load_dir` is specified
+    Prove,
+
+    // reproduce the blocks by loading the dumped input files of `input_load_dir` without the RPC
+    // node, the inputs are not generated
+    Reproduce,
+}
+
+impl Args {
+    // return the mode selected by `--reproduce`
+    pub fn mode(&self) -> Mode {
+        if self.reproduce {
+            Mode::Reproduce
+        } else {
+            Mode::Prove
+        }
+    }
+
+    // check the flag set of the mode and the components run in this process which clap can't
+    // express, the nonsensical combinations are returned as the usage errors like the cli parsing
+    pub fn validate(&self) -> Result<(), clap::Error> {
+        // the `prove` subcommand generates the input of its block
+        if self.mode() == Mode::Reproduce && matches!(self.command, Some(Command::Prove(_))) {
+            return Err(usage_error(
+                ErrorKind::ArgumentConflict,
+                "the `prove` subcommand can't be used with `--reproduce` since the inputs are loaded instead of generated",
+            ));
+        }
+
+        // the recorded responses are flushed to the fixture file of each block, so the blocks are
+        // fetched one by one to keep the responses of a block in its own file
+        if self.rpc_fixture_mode == Some(RpcFixtureMode::Record) && self.prefetch_block_count > 1 {
+            return Err(usage_error(
+                ErrorKind::ArgumentConflict,
+                "`--rpc-fixture-mode record` requires `--prefetch-block-count 1` to record the responses of each block separately",
+            ));
+        }
+
+        if self.profile == Some(Profile::Prod) && self.is_mock_proving {
+            return Err(usage_error(
+                ErrorKind::ArgumentConflict,
+                "`--is-mock-proving` can't be used with `--profile prod`, use `--profile dev` for testing",
+            ));
+        }
+
+        // the proving cluster is only required by a proving-client of the default pipeline run in
+        // this process, the mock proving service or the NATS dispatch replaces it
+        if !self.is_mock_proving && self.proving_nats_url.is_none() && self.runs_proving_client() {
+            let missing_flags: Vec<&str> = [
+                (
+                    "--proving-agg-url <PROVING_AGG_URL>",
+                    self.proving_agg_url.is_none(),
+                ),
+                (
+                    "--proving-subblock-urls <PROVING_SUBBLOCK_URLS>` or `--proving-subblock-service <PROVING_SUBBLOCK_SERVICE>",
+                    self.proving_subblock_urls.is_none() && self.proving_subblock_service.is_none(),
+                ),
+            ]
+            .into_iter()
+            .filter_map(|(flag, is_missing)| is_missing.then_some(flag))
+            .collect();
+            if !missing_flags.is_empty() {
+                return Err(usage_error(
+                    ErrorKind::MissingRequiredArgument,
+                    format!(
+                        "the proving-client requires `{}` of the proving cluster, `--proving-nats-url` of the prover workers, or `--is-mock-proving` for testing",
+                        missing_flags.join("` and `"),
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    // identify if the proving-client of the default pipeline runs in this process
+    fn runs_proving_client(&self) -> bool {
+        match self.command {
+            Some(Command::Component(component)) => component == Component::ProvingClient,
+            _ => is_local_component(self, "proving-client"),
+        }
+    }
+}
+
+// create a usage error of the cli arguments
+fn usage_error(kind: ErrorKind, message: impl Display) -> clap::Error {
+    Args::command().error(kind, message)
+}
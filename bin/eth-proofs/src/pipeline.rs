@@ -0,0 +1,207 @@
+use anyhow::{Result, ensure};
+use common::{
+    ids::DEFAULT_CHAIN_ID,
+    utils::{DEFAULT_PIPELINE_ID, PipelineId},
+};
+use reqwest::Url;
+use serde::Deserialize;
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+// pipeline of a fetcher and a proving-client, the default pipeline is configured by the cli
+// arguments and the additional pipelines are loaded from the pipelines config file
+#[derive(Debug)]
+pub struct Pipeline {
+    // pipeline id
+    pub pipeline_id: PipelineId,
+
+    // chain id of the blocks fetched by the pipeline
+    pub chain_id: u64,
+
+    // RPC node HTTP URL, none in the reproduce mode
+    pub rpc_http_url: Option<Url>,
+
+    // RPC node websocket URL, none in the reproduce mode
+    pub rpc_ws_url: Option<Url>,
+
+    // RPC node HTTP URL for fetching the block execution witnesses
+    pub witness_rpc_url: Option<Url>,
+
+    // directory of the RPC fixture files
+    pub rpc_fixture_dir: PathBuf,
+
+    // base directory or object store URL for saving input files
+    pub input_dump_dir: Option<PathBuf>,
+
+    // base directory or object store URL for reproducing blocks by loading input files
+    pub input_load_dir: Option<PathBuf>,
+
+    // checkpoint file path for resuming the backfill campaign
+    pub backfill_checkpoint_path: PathBuf,
+
+    // aggregator proving grpc url
+    pub proving_agg_url: Option<Url>,
+
+    // subblock proving grpc urls
+    pub proving_subblock_urls: Option<Vec<Url>>,
+
+    // headless service of the subblock provers discovered by DNS, it replaces the subblock urls
+    pub proving_subblock_service: Option<String>,
+
+    // NATS server URL of publishing the proving inputs, it replaces the proving urls
+    pub proving_nats_url: Option<String>,
+
+    // docker control config file of the prover containers restarted on a proving timeout
+    pub docker_control_path: Option<PathBuf>,
+}
+
+impl Pipeline {
+    // return the name of a pipeline component, the default pipeline keeps the plain name
+    pub fn component_name(&self, component: &str) -> String {
+        if self.pipeline_id == DEFAULT_PIPELINE_ID {
+            component.to_string()
+        } else {
+            format!("{component}-{}", self.pipeline_id)
+        }
+    }
+}
+
+// config of an additional pipeline in the pipelines config file, the settings not listed here are
+// shared with the default pipeline
+#[derive(Debug, Deserialize)]
+pub struct PipelineConfig {
+    // pipeline id, it must be unique and non-zero since zero is the default pipeline
+    pub pipeline_id: PipelineId,
+
+    // chain id of the blocks fetched by the pipeline; the Ethereum mainnet if not specified
+    pub chain_id: Option<u64>,
+
+    // RPC node HTTP URL
+    pub rpc_http_url: String,
+
+    // RPC node websocket URL
+    pub rpc_ws_url: String,
+
+    // RPC node HTTP URL for fetching the block execution witnesses; `rpc_http_url` is used if not
+    // specified
+    pub witness_rpc_url: Option<String>,
+
+    // base directory or object store URL for saving input files; nothing will be saved if not
+    // specified
+    pub input_dump_dir: Option<PathBuf>,
+
+    // base directory or object store URL for reproducing blocks by loading input files
+    pub input_load_dir: Option<PathBuf>,
+
+    // aggregator proving grpc url of the cluster, it's required unless `proving_nats_url` is
+    // specified
+    pub proving_agg_url: Option<String>,
+
+    // subblock proving grpc urls of the cluster, either it or `proving_subblock_service` is
+    // required with `proving_agg_url`
+    pub proving_subblock_urls: Option<Vec<String>>,
+
+    // headless kubernetes service `<host>:<port>` of the subblock provers of the cluster, the pods
+    // are discovered by DNS
+    pub proving_subblock_service: Option<String>,
+
+    // NATS server URL of publishing the proving inputs to the JetStream subjects of the pipeline
+    // instead of the grpc services of the cluster
+    pub proving_nats_url: Option<String>,
+
+    // docker control config file of the prover containers of the cluster; the containers are not
+    // restarted on a proving timeout if not specified
+    pub docker_control_path: Option<PathBuf>,
+}
+
+impl PipelineConfig {
+    // convert to a pipeline, the files shared with the default pipeline are suffixed by the
+    // pipeline id to avoid conflicts
+    pub fn into_pipeline(
+        self,
+        rpc_fixture_dir: &Path,
+        backfill_checkpoint_path: &Path,
+    ) -> Result<Pipeline> {
+        let pipeline_id = self.pipeline_id;
+        if self.proving_nats_url.is_some() {
+            ensure!(
+                self.proving_agg_url.is_none()
+                    && self.proving_subblock_urls.is_none()
+                    && self.proving_subblock_service.is_none(),
+                "the proving urls can't be configured with `proving_nats_url` for pipeline {pipeline_id}",
+            );
+        } else {
+            ensure!(
+                self.proving_agg_url.is_some(),
+                "either `proving_agg_url` or `proving_nats_url` must be configured for pipeline {pipeline_id}",
+            );
+            ensure!(
+                self.proving_subblock_urls.is_some() != self.proving_subblock_service.is_some(),
+                "either `proving_subblock_urls` or `proving_subblock_service` must be configured for pipeline {pipeline_id}",
+            );
+        }
+
+        Ok(Pipeline {
+            pipeline_id,
+            chain_id: self.chain_id.unwrap_or(DEFAULT_CHAIN_ID),
+            rpc_http_url: Some(Url::parse(&self.rpc_http_url)?),
+            rpc_ws_url: Some(Url::parse(&self.rpc_ws_url)?),
+            witness_rpc_url: self
+                .witness_rpc_url
+                .map(|url| Url::parse(&url))
+                .transpose()?,
+            rpc_fixture_dir: rpc_fixture_dir.join(pipeline_id.to_string()),
+            input_dump_dir: self.input_dump_dir,
+            input_load_dir: self.input_load_dir,
+            backfill_checkpoint_path: suffix_file_path(backfill_checkpoint_path, pipeline_id),
+            proving_agg_url: self
+                .proving_agg_url
+                .map(|url| Url::parse(&url))
+                .transpose()?,
+            proving_subblock_urls: self
+                .proving_subblock_urls
+                .map(|urls| urls.iter().map(|url| Url::parse(url)).collect())
+                .transpose()?,
+            proving_subblock_service: self.proving_subblock_service,
+            proving_nats_url: self.proving_nats_url,
+            docker_control_path: self.docker_control_path,
+        })
+    }
+}
+
+// load the additional pipeline configs from a json file
+pub fn load_pipeline_configs(file_path: &Path) -> Result<Vec<PipelineConfig>> {
+    let configs: Vec<PipelineConfig> = serde_json::from_slice(&fs::read(file_path)?)?;
+
+    let mut pipeline_ids = HashSet::new();
+    for config in &configs {
+        let pipeline_id = config.pipeline_id;
+        ensure!(
+            pipeline_id != DEFAULT_PIPELINE_ID,
+            "pipeline id {DEFAULT_PIPELINE_ID} is reserved for the default pipeline",
+        );
+        ensure!(
+            pipeline_ids.insert(pipeline_id),
+            "duplicate pipeline id {pipeline_id}",
+        );
+    }
+
+    Ok(configs)
+}
+
+// suffix the file name by the pipeline id, e.g. `backfill_checkpoint-1.json`
+fn suffix_file_path(file_path: &Path, pipeline_id: PipelineId) -> PathBuf {
+    let stem = file_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let file_name = match file_path.extension() {
+        Some(extension) => format!("{stem}-{pipeline_id}.{}", extension.to_string_lossy()),
+        None => format!("{stem}-{pipeline_id}"),
+    };
+
+    file_path.with_file_name(file_name)
+}
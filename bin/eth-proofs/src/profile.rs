@@ -0,0 +1,123 @@
+use anyhow::{Context, Result, anyhow};
+use clap::ValueEnum;
+use std::{env, ffi::OsString, path::PathBuf};
+
+// env variable of the env file loaded before `.env`
+const ENV_FILE_VAR: &str = "ENV_FILE";
+
+// env variable of the profile
+const PROFILE_VAR: &str = "PROFILE";
+
+// built-in defaults of the dev profile in the dotenv format, proving by the mock proving service
+// against a local RPC node
+const DEV_DEFAULTS: &str = "\
+IS_MOCK_PROVING=true
+RPC_HTTP_URL=http://127.0.0.1:8545
+RPC_WS_URL=ws://127.0.0.1:8546
+";
+
+// deployment profile selecting the env file and the defaults of the settings
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Profile {
+    // local testing, the mock proving service and the local RPC node are used by default
+    Dev,
+
+    // staging deployment proving by a real proving cluster
+    Staging,
+
+    // production deployment, the mock proving service is rejected
+    Prod,
+}
+
+impl Profile {
+    // name of the profile, the env file of the profile is `.env.<name>`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Dev => "dev",
+            Self::Staging => "staging",
+            Self::Prod => "prod",
+        }
+    }
+
+    // built-in defaults of the profile in the dotenv format
+    fn defaults(self) -> &'static str {
+        match self {
+            Self::Dev => DEV_DEFAULTS,
+            Self::Staging | Self::Prod => "",
+        }
+    }
+}
+
+// load the env variables before parsing the cli arguments, the variables already set take
+// precedence over `--env-file`, then `.env.<profile>` of `--profile`, then `.env`, then the
+// built-in defaults of the profile; the profile could also be set by `PROFILE` of the env files
+pub fn load_env() -> Result<()> {
+    let argv: Vec<OsString> = env::args_os().collect();
+
+    if let Some(env_file) = arg_value(&argv, "env-file").or_else(|| env::var_os(ENV_FILE_VAR)) {
+        let env_file = PathBuf::from(env_file);
+        dotenvy::from_path(&env_file)
+            .with_context(|| format!("failed to load env file {env_file:?}"))?;
+    }
+
+    let profile = arg_value(&argv, "profile")
+        .or_else(|| env::var_os(PROFILE_VAR))
+        .map(|profile| profile.to_string_lossy().into_owned())
+        .or_else(dotenv_profile)
+        .map(|profile| {
+            Profile::from_str(&profile, true)
+                .map_err(|e| anyhow!("invalid profile `{profile}`: {e}"))
+        })
+        .transpose()?;
+
+    // the env file of the profile is optional, the settings could all be given otherwise
+    if let Some(profile) = profile {
+        let profile_env_file = format!(".env.{}", profile.name());
+        if let Err(e) = dotenvy::from_filename(&profile_env_file)
+            && !e.not_found()
+        {
+            return Err(e).with_context(|| format!("failed to load env file {profile_env_file}"));
+        }
+    }
+
+    dotenvy::dotenv().ok();
+
+    if let Some(profile) = profile {
+        dotenvy::from_read(profile.defaults().as_bytes())
+            .with_context(|| format!("failed to load defaults of profile {}", profile.name()))?;
+    }
+
+    Ok(())
+}
+
+// return the value of a named cli argument before the full parsing, given as `--<long> <value>`
+// or `--<long>=<value>`
+fn arg_value(argv: &[OsString], long: &str) -> Option<OsString> {
+    let flag = format!("--{long}");
+    let prefix = format!("--{long}=");
+    let mut args = argv.iter().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            // the following are positional arguments
+            Some("--") => return None,
+            Some(arg) if arg == flag => return args.next().cloned(),
+            Some(arg) => {
+                if let Some(value) = arg.strip_prefix(prefix.as_str()) {
+                    return Some(value.into());
+                }
+            }
+            None => {}
+        }
+    }
+
+    None
+}
+
+// return `PROFILE` of `.env` without loading it, so the env file of the profile is still loaded
+// before `.env`
+fn dotenv_profile() -> Option<String> {
+    dotenvy::dotenv_iter()
+        .ok()?
+        .filter_map(Result::ok)
+        .find_map(|(key, value)| (key == PROFILE_VAR).then_some(value))
+}
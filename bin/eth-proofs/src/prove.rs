@@ -0,0 +1,149 @@
+use anyhow::{Result, anyhow, bail};
+use clap::Args;
+use common::{
+    channel::WatchReceiver, fetch::ProveBlockByNumberParams, proof_store::ProofStore,
+    readiness::wait_ready, report::BlockProvingReport, runtime::RuntimeState,
+    utils::DEFAULT_PIPELINE_ID,
+};
+use fetch_service::service::FetchService;
+use messages::BlockMsg;
+use std::{fs, path::PathBuf, process::exit, sync::Arc};
+use tokio::{
+    spawn,
+    task::JoinHandle,
+    time::{Duration, timeout},
+};
+use tracing::{error, info};
+
+// exit code if the one-shot proving failed to run, e.g. the proof couldn't be written
+const EXIT_ERROR: i32 = 1;
+
+// exit code if the block failed to be proved
+const EXIT_PROVING_FAILED: i32 = 2;
+
+// exit code if no report of the block is received within the timeout
+const EXIT_TIMEOUT: i32 = 4;
+
+// arguments of the `prove` subcommand proving a single block by the whole pipeline in this process
+#[derive(Args, Clone, Debug)]
+pub struct ProveArgs {
+    #[clap(long, help = "Block number to prove")]
+    pub block: u64,
+
+    #[clap(long, help = "File path of the bincode serialized aggregated proof")]
+    pub out: PathBuf,
+
+    #[clap(
+        long,
+        help = "File path of the JSON report of the block; the proof path with the `.json` extension if not specified"
+    )]
+    pub report_out: Option<PathBuf>,
+
+    #[clap(
+        long,
+        default_value = "3600",
+        help = "Maximum seconds of waiting for the report of the block since the pipeline is ready"
+    )]
+    pub timeout_seconds: u64,
+}
+
+impl ProveArgs {
+    // return the file path of the JSON report
+    fn report_path(&self) -> PathBuf {
+        self.report_out
+            .clone()
+            .unwrap_or_else(|| self.out.with_extension("json"))
+    }
+}
+
+// prove the block once the pipeline is ready and exit the process, the block is requested by the
+// fetch service without listening; the exit code is zero only if the block is proved
+pub fn run_prove(
+    prove_args: ProveArgs,
+    fetch_service: Arc<FetchService>,
+    proof_store: Option<Arc<ProofStore>>,
+    mut runtime_state: WatchReceiver<RuntimeState>,
+) -> JoinHandle<()> {
+    spawn(async move {
+        // wait until the components are initialized
+        info!("eth-proofs: waiting for the readiness checks before proving");
+        wait_ready(&mut runtime_state).await;
+
+        let block_number = prove_args.block;
+        let result = timeout(
+            Duration::from_secs(prove_args.timeout_seconds),
+            prove_block(&prove_args, fetch_service, proof_store),
+        )
+        .await;
+        let exit_code = match result {
+            Ok(Ok(report)) if report.success => {
+                info!(
+                    "eth-proofs: proved block {block_number}, the proof is written to {:?}",
+                    prove_args.out,
+                );
+                0
+            }
+            Ok(Ok(report)) => {
+                error!("eth-proofs: failed to prove block {block_number} {report}");
+                EXIT_PROVING_FAILED
+            }
+            Ok(Err(e)) => {
+                error!("eth-proofs: failed to prove block {block_number} {e:?}");
+                EXIT_ERROR
+            }
+            Err(_) => {
+                error!("eth-proofs: timed out waiting for the report of block {block_number}");
+                EXIT_TIMEOUT
+            }
+        };
+        exit(exit_code);
+    })
+}
+
+// request proving the block and wait for its report, the JSON report is written for any result and
+// the proof only if proved
+async fn prove_block(
+    prove_args: &ProveArgs,
+    fetch_service: Arc<FetchService>,
+    proof_store: Option<Arc<ProofStore>>,
+) -> Result<BlockProvingReport> {
+    // watch the reports before requesting, so the report is not missed
+    let watcher = fetch_service.watch()?;
+    let block_number = prove_args.block;
+    let params = ProveBlockByNumberParams::new(block_number, Some(1));
+    let job_id = fetch_service
+        .prove_block_by_number(params, DEFAULT_PIPELINE_ID)
+        .await?;
+    info!("eth-proofs: requested block {block_number} by job {job_id}");
+
+    // wait for the report of the block
+    let report = loop {
+        if let BlockMsg::Report(report) = watcher.recv().await?
+            && report.job_id == job_id
+            && report.block_number == block_number
+        {
+            break report;
+        }
+    };
+
+    // write the report and the proof carried by the report or persisted by the proof service
+    let report_path = prove_args.report_path();
+    fs::write(
+        &report_path,
+        serde_json::to_string_pretty(&report.to_json_value()?)?,
+    )?;
+    info!("eth-proofs: the report of block {block_number} is written to {report_path:?}");
+    if report.success {
+        let proof = match (&report.proof, &report.proof_uri, &proof_store) {
+            (Some(proof), _, _) => proof.clone(),
+            (None, Some(proof_uri), Some(proof_store)) => proof_store
+                .load(proof_uri)
+                .await?
+                .ok_or_else(|| anyhow!("persisted proof {proof_uri} is not found"))?,
+            _ => bail!("no proof of block {block_number} is reported"),
+        };
+        fs::write(&prove_args.out, proof)?;
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,93 @@
+use common::{
+    in_flight::InFlightBlocks,
+    shutdown::{ShutdownPhase, advance_shutdown, wait_flushed},
+};
+use fetch_service::service::FetchService;
+use messages::ControlMsg;
+use std::sync::Arc;
+use tokio::{
+    select,
+    signal::{
+        ctrl_c,
+        unix::{SignalKind, signal},
+    },
+    time::{Duration, sleep, timeout},
+};
+use tracing::{info, warn};
+
+// interval of checking the blocks in flight while draining
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// maximum waiting time for the components to flush the queued reports
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(30);
+
+// shut down the process in order on `Ctrl+C` or `SIGTERM`: stop accepting the HTTP requests and
+// pause the pipelines, wait for the blocks in flight to be proved within the drain timeout, then
+// stop the proving services and flush the queued reports; it returns once the process could exit,
+// and a second signal skips the draining
+pub async fn shutdown(
+    drain_timeout: Duration,
+    fetch_service: Option<Arc<FetchService>>,
+    in_flight_blocks: Arc<InFlightBlocks>,
+) {
+    wait_signal().await;
+    info!(
+        "eth-proofs: shutting down, draining {} blocks in flight within {} s",
+        in_flight_blocks.len(),
+        drain_timeout.as_secs(),
+    );
+    advance_shutdown(ShutdownPhase::Draining);
+
+    // the pipelines stop fetching and dispatching new blocks, the pause of the remote pipelines is
+    // sent by the process of the fetch-service
+    if let Some(fetch_service) = fetch_service
+        && let Err(e) = fetch_service.send_control(ControlMsg::Pause)
+    {
+        warn!("eth-proofs: failed to pause the pipelines for shutdown {e:?}");
+    }
+
+    select! {
+        result = timeout(drain_timeout, wait_drained(&in_flight_blocks)) => match result {
+            Ok(()) => info!("eth-proofs: drained the blocks in flight"),
+            Err(_) => warn!(
+                "eth-proofs: dropped {} blocks in flight after the drain timeout",
+                in_flight_blocks.len(),
+            ),
+        },
+        () = wait_signal() => warn!(
+            "eth-proofs: dropped {} blocks in flight on another signal",
+            in_flight_blocks.len(),
+        ),
+    }
+
+    advance_shutdown(ShutdownPhase::Flushing);
+    if timeout(FLUSH_TIMEOUT, wait_flushed()).await.is_err() {
+        warn!(
+            "eth-proofs: reports are not flushed in {} s",
+            FLUSH_TIMEOUT.as_secs(),
+        );
+    }
+    info!("eth-proofs: shut down");
+}
+
+// wait until no block is in flight, the results received last are given one more interval to be
+// reported
+async fn wait_drained(in_flight_blocks: &InFlightBlocks) {
+    while !in_flight_blocks.is_empty() {
+        sleep(DRAIN_POLL_INTERVAL).await;
+    }
+    sleep(DRAIN_POLL_INTERVAL).await;
+}
+
+// wait for `Ctrl+C` or `SIGTERM`
+async fn wait_signal() {
+    let mut terminate = signal(SignalKind::terminate())
+        .unwrap_or_else(|e| panic!("eth-proofs: failed to listen for SIGTERM: {e}"));
+    select! {
+        result = ctrl_c() => {
+            result.unwrap_or_else(|e| panic!("eth-proofs: failed to listen for Ctrl+C: {e}"));
+            info!("eth-proofs: Ctrl+C signal received");
+        }
+        _ = terminate.recv() => info!("eth-proofs: SIGTERM signal received"),
+    }
+}
@@ -0,0 +1,279 @@
+use crate::{
+    prover::{ProvedInput, ProvingFailure},
+    service::SidecarService,
+};
+use aggregator_proto::{
+    FILE_DESCRIPTOR_SET, ProveAggregationRequest,
+    aggregator_server::{Aggregator, AggregatorServer},
+};
+use common::inputs::input_digest;
+use derive_more::Constructor;
+use proof_proto::{
+    CompleteProvingRequest, ProvingErrorCode, SubblockResult, extract_trace_id,
+    traceparent::TraceParentInterceptor,
+};
+use std::{sync::Arc, time::Instant};
+use tokio::{
+    signal::ctrl_c,
+    spawn,
+    task::{JoinHandle, spawn_blocking},
+};
+use tonic::{
+    Request, Response, Status, async_trait,
+    codec::CompressionEncoding,
+    service::{LayerExt, interceptor::InterceptedService},
+    transport::Server,
+};
+use tonic_health::server::health_reporter;
+use tonic_web::GrpcWebLayer;
+use tower::ServiceBuilder;
+use tower_http::cors::{Any, CorsLayer};
+use tracing::{error, info};
+
+impl SidecarService {
+    // start the aggregator grpc service
+    pub fn run_aggregator_service(self: Arc<Self>) -> JoinHandle<()> {
+        let addr = self.config.aggregator_addr();
+        info!("prover-sidecar: start aggregator grpc service on {addr}");
+
+        spawn(async move {
+            let max_msg_bytes = self.config.max_msg_bytes;
+
+            // create the base grpc service
+            let agg_service = SidecarAggregatorService::new(self.clone());
+            let grpc = AggregatorServer::new(agg_service)
+                .max_encoding_message_size(max_msg_bytes)
+                .max_decoding_message_size(max_msg_bytes)
+                .accept_compressed(CompressionEncoding::Zstd)
+                .send_compressed(CompressionEncoding::Zstd);
+
+            // carry the block trace id by the W3C trace context of the requests
+            let grpc = InterceptedService::new(grpc, TraceParentInterceptor);
+
+            // add a web layer to the grpc service
+            let service = ServiceBuilder::new()
+                .layer(
+                    CorsLayer::new()
+                        .allow_origin(Any)
+                        .allow_methods(Any)
+                        .allow_headers(Any),
+                )
+                .layer(GrpcWebLayer::new())
+                .into_inner()
+                .named_layer(grpc);
+
+            // create the health and reflection services for probing and discovering the api
+            let (health_reporter, health_service) = health_reporter();
+            health_reporter
+                .set_serving::<AggregatorServer<SidecarAggregatorService>>()
+                .await;
+            let reflection_service = tonic_reflection::server::Builder::configure()
+                .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+                .build_v1()
+                .expect("prover-sidecar: failed to build reflection service");
+
+            Server::builder()
+                .accept_http1(true)
+                .add_service(service)
+                .add_service(health_service)
+                .add_service(reflection_service)
+                .serve_with_shutdown(addr, async {
+                    ctrl_c()
+                        .await
+                        .expect("prover-sidecar: failed to wait for graceful shutdown");
+                })
+                .await
+                .expect("prover-sidecar: failed to start aggregator service");
+
+            info!("prover-sidecar: aggregator grpc service stopped");
+        })
+    }
+}
+
+// aggregator grpc service
+#[derive(Constructor, Debug)]
+struct SidecarAggregatorService {
+    // shared state of the sidecar services
+    service: Arc<SidecarService>,
+}
+
+#[async_trait]
+impl Aggregator for SidecarAggregatorService {
+    async fn prove_aggregation(
+        &self,
+        request: Request<ProveAggregationRequest>,
+    ) -> Result<Response<()>, Status> {
+        // get the request block number, the trace id is echoed back in the callback metadata
+        let trace_id = extract_trace_id(request.metadata());
+        let request = request.into_inner();
+        let block_number = request.block_number;
+        info!("prover-sidecar: received aggregation proving request of block {block_number}");
+
+        // verify the received input by its digest if sent
+        if !request.input_digest.is_empty() && input_digest(&request.input) != request.input_digest
+        {
+            return Err(Status::data_loss(format!(
+                "aggregator input of block {block_number} doesn't match its digest"
+            )));
+        }
+
+        // the subblock public values are checked against the subblock proofs before aggregating
+        let public_values: Vec<Vec<u8>> = bincode::deserialize(&request.subblock_public_values)
+            .map_err(|e| {
+                Status::invalid_argument(format!(
+                    "failed to deserialize subblock public values of block {block_number}: {e}",
+                ))
+            })?;
+        if public_values.len() != request.num_subblocks as usize {
+            return Err(Status::invalid_argument(format!(
+                "{} subblock public values of block {block_number} mismatch {} subblocks",
+                public_values.len(),
+                request.num_subblocks,
+            )));
+        }
+
+        // aggregate the subblock proofs in the background and return the proving result, so the
+        // request is acknowledged immediately like the proving cluster
+        let service = self.service.clone();
+        spawn(async move {
+            let (job_id, pipeline_id) = (request.job_id, request.pipeline_id);
+            let result = match prove_block(&service, request, public_values).await {
+                Ok(result) => {
+                    info!(
+                        "prover-sidecar: proved block {block_number} with cycles {} in {} ms",
+                        result.cycles, result.proving_milliseconds,
+                    );
+                    result
+                }
+                Err(failure) => {
+                    error!("prover-sidecar: failed to prove block {block_number} {failure}");
+                    CompleteProvingRequest {
+                        success: false,
+                        block_number,
+                        job_id,
+                        pipeline_id,
+                        error_code: failure.error_code.into(),
+                        error_message: Some(failure.error_message),
+                        ..Default::default()
+                    }
+                }
+            };
+            service.callback.complete_proving(result, &trace_id).await;
+        });
+
+        Ok(Response::new(()))
+    }
+}
+
+// wait for the subblock proofs of a block, verify them against the public values and prove the
+// aggregator input, the proving milliseconds are counted since the aggregation request is received
+async fn prove_block(
+    service: &SidecarService,
+    request: ProveAggregationRequest,
+    public_values: Vec<Vec<u8>>,
+) -> Result<CompleteProvingRequest, ProvingFailure> {
+    let start_time = Instant::now();
+    let ProveAggregationRequest {
+        block_number,
+        num_subblocks,
+        input,
+        job_id,
+        pipeline_id,
+        ..
+    } = request;
+
+    let config = &service.config;
+    let outcomes = service
+        .subblock_proofs
+        .take_all(
+            job_id,
+            block_number,
+            num_subblocks,
+            config.subblock_proof_timeout,
+        )
+        .await
+        .ok_or_else(|| {
+            ProvingFailure::new(
+                ProvingErrorCode::Timeout,
+                format!(
+                    "not all {num_subblocks} subblocks are proved in {} s",
+                    config.subblock_proof_timeout.as_secs(),
+                ),
+            )
+        })?;
+    let subblocks = outcomes.into_iter().collect::<Result<Vec<_>, _>>()?;
+
+    // verify the subblock proofs and prove the aggregator input on a blocking thread
+    info!("prover-sidecar: aggregating {num_subblocks} subblock proofs of block {block_number}");
+    let permit = service
+        .proving_permits
+        .clone()
+        .acquire_owned()
+        .await
+        .expect("prover-sidecar: proving permits are closed");
+    let prover = service.prover.clone();
+    let (subblocks, aggregated) = spawn_blocking(move || {
+        for (index, (subblock, public_values)) in subblocks.iter().zip(&public_values).enumerate() {
+            prover
+                .verify_subblock(&subblock.proof, public_values)
+                .map_err(|failure| {
+                    ProvingFailure::new(
+                        failure.error_code,
+                        format!("subblock {index}: {}", failure.error_message),
+                    )
+                })?;
+        }
+        let subblock_proofs = subblocks
+            .iter()
+            .map(|subblock| subblock.proof.clone())
+            .collect();
+        let aggregated = prover.prove_aggregation(&input, subblock_proofs)?;
+
+        Ok::<_, ProvingFailure>((subblocks, aggregated))
+    })
+    .await
+    .map_err(|e| {
+        ProvingFailure::new(
+            ProvingErrorCode::Unspecified,
+            format!("proving thread failed {e}"),
+        )
+    })??;
+    drop(permit);
+
+    let ProvedInput {
+        proof,
+        cycles: agg_cycles,
+        proving_milliseconds: aggregation_milliseconds,
+    } = aggregated;
+    let proof = bincode::serialize(&proof).map_err(|e| {
+        ProvingFailure::new(
+            ProvingErrorCode::Unspecified,
+            format!("failed to serialize the proof {e}"),
+        )
+    })?;
+    let subblock_cycles: Vec<u64> = subblocks.iter().map(|subblock| subblock.cycles).collect();
+    let subblock_results = subblocks
+        .iter()
+        .enumerate()
+        .map(|(index, subblock)| SubblockResult {
+            subblock_index: index as u32,
+            cycles: subblock.cycles,
+            proving_milliseconds: subblock.proving_milliseconds,
+            prover_id: config.prover_id.clone(),
+        })
+        .collect();
+
+    Ok(CompleteProvingRequest {
+        success: true,
+        block_number,
+        cycles: subblock_cycles.iter().sum::<u64>() + agg_cycles,
+        proving_milliseconds: start_time.elapsed().as_millis() as u64,
+        proof: Some(proof),
+        subblock_cycles,
+        job_id,
+        pipeline_id,
+        subblock_results,
+        aggregation_milliseconds,
+        ..Default::default()
+    })
+}
@@ -0,0 +1,86 @@
+use crate::config::{CALLBACK_ATTEMPTS, CALLBACK_RETRY_INTERVAL, SidecarConfig};
+use anyhow::Result;
+use derive_more::Constructor;
+use proof_proto::{
+    AUTH_METADATA_KEY, CompleteProvingRequest, ReportProgressRequest, bearer_token,
+    proof_client::ProofClient,
+    traced_request,
+    traceparent::{TracedChannel, connect_traced},
+};
+use std::sync::Arc;
+use tokio::time::sleep;
+use tonic::{Request, codec::CompressionEncoding};
+use tracing::{error, warn};
+
+// callbacks to the proof service of the eth-proofs server
+#[derive(Constructor, Debug)]
+pub struct ProofCallback {
+    config: Arc<SidecarConfig>,
+}
+
+impl ProofCallback {
+    // return the proving result of a block, it's retried if the proof service is unreachable
+    pub async fn complete_proving(&self, result: CompleteProvingRequest, trace_id: &str) {
+        let block_number = result.block_number;
+        for attempt in 1..=CALLBACK_ATTEMPTS {
+            let request = self.request(result.clone(), trace_id);
+            match self.connect().await {
+                Ok(mut client) => match client.complete_proving(request).await {
+                    Ok(_) => return,
+                    Err(e) => warn!(
+                        "prover-sidecar: failed to return the proving result of block {block_number} by attempt {attempt} {e:?}"
+                    ),
+                },
+                Err(e) => warn!(
+                    "prover-sidecar: failed to connect to the proof service by attempt {attempt} {e:?}"
+                ),
+            }
+            if attempt < CALLBACK_ATTEMPTS {
+                sleep(CALLBACK_RETRY_INTERVAL).await;
+            }
+        }
+
+        error!("prover-sidecar: dropped the proving result of block {block_number}");
+    }
+
+    // report a proved subblock to the proof service, the progress is best effort
+    pub async fn report_progress(&self, progress: ReportProgressRequest, trace_id: &str) {
+        let (block_number, subblock_index) = (progress.block_number, progress.subblock_index);
+        let request = self.request(progress, trace_id);
+        let result = match self.connect().await {
+            Ok(mut client) => client.report_progress(request).await.map_err(Into::into),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            warn!(
+                "prover-sidecar: failed to report the progress of subblock {subblock_index} of block {block_number} {e:?}"
+            );
+        }
+    }
+
+    // connect to the proof service
+    async fn connect(&self) -> Result<ProofClient<TracedChannel>> {
+        let max_msg_bytes = self.config.max_msg_bytes;
+        let channel = connect_traced(self.config.proof_service_url.to_string()).await?;
+        let client = ProofClient::new(channel)
+            .max_encoding_message_size(max_msg_bytes)
+            .max_decoding_message_size(max_msg_bytes)
+            .accept_compressed(CompressionEncoding::Zstd)
+            .send_compressed(CompressionEncoding::Zstd);
+
+        Ok(client)
+    }
+
+    // wrap a message into a request carrying the trace id and the shared token if specified
+    fn request<T>(&self, message: T, trace_id: &str) -> Request<T> {
+        let mut request = traced_request(message, trace_id);
+        if let Some(token) = &self.config.proof_service_token {
+            let token = bearer_token(token)
+                .parse()
+                .expect("prover-sidecar: invalid proof service token");
+            request.metadata_mut().insert(AUTH_METADATA_KEY, token);
+        }
+
+        request
+    }
+}
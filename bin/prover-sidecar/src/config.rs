@@ -0,0 +1,75 @@
+use anyhow::{Result, ensure};
+use reqwest::Url;
+use std::{
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+
+// number of the attempts of returning a proving result to the proof service
+pub const CALLBACK_ATTEMPTS: usize = 3;
+
+// interval between the attempts of returning a proving result
+pub const CALLBACK_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+// prover sidecar configuration
+#[derive(Debug)]
+pub struct SidecarConfig {
+    // maximum grpc message bytes
+    pub max_msg_bytes: usize,
+
+    // number of the subblock proving services
+    pub num_subblocks: usize,
+
+    // bind address of the proving services
+    pub bind_ip: IpAddr,
+
+    // port of the aggregator, the subblock services listen on the following ports by subblock
+    // index
+    pub base_port: u16,
+
+    // proof service grpc url for returning the proving results
+    pub proof_service_url: Url,
+
+    // shared token for authenticating the callbacks to the proof service
+    pub proof_service_token: Option<String>,
+
+    // id of this prover reported with the subblock results, e.g. the host name
+    pub prover_id: String,
+
+    // maximum number of the inputs proved at the same time
+    pub max_concurrent_proofs: usize,
+
+    // maximum duration of waiting for the subblock proofs of a block by the aggregator
+    pub subblock_proof_timeout: Duration,
+}
+
+impl SidecarConfig {
+    // check the configuration is valid
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            self.base_port as usize + self.num_subblocks <= u16::MAX as usize,
+            "ports of {} subblocks from {} overflow",
+            self.num_subblocks,
+            self.base_port,
+        );
+        ensure!(
+            self.max_concurrent_proofs > 0,
+            "maximum number of the concurrent proofs should be positive",
+        );
+
+        Ok(())
+    }
+
+    // return the aggregator grpc address
+    pub fn aggregator_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.bind_ip, self.base_port)
+    }
+
+    // return the subblock grpc addresses on the ports following the aggregator, one for each
+    // subblock
+    pub fn subblock_addrs(&self) -> Vec<SocketAddr> {
+        (1..=self.num_subblocks)
+            .map(|i| SocketAddr::new(self.bind_ip, self.base_port + i as u16))
+            .collect()
+    }
+}
@@ -0,0 +1,143 @@
+mod aggregator;
+mod callback;
+mod config;
+mod proofs;
+mod prover;
+mod service;
+mod subblock;
+
+use clap::Parser;
+use common::{logger::setup_logger, utils::DEFAULT_MAX_NUM_SUBBLOCKS};
+use config::SidecarConfig;
+use dotenvy::dotenv;
+use futures::future::join_all;
+use prover::SidecarProver;
+use reqwest::Url;
+use service::SidecarService;
+use std::{net::IpAddr, path::PathBuf, sync::Arc, time::Duration};
+use tracing::info;
+
+#[derive(Parser)]
+struct Args {
+    #[clap(
+        long,
+        env = "SUBBLOCK_ELF_PATH",
+        default_value = "data/subblock-elf",
+        help = "Subblock ELF file path"
+    )]
+    subblock_elf_path: PathBuf,
+
+    #[clap(
+        long,
+        env = "AGG_ELF_PATH",
+        default_value = "data/aggregator-elf",
+        help = "Aggregator ELF file path"
+    )]
+    agg_elf_path: PathBuf,
+
+    #[clap(
+        long,
+        env = "SIDECAR_BIND_IP",
+        default_value = "::",
+        help = "Bind address of the proving services"
+    )]
+    bind_ip: IpAddr,
+
+    #[clap(
+        long,
+        env = "SIDECAR_BASE_PORT",
+        default_value = "50061",
+        help = "Port of the aggregator service, the subblock services listen on the following ports by subblock index"
+    )]
+    base_port: u16,
+
+    #[clap(
+        long,
+        env = "MAX_NUM_SUBBLOCKS",
+        default_value_t = DEFAULT_MAX_NUM_SUBBLOCKS,
+        help = "Number of the subblock proving services, it should be the `MAX_NUM_SUBBLOCKS` of the eth-proofs server"
+    )]
+    num_subblocks: usize,
+
+    #[clap(
+        long,
+        env = "PROOF_SERVICE_URL",
+        default_value = "http://127.0.0.1:50052",
+        help = "Proof service GRPC URL of the eth-proofs server for returning the proving results"
+    )]
+    proof_service_url: Url,
+
+    #[clap(
+        long,
+        env = "PROOF_SERVICE_TOKEN",
+        help = "Shared token sent to the proof service as the `authorization: Bearer <token>` gRPC metadata"
+    )]
+    proof_service_token: Option<String>,
+
+    #[clap(
+        long,
+        env = "PROVER_ID",
+        default_value = "prover-sidecar",
+        help = "Id of this prover reported with the subblock results, e.g. the host name"
+    )]
+    prover_id: String,
+
+    #[clap(
+        long,
+        env = "MAX_CONCURRENT_PROOFS",
+        default_value = "1",
+        help = "Maximum number of the subblock and aggregator inputs proved at the same time"
+    )]
+    max_concurrent_proofs: usize,
+
+    #[clap(
+        long,
+        env = "SUBBLOCK_PROOF_TIMEOUT_SECONDS",
+        default_value = "1800",
+        help = "Maximum seconds of waiting for the subblock proofs of a block by the aggregator, the block is failed after it"
+    )]
+    subblock_proof_timeout_seconds: u64,
+
+    #[clap(
+        long,
+        env = "MAX_GRPC_MSG_BYTES",
+        default_value = "1073741824",
+        help = "Maximum GRPC message bytes"
+    )]
+    max_grpc_msg_bytes: usize,
+}
+
+// serve the subblock and aggregator proving services by the pico prover sdk, the subblock proofs
+// are kept in process for the aggregator, and the aggregated proofs are returned to the proof
+// service of the eth-proofs server
+#[tokio::main]
+async fn main() {
+    // setup env and logger
+    dotenv().ok();
+    setup_logger();
+
+    // parse the cli arguments
+    let args = Args::parse();
+
+    let config = Arc::new(SidecarConfig {
+        max_msg_bytes: args.max_grpc_msg_bytes,
+        num_subblocks: args.num_subblocks,
+        bind_ip: args.bind_ip,
+        base_port: args.base_port,
+        proof_service_url: args.proof_service_url,
+        proof_service_token: args.proof_service_token,
+        prover_id: args.prover_id,
+        max_concurrent_proofs: args.max_concurrent_proofs,
+        subblock_proof_timeout: Duration::from_secs(args.subblock_proof_timeout_seconds),
+    });
+    config
+        .validate()
+        .unwrap_or_else(|e| panic!("prover-sidecar: invalid configuration {e}"));
+
+    info!("prover-sidecar: loading the subblock and aggregator ELFs");
+    let prover = SidecarProver::new(&args.subblock_elf_path, &args.agg_elf_path)
+        .unwrap_or_else(|e| panic!("prover-sidecar: failed to create the prover {e}"));
+
+    let service = Arc::new(SidecarService::new(config, prover));
+    join_all(service.run()).await;
+}
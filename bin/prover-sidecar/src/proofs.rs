@@ -0,0 +1,88 @@
+use crate::prover::{ProvedInput, ProvingFailure};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::Mutex,
+    time::Duration,
+};
+use tokio::{
+    sync::Notify,
+    time::{Instant, timeout_at},
+};
+
+// result of proving a subblock
+pub type SubblockOutcome = Result<ProvedInput, ProvingFailure>;
+
+// subblock proofs kept by job id and block number until taken by the aggregator
+#[derive(Default)]
+pub struct SubblockProofs {
+    // proving results of each subblock by job id and block number
+    outcomes: Mutex<HashMap<(u64, u64), BTreeMap<u32, SubblockOutcome>>>,
+
+    // notification of the proved subblocks
+    proved: Notify,
+}
+
+impl SubblockProofs {
+    // record the proving result of a subblock, the retried subblock overwrites the previous result
+    pub fn record(
+        &self,
+        job_id: u64,
+        block_number: u64,
+        subblock_index: u32,
+        outcome: SubblockOutcome,
+    ) {
+        self.outcomes
+            .lock()
+            .expect("prover-sidecar: failed to lock the subblock proofs")
+            .entry((job_id, block_number))
+            .or_default()
+            .insert(subblock_index, outcome);
+        self.proved.notify_waiters();
+    }
+
+    // wait for and take the proving results of all the subblocks of a block in the subblock index
+    // order, it returns none if not all proved within the timeout
+    pub async fn take_all(
+        &self,
+        job_id: u64,
+        block_number: u64,
+        num_subblocks: u32,
+        timeout: Duration,
+    ) -> Option<Vec<SubblockOutcome>> {
+        let key = (job_id, block_number);
+        let deadline = Instant::now() + timeout;
+        loop {
+            // register the notification before checking, so a proof in between isn't missed
+            let proved = self.proved.notified();
+            {
+                let mut outcomes = self
+                    .outcomes
+                    .lock()
+                    .expect("prover-sidecar: failed to lock the subblock proofs");
+                if outcomes
+                    .get(&key)
+                    .is_some_and(|outcomes| outcomes.len() >= num_subblocks as usize)
+                {
+                    return outcomes
+                        .remove(&key)
+                        .map(|outcomes| outcomes.into_values().collect());
+                }
+            }
+
+            if timeout_at(deadline, proved).await.is_err() {
+                self.outcomes
+                    .lock()
+                    .expect("prover-sidecar: failed to lock the subblock proofs")
+                    .remove(&key);
+                return None;
+            }
+        }
+    }
+}
+
+impl fmt::Debug for SubblockProofs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SubblockProofs").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,190 @@
+use anyhow::{Context, Result};
+use pico_sdk::client::DefaultProverClient;
+use pico_vm::{
+    configs::stark_config::{KoalaBearBn254Poseidon2, KoalaBearPoseidon2},
+    emulator::stdin::EmulatorStdinBuilder,
+    machine::proof::MetaProof,
+    proverchain::ProverChain,
+};
+use proof_proto::ProvingErrorCode;
+use std::{fmt, fs, path::Path, time::Instant};
+
+// stdin builder serialized by the fetcher for both the subblock and aggregator inputs
+type StdinBuilder = EmulatorStdinBuilder<Vec<u8>, KoalaBearPoseidon2>;
+
+// proof of an input proved by the pico prover sdk, it's the riscv proof of a subblock and the final
+// embed proof of the aggregator like the proving cluster
+pub struct ProvedInput<SC = KoalaBearPoseidon2> {
+    // proof of the input, its public values are committed by the program
+    pub proof: MetaProof<SC>,
+
+    // cycles of the input counted by the proving
+    pub cycles: u64,
+
+    // milliseconds of proving the input
+    pub proving_milliseconds: u64,
+}
+
+// failure of proving an input, it's returned to the proof service with the error code
+#[derive(Clone, Debug)]
+pub struct ProvingFailure {
+    // error code of the failure
+    pub error_code: ProvingErrorCode,
+
+    // error message for triage
+    pub error_message: String,
+}
+
+impl ProvingFailure {
+    pub fn new(error_code: ProvingErrorCode, error_message: impl Into<String>) -> Self {
+        Self {
+            error_code,
+            error_message: error_message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ProvingFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.error_code, self.error_message)
+    }
+}
+
+// prover of the subblock and aggregator inputs by the pico prover sdk, the proving is blocking
+// and should run on a blocking thread
+pub struct SidecarProver {
+    // prover client of the subblock elf
+    subblock_prover_client: DefaultProverClient,
+
+    // prover client of the aggregator elf
+    agg_prover_client: DefaultProverClient,
+}
+
+impl SidecarProver {
+    // create a prover by the subblock and aggregator elf files
+    pub fn new(subblock_elf_path: &Path, agg_elf_path: &Path) -> Result<Self> {
+        let subblock_elf = fs::read(subblock_elf_path)
+            .with_context(|| format!("failed to read the subblock elf {subblock_elf_path:?}"))?;
+        let agg_elf = fs::read(agg_elf_path)
+            .with_context(|| format!("failed to read the aggregator elf {agg_elf_path:?}"))?;
+
+        Ok(Self {
+            subblock_prover_client: DefaultProverClient::new(&subblock_elf),
+            agg_prover_client: DefaultProverClient::new(&agg_elf),
+        })
+    }
+
+    // prove a subblock input
+    pub fn prove_subblock(&self, input: &[u8]) -> Result<ProvedInput, ProvingFailure> {
+        prove(
+            &self.subblock_prover_client,
+            deserialize_stdin_builder(input)?,
+        )
+    }
+
+    // prove an aggregator input with the subblock proofs, the proofs are written to the stdin for
+    // the aggregator to verify them recursively against the subblock verification key, and the
+    // riscv proof is recursed to the final embed proof verifiable against the aggregator
+    // verification key like the proofs of the cluster
+    pub fn prove_aggregation(
+        &self,
+        input: &[u8],
+        subblock_proofs: Vec<MetaProof<KoalaBearPoseidon2>>,
+    ) -> Result<ProvedInput<KoalaBearBn254Poseidon2>, ProvingFailure> {
+        let start_time = Instant::now();
+        let mut stdin_builder = deserialize_stdin_builder(input)?;
+        let subblock_vk = self.subblock_prover_client.riscv_vk();
+        for proof in subblock_proofs {
+            stdin_builder.write_pico_proof(proof, subblock_vk.clone());
+        }
+        let ProvedInput { proof, cycles, .. } = prove(&self.agg_prover_client, stdin_builder)?;
+
+        let client = &self.agg_prover_client;
+        let convert_proof = client.convert().prove(proof);
+        let combine_proof = client.combine().prove(convert_proof);
+        let compress_proof = client.compress().prove(combine_proof);
+        let embed_proof = client.embed().prove(compress_proof);
+        if !client.embed().verify(&embed_proof, client.riscv_vk()) {
+            return Err(ProvingFailure::new(
+                ProvingErrorCode::ConstraintFailure,
+                "aggregator embed proof is not valid for the aggregator verification key",
+            ));
+        }
+
+        Ok(ProvedInput {
+            proof: embed_proof,
+            cycles,
+            proving_milliseconds: start_time.elapsed().as_millis() as u64,
+        })
+    }
+
+    // verify a subblock proof against the subblock verification key and check it commits the
+    // public values consumed by the aggregator
+    pub fn verify_subblock(
+        &self,
+        proof: &MetaProof<KoalaBearPoseidon2>,
+        public_values: &[u8],
+    ) -> Result<(), ProvingFailure> {
+        let subblock_vk = self.subblock_prover_client.riscv_vk();
+        if !self
+            .subblock_prover_client
+            .riscv()
+            .verify(proof, subblock_vk)
+        {
+            return Err(ProvingFailure::new(
+                ProvingErrorCode::ConstraintFailure,
+                "subblock proof is not valid for the subblock verification key",
+            ));
+        }
+        if proof.pv_stream.as_deref().unwrap_or_default() != public_values {
+            return Err(ProvingFailure::new(
+                ProvingErrorCode::Unspecified,
+                "subblock proof doesn't commit the public values of the aggregator input",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for SidecarProver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SidecarProver").finish_non_exhaustive()
+    }
+}
+
+// prove an input by the prover client, the cycles are counted by the proving instead of emulating
+// the input again
+fn prove(
+    prover_client: &DefaultProverClient,
+    stdin_builder: StdinBuilder,
+) -> Result<ProvedInput, ProvingFailure> {
+    let start_time = Instant::now();
+    let (stdin, _) = stdin_builder.finalize();
+    let (proof, cycles) = prover_client.riscv().prove_cycles(stdin);
+    if !prover_client
+        .riscv()
+        .verify(&proof, prover_client.riscv_vk())
+    {
+        return Err(ProvingFailure::new(
+            ProvingErrorCode::ConstraintFailure,
+            "proof is not valid for the verification key",
+        ));
+    }
+
+    Ok(ProvedInput {
+        proof,
+        cycles,
+        proving_milliseconds: start_time.elapsed().as_millis() as u64,
+    })
+}
+
+// bincode deserialize a stdin builder of the input
+fn deserialize_stdin_builder(input: &[u8]) -> Result<StdinBuilder, ProvingFailure> {
+    bincode::deserialize(input).map_err(|e| {
+        ProvingFailure::new(
+            ProvingErrorCode::InputDecodeError,
+            format!("failed to deserialize the input {e}"),
+        )
+    })
+}
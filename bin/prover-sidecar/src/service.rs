@@ -0,0 +1,45 @@
+use crate::{
+    callback::ProofCallback, config::SidecarConfig, proofs::SubblockProofs, prover::SidecarProver,
+};
+use std::sync::Arc;
+use tokio::{sync::Semaphore, task::JoinHandle};
+use tracing::info;
+
+// subblock and aggregator proving services of the prover sidecar
+#[derive(Debug)]
+pub struct SidecarService {
+    pub config: Arc<SidecarConfig>,
+
+    // prover of the inputs by the pico prover sdk
+    pub prover: Arc<SidecarProver>,
+
+    // subblock proofs shared with the aggregator
+    pub subblock_proofs: Arc<SubblockProofs>,
+
+    // callbacks to the proof service
+    pub callback: Arc<ProofCallback>,
+
+    // permits of proving the inputs, it limits the inputs proved at the same time
+    pub proving_permits: Arc<Semaphore>,
+}
+
+impl SidecarService {
+    pub fn new(config: Arc<SidecarConfig>, prover: SidecarProver) -> Self {
+        Self {
+            prover: prover.into(),
+            subblock_proofs: Arc::default(),
+            callback: ProofCallback::new(config.clone()).into(),
+            proving_permits: Semaphore::new(config.max_concurrent_proofs).into(),
+            config,
+        }
+    }
+
+    pub fn run(self: Arc<Self>) -> Vec<JoinHandle<()>> {
+        info!("prover-sidecar: start");
+
+        let agg_handle = self.clone().run_aggregator_service();
+        let subblock_handles = self.run_subblock_services();
+
+        [agg_handle].into_iter().chain(subblock_handles).collect()
+    }
+}
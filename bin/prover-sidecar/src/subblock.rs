@@ -0,0 +1,202 @@
+use crate::{prover::ProvingFailure, service::SidecarService};
+use common::inputs::input_digest;
+use derive_more::Constructor;
+use proof_proto::{
+    ProvingErrorCode, ReportProgressRequest, extract_trace_id, traceparent::TraceParentInterceptor,
+};
+use std::{net::SocketAddr, sync::Arc};
+use subblock_proto::{
+    FILE_DESCRIPTOR_SET, ProveSubblockRequest,
+    subblock_server::{Subblock, SubblockServer},
+};
+use tokio::{
+    signal::ctrl_c,
+    spawn,
+    task::{JoinHandle, spawn_blocking},
+};
+use tonic::{
+    Request, Response, Status, async_trait,
+    codec::CompressionEncoding,
+    service::{LayerExt, interceptor::InterceptedService},
+    transport::Server,
+};
+use tonic_health::server::health_reporter;
+use tonic_web::GrpcWebLayer;
+use tower::ServiceBuilder;
+use tower_http::cors::{Any, CorsLayer};
+use tracing::{error, info};
+
+impl SidecarService {
+    // start the subblock grpc services, one for each subblock address
+    pub fn run_subblock_services(self: Arc<Self>) -> Vec<JoinHandle<()>> {
+        self.config
+            .subblock_addrs()
+            .into_iter()
+            .enumerate()
+            .map(|(i, addr)| self.clone().run_subblock_service(i as u32, addr))
+            .collect()
+    }
+
+    // start the subblock grpc service of an indexed subblock
+    fn run_subblock_service(self: Arc<Self>, index: u32, addr: SocketAddr) -> JoinHandle<()> {
+        info!("prover-sidecar: start subblock {index} grpc service on {addr}");
+
+        spawn(async move {
+            let max_msg_bytes = self.config.max_msg_bytes;
+
+            // create the base grpc service
+            let subblock_service = SidecarSubblockService::new(index, self.clone());
+            let grpc = SubblockServer::new(subblock_service)
+                .max_encoding_message_size(max_msg_bytes)
+                .max_decoding_message_size(max_msg_bytes)
+                .accept_compressed(CompressionEncoding::Zstd)
+                .send_compressed(CompressionEncoding::Zstd);
+
+            // carry the block trace id by the W3C trace context of the requests
+            let grpc = InterceptedService::new(grpc, TraceParentInterceptor);
+
+            // add a web layer to the grpc service
+            let service = ServiceBuilder::new()
+                .layer(
+                    CorsLayer::new()
+                        .allow_origin(Any)
+                        .allow_methods(Any)
+                        .allow_headers(Any),
+                )
+                .layer(GrpcWebLayer::new())
+                .into_inner()
+                .named_layer(grpc);
+
+            // create the health and reflection services for probing and discovering the api
+            let (health_reporter, health_service) = health_reporter();
+            health_reporter
+                .set_serving::<SubblockServer<SidecarSubblockService>>()
+                .await;
+            let reflection_service = tonic_reflection::server::Builder::configure()
+                .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+                .build_v1()
+                .expect("prover-sidecar: failed to build reflection service");
+
+            Server::builder()
+                .accept_http1(true)
+                .add_service(service)
+                .add_service(health_service)
+                .add_service(reflection_service)
+                .serve_with_shutdown(addr, async {
+                    ctrl_c()
+                        .await
+                        .expect("prover-sidecar: failed to wait for graceful shutdown");
+                })
+                .await
+                .expect("prover-sidecar: failed to start subblock service");
+
+            info!("prover-sidecar: subblock {index} grpc service stopped");
+        })
+    }
+}
+
+// subblock grpc service of an indexed subblock
+#[derive(Constructor, Debug)]
+struct SidecarSubblockService {
+    // index of the subblock served by this service
+    index: u32,
+
+    // shared state of the sidecar services
+    service: Arc<SidecarService>,
+}
+
+#[async_trait]
+impl Subblock for SidecarSubblockService {
+    async fn prove_subblock(
+        &self,
+        request: Request<ProveSubblockRequest>,
+    ) -> Result<Response<()>, Status> {
+        let trace_id = extract_trace_id(request.metadata());
+        let request = request.into_inner();
+        let (block_number, subblock_index) = (request.block_number, request.subblock_index);
+        info!(
+            "prover-sidecar: received subblock proving request of block {block_number}, num_subblocks {}, subblock_index {subblock_index}",
+            request.num_subblocks,
+        );
+
+        // each subblock service only accepts the requests of its own subblock
+        if subblock_index != self.index {
+            return Err(Status::invalid_argument(format!(
+                "subblock {subblock_index} of block {block_number} is sent to the service of subblock {}",
+                self.index,
+            )));
+        }
+
+        // verify the received input by its digest if sent
+        if !request.input_digest.is_empty() && input_digest(&request.input) != request.input_digest
+        {
+            return Err(Status::data_loss(format!(
+                "subblock input {subblock_index} of block {block_number} doesn't match its digest",
+            )));
+        }
+
+        // the padded subblocks beyond the block are only for keeping all the services ready
+        if subblock_index >= request.num_subblocks {
+            return Ok(Response::new(()));
+        }
+
+        // prove the input in the background, so the request is acknowledged immediately like the
+        // proving cluster
+        let service = self.service.clone();
+        spawn(async move {
+            let ProveSubblockRequest {
+                block_number,
+                num_subblocks,
+                subblock_index,
+                input,
+                job_id,
+                pipeline_id,
+                ..
+            } = request;
+
+            let permit = service
+                .proving_permits
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("prover-sidecar: proving permits are closed");
+            let prover = service.prover.clone();
+            let outcome = spawn_blocking(move || prover.prove_subblock(&input))
+                .await
+                .unwrap_or_else(|e| {
+                    Err(ProvingFailure::new(
+                        ProvingErrorCode::Unspecified,
+                        format!("proving thread failed {e}"),
+                    ))
+                });
+            drop(permit);
+
+            match &outcome {
+                Ok(proved) => info!(
+                    "prover-sidecar: proved subblock {subblock_index} of block {block_number} with cycles {} in {} ms",
+                    proved.cycles, proved.proving_milliseconds,
+                ),
+                Err(failure) => error!(
+                    "prover-sidecar: failed to prove subblock {subblock_index} of block {block_number} {failure}",
+                ),
+            }
+            let is_proved = outcome.is_ok();
+            service
+                .subblock_proofs
+                .record(job_id, block_number, subblock_index, outcome);
+
+            if is_proved {
+                let progress = ReportProgressRequest {
+                    block_number,
+                    job_id,
+                    pipeline_id,
+                    subblock_index,
+                    num_subblocks,
+                };
+                service.callback.report_progress(progress, &trace_id).await;
+            }
+        });
+
+        Ok(Response::new(()))
+    }
+}
@@ -0,0 +1,307 @@
+use anyhow::{Context, Result, bail};
+use clap::{Parser, Subcommand};
+use common::{
+    ids::{BlockKey, DEFAULT_CHAIN_ID},
+    report::{BlockProvingReport, CSV_HEADER, open_report_store},
+    summary::ProvingCampaignSummary,
+};
+use dotenvy::dotenv;
+use serde_json::json;
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// seconds of a day
+const DAY_SECONDS: u64 = 86_400;
+
+#[derive(Parser)]
+struct Args {
+    #[clap(subcommand)]
+    pub query: ReportQuery,
+
+    #[clap(
+        long,
+        env = "REPORT_STORE_URL",
+        default_value = "sqlite://proving_reports.db",
+        help = "Report store URL of the eth-proofs server, e.g. `sqlite://proving_reports.db`"
+    )]
+    pub report_store_url: String,
+
+    #[clap(
+        long,
+        default_value_t = DEFAULT_CHAIN_ID,
+        help = "Chain id of the queried blocks"
+    )]
+    pub chain_id: u64,
+
+    #[clap(
+        long,
+        default_value = "table",
+        help = "Output format, `table` of the main columns with a summary, `csv` of the all columns or `json` of the reports with a summary"
+    )]
+    pub format: OutputFormat,
+
+    #[clap(long, help = "Only output the failed blocks")]
+    pub failed_only: bool,
+
+    #[clap(long, help = "Output file path; printed to stdout if not specified")]
+    pub output: Option<PathBuf>,
+}
+
+// query of the stored reports
+#[derive(Subcommand)]
+enum ReportQuery {
+    #[clap(about = "Query the report of a block")]
+    Block {
+        #[clap(long, help = "Block number")]
+        block_number: u64,
+    },
+
+    #[clap(about = "Query the reports of a block range (inclusive)")]
+    Range {
+        #[clap(long, help = "First block number of the range")]
+        from_block: u64,
+
+        #[clap(long, help = "Last block number of the range")]
+        to_block: u64,
+    },
+
+    #[clap(about = "Query the reports of the blocks reported in a date range (inclusive, UTC)")]
+    Date {
+        #[clap(long, help = "First date of the range, e.g. `2025-09-01`")]
+        from_date: Date,
+
+        #[clap(
+            long,
+            help = "Last date of the range; only the first date if not specified"
+        )]
+        to_date: Option<Date>,
+    },
+
+    #[clap(about = "Query the reports of the blocks reported in the recent days")]
+    Recent {
+        #[clap(long, default_value = "7", help = "Number of the recent days")]
+        days: u64,
+    },
+}
+
+// format of the output reports
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    // aligned table of the main columns followed by a summary
+    Table,
+
+    // csv of the all columns with a header
+    Csv,
+
+    // json object of the summary and the reports
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(format: &str) -> Result<Self> {
+        match format {
+            "table" => Ok(Self::Table),
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            _ => bail!("unsupported output format `{format}`, expected `table`, `csv` or `json`"),
+        }
+    }
+}
+
+// calendar date in UTC, it's kept as the unix seconds of its start
+#[derive(Clone, Copy, Debug)]
+struct Date(u64);
+
+impl FromStr for Date {
+    type Err = anyhow::Error;
+
+    fn from_str(date: &str) -> Result<Self> {
+        let parse = || -> Option<(i64, u64, u64)> {
+            let mut parts = date.splitn(3, '-');
+            let year = parts.next()?.parse().ok()?;
+            let month = parts.next()?.parse().ok()?;
+            let day = parts.next()?.parse().ok()?;
+            Some((year, month, day))
+        };
+        let Some((year, month, day)) = parse() else {
+            bail!("invalid date `{date}`, expected `YYYY-MM-DD`");
+        };
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            bail!("invalid date `{date}`, expected `YYYY-MM-DD`");
+        }
+        let days = days_from_civil(year, month, day);
+        if days < 0 {
+            bail!("date `{date}` is before the unix epoch");
+        }
+
+        Ok(Self(days as u64 * DAY_SECONDS))
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // setup env
+    dotenv().ok();
+
+    // parse the cli arguments
+    let args = Args::parse();
+
+    // query the reports from the store
+    let report_store = open_report_store(&args.report_store_url)?;
+    let chain_id = args.chain_id;
+    let mut reports = match args.query {
+        ReportQuery::Block { block_number } => report_store
+            .get(BlockKey::new(chain_id, block_number))
+            .await?
+            .into_iter()
+            .collect(),
+        ReportQuery::Range {
+            from_block,
+            to_block,
+        } => report_store.range(chain_id, from_block, to_block).await?,
+        ReportQuery::Date { from_date, to_date } => {
+            let to_date = to_date.unwrap_or(from_date);
+            report_store
+                .reported_between(chain_id, from_date.0, to_date.0 + DAY_SECONDS - 1)
+                .await?
+        }
+        ReportQuery::Recent { days } => {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            let from_timestamp = now.saturating_sub(days * DAY_SECONDS);
+            report_store
+                .reported_between(chain_id, from_timestamp, now)
+                .await?
+        }
+    };
+    if args.failed_only {
+        reports.retain(|report| !report.success);
+    }
+
+    // write the reports to the output file or stdout
+    let mut output: Box<dyn Write> = match &args.output {
+        Some(output) => Box::new(BufWriter::new(
+            File::create(output).with_context(|| format!("failed to create {output:?}"))?,
+        )),
+        None => Box::new(io::stdout().lock()),
+    };
+    match args.format {
+        OutputFormat::Table => write_table(&mut output, &reports)?,
+        OutputFormat::Csv => {
+            writeln!(output, "{CSV_HEADER}")?;
+            for report in &reports {
+                writeln!(output, "{}", report.to_csv_row())?;
+            }
+        }
+        OutputFormat::Json => {
+            let summary = ProvingCampaignSummary::from_reports(&reports);
+            let reports = reports
+                .iter()
+                .map(BlockProvingReport::to_json_value)
+                .collect::<Result<Vec<_>>>()?;
+            let value = json!({ "summary": summary, "reports": reports });
+            writeln!(output, "{}", serde_json::to_string_pretty(&value)?)?;
+        }
+    }
+    output.flush()?;
+
+    Ok(())
+}
+
+// write the main columns of the reports as an aligned table followed by the summary
+fn write_table(output: &mut impl Write, reports: &[BlockProvingReport]) -> Result<()> {
+    writeln!(
+        output,
+        "{:>10} {:>8} {:>7} {:>13} {:>11} {:>9} {:>12} {:>19}  {}",
+        "block",
+        "job",
+        "success",
+        "cycles",
+        "proving_ms",
+        "khz",
+        "gas_used",
+        "reported_at",
+        "failure",
+    )?;
+    for report in reports {
+        let failure = match (&report.failure_stage, &report.failure_reason) {
+            (Some(stage), Some(reason)) => format!("{stage}: {reason}"),
+            (Some(stage), None) => stage.to_string(),
+            (None, Some(reason)) => reason.clone(),
+            (None, None) => String::new(),
+        };
+        writeln!(
+            output,
+            "{:>10} {:>8} {:>7} {:>13} {:>11} {:>9} {:>12} {:>19}  {}",
+            report.block_number,
+            report.job_id.to_string(),
+            report.success,
+            report.cycles,
+            report.proving_milliseconds,
+            report
+                .proving_khz()
+                .map(|khz| format!("{khz:.1}"))
+                .unwrap_or_default(),
+            report.gas_used,
+            format_timestamp(report.reported_at),
+            failure,
+        )?;
+    }
+    writeln!(output)?;
+    writeln!(output, "{}", ProvingCampaignSummary::from_reports(reports))?;
+
+    Ok(())
+}
+
+// format the unix seconds as `YYYY-MM-DD HH:MM:SS` in UTC, it's empty if not recorded
+fn format_timestamp(timestamp: u64) -> String {
+    if timestamp == 0 {
+        return String::new();
+    }
+
+    let (year, month, day) = civil_from_days((timestamp / DAY_SECONDS) as i64);
+    let seconds = timestamp % DAY_SECONDS;
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+    )
+}
+
+// return the days since the unix epoch of a proleptic Gregorian date
+fn days_from_civil(year: i64, month: u64, day: u64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = ((153 * ((month + 9) % 12) + 2) / 5 + day - 1) as i64;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+// return the proleptic Gregorian date of the days since the unix epoch
+fn civil_from_days(days: i64) -> (i64, u64, u64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u64;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u64;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
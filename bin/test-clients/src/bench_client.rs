@@ -0,0 +1,349 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use common::{
+    fetch::{ProveBlockByNumberParams, ProveLatestBlockParams},
+    ids::JobId,
+    logger::setup_logger,
+    report::BlockProvingReport,
+    summary::{ProvingCampaignSummary, percentile},
+};
+use dotenvy::dotenv;
+use fetch_client::{
+    http::{prove_block_by_number, prove_latest_block},
+    ws::{ReportWatcher, append_report},
+};
+use futures::future::try_join_all;
+use reqwest::Url;
+use serde::Serialize;
+use std::{collections::HashMap, fmt, fs, path::PathBuf};
+use tokio::{
+    select, spawn,
+    sync::mpsc::{UnboundedSender, unbounded_channel},
+    time::{Duration, Instant, sleep, sleep_until},
+};
+use tracing::{error, info, warn};
+
+#[derive(Parser)]
+struct Args {
+    #[clap(subcommand)]
+    pub pattern: RequestPattern,
+
+    #[clap(
+        long,
+        default_value = "3600",
+        help = "Maximum seconds of waiting for the all reports since the first request, the missing blocks are counted in the summary"
+    )]
+    pub timeout_seconds: u64,
+
+    #[clap(
+        long,
+        help = "File path of the proving results, it's in NDJSON if ending with `.ndjson` or `.jsonl` otherwise in CSV; only logged if not specified"
+    )]
+    pub report_path: Option<PathBuf>,
+
+    #[clap(
+        long,
+        help = "JSON file path of the benchmark summary; only logged if not specified"
+    )]
+    pub summary_path: Option<PathBuf>,
+
+    #[clap(
+        long,
+        env = "FETCH_HTTP_URL",
+        default_value = "http://127.0.0.1:8080",
+        help = "Fetch service HTTP URL"
+    )]
+    pub http_url: Url,
+
+    #[clap(
+        long,
+        env = "FETCH_WS_URL",
+        default_value = "ws://127.0.0.1:8080",
+        help = "Fetch service websocket URL"
+    )]
+    pub ws_url: Url,
+}
+
+// pattern of the fetch requests submitted for the benchmark
+#[derive(Clone, Copy, Debug, Subcommand)]
+enum RequestPattern {
+    #[clap(
+        about = "Prove a range of blocks by the requests of a batch of blocks at a fixed interval"
+    )]
+    Range {
+        #[clap(long, help = "Start block number of the range")]
+        start_block_num: u64,
+
+        #[clap(long, help = "Number of blocks in the range")]
+        count: u64,
+
+        #[clap(long, default_value = "1", help = "Number of blocks of each request")]
+        batch_size: u64,
+
+        #[clap(
+            long,
+            default_value = "0",
+            help = "Seconds between the requests, the all requests are sent at once if zero"
+        )]
+        interval_seconds: u64,
+    },
+
+    #[clap(about = "Prove the consecutive blocks by bursts of concurrent single-block requests")]
+    Burst {
+        #[clap(long, help = "Block number of the first request")]
+        start_block_num: u64,
+
+        #[clap(long, help = "Number of the concurrent requests of each burst")]
+        burst_size: u64,
+
+        #[clap(long, default_value = "1", help = "Number of the bursts")]
+        bursts: u64,
+
+        #[clap(long, default_value = "60", help = "Seconds between the bursts")]
+        interval_seconds: u64,
+    },
+
+    #[clap(about = "Follow the chain by proving the latest blocks as they're produced")]
+    Latest {
+        #[clap(long, help = "Number of the latest blocks to prove")]
+        count: u64,
+    },
+}
+
+impl RequestPattern {
+    // return the number of the requested blocks
+    fn block_count(&self) -> u64 {
+        match *self {
+            Self::Range { count, .. } | Self::Latest { count } => count,
+            Self::Burst {
+                burst_size, bursts, ..
+            } => burst_size * bursts,
+        }
+    }
+
+    // send the requests of the pattern, the job id and sending time of each request are sent to
+    // the collector
+    async fn send_requests(
+        self,
+        http_url: Url,
+        submitted: UnboundedSender<(JobId, Instant)>,
+    ) -> Result<()> {
+        match self {
+            Self::Range {
+                start_block_num,
+                count,
+                batch_size,
+                interval_seconds,
+            } => {
+                let end_block_num = start_block_num + count;
+                let batch_size = batch_size.max(1);
+                for batch_start in (start_block_num..end_block_num).step_by(batch_size as usize) {
+                    if batch_start > start_block_num {
+                        sleep(Duration::from_secs(interval_seconds)).await;
+                    }
+                    let batch_count = batch_size.min(end_block_num - batch_start);
+                    let params = ProveBlockByNumberParams::new(batch_start, Some(batch_count));
+                    let sent_at = Instant::now();
+                    let job_id = prove_block_by_number(&http_url, &params).await?;
+                    let _ = submitted.send((job_id, sent_at));
+                }
+            }
+            Self::Burst {
+                start_block_num,
+                burst_size,
+                bursts,
+                interval_seconds,
+            } => {
+                for burst in 0..bursts {
+                    if burst > 0 {
+                        sleep(Duration::from_secs(interval_seconds)).await;
+                    }
+                    let first_block_num = start_block_num + burst * burst_size;
+                    let requests =
+                        (first_block_num..first_block_num + burst_size).map(|block_num| {
+                            let http_url = &http_url;
+                            async move {
+                                let params = ProveBlockByNumberParams::new(block_num, Some(1));
+                                let sent_at = Instant::now();
+                                let job_id = prove_block_by_number(http_url, &params).await?;
+                                anyhow::Ok((job_id, sent_at))
+                            }
+                        });
+                    for request in try_join_all(requests).await? {
+                        let _ = submitted.send(request);
+                    }
+                }
+            }
+            Self::Latest { count } => {
+                let params = ProveLatestBlockParams::new(Some(count));
+                let sent_at = Instant::now();
+                let job_id = prove_latest_block(&http_url, &params).await?;
+                let _ = submitted.send((job_id, sent_at));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// summary of a benchmark run, the latency of a block is from sending its request to receiving its
+// report, so it includes the queueing in the pipeline
+#[derive(Debug, Serialize)]
+struct BenchSummary {
+    // number of the requested blocks
+    requested_blocks: u64,
+
+    // number of the reported blocks
+    reported_blocks: u64,
+
+    // seconds from sending the first request to receiving the last report
+    elapsed_seconds: f64,
+
+    // reported blocks per minute
+    blocks_per_minute: f64,
+
+    // median latency milliseconds
+    latency_p50_milliseconds: u64,
+
+    // 90th percentile of the latency milliseconds
+    latency_p90_milliseconds: u64,
+
+    // 99th percentile of the latency milliseconds
+    latency_p99_milliseconds: u64,
+
+    // maximum latency milliseconds
+    latency_max_milliseconds: u64,
+
+    // summary of the proving results
+    proving: ProvingCampaignSummary,
+}
+
+impl BenchSummary {
+    // summarize the reports received at the instants, the latencies are measured for the blocks
+    // of the known requests
+    fn new(
+        requested_blocks: u64,
+        started_at: Instant,
+        submitted: &HashMap<JobId, Instant>,
+        reports: &[(BlockProvingReport, Instant)],
+    ) -> Self {
+        let mut latency_milliseconds: Vec<u64> = reports
+            .iter()
+            .filter_map(|(report, received_at)| {
+                let sent_at = submitted.get(&report.job_id)?;
+                Some(received_at.duration_since(*sent_at).as_millis() as u64)
+            })
+            .collect();
+        latency_milliseconds.sort_unstable();
+
+        let elapsed_seconds = reports
+            .iter()
+            .map(|(_, received_at)| received_at.duration_since(started_at))
+            .max()
+            .unwrap_or_default()
+            .as_secs_f64();
+        let reported_blocks = reports.len() as u64;
+        let blocks_per_minute = if elapsed_seconds > 0.0 {
+            reported_blocks as f64 * 60.0 / elapsed_seconds
+        } else {
+            0.0
+        };
+
+        Self {
+            requested_blocks,
+            reported_blocks,
+            elapsed_seconds,
+            blocks_per_minute,
+            latency_p50_milliseconds: percentile(&latency_milliseconds, 50),
+            latency_p90_milliseconds: percentile(&latency_milliseconds, 90),
+            latency_p99_milliseconds: percentile(&latency_milliseconds, 99),
+            latency_max_milliseconds: latency_milliseconds.last().copied().unwrap_or_default(),
+            proving: ProvingCampaignSummary::from_reports(reports.iter().map(|(report, _)| report)),
+        }
+    }
+}
+
+impl fmt::Display for BenchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "reported: {}/{} blocks in {:.1} s | throughput: {:.2} blocks/min | latency p50/p90/p99/max: {}/{}/{}/{} ms | {}",
+            self.reported_blocks,
+            self.requested_blocks,
+            self.elapsed_seconds,
+            self.blocks_per_minute,
+            self.latency_p50_milliseconds,
+            self.latency_p90_milliseconds,
+            self.latency_p99_milliseconds,
+            self.latency_max_milliseconds,
+            self.proving,
+        )
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // setup env and logger
+    dotenv().ok();
+    setup_logger();
+
+    // parse the cli arguments
+    let args = Args::parse();
+    let requested_blocks = args.pattern.block_count();
+
+    // connect the websocket before sending the requests, so no report is missed
+    let mut watcher = ReportWatcher::connect(&args.ws_url).await?;
+
+    // send the requests of the pattern in another thread
+    let started_at = Instant::now();
+    let (submitted_sender, mut submitted_receiver) = unbounded_channel();
+    let sender_thread = spawn(
+        args.pattern
+            .send_requests(args.http_url.clone(), submitted_sender),
+    );
+
+    // collect the reports of the requested blocks until the timeout
+    let deadline = started_at + Duration::from_secs(args.timeout_seconds);
+    let mut submitted = HashMap::new();
+    let mut reports = vec![];
+    while (reports.len() as u64) < requested_blocks {
+        select! {
+            report = watcher.next_report() => {
+                let Some(mut report) = report? else {
+                    break;
+                };
+                append_report(&report, &args.report_path)?;
+                report.proof = None;
+                reports.push((report, Instant::now()));
+            }
+            Some((job_id, sent_at)) = submitted_receiver.recv() => {
+                info!("bench-client: sent the request of job {job_id}");
+                submitted.insert(job_id, sent_at);
+            }
+            _ = sleep_until(deadline) => {
+                warn!("bench-client: timed out waiting for the reports");
+                break;
+            }
+        }
+    }
+    watcher.close().await;
+
+    // collect the requests sent but not received yet
+    sender_thread.abort();
+    match sender_thread.await {
+        Ok(Err(e)) => error!("bench-client: failed to send the requests {e:?}"),
+        Ok(Ok(())) | Err(_) => {}
+    }
+    while let Ok((job_id, sent_at)) = submitted_receiver.try_recv() {
+        submitted.insert(job_id, sent_at);
+    }
+
+    // summarize the benchmark
+    let summary = BenchSummary::new(requested_blocks, started_at, &submitted, &reports);
+    info!("bench-client: benchmark summary {summary}");
+    if let Some(summary_path) = &args.summary_path {
+        fs::write(summary_path, serde_json::to_string(&summary)?)?;
+    }
+
+    Ok(())
+}
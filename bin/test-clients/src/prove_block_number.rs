@@ -0,0 +1,167 @@
+use anyhow::{Context, Result, anyhow};
+use clap::Parser;
+use common::{
+    fetch::{ProveBlockByNumberParams, ReportParams},
+    ids::JobId,
+    logger::setup_logger,
+    report::BlockProvingReport,
+};
+use dotenvy::dotenv;
+use fetch_client::{
+    http::{self, prove_block_by_number},
+    ws::{ReportWatcher, append_report},
+};
+use proof_service::verifier::ProofVerifier;
+use reqwest::Url;
+use std::{path::PathBuf, process::exit};
+use tokio::time::{Duration, timeout};
+use tracing::{error, info, warn};
+
+// exit code if the block failed to be proved
+const EXIT_PROVING_FAILED: i32 = 2;
+
+// exit code if the proof of the block is invalid
+const EXIT_INVALID_PROOF: i32 = 3;
+
+// exit code if no report of the block is received within the timeout
+const EXIT_TIMEOUT: i32 = 4;
+
+#[derive(Parser)]
+struct Args {
+    #[clap(long, help = "Block number to prove")]
+    pub block_number: u64,
+
+    #[clap(
+        long,
+        default_value = "3600",
+        help = "Maximum seconds of waiting for the report of the block"
+    )]
+    pub timeout_seconds: u64,
+
+    #[clap(
+        long,
+        env = "AGG_ELF_PATH",
+        default_value = "data/aggregator-elf",
+        help = "Aggregator ELF file path, the proof is verified against its verification key"
+    )]
+    pub agg_elf_path: PathBuf,
+
+    #[clap(long, help = "Skip verifying the proof, e.g. for the mock proving")]
+    pub skip_verification: bool,
+
+    #[clap(
+        long,
+        help = "File path of the proving result, it's in NDJSON if ending with `.ndjson` or `.jsonl` otherwise in CSV; only logged if not specified"
+    )]
+    pub report_path: Option<PathBuf>,
+
+    #[clap(
+        long,
+        env = "FETCH_HTTP_URL",
+        default_value = "http://127.0.0.1:8080",
+        help = "Fetch service HTTP URL"
+    )]
+    pub http_url: Url,
+
+    #[clap(
+        long,
+        env = "FETCH_WS_URL",
+        default_value = "ws://127.0.0.1:8080",
+        help = "Fetch service websocket URL"
+    )]
+    pub ws_url: Url,
+}
+
+// prove a single block and verify its proof, the exit code is zero only if the block is proved
+// with a valid proof
+#[tokio::main]
+async fn main() -> Result<()> {
+    // setup env and logger
+    dotenv().ok();
+    setup_logger();
+
+    // parse the cli arguments
+    let args = Args::parse();
+    let block_number = args.block_number;
+
+    // connect the websocket before sending the request, so the report is not missed
+    let mut watcher = ReportWatcher::connect(&args.ws_url).await?;
+
+    // send a http request for proving the block
+    let params = ProveBlockByNumberParams::new(block_number, Some(1));
+    let job_id = prove_block_by_number(&args.http_url, &params).await?;
+    info!("prove-block-number: requested block {block_number} by job {job_id}");
+
+    // wait for the report of the block
+    let report = timeout(
+        Duration::from_secs(args.timeout_seconds),
+        wait_report(&mut watcher, job_id, block_number),
+    )
+    .await;
+    watcher.close().await;
+    let report = match report {
+        Ok(report) => report?,
+        Err(_) => {
+            error!("prove-block-number: timed out waiting for the report of block {block_number}");
+            exit(EXIT_TIMEOUT);
+        }
+    };
+    info!("prove-block-number: received the report {report}");
+    append_report(&report, &args.report_path)?;
+
+    if !report.success {
+        error!("prove-block-number: failed to prove block {block_number}");
+        exit(EXIT_PROVING_FAILED);
+    }
+    if args.skip_verification {
+        warn!("prove-block-number: skipped verifying the proof of block {block_number}");
+        return Ok(());
+    }
+
+    // verify the proof carried by the report or downloaded from the fetch service
+    let proof = load_proof(&args.http_url, &report).await?;
+    let verifier = ProofVerifier::new(&args.agg_elf_path).with_context(|| {
+        format!(
+            "failed to create the proof verifier by {:?}",
+            args.agg_elf_path,
+        )
+    })?;
+    if let Err(e) = verifier.verify_public_values(&proof) {
+        error!("prove-block-number: invalid proof of block {block_number} {e:?}");
+        exit(EXIT_INVALID_PROOF);
+    }
+    info!("prove-block-number: verified the proof of block {block_number}");
+
+    Ok(())
+}
+
+// wait for the report of the block requested by the job, the reports of the other requests are
+// skipped
+async fn wait_report(
+    watcher: &mut ReportWatcher,
+    job_id: JobId,
+    block_number: u64,
+) -> Result<BlockProvingReport> {
+    while let Some(report) = watcher.next_report().await? {
+        if report.job_id == job_id && report.block_number == block_number {
+            return Ok(report);
+        }
+    }
+
+    Err(anyhow!(
+        "websocket is closed before the report of block {block_number}"
+    ))
+}
+
+// return the proof bytes of the report, it's downloaded from the fetch service if persisted by the
+// proof service
+async fn load_proof(http_url: &Url, report: &BlockProvingReport) -> Result<Vec<u8>> {
+    if let Some(proof) = &report.proof {
+        return Ok(proof.clone());
+    }
+
+    let params = ReportParams::new(report.block_number, Some(report.chain_id));
+    http::proof(http_url, &params)
+        .await?
+        .ok_or_else(|| anyhow!("no proof of block {} is found", report.block_number))
+}
@@ -0,0 +1,174 @@
+use anyhow::Result;
+use clap::Parser;
+use common::{
+    fetch::{
+        HTTP_BACKFILL_BLOCK_RANGE_PATH, HTTP_ESTIMATE_BLOCK_PATH, HTTP_PROVE_BLOCK_BY_NUMBER_PATH,
+        HTTP_PROVE_LATEST_BLOCK_PATH, HTTP_REPRODUCE_BLOCK_BY_LIST_PATH,
+        HTTP_REPRODUCE_BLOCK_BY_NUMBER_PATH, HTTP_REPRODUCE_LATEST_BLOCK_PATH,
+    },
+    logger::setup_logger,
+};
+use dotenvy::dotenv;
+use messages::FetchMsg;
+use reqwest::{Client, Url};
+use scheduler::journal::{JournalPayload, read_journal};
+use std::{collections::HashMap, path::PathBuf};
+use tracing::info;
+
+#[derive(Parser)]
+struct Args {
+    #[clap(
+        long,
+        help = "Message journal file path written by the eth-proofs server"
+    )]
+    pub journal_path: PathBuf,
+
+    #[clap(
+        long,
+        value_delimiter = ',',
+        help = "Job ids of the fetch requests to replay separated by comma; the all fetch requests are replayed if not specified"
+    )]
+    pub job_ids: Option<Vec<u64>>,
+
+    #[clap(
+        long,
+        default_value = "false",
+        help = "Only print the fetch requests in the journal without sending them"
+    )]
+    pub dry_run: bool,
+
+    #[clap(
+        long,
+        env = "FETCH_HTTP_URL",
+        default_value = "http://127.0.0.1:8080",
+        help = "Fetch service HTTP URL"
+    )]
+    pub http_url: Url,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // setup env and logger
+    dotenv().ok();
+    setup_logger();
+
+    // parse the cli arguments
+    let args = Args::parse();
+
+    // collect the fetch requests in the journal, the other messages are summarized and could not
+    // be re-injected
+    let fetch_msgs: Vec<_> = read_journal(&args.journal_path)?
+        .into_iter()
+        .filter_map(|entry| match entry.payload {
+            JournalPayload::Fetch { fetch_msg } => Some(fetch_msg),
+            _ => None,
+        })
+        .filter(|fetch_msg| {
+            args.job_ids
+                .as_ref()
+                .is_none_or(|job_ids| job_ids.contains(&fetch_msg.job_id().into()))
+        })
+        .collect();
+    info!(
+        "replay-journal: found {} fetch requests to replay",
+        fetch_msgs.len()
+    );
+
+    // re-send the fetch requests by the journal order
+    for fetch_msg in fetch_msgs {
+        let (path, params) = to_http_request(&fetch_msg);
+        if args.dry_run {
+            info!("replay-journal: {path} {params:?}");
+            continue;
+        }
+
+        let url = args.http_url.join(path)?;
+        info!(
+            "replay-journal: replaying job {}: url = {url}, params = {params:?}",
+            fetch_msg.job_id()
+        );
+        let job_id = Client::new()
+            .get(url)
+            .query(&params)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        info!("replay-journal: replayed as job {job_id}");
+    }
+
+    Ok(())
+}
+
+// convert a journaled fetch message to the HTTP request path and parameters
+fn to_http_request(fetch_msg: &FetchMsg) -> (&'static str, HashMap<&'static str, String>) {
+    let (path, mut params) = match fetch_msg {
+        FetchMsg::ProveFromStart {
+            start_block_number,
+            count,
+            ..
+        } => (
+            HTTP_PROVE_BLOCK_BY_NUMBER_PATH,
+            HashMap::from([
+                ("start_block_num", start_block_number.to_string()),
+                ("count", count.to_string()),
+            ]),
+        ),
+        FetchMsg::ProveLatest { count, .. } => (
+            HTTP_PROVE_LATEST_BLOCK_PATH,
+            HashMap::from([("count", count.to_string())]),
+        ),
+        FetchMsg::ReproduceFromStart {
+            start_block_number,
+            count,
+            ..
+        } => (
+            HTTP_REPRODUCE_BLOCK_BY_NUMBER_PATH,
+            HashMap::from([
+                ("start_block_num", start_block_number.to_string()),
+                ("count", count.to_string()),
+            ]),
+        ),
+        FetchMsg::ReproduceLatest { count, .. } => (
+            HTTP_REPRODUCE_LATEST_BLOCK_PATH,
+            HashMap::from([("count", count.to_string())]),
+        ),
+        FetchMsg::ReproduceByList { block_numbers, .. } => {
+            let block_nums = block_numbers
+                .iter()
+                .map(|block_number| block_number.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            (
+                HTTP_REPRODUCE_BLOCK_BY_LIST_PATH,
+                HashMap::from([("block_nums", block_nums)]),
+            )
+        }
+        FetchMsg::Backfill {
+            start_block_number,
+            end_block_number,
+            ..
+        } => (
+            HTTP_BACKFILL_BLOCK_RANGE_PATH,
+            HashMap::from([
+                ("start_block_num", start_block_number.to_string()),
+                ("end_block_num", end_block_number.to_string()),
+            ]),
+        ),
+        FetchMsg::Estimate {
+            start_block_number,
+            count,
+            ..
+        } => (
+            HTTP_ESTIMATE_BLOCK_PATH,
+            HashMap::from([
+                ("start_block_num", start_block_number.to_string()),
+                ("count", count.to_string()),
+            ]),
+        ),
+    };
+    params.insert("pipeline_id", fetch_msg.pipeline_id().to_string());
+
+    (path, params)
+}
@@ -17,10 +17,16 @@ struct Args {
     #[clap(
         long,
         default_value = "proving_report.csv",
-        help = "CSV file path containing the proving result"
+        help = "CSV file path containing the proving result, it's in NDJSON if ending with `.ndjson` or `.jsonl`"
     )]
     pub report_path: PathBuf,
 
+    #[clap(
+        long,
+        help = "File path of the summary of the proving results, it's in JSON if ending with `.json` otherwise appended in CSV; only logged if not specified"
+    )]
+    pub summary_path: Option<PathBuf>,
+
     #[clap(
         long,
         env = "FETCH_HTTP_URL",
@@ -52,5 +58,11 @@ async fn main() -> Result<()> {
     reproduce_block_by_number(&args.http_url, &params).await?;
 
     // wait for the proving result by a websocket connection
-    wait_for_proving_complete(&args.ws_url, args.count as usize, &Some(args.report_path)).await
+    wait_for_proving_complete(
+        &args.ws_url,
+        args.count as usize,
+        &Some(args.report_path),
+        &args.summary_path,
+    )
+    .await
 }
@@ -0,0 +1,360 @@
+use anyhow::Result;
+use clap::Parser;
+use common::{
+    fetch::ProveLatestBlockParams, ids::JobId, logger::setup_logger, report::BlockProvingReport,
+    summary::ProvingCampaignSummary,
+};
+use dotenvy::dotenv;
+use fetch_client::{
+    http::prove_latest_block,
+    ws::{ReportWatcher, append_report},
+};
+use reqwest::Url;
+use serde::Serialize;
+use std::{
+    collections::BTreeSet,
+    fmt,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    process::exit,
+};
+use tokio::{
+    select,
+    time::{Duration, Instant, interval, sleep_until},
+};
+use tracing::{error, info, warn};
+
+// exit code if the soak test found gaps or duplicates of the reported blocks
+const EXIT_UNHEALTHY: i32 = 2;
+
+// seconds of an Ethereum slot, it estimates the number of blocks produced in the soak duration
+const SLOT_SECONDS: u64 = 12;
+
+// maximum number of the missing block ranges kept in a health summary
+const MAX_MISSING_RANGES: usize = 20;
+
+#[derive(Parser)]
+struct Args {
+    #[clap(
+        long,
+        default_value = "6",
+        help = "Hours of following the chain, the latest blocks produced in the duration are requested"
+    )]
+    pub duration_hours: u64,
+
+    #[clap(
+        long,
+        default_value = "1800",
+        help = "Maximum seconds of waiting for the blocks in flight after the duration"
+    )]
+    pub grace_seconds: u64,
+
+    #[clap(
+        long,
+        default_value = "600",
+        help = "Seconds between the periodic health summaries"
+    )]
+    pub summary_interval_seconds: u64,
+
+    #[clap(
+        long,
+        default_value = "300",
+        help = "Seconds without any report before the stall is warned"
+    )]
+    pub stall_seconds: u64,
+
+    #[clap(
+        long,
+        help = "Only watch the reports of a server already following the chain, e.g. started with `--follow-on-start`, without sending a request"
+    )]
+    pub watch_only: bool,
+
+    #[clap(
+        long,
+        default_value = "soak_report.csv",
+        help = "File path of the all received reports, it's in NDJSON if ending with `.ndjson` or `.jsonl` otherwise in CSV"
+    )]
+    pub report_path: PathBuf,
+
+    #[clap(
+        long,
+        help = "File path of the health summaries appended as JSON lines; only logged if not specified"
+    )]
+    pub summary_path: Option<PathBuf>,
+
+    #[clap(
+        long,
+        env = "FETCH_HTTP_URL",
+        default_value = "http://127.0.0.1:8080",
+        help = "Fetch service HTTP URL"
+    )]
+    pub http_url: Url,
+
+    #[clap(
+        long,
+        env = "FETCH_WS_URL",
+        default_value = "ws://127.0.0.1:8080",
+        help = "Fetch service websocket URL"
+    )]
+    pub ws_url: Url,
+}
+
+// tracker of the reported blocks in a soak test
+struct SoakTracker {
+    // instant of starting the soak test
+    started_at: Instant,
+
+    // instant of receiving the last report
+    last_reported_at: Instant,
+
+    // block numbers of the received reports
+    block_numbers: BTreeSet<u64>,
+
+    // number of the reports of an already reported block
+    duplicate_blocks: u64,
+
+    // received reports without the proofs
+    reports: Vec<BlockProvingReport>,
+}
+
+impl SoakTracker {
+    // create a tracker started now
+    fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            started_at: now,
+            last_reported_at: now,
+            block_numbers: BTreeSet::new(),
+            duplicate_blocks: 0,
+            reports: vec![],
+        }
+    }
+
+    // record a received report, the duplicate block is warned
+    fn record(&mut self, mut report: BlockProvingReport) {
+        self.last_reported_at = Instant::now();
+        if !self.block_numbers.insert(report.block_number) {
+            warn!(
+                "soak-client: received a duplicate report of block {}",
+                report.block_number,
+            );
+            self.duplicate_blocks += 1;
+        }
+        report.proof = None;
+        self.reports.push(report);
+    }
+
+    // return the number of the distinct reported blocks
+    fn reported_blocks(&self) -> u64 {
+        self.block_numbers.len() as u64
+    }
+
+    // return the inclusive ranges of the blocks not reported between the lowest and highest
+    // reported blocks
+    fn missing_ranges(&self) -> Vec<(u64, u64)> {
+        let mut missing_ranges = vec![];
+        let mut block_numbers = self.block_numbers.iter();
+        let Some(mut previous) = block_numbers.next().copied() else {
+            return missing_ranges;
+        };
+        for &block_number in block_numbers {
+            if block_number > previous + 1 {
+                missing_ranges.push((previous + 1, block_number - 1));
+            }
+            previous = block_number;
+        }
+
+        missing_ranges
+    }
+
+    // summarize the health of the reports received so far
+    fn health(&self) -> SoakHealth {
+        let elapsed_seconds = self.started_at.elapsed().as_secs_f64();
+        let reported_blocks = self.reported_blocks();
+        let missing_ranges = self.missing_ranges();
+        let blocks_per_hour = if elapsed_seconds > 0.0 {
+            reported_blocks as f64 * 3600.0 / elapsed_seconds
+        } else {
+            0.0
+        };
+
+        SoakHealth {
+            elapsed_seconds,
+            reported_blocks,
+            blocks_per_hour,
+            duplicate_blocks: self.duplicate_blocks,
+            missing_blocks: missing_ranges
+                .iter()
+                .map(|(first, last)| last - first + 1)
+                .sum(),
+            missing_ranges: missing_ranges
+                .into_iter()
+                .take(MAX_MISSING_RANGES)
+                .collect(),
+            seconds_since_last_report: self.last_reported_at.elapsed().as_secs(),
+            proving: ProvingCampaignSummary::from_reports(&self.reports),
+        }
+    }
+}
+
+// health summary of a soak test, the gaps are counted between the lowest and highest reported
+// blocks, so the blocks still in flight above the highest one are not counted
+#[derive(Debug, Serialize)]
+struct SoakHealth {
+    // seconds since the soak test started
+    elapsed_seconds: f64,
+
+    // number of the distinct reported blocks
+    reported_blocks: u64,
+
+    // distinct reported blocks per hour
+    blocks_per_hour: f64,
+
+    // number of the reports of an already reported block
+    duplicate_blocks: u64,
+
+    // number of the blocks not reported between the lowest and highest reported blocks
+    missing_blocks: u64,
+
+    // first inclusive ranges of the missing blocks
+    missing_ranges: Vec<(u64, u64)>,
+
+    // seconds since the last report was received
+    seconds_since_last_report: u64,
+
+    // summary of the proving results
+    proving: ProvingCampaignSummary,
+}
+
+impl SoakHealth {
+    // identify if no gap or duplicate of the reported blocks is found
+    fn is_healthy(&self) -> bool {
+        self.duplicate_blocks == 0 && self.missing_blocks == 0
+    }
+
+    // append the health summary to a file as a JSON line
+    fn append_to_ndjson(&self, path: &Path) -> Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", serde_json::to_string(self)?)?;
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for SoakHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "elapsed: {:.0} s | reported: {} blocks ({:.1} blocks/h) | duplicates: {} | missing: {} blocks {:?} | last report: {} s ago | {}",
+            self.elapsed_seconds,
+            self.reported_blocks,
+            self.blocks_per_hour,
+            self.duplicate_blocks,
+            self.missing_blocks,
+            self.missing_ranges,
+            self.seconds_since_last_report,
+            self.proving,
+        )
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // setup env and logger
+    dotenv().ok();
+    setup_logger();
+
+    // parse the cli arguments
+    let args = Args::parse();
+    let duration = Duration::from_secs(args.duration_hours * 3600);
+
+    // connect the websocket before sending the request, so no report is missed
+    let mut watcher = ReportWatcher::connect(&args.ws_url).await?;
+
+    // request the latest blocks produced in the duration, the reports of the other requests are
+    // skipped
+    let mut tracker = SoakTracker::new();
+    let (job_id, requested_blocks) = if args.watch_only {
+        (None, None)
+    } else {
+        let requested_blocks = duration.as_secs() / SLOT_SECONDS;
+        let params = ProveLatestBlockParams::new(Some(requested_blocks));
+        let job_id = prove_latest_block(&args.http_url, &params).await?;
+        info!("soak-client: requested {requested_blocks} latest blocks by job {job_id}");
+        (Some(job_id), Some(requested_blocks))
+    };
+
+    // receive the reports until the duration ends, or the requested blocks are reported within
+    // the grace period
+    let deadline = tracker.started_at
+        + duration
+        + if args.watch_only {
+            Duration::ZERO
+        } else {
+            Duration::from_secs(args.grace_seconds)
+        };
+    let mut summary_interval = interval(Duration::from_secs(args.summary_interval_seconds.max(1)));
+    summary_interval.reset();
+    let stall_duration = Duration::from_secs(args.stall_seconds);
+    let report_path = Some(args.report_path.clone());
+    let mut closed = false;
+    while requested_blocks.is_none_or(|requested| tracker.reported_blocks() < requested) {
+        select! {
+            report = watcher.next_report() => {
+                let Some(report) = report? else {
+                    error!("soak-client: websocket is closed by the server");
+                    closed = true;
+                    break;
+                };
+                if is_requested(&report, job_id) {
+                    append_report(&report, &report_path)?;
+                    tracker.record(report);
+                }
+            }
+            _ = summary_interval.tick() => {
+                let health = tracker.health();
+                info!("soak-client: health summary {health}");
+                if tracker.last_reported_at.elapsed() >= stall_duration {
+                    warn!(
+                        "soak-client: no report is received in the last {} seconds",
+                        health.seconds_since_last_report,
+                    );
+                }
+                if let Some(summary_path) = &args.summary_path {
+                    health.append_to_ndjson(summary_path)?;
+                }
+            }
+            _ = sleep_until(deadline) => {
+                info!("soak-client: soak duration is over");
+                break;
+            }
+        }
+    }
+    watcher.close().await;
+
+    // summarize the soak test, it's unhealthy if any block is missing or duplicate
+    let health = tracker.health();
+    info!("soak-client: final health summary {health}");
+    if let Some(summary_path) = &args.summary_path {
+        health.append_to_ndjson(summary_path)?;
+    }
+    let unreported_blocks = requested_blocks
+        .map(|requested| requested.saturating_sub(tracker.reported_blocks()))
+        .unwrap_or_default();
+    if unreported_blocks > 0 {
+        error!("soak-client: {unreported_blocks} requested blocks are not reported");
+    }
+    if closed || unreported_blocks > 0 || !health.is_healthy() {
+        error!("soak-client: soak test is unhealthy");
+        exit(EXIT_UNHEALTHY);
+    }
+    info!("soak-client: soak test is healthy");
+
+    Ok(())
+}
+
+// identify if the report is of the requested job, the all reports are accepted if only watching
+fn is_requested(report: &BlockProvingReport, job_id: Option<JobId>) -> bool {
+    job_id.is_none_or(|job_id| report.job_id == job_id)
+}
@@ -0,0 +1,231 @@
+use alloy_primitives::{B256, Bytes};
+use anyhow::{Context, Result, anyhow, ensure};
+use clap::Parser;
+use common::{
+    fetch::ReportParams,
+    ids::{BlockKey, DEFAULT_CHAIN_ID},
+    logger::setup_logger,
+    proof_store::{ProofStore, proof_hash},
+    report::{BlockProvingReport, open_report_store},
+};
+use dotenvy::dotenv;
+use fetch_client::http;
+use proof_service::verifier::ProofVerifier;
+use reqwest::Url;
+use std::{fs, path::PathBuf};
+use tracing::{info, warn};
+
+#[derive(Parser)]
+struct Args {
+    #[clap(
+        long,
+        required_unless_present = "block_number",
+        conflicts_with = "block_number",
+        help = "File of the bincode serialized aggregated proof, e.g. `proofs/block23264565/job3.bin`"
+    )]
+    pub proof_path: Option<PathBuf>,
+
+    #[clap(
+        long,
+        help = "Block number of the proof loaded by its report from the fetch service or the report store"
+    )]
+    pub block_number: Option<u64>,
+
+    #[clap(
+        long,
+        help = "Chain id of the block; the Ethereum mainnet if not specified"
+    )]
+    pub chain_id: Option<u64>,
+
+    #[clap(
+        long,
+        env = "FETCH_HTTP_URL",
+        default_value = "http://127.0.0.1:8080",
+        help = "Fetch service HTTP URL for loading the report and proof of the block"
+    )]
+    pub http_url: Url,
+
+    #[clap(
+        long,
+        help = "Report store URL of the eth-proofs server for loading the report of the block offline instead of the fetch service, e.g. `sqlite://proving_reports.db`"
+    )]
+    pub report_store_url: Option<String>,
+
+    #[clap(
+        long,
+        help = "Proof store directory or object store URL of the eth-proofs server for loading the persisted proof with `--report-store-url`"
+    )]
+    pub proof_store_dir: Option<PathBuf>,
+
+    #[clap(
+        long,
+        env = "AGG_ELF_PATH",
+        default_value = "data/aggregator-elf",
+        help = "Aggregator ELF file path, the proof is verified against its verification key"
+    )]
+    pub agg_elf_path: PathBuf,
+
+    #[clap(
+        long,
+        help = "Expected block hash committed by the aggregator, e.g. from a trusted RPC node"
+    )]
+    pub expected_block_hash: Option<B256>,
+
+    #[clap(
+        long,
+        conflicts_with = "expected_block_hash",
+        help = "Expected public values committed by the aggregator in hex"
+    )]
+    pub expected_public_values: Option<Bytes>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // setup env and logger
+    dotenv().ok();
+    setup_logger();
+
+    // parse the cli arguments
+    let args = Args::parse();
+
+    // load the proof from the file or by the report of the block
+    let proof = load_proof(&args).await?;
+    info!(
+        "verify-proof: loaded the proof of {} bytes with hash {}",
+        proof.len(),
+        proof_hash(&proof),
+    );
+
+    // verify the proof against the aggregator verification key
+    let verifier = ProofVerifier::new(&args.agg_elf_path).with_context(|| {
+        format!(
+            "failed to create the proof verifier by {:?}",
+            args.agg_elf_path,
+        )
+    })?;
+    let public_values = verifier.verify_public_values(&proof)?;
+    info!("verify-proof: the proof is valid for the aggregator verification key");
+    info!(
+        "verify-proof: committed public values {}",
+        Bytes::copy_from_slice(&public_values),
+    );
+    if let Ok(block_hash) = bincode::deserialize::<B256>(&public_values) {
+        info!("verify-proof: committed block hash {block_hash}");
+    }
+
+    // check the committed public values if expected
+    let expected_public_values = match (&args.expected_block_hash, &args.expected_public_values) {
+        (Some(block_hash), _) => Some(bincode::serialize(block_hash)?),
+        (_, Some(public_values)) => Some(public_values.to_vec()),
+        _ => None,
+    };
+    match expected_public_values {
+        Some(expected_public_values) => {
+            ensure!(
+                public_values == expected_public_values,
+                "committed public values {} mismatch the expected {}",
+                Bytes::from(public_values),
+                Bytes::from(expected_public_values),
+            );
+            info!("verify-proof: the committed public values match the expected");
+        }
+        None => warn!("verify-proof: no expected public values, only the proof is verified"),
+    }
+
+    Ok(())
+}
+
+// load the proof from the file, or the proof of the block by its report in the report store or
+// the fetch service
+async fn load_proof(args: &Args) -> Result<Vec<u8>> {
+    if let Some(proof_path) = &args.proof_path {
+        return fs::read(proof_path)
+            .with_context(|| format!("failed to read the proof {proof_path:?}"));
+    }
+
+    let block_number = args
+        .block_number
+        .expect("verify-proof: block number is required without the proof path");
+    let params = ReportParams::new(block_number, args.chain_id);
+    let proof = match &args.report_store_url {
+        Some(report_store_url) => load_stored_proof(args, report_store_url, &params).await?,
+        None => load_served_proof(&args.http_url, &params).await?,
+    };
+
+    proof.ok_or_else(|| anyhow!("no proof of block {block_number} is found"))
+}
+
+// load the proof of a block by its report in the report store, the persisted proof is read from
+// the proof store; it returns none if not found
+async fn load_stored_proof(
+    args: &Args,
+    report_store_url: &str,
+    params: &ReportParams,
+) -> Result<Option<Vec<u8>>> {
+    let report_store = open_report_store(report_store_url)?;
+    let block_key = BlockKey::new(
+        params.chain_id.unwrap_or(DEFAULT_CHAIN_ID),
+        params.block_number,
+    );
+    let Some(report) = report_store.get(block_key).await? else {
+        return Ok(None);
+    };
+    ensure!(
+        report.success,
+        "block {} is not proved",
+        params.block_number
+    );
+    if report.proof.is_some() {
+        return Ok(report.proof);
+    }
+
+    let Some(proof_uri) = &report.proof_uri else {
+        return Ok(None);
+    };
+    let proof_store_dir = args.proof_store_dir.as_ref().ok_or_else(|| {
+        anyhow!("proof store directory is required for the persisted proof {proof_uri}")
+    })?;
+    let proof = ProofStore::open(proof_store_dir)?.load(proof_uri).await?;
+    if let Some(proof) = &proof {
+        check_proof_hash(&report, proof)?;
+    }
+
+    Ok(proof)
+}
+
+// load the proof of a block by its report from the fetch service, the persisted proof is
+// downloaded separately; it returns none if not found
+async fn load_served_proof(http_url: &Url, params: &ReportParams) -> Result<Option<Vec<u8>>> {
+    let Some(report) = http::report(http_url, params).await? else {
+        return Ok(None);
+    };
+    ensure!(
+        report.success,
+        "block {} is not proved",
+        params.block_number
+    );
+    if report.proof.is_some() {
+        return Ok(report.proof);
+    }
+
+    let proof = http::proof(http_url, params).await?;
+    if let Some(proof) = &proof {
+        check_proof_hash(&report, proof)?;
+    }
+
+    Ok(proof)
+}
+
+// check the loaded proof bytes against the proof hash of the report if recorded
+fn check_proof_hash(report: &BlockProvingReport, proof: &[u8]) -> Result<()> {
+    if let Some(expected_hash) = &report.proof_hash {
+        let hash = proof_hash(proof);
+        ensure!(
+            &hash == expected_hash,
+            "proof hash {hash} of block {} is not consistent with the report {expected_hash}",
+            report.block_number,
+        );
+    }
+
+    Ok(())
+}
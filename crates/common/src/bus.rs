@@ -0,0 +1,235 @@
+use crate::channel::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+use anyhow::{Result, bail, ensure};
+use async_trait::async_trait;
+use derive_more::Constructor;
+use futures::StreamExt;
+use redis::{
+    AsyncCommands,
+    aio::MultiplexedConnection,
+    streams::{StreamMaxlen, StreamReadOptions, StreamReadReply},
+};
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
+use tokio::{
+    spawn,
+    sync::Mutex,
+    time::{Duration, sleep},
+};
+use tracing::{error, warn};
+
+// maximum number of the entries kept in a redis stream, the older entries are trimmed
+const REDIS_STREAM_MAX_LEN: usize = 10_000;
+
+// blocking time of reading a redis stream (in milliseconds)
+const REDIS_READ_BLOCK_MILLISECONDS: usize = 5_000;
+
+// initial backoff for reconnecting to redis after a failed read (in milliseconds)
+const REDIS_INITIAL_RECONNECT_BACKOFF_MILLISECONDS: u64 = 500;
+
+// maximum backoff for reconnecting to redis after a failed read (in seconds)
+const REDIS_MAX_RECONNECT_BACKOFF_SECONDS: u64 = 30;
+
+// field name of the payload in a redis stream entry
+const REDIS_PAYLOAD_FIELD: &str = "payload";
+
+// message bus for transmitting the encoded messages between the components, so the components
+// could run in the same process or on different hosts; the messages published to a subject are
+// delivered to the all current subscribers of the subject, the messages published before
+// subscribing are not delivered
+#[async_trait]
+pub trait MessageBus: Debug + Send + Sync {
+    // publish an encoded message to the subject
+    async fn publish(&self, subject: &str, payload: Vec<u8>) -> Result<()>;
+
+    // subscribe the subject, the receiver is closed if the subscription is broken
+    async fn subscribe(&self, subject: &str) -> Result<UnboundedReceiver<Vec<u8>>>;
+}
+
+// open a message bus by the URL, it could be `memory://` for the in-process bus, a NATS URL
+// prefixed with `nats://` or a Redis URL prefixed with `redis://` or `rediss://`
+pub async fn open_message_bus(url: &str) -> Result<Arc<dyn MessageBus>> {
+    let bus: Arc<dyn MessageBus> = match url.split_once("://") {
+        Some(("memory", _)) => Arc::new(InProcessBus::default()),
+        Some(("nats", _)) => Arc::new(NatsBus::new(async_nats::connect(url).await?)),
+        Some(("redis" | "rediss", _)) => Arc::new(RedisStreamBus::connect(url).await?),
+        Some((scheme, _)) => bail!("unsupported message bus scheme `{scheme}`"),
+        None => bail!("invalid message bus URL `{url}`"),
+    };
+
+    Ok(bus)
+}
+
+// in-process message bus by the tokio mpsc channels
+#[derive(Debug, Default)]
+pub struct InProcessBus {
+    // senders of the subscribers by subject
+    subscribers: Mutex<HashMap<String, Vec<UnboundedSender<Vec<u8>>>>>,
+}
+
+#[async_trait]
+impl MessageBus for InProcessBus {
+    async fn publish(&self, subject: &str, payload: Vec<u8>) -> Result<()> {
+        let mut subscribers = self.subscribers.lock().await;
+        if let Some(senders) = subscribers.get_mut(subject) {
+            // remove the closed subscribers
+            senders.retain(|sender| sender.send(payload.clone()).is_ok());
+        }
+
+        Ok(())
+    }
+
+    async fn subscribe(&self, subject: &str) -> Result<UnboundedReceiver<Vec<u8>>> {
+        let (sender, receiver) = unbounded_channel();
+        self.subscribers
+            .lock()
+            .await
+            .entry(subject.to_string())
+            .or_default()
+            .push(sender);
+
+        Ok(receiver)
+    }
+}
+
+// message bus by the NATS core publish-subscribe
+#[derive(Constructor, Debug)]
+pub struct NatsBus {
+    // connected NATS client
+    client: async_nats::Client,
+}
+
+#[async_trait]
+impl MessageBus for NatsBus {
+    async fn publish(&self, subject: &str, payload: Vec<u8>) -> Result<()> {
+        // the NATS server closes the connection on a message exceeding its maximum payload
+        let max_payload = self.client.server_info().max_payload;
+        ensure!(
+            payload.len() <= max_payload,
+            "message of {} bytes to subject {subject} exceeds `max_payload` {max_payload} of the NATS server",
+            payload.len(),
+        );
+
+        self.client
+            .publish(subject.to_string(), payload.into())
+            .await?;
+
+        Ok(())
+    }
+
+    async fn subscribe(&self, subject: &str) -> Result<UnboundedReceiver<Vec<u8>>> {
+        let mut subscriber = self.client.subscribe(subject.to_string()).await?;
+        let (sender, receiver) = unbounded_channel();
+        spawn(async move {
+            while let Some(msg) = subscriber.next().await {
+                if sender.send(msg.payload.to_vec()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(receiver)
+    }
+}
+
+// message bus by the Redis streams, each subject is a stream and the subscribers read the entries
+// added after subscribing
+#[derive(Debug)]
+pub struct RedisStreamBus {
+    // Redis client for opening the dedicated connections of the subscribers
+    client: redis::Client,
+
+    // shared connection for publishing
+    connection: MultiplexedConnection,
+}
+
+impl RedisStreamBus {
+    pub async fn connect(url: &str) -> Result<Self> {
+        let client = redis::Client::open(url)?;
+        let connection = client.get_multiplexed_async_connection().await?;
+
+        Ok(Self { client, connection })
+    }
+}
+
+#[async_trait]
+impl MessageBus for RedisStreamBus {
+    async fn publish(&self, subject: &str, payload: Vec<u8>) -> Result<()> {
+        let _: String = self
+            .connection
+            .clone()
+            .xadd_maxlen(
+                subject,
+                StreamMaxlen::Approx(REDIS_STREAM_MAX_LEN),
+                "*",
+                &[(REDIS_PAYLOAD_FIELD, payload)],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn subscribe(&self, subject: &str) -> Result<UnboundedReceiver<Vec<u8>>> {
+        // a dedicated connection for the blocking reads
+        let client = self.client.clone();
+        let mut connection = client.get_multiplexed_async_connection().await?;
+        let subject = subject.to_string();
+        let (sender, receiver) = unbounded_channel();
+        spawn(async move {
+            let options = StreamReadOptions::default().block(REDIS_READ_BLOCK_MILLISECONDS);
+            // read the entries added after subscribing
+            let mut last_id = "$".to_string();
+            let mut reconnect_backoff =
+                Duration::from_millis(REDIS_INITIAL_RECONNECT_BACKOFF_MILLISECONDS);
+            loop {
+                let reply: Option<StreamReadReply> = match connection
+                    .xread_options(&[&subject], &[&last_id], &options)
+                    .await
+                {
+                    Ok(reply) => reply,
+                    Err(e) => {
+                        // reconnect with exponential backoff and resume from the last read entry,
+                        // until the subscriber is dropped
+                        warn!(
+                            "message-bus: failed to read the redis stream {subject} {e:?}, reconnecting in {reconnect_backoff:?}",
+                        );
+                        sleep(reconnect_backoff).await;
+                        reconnect_backoff = (reconnect_backoff * 2)
+                            .min(Duration::from_secs(REDIS_MAX_RECONNECT_BACKOFF_SECONDS));
+                        if sender.is_closed() {
+                            return;
+                        }
+                        match client.get_multiplexed_async_connection().await {
+                            Ok(new_connection) => connection = new_connection,
+                            Err(e) => error!(
+                                "message-bus: failed to reconnect redis for the stream {subject} {e:?}"
+                            ),
+                        }
+                        continue;
+                    }
+                };
+                reconnect_backoff =
+                    Duration::from_millis(REDIS_INITIAL_RECONNECT_BACKOFF_MILLISECONDS);
+
+                let entries = reply
+                    .into_iter()
+                    .flat_map(|reply| reply.keys)
+                    .flat_map(|key| key.ids);
+                for entry in entries {
+                    match entry.get::<Vec<u8>>(REDIS_PAYLOAD_FIELD) {
+                        Some(payload) => {
+                            if sender.send(payload).is_err() {
+                                return;
+                            }
+                        }
+                        None => warn!(
+                            "message-bus: skipped the entry {} without payload in the redis stream {subject}",
+                            entry.id,
+                        ),
+                    }
+                    last_id = entry.id;
+                }
+            }
+        });
+
+        Ok(receiver)
+    }
+}
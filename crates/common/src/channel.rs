@@ -1,9 +1,308 @@
-pub use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+pub use tokio::sync::mpsc::error::{SendError, TryRecvError};
 
+use crate::metrics::MetricsRegistry;
 use anyhow::{Result, anyhow};
 use derive_more::Constructor;
-use std::sync::Arc;
-use tokio::sync::{Mutex, mpsc::unbounded_channel};
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{Mutex, broadcast, mpsc, watch},
+    time::timeout,
+};
+use tracing::warn;
+
+// counter name of the messages sent to the instrumented channels
+const CHANNEL_SENT_METRIC: &str = "channel_messages_sent_total";
+
+// gauge name of the number of messages queued in the instrumented channels
+const CHANNEL_DEPTH_METRIC: &str = "channel_depth";
+
+// summary name of the time from sending a message to receiving it in the instrumented channels
+const CHANNEL_RECEIVE_LATENCY_METRIC: &str = "channel_receive_latency_seconds";
+
+/// instrumentation of a named channel, it records the queue depth, the sent messages and the
+/// receive latency to the metrics registry; the latency of a bounded channel includes the waiting
+/// time for the capacity
+#[derive(Debug)]
+pub struct ChannelMetrics {
+    // channel name of the metric labels
+    name: String,
+
+    // registry of the process metrics
+    registry: Arc<MetricsRegistry>,
+
+    // sending instants of the queued messages by the sending order
+    queued_instants: std::sync::Mutex<VecDeque<Instant>>,
+}
+
+impl ChannelMetrics {
+    pub fn new(name: impl Into<String>, registry: Arc<MetricsRegistry>) -> Self {
+        Self {
+            name: name.into(),
+            registry,
+            queued_instants: Default::default(),
+        }
+    }
+
+    // record a message is sending to the channel
+    fn on_send(&self) {
+        let depth = {
+            let mut queued_instants = self.queued_instants();
+            queued_instants.push_back(Instant::now());
+            queued_instants.len()
+        };
+        self.registry
+            .inc_counter(CHANNEL_SENT_METRIC, &[("channel", &self.name)]);
+        self.set_depth(depth);
+    }
+
+    // record a message is failed to send since the channel is closed
+    fn on_send_failed(&self) {
+        let depth = {
+            let mut queued_instants = self.queued_instants();
+            queued_instants.pop_back();
+            queued_instants.len()
+        };
+        self.set_depth(depth);
+    }
+
+    // record a message is received from the channel
+    fn on_recv(&self) {
+        let (sent_at, depth) = {
+            let mut queued_instants = self.queued_instants();
+            (queued_instants.pop_front(), queued_instants.len())
+        };
+        if let Some(sent_at) = sent_at {
+            self.registry.observe(
+                CHANNEL_RECEIVE_LATENCY_METRIC,
+                &[("channel", &self.name)],
+                sent_at.elapsed().as_secs_f64(),
+            );
+        }
+        self.set_depth(depth);
+    }
+
+    fn set_depth(&self, depth: usize) {
+        self.registry.set_gauge(
+            CHANNEL_DEPTH_METRIC,
+            &[("channel", &self.name)],
+            depth as f64,
+        );
+    }
+
+    fn queued_instants(&self) -> std::sync::MutexGuard<'_, VecDeque<Instant>> {
+        self.queued_instants
+            .lock()
+            .expect("channel-metrics: lock poisoned")
+    }
+}
+
+/// create an unbounded channel
+pub fn unbounded_channel<T>() -> (UnboundedSender<T>, UnboundedReceiver<T>) {
+    instrumented_unbounded_channel(None)
+}
+
+/// create an unbounded channel recording to the metrics if specified
+pub fn instrumented_unbounded_channel<T>(
+    metrics: Option<Arc<ChannelMetrics>>,
+) -> (UnboundedSender<T>, UnboundedReceiver<T>) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+
+    (
+        UnboundedSender {
+            inner: sender,
+            metrics: metrics.clone(),
+        },
+        UnboundedReceiver {
+            inner: receiver,
+            metrics,
+        },
+    )
+}
+
+/// create a bounded channel, the sender waits for the capacity when the channel is full
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    instrumented_channel(capacity, None)
+}
+
+/// create a bounded channel recording to the metrics if specified
+pub fn instrumented_channel<T>(
+    capacity: usize,
+    metrics: Option<Arc<ChannelMetrics>>,
+) -> (Sender<T>, Receiver<T>) {
+    let (sender, receiver) = mpsc::channel(capacity);
+
+    (
+        Sender {
+            inner: sender,
+            metrics: metrics.clone(),
+        },
+        Receiver {
+            inner: receiver,
+            metrics,
+        },
+    )
+}
+
+/// sender of an unbounded channel, it's the tokio sender with the optional instrumentation
+#[derive(Debug)]
+pub struct UnboundedSender<T> {
+    inner: mpsc::UnboundedSender<T>,
+    metrics: Option<Arc<ChannelMetrics>>,
+}
+
+impl<T> Clone for UnboundedSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+impl<T> UnboundedSender<T> {
+    // send a message, it fails if the receiver is dropped
+    pub fn send(&self, msg: T) -> Result<(), SendError<T>> {
+        if let Some(metrics) = &self.metrics {
+            metrics.on_send();
+        }
+        self.inner.send(msg).inspect_err(|_| {
+            if let Some(metrics) = &self.metrics {
+                metrics.on_send_failed();
+            }
+        })
+    }
+
+    // identify if the receiver is dropped
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+}
+
+/// receiver of an unbounded channel, it's the tokio receiver with the optional instrumentation
+#[derive(Debug)]
+pub struct UnboundedReceiver<T> {
+    inner: mpsc::UnboundedReceiver<T>,
+    metrics: Option<Arc<ChannelMetrics>>,
+}
+
+impl<T> UnboundedReceiver<T> {
+    // receive a message, it returns none if the channel is closed
+    pub async fn recv(&mut self) -> Option<T> {
+        let msg = self.inner.recv().await;
+        if msg.is_some()
+            && let Some(metrics) = &self.metrics
+        {
+            metrics.on_recv();
+        }
+
+        msg
+    }
+
+    // receive a message without waiting
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let msg = self.inner.try_recv();
+        if msg.is_ok()
+            && let Some(metrics) = &self.metrics
+        {
+            metrics.on_recv();
+        }
+
+        msg
+    }
+}
+
+/// sender of a bounded channel, it's the tokio sender with the optional instrumentation
+#[derive(Debug)]
+pub struct Sender<T> {
+    inner: mpsc::Sender<T>,
+    metrics: Option<Arc<ChannelMetrics>>,
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+impl<T> Sender<T> {
+    // send a message, it waits for the capacity if the channel is full and fails if the receiver
+    // is dropped
+    pub async fn send(&self, msg: T) -> Result<(), SendError<T>> {
+        if let Some(metrics) = &self.metrics {
+            metrics.on_send();
+        }
+        self.inner.send(msg).await.inspect_err(|_| {
+            if let Some(metrics) = &self.metrics {
+                metrics.on_send_failed();
+            }
+        })
+    }
+
+    // identify if the receiver is dropped
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+}
+
+/// receiver of a bounded channel, it's the tokio receiver with the optional instrumentation
+#[derive(Debug)]
+pub struct Receiver<T> {
+    inner: mpsc::Receiver<T>,
+    metrics: Option<Arc<ChannelMetrics>>,
+}
+
+impl<T> Receiver<T> {
+    // receive a message, it returns none if the channel is closed
+    pub async fn recv(&mut self) -> Option<T> {
+        let msg = self.inner.recv().await;
+        if msg.is_some()
+            && let Some(metrics) = &self.metrics
+        {
+            metrics.on_recv();
+        }
+
+        msg
+    }
+
+    // receive a message without waiting
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let msg = self.inner.try_recv();
+        if msg.is_ok()
+            && let Some(metrics) = &self.metrics
+        {
+            metrics.on_recv();
+        }
+
+        msg
+    }
+}
+
+// receive a message by the receiving future with a timeout, it returns none if timed out
+async fn recv_with_timeout<T>(
+    duration: Duration,
+    recv: impl Future<Output = Result<T>>,
+) -> Result<Option<T>> {
+    match timeout(duration, recv).await {
+        Ok(msg) => msg.map(Some),
+        Err(_) => Ok(None),
+    }
+}
+
+// convert the result of receiving without waiting, it returns none if no message is queued
+fn try_recv_result<T>(result: Result<T, TryRecvError>) -> Result<Option<T>> {
+    match result {
+        Ok(msg) => Ok(Some(msg)),
+        Err(TryRecvError::Empty) => Ok(None),
+        Err(TryRecvError::Disconnected) => Err(anyhow!("channel closed")),
+    }
+}
 
 /// unidirectional unbounded async channel, sender -> receiver
 #[derive(Debug, Clone)]
@@ -14,16 +313,21 @@ pub struct SingleUnboundedChannel<T> {
 
 impl<T> Default for SingleUnboundedChannel<T> {
     fn default() -> Self {
-        let (sender, receiver) = unbounded_channel();
+        Self::instrumented(None)
+    }
+}
+
+impl<T> SingleUnboundedChannel<T> {
+    // create a channel recording to the metrics if specified
+    pub fn instrumented(metrics: Option<Arc<ChannelMetrics>>) -> Self {
+        let (sender, receiver) = instrumented_unbounded_channel(metrics);
 
         Self {
             sender: Arc::new(sender),
             receiver: Arc::new(Mutex::new(receiver)),
         }
     }
-}
 
-impl<T> SingleUnboundedChannel<T> {
     pub fn sender(&self) -> Arc<UnboundedSender<T>> {
         self.sender.clone()
     }
@@ -45,6 +349,21 @@ impl<T> SingleUnboundedChannel<T> {
             .await
             .ok_or_else(|| anyhow!("channel closed"))
     }
+
+    // receive a message with a timeout, it returns none if timed out and fails if the channel is
+    // closed; the waiting time for the receiver lock is counted in the timeout
+    pub async fn recv_with_timeout(&self, duration: Duration) -> Result<Option<T>> {
+        recv_with_timeout(duration, self.recv()).await
+    }
+
+    // receive a message without waiting, it returns none if no message is queued or the receiver
+    // is locked by another task, and fails if the channel is closed
+    pub fn try_recv(&self) -> Result<Option<T>> {
+        match self.receiver.try_lock() {
+            Ok(mut receiver) => try_recv_result(receiver.try_recv()),
+            Err(_) => Ok(None),
+        }
+    }
 }
 
 /// duplex unbounded async endpoint includes a sender for type T and a receiver for type U
@@ -77,6 +396,21 @@ impl<T, U> DuplexUnboundedEndpoint<T, U> {
             .ok_or_else(|| anyhow!("channel closed"))
     }
 
+    // receive a message with a timeout, it returns none if timed out and fails if the channel is
+    // closed; the waiting time for the receiver lock is counted in the timeout
+    pub async fn recv_with_timeout(&self, duration: Duration) -> Result<Option<U>> {
+        recv_with_timeout(duration, self.recv()).await
+    }
+
+    // receive a message without waiting, it returns none if no message is queued or the receiver
+    // is locked by another task, and fails if the channel is closed
+    pub fn try_recv(&self) -> Result<Option<U>> {
+        match self.receiver.try_lock() {
+            Ok(mut receiver) => try_recv_result(receiver.try_recv()),
+            Err(_) => Ok(None),
+        }
+    }
+
     pub fn clone_sender(&self) -> Arc<UnboundedSender<T>> {
         Arc::new((*self.sender).clone())
     }
@@ -91,8 +425,19 @@ pub struct DuplexUnboundedChannel<T, U> {
 
 impl<T, U> Default for DuplexUnboundedChannel<T, U> {
     fn default() -> Self {
-        let (sender1, receiver1) = unbounded_channel();
-        let (sender2, receiver2) = unbounded_channel();
+        Self::instrumented(None, None)
+    }
+}
+
+impl<T, U> DuplexUnboundedChannel<T, U> {
+    // create a channel recording to the metrics if specified, metrics1 is of the direction from
+    // endpoint1 to endpoint2 and metrics2 is of the reverse
+    pub fn instrumented(
+        metrics1: Option<Arc<ChannelMetrics>>,
+        metrics2: Option<Arc<ChannelMetrics>>,
+    ) -> Self {
+        let (sender1, receiver1) = instrumented_unbounded_channel(metrics1);
+        let (sender2, receiver2) = instrumented_unbounded_channel(metrics2);
 
         let endpoint1 = Arc::new(DuplexUnboundedEndpoint::new(
             Arc::new(sender1),
@@ -108,9 +453,7 @@ impl<T, U> Default for DuplexUnboundedChannel<T, U> {
             endpoint2,
         }
     }
-}
 
-impl<T, U> DuplexUnboundedChannel<T, U> {
     pub fn endpoint1(&self) -> Arc<DuplexUnboundedEndpoint<T, U>> {
         self.endpoint1.clone()
     }
@@ -119,3 +462,512 @@ impl<T, U> DuplexUnboundedChannel<T, U> {
         self.endpoint2.clone()
     }
 }
+
+/// unidirectional bounded async channel, sender -> receiver, the sender waits for the capacity
+/// when the channel is full
+#[derive(Debug, Clone)]
+pub struct SingleBoundedChannel<T> {
+    sender: Arc<Sender<T>>,
+    receiver: Arc<Mutex<Receiver<T>>>,
+}
+
+impl<T> SingleBoundedChannel<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self::instrumented(capacity, None)
+    }
+
+    // create a channel recording to the metrics if specified
+    pub fn instrumented(capacity: usize, metrics: Option<Arc<ChannelMetrics>>) -> Self {
+        let (sender, receiver) = instrumented_channel(capacity, metrics);
+
+        Self {
+            sender: Arc::new(sender),
+            receiver: Arc::new(Mutex::new(receiver)),
+        }
+    }
+
+    pub fn sender(&self) -> Arc<Sender<T>> {
+        self.sender.clone()
+    }
+
+    pub fn receiver(&self) -> Arc<Mutex<Receiver<T>>> {
+        self.receiver.clone()
+    }
+
+    pub async fn send(&self, msg: T) -> Result<()> {
+        self.sender
+            .send(msg)
+            .await
+            .map_err(|err| anyhow!("failed to send msg: {err}"))
+    }
+
+    pub async fn recv(&self) -> Result<T> {
+        let mut receiver = self.receiver.lock().await;
+        receiver
+            .recv()
+            .await
+            .ok_or_else(|| anyhow!("channel closed"))
+    }
+
+    // receive a message with a timeout, it returns none if timed out and fails if the channel is
+    // closed; the waiting time for the receiver lock is counted in the timeout
+    pub async fn recv_with_timeout(&self, duration: Duration) -> Result<Option<T>> {
+        recv_with_timeout(duration, self.recv()).await
+    }
+
+    // receive a message without waiting, it returns none if no message is queued or the receiver
+    // is locked by another task, and fails if the channel is closed
+    pub fn try_recv(&self) -> Result<Option<T>> {
+        match self.receiver.try_lock() {
+            Ok(mut receiver) => try_recv_result(receiver.try_recv()),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+/// duplex bounded async endpoint includes a sender for type T and a receiver for type U, the
+/// sender waits for the capacity when the channel is full
+#[derive(Constructor, Debug, Clone)]
+pub struct DuplexBoundedEndpoint<T, U> {
+    sender: Arc<Sender<T>>,
+    receiver: Arc<Mutex<Receiver<U>>>,
+}
+
+impl<T, U> DuplexBoundedEndpoint<T, U> {
+    pub fn sender(&self) -> Arc<Sender<T>> {
+        self.sender.clone()
+    }
+
+    pub fn receiver(&self) -> Arc<Mutex<Receiver<U>>> {
+        self.receiver.clone()
+    }
+
+    pub async fn send(&self, msg: T) -> Result<()> {
+        self.sender
+            .send(msg)
+            .await
+            .map_err(|err| anyhow!("failed to send msg: {err}"))
+    }
+
+    pub async fn recv(&self) -> Result<U> {
+        let mut receiver = self.receiver.lock().await;
+        receiver
+            .recv()
+            .await
+            .ok_or_else(|| anyhow!("channel closed"))
+    }
+
+    // receive a message with a timeout, it returns none if timed out and fails if the channel is
+    // closed; the waiting time for the receiver lock is counted in the timeout
+    pub async fn recv_with_timeout(&self, duration: Duration) -> Result<Option<U>> {
+        recv_with_timeout(duration, self.recv()).await
+    }
+
+    // receive a message without waiting, it returns none if no message is queued or the receiver
+    // is locked by another task, and fails if the channel is closed
+    pub fn try_recv(&self) -> Result<Option<U>> {
+        match self.receiver.try_lock() {
+            Ok(mut receiver) => try_recv_result(receiver.try_recv()),
+            Err(_) => Ok(None),
+        }
+    }
+
+    pub fn clone_sender(&self) -> Arc<Sender<T>> {
+        Arc::new((*self.sender).clone())
+    }
+}
+
+/// duplex bounded async channel, endpoint1(sender<T>, receiver<U>) <-> endpoint2(sender<U>, Receiver<T>),
+/// both directions have the same capacity
+#[derive(Debug)]
+pub struct DuplexBoundedChannel<T, U> {
+    endpoint1: Arc<DuplexBoundedEndpoint<T, U>>,
+    endpoint2: Arc<DuplexBoundedEndpoint<U, T>>,
+}
+
+impl<T, U> DuplexBoundedChannel<T, U> {
+    pub fn new(capacity: usize) -> Self {
+        Self::instrumented(capacity, None, None)
+    }
+
+    // create a channel recording to the metrics if specified, metrics1 is of the direction from
+    // endpoint1 to endpoint2 and metrics2 is of the reverse
+    pub fn instrumented(
+        capacity: usize,
+        metrics1: Option<Arc<ChannelMetrics>>,
+        metrics2: Option<Arc<ChannelMetrics>>,
+    ) -> Self {
+        let (sender1, receiver1) = instrumented_channel(capacity, metrics1);
+        let (sender2, receiver2) = instrumented_channel(capacity, metrics2);
+
+        let endpoint1 = Arc::new(DuplexBoundedEndpoint::new(
+            Arc::new(sender1),
+            Arc::new(Mutex::new(receiver2)),
+        ));
+        let endpoint2 = Arc::new(DuplexBoundedEndpoint::new(
+            Arc::new(sender2),
+            Arc::new(Mutex::new(receiver1)),
+        ));
+
+        Self {
+            endpoint1,
+            endpoint2,
+        }
+    }
+
+    pub fn endpoint1(&self) -> Arc<DuplexBoundedEndpoint<T, U>> {
+        self.endpoint1.clone()
+    }
+
+    pub fn endpoint2(&self) -> Arc<DuplexBoundedEndpoint<U, T>> {
+        self.endpoint2.clone()
+    }
+}
+
+/// broadcast async channel, sender -> the all current subscribers, each message is cloned once
+/// per subscriber and a subscriber falling behind the capacity skips the oldest messages
+#[derive(Debug)]
+pub struct BroadcastChannel<T> {
+    sender: broadcast::Sender<T>,
+}
+
+impl<T> Clone for BroadcastChannel<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<T: Clone> BroadcastChannel<T> {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+
+        Self { sender }
+    }
+
+    // send a message to the all current subscribers and return the number of them, the message is
+    // dropped if there's no subscriber
+    pub fn send(&self, msg: T) -> usize {
+        self.sender.send(msg).unwrap_or_default()
+    }
+
+    // subscribe the messages sent after subscribing
+    pub fn subscribe(&self) -> BroadcastReceiver<T> {
+        BroadcastReceiver {
+            receiver: self.sender.subscribe(),
+        }
+    }
+
+    // return the number of the current subscribers
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+/// subscriber of a broadcast channel
+#[derive(Debug)]
+pub struct BroadcastReceiver<T> {
+    receiver: broadcast::Receiver<T>,
+}
+
+impl<T: Clone> BroadcastReceiver<T> {
+    // receive a message, the messages overwritten since the subscriber falls behind are skipped
+    // with a warning; it returns none if the channel is closed
+    pub async fn recv(&mut self) -> Option<T> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(msg) => return Some(msg),
+                Err(broadcast::error::RecvError::Lagged(skipped_count)) => {
+                    warn!("broadcast-channel: skipped {skipped_count} messages since lagged");
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+/// latest-value async channel, sender -> the all subscribers, a subscriber observes the latest
+/// value instead of every sent one, e.g. for broadcasting the dynamic configuration and status
+#[derive(Debug)]
+pub struct WatchChannel<T> {
+    sender: Arc<watch::Sender<T>>,
+}
+
+impl<T> Clone for WatchChannel<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<T> WatchChannel<T> {
+    pub fn new(value: T) -> Self {
+        let (sender, _) = watch::channel(value);
+
+        Self {
+            sender: Arc::new(sender),
+        }
+    }
+
+    // replace the latest value and notify the subscribers, it's kept even if there's no
+    // subscriber
+    pub fn send(&self, value: T) {
+        self.sender.send_replace(value);
+    }
+
+    // modify the latest value in place and notify the subscribers
+    pub fn send_modify(&self, modify: impl FnOnce(&mut T)) {
+        self.sender.send_modify(modify);
+    }
+
+    // modify the latest value in place and only notify the subscribers if the modification
+    // returns true
+    pub fn send_if_modified(&self, modify: impl FnOnce(&mut T) -> bool) -> bool {
+        self.sender.send_if_modified(modify)
+    }
+
+    // subscribe the changes of the value, the current value is taken as seen
+    pub fn subscribe(&self) -> WatchReceiver<T> {
+        WatchReceiver {
+            receiver: self.sender.subscribe(),
+        }
+    }
+}
+
+impl<T: Clone> WatchChannel<T> {
+    // return the latest value
+    pub fn latest(&self) -> T {
+        self.sender.borrow().clone()
+    }
+}
+
+/// subscriber of a watch channel
+#[derive(Debug)]
+pub struct WatchReceiver<T> {
+    receiver: watch::Receiver<T>,
+}
+
+impl<T> Clone for WatchReceiver<T> {
+    fn clone(&self) -> Self {
+        Self {
+            receiver: self.receiver.clone(),
+        }
+    }
+}
+
+impl<T: Clone> WatchReceiver<T> {
+    // return the latest value and mark it as seen
+    pub fn latest(&mut self) -> T {
+        self.receiver.borrow_and_update().clone()
+    }
+
+    // wait for a value not seen yet and return it, the intermediate values are skipped if changed
+    // multiple times; it returns none if the channel is closed
+    pub async fn changed(&mut self) -> Option<T> {
+        self.receiver.changed().await.ok()?;
+
+        Some(self.latest())
+    }
+}
+
+/// multi-producer multi-consumer work queue, senders -> the all workers, each message is received
+/// by only one of the workers, e.g. for sharing the jobs between the workers of a pool
+#[derive(Debug)]
+pub struct WorkQueue<T> {
+    sender: WorkQueueSender<T>,
+    receiver: WorkQueueReceiver<T>,
+}
+
+impl<T> WorkQueue<T> {
+    // create a work queue holding the capacity of messages, the senders wait if it's full
+    pub fn bounded(capacity: usize) -> Self {
+        Self::from_channel(async_channel::bounded(capacity))
+    }
+
+    // create a work queue without limit of the queued messages
+    pub fn unbounded() -> Self {
+        Self::from_channel(async_channel::unbounded())
+    }
+
+    fn from_channel(
+        (sender, receiver): (async_channel::Sender<T>, async_channel::Receiver<T>),
+    ) -> Self {
+        Self {
+            sender: WorkQueueSender { sender },
+            receiver: WorkQueueReceiver { receiver },
+        }
+    }
+
+    pub fn sender(&self) -> WorkQueueSender<T> {
+        self.sender.clone()
+    }
+
+    pub fn receiver(&self) -> WorkQueueReceiver<T> {
+        self.receiver.clone()
+    }
+}
+
+/// sender of a work queue, it could be cloned for multiple producers
+#[derive(Debug)]
+pub struct WorkQueueSender<T> {
+    sender: async_channel::Sender<T>,
+}
+
+impl<T> Clone for WorkQueueSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<T> WorkQueueSender<T> {
+    // send a message to one of the workers, it waits for the capacity if the queue is bounded and
+    // full, and returns an error if the queue is closed
+    pub async fn send(&self, msg: T) -> Result<()> {
+        self.sender
+            .send(msg)
+            .await
+            .map_err(|_| anyhow!("work queue is closed"))
+    }
+
+    // close the queue, the workers receive the queued messages and then none
+    pub fn close(&self) {
+        self.sender.close();
+    }
+
+    // return the number of the queued messages
+    pub fn len(&self) -> usize {
+        self.sender.len()
+    }
+
+    // return true if there's no queued message
+    pub fn is_empty(&self) -> bool {
+        self.sender.is_empty()
+    }
+}
+
+/// receiver of a work queue, it could be cloned for each worker of a pool
+#[derive(Debug)]
+pub struct WorkQueueReceiver<T> {
+    receiver: async_channel::Receiver<T>,
+}
+
+impl<T> Clone for WorkQueueReceiver<T> {
+    fn clone(&self) -> Self {
+        Self {
+            receiver: self.receiver.clone(),
+        }
+    }
+}
+
+impl<T> WorkQueueReceiver<T> {
+    // receive the next message not taken by the other workers, it fails if the queue is closed
+    // and empty, or the all senders are dropped
+    pub async fn recv(&self) -> Result<T> {
+        self.receiver
+            .recv()
+            .await
+            .map_err(|_| anyhow!("work queue is closed"))
+    }
+
+    // receive a message with a timeout, it returns none if timed out and fails if the queue is
+    // closed and empty
+    pub async fn recv_with_timeout(&self, duration: Duration) -> Result<Option<T>> {
+        recv_with_timeout(duration, self.recv()).await
+    }
+
+    // receive a message without waiting, it returns none if no message is queued and fails if the
+    // queue is closed and empty
+    pub fn try_recv(&self) -> Result<Option<T>> {
+        match self.receiver.try_recv() {
+            Ok(msg) => Ok(Some(msg)),
+            Err(async_channel::TryRecvError::Empty) => Ok(None),
+            Err(async_channel::TryRecvError::Closed) => Err(anyhow!("work queue is closed")),
+        }
+    }
+}
+
+/// sender of either an unbounded or a bounded channel, for sending to the receivers of both kinds
+#[derive(Debug)]
+pub enum ChannelSender<T> {
+    Unbounded(Arc<UnboundedSender<T>>),
+    Bounded(Arc<Sender<T>>),
+}
+
+impl<T> Clone for ChannelSender<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Unbounded(sender) => Self::Unbounded(sender.clone()),
+            Self::Bounded(sender) => Self::Bounded(sender.clone()),
+        }
+    }
+}
+
+impl<T> From<Arc<UnboundedSender<T>>> for ChannelSender<T> {
+    fn from(sender: Arc<UnboundedSender<T>>) -> Self {
+        Self::Unbounded(sender)
+    }
+}
+
+impl<T> From<Arc<Sender<T>>> for ChannelSender<T> {
+    fn from(sender: Arc<Sender<T>>) -> Self {
+        Self::Bounded(sender)
+    }
+}
+
+impl<T> ChannelSender<T> {
+    // send a message, it waits for the capacity if the channel is bounded and full
+    pub async fn send(&self, msg: T) -> Result<()> {
+        match self {
+            Self::Unbounded(sender) => sender
+                .send(msg)
+                .map_err(|err| anyhow!("failed to send msg: {err}")),
+            Self::Bounded(sender) => sender
+                .send(msg)
+                .await
+                .map_err(|err| anyhow!("failed to send msg: {err}")),
+        }
+    }
+}
+
+/// receiver of either an unbounded or a bounded channel, for receiving from the senders of both
+/// kinds
+#[derive(Debug)]
+pub enum ChannelReceiver<T> {
+    Unbounded(Arc<Mutex<UnboundedReceiver<T>>>),
+    Bounded(Arc<Mutex<Receiver<T>>>),
+}
+
+impl<T> Clone for ChannelReceiver<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Unbounded(receiver) => Self::Unbounded(receiver.clone()),
+            Self::Bounded(receiver) => Self::Bounded(receiver.clone()),
+        }
+    }
+}
+
+impl<T> From<Arc<Mutex<UnboundedReceiver<T>>>> for ChannelReceiver<T> {
+    fn from(receiver: Arc<Mutex<UnboundedReceiver<T>>>) -> Self {
+        Self::Unbounded(receiver)
+    }
+}
+
+impl<T> From<Arc<Mutex<Receiver<T>>>> for ChannelReceiver<T> {
+    fn from(receiver: Arc<Mutex<Receiver<T>>>) -> Self {
+        Self::Bounded(receiver)
+    }
+}
+
+impl<T> ChannelReceiver<T> {
+    // receive a message, it returns none if the channel is closed
+    pub async fn recv(&self) -> Option<T> {
+        match self {
+            Self::Unbounded(receiver) => receiver.lock().await.recv().await,
+            Self::Bounded(receiver) => receiver.lock().await.recv().await,
+        }
+    }
+}
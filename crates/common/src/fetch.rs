@@ -1,7 +1,12 @@
+use crate::utils::{DEFAULT_PIPELINE_ID, PipelineId};
+use anyhow::Result;
 use derive_more::Constructor;
 use serde::Deserialize;
 use std::collections::HashMap;
 
+// the all fetch requests below support an optional `pipeline_id` parameter, it specifies the
+// pipeline to fetch and prove the blocks, the default pipeline is used if not specified
+
 // HTTP Get request path for proving blocks by the specified block number
 // It supports two parameters:
 // - start_block_num: it specifies the `start` block number to prove
@@ -20,6 +25,100 @@ pub const HTTP_PROVE_LATEST_BLOCK_PATH: &str = "/prove_latest_block";
 // - count: it's optional and `1` is the default value, it specifies the number of blocks to reproduce
 pub const HTTP_REPRODUCE_BLOCK_BY_NUMBER_PATH: &str = "/reproduce_block_by_number";
 
+// HTTP Get request path for reproducing the most recently dumped blocks
+// It supports one parameter:
+// - count: it's optional and `1` is the default value, it specifies the number of the most recently
+//   dumped blocks to reproduce
+pub const HTTP_REPRODUCE_LATEST_BLOCK_PATH: &str = "/reproduce_latest_block";
+
+// HTTP Get request path for reproducing blocks by a list of block numbers
+// It supports one parameter:
+// - block_nums: it specifies the block numbers to reproduce separated by comma, e.g. `1,2,3`
+pub const HTTP_REPRODUCE_BLOCK_BY_LIST_PATH: &str = "/reproduce_block_by_list";
+
+// HTTP Get request path for backfilling a range of blocks with checkpointing, the backfill is
+// resumed from the last submitted block after a restart
+// It supports two parameters:
+// - start_block_num: it specifies the first block number to backfill
+// - end_block_num: it specifies the last block number to backfill (inclusive)
+pub const HTTP_BACKFILL_BLOCK_RANGE_PATH: &str = "/backfill_block_range";
+
+// HTTP Get request path for estimating blocks in a dry-run mode, the inputs are generated and
+// emulated without proving, and the reports of cycles, gas and input sizes are sent to the
+// websocket watchers
+// It supports two parameters:
+// - start_block_num: it specifies the `start` block number to estimate
+// - count: it's optional and `1` is the default value, it specifies the number of blocks to estimate
+pub const HTTP_ESTIMATE_BLOCK_PATH: &str = "/estimate_block";
+
+// HTTP Get admin request path for pausing the all pipelines, the fetchers stop fetching new blocks
+// and the proving-clients stop dispatching new blocks after the blocks proving in progress
+pub const HTTP_PAUSE_PATH: &str = "/pause";
+
+// HTTP Get admin request path for resuming the all pipelines after a pause or drain
+pub const HTTP_RESUME_PATH: &str = "/resume";
+
+// HTTP Get admin request path for draining the all pipelines, the fetchers stop fetching new blocks
+// and the proving-clients complete proving the fetched blocks, so the proving cluster becomes idle
+// for maintenance
+pub const HTTP_DRAIN_PATH: &str = "/drain";
+
+// HTTP Get admin request path for the runtime configuration and status in json, e.g. the pipeline
+// state and the current log filter
+pub const HTTP_RUNTIME_STATE_PATH: &str = "/runtime_state";
+
+// HTTP Get admin request path for changing the maximum number of in-flight blocks of a pipeline at
+// runtime
+// It supports one parameter:
+// - count: it specifies the maximum number of blocks dispatched but not reported, zero disables
+//   the limit
+pub const HTTP_SET_MAX_IN_FLIGHT_BLOCKS_PATH: &str = "/set_max_in_flight_blocks";
+
+// HTTP Get admin request path for changing the log filter at runtime
+// It supports one parameter:
+// - filter: it specifies the log filter directives, e.g. `info,scheduler=debug`
+pub const HTTP_SET_LOG_FILTER_PATH: &str = "/set_log_filter";
+
+// HTTP Get admin request path for reloading the tunable settings of the config file without
+// restarting, the same as sending `SIGHUP` to the process
+pub const HTTP_RELOAD_CONFIG_PATH: &str = "/reload_config";
+
+// HTTP Get request path for the readiness probe, it responds `200` once the proving-clients are
+// connected to the proving clusters and the rpc nodes of the fetchers are verified, or `503` with
+// the pending checks
+pub const HTTP_READYZ_PATH: &str = "/readyz";
+
+// HTTP Get request path for the process metrics in the prometheus text format
+pub const HTTP_METRICS_PATH: &str = "/metrics";
+
+// HTTP Get request path for the build info of the instance in json, e.g. the git commit, the
+// pico-sdk and rsp versions and the digests of the ELFs in use
+pub const HTTP_VERSION_PATH: &str = "/version";
+
+// HTTP Get request path for the recent pipeline errors in json, e.g. the messages failed to send
+// or routed by the scheduler
+pub const HTTP_PIPELINE_ERRORS_PATH: &str = "/pipeline_errors";
+
+// HTTP Get request path for the latest report of a block in json
+// It supports two parameters:
+// - block_number: it specifies the block number
+// - chain_id: it specifies the chain of the block, the Ethereum mainnet if not specified
+pub const HTTP_REPORT_PATH: &str = "/report";
+
+// HTTP Get request path for the reports of a range of blocks in json
+// It supports three parameters:
+// - from_block_num: it specifies the first block number
+// - to_block_num: it specifies the last block number (inclusive)
+// - chain_id: it specifies the chain of the blocks, the Ethereum mainnet if not specified
+pub const HTTP_REPORTS_PATH: &str = "/reports";
+
+// HTTP Get request path for the persisted proof bytes of a block, the reports carry the proof hash
+// and storage reference instead of the bytes if the proofs are persisted by the proof service
+// It supports two parameters:
+// - block_number: it specifies the block number
+// - chain_id: it specifies the chain of the block, the Ethereum mainnet if not specified
+pub const HTTP_PROOF_PATH: &str = "/proof";
+
 // HTTP Get `prove_block_by_number` parameters
 #[derive(Constructor, Debug, Deserialize)]
 pub struct ProveBlockByNumberParams {
@@ -44,6 +143,9 @@ impl ProveBlockByNumberParams {
     }
 }
 
+// count of the latest blocks for following the chain without an end
+pub const FOLLOW_LATEST_COUNT: u64 = u64::MAX;
+
 // HTTP Get `prove_latest_block` parameters
 #[derive(Constructor, Debug, Deserialize)]
 pub struct ProveLatestBlockParams {
@@ -87,3 +189,199 @@ impl ReproduceBlockByNumberParams {
         params
     }
 }
+
+// HTTP Get `reproduce_latest_block` parameters
+#[derive(Constructor, Debug, Deserialize)]
+pub struct ReproduceLatestBlockParams {
+    // it specifies the number of the most recently dumped blocks to reproduce
+    pub count: Option<u64>,
+}
+
+impl ReproduceLatestBlockParams {
+    // convert to hash map
+    pub fn to_hash_map(&self) -> HashMap<&'static str, u64> {
+        let mut params = HashMap::new();
+
+        if let Some(count) = self.count {
+            params.insert("count", count);
+        }
+
+        params
+    }
+}
+
+// HTTP Get `reproduce_block_by_list` parameters
+#[derive(Constructor, Debug, Deserialize)]
+pub struct ReproduceBlockByListParams {
+    // specifies the block numbers to reproduce separated by comma
+    pub block_nums: String,
+}
+
+impl ReproduceBlockByListParams {
+    // create the parameters from a list of block numbers
+    pub fn from_block_numbers(block_numbers: &[u64]) -> Self {
+        let block_nums = block_numbers
+            .iter()
+            .map(|block_number| block_number.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        Self { block_nums }
+    }
+
+    // parse the comma separated block numbers
+    pub fn block_numbers(&self) -> Result<Vec<u64>> {
+        self.block_nums
+            .split(',')
+            .map(str::trim)
+            .filter(|block_num| !block_num.is_empty())
+            .map(|block_num| Ok(block_num.parse()?))
+            .collect()
+    }
+
+    // convert to hash map
+    pub fn to_hash_map(&self) -> HashMap<&'static str, String> {
+        let mut params = HashMap::new();
+
+        params.insert("block_nums", self.block_nums.clone());
+
+        params
+    }
+}
+
+// HTTP Get `backfill_block_range` parameters
+#[derive(Constructor, Debug, Deserialize)]
+pub struct BackfillBlockRangeParams {
+    // specifies the first block number to backfill
+    pub start_block_num: u64,
+
+    // specifies the last block number to backfill (inclusive)
+    pub end_block_num: u64,
+}
+
+impl BackfillBlockRangeParams {
+    // convert to hash map
+    pub fn to_hash_map(&self) -> HashMap<&'static str, u64> {
+        let mut params = HashMap::new();
+
+        params.insert("start_block_num", self.start_block_num);
+        params.insert("end_block_num", self.end_block_num);
+
+        params
+    }
+}
+
+// HTTP Get `estimate_block` parameters
+#[derive(Constructor, Debug, Deserialize)]
+pub struct EstimateBlockParams {
+    // specifies the `start` block number to estimate
+    pub start_block_num: u64,
+
+    // specifies the number of blocks to estimate
+    pub count: Option<u64>,
+}
+
+impl EstimateBlockParams {
+    // convert to hash map
+    pub fn to_hash_map(&self) -> HashMap<&'static str, u64> {
+        let mut params = HashMap::new();
+
+        params.insert("start_block_num", self.start_block_num);
+        if let Some(count) = self.count {
+            params.insert("count", count);
+        }
+
+        params
+    }
+}
+
+// HTTP Get `set_max_in_flight_blocks` parameters
+#[derive(Constructor, Debug, Deserialize)]
+pub struct SetMaxInFlightBlocksParams {
+    // specifies the maximum number of blocks dispatched but not reported
+    pub count: usize,
+}
+
+// HTTP Get `set_log_filter` parameters
+#[derive(Constructor, Debug, Deserialize)]
+pub struct SetLogFilterParams {
+    // specifies the log filter directives
+    pub filter: String,
+}
+
+// HTTP Get `report` and `proof` parameters
+#[derive(Constructor, Debug, Deserialize)]
+pub struct ReportParams {
+    // specifies the block number
+    pub block_number: u64,
+
+    // specifies the chain of the block
+    pub chain_id: Option<u64>,
+}
+
+impl ReportParams {
+    // convert to hash map
+    pub fn to_hash_map(&self) -> HashMap<&'static str, u64> {
+        let mut params = HashMap::new();
+
+        params.insert("block_number", self.block_number);
+        if let Some(chain_id) = self.chain_id {
+            params.insert("chain_id", chain_id);
+        }
+
+        params
+    }
+}
+
+// HTTP Get `reports` parameters
+#[derive(Constructor, Debug, Deserialize)]
+pub struct ReportRangeParams {
+    // specifies the first block number
+    pub from_block_num: u64,
+
+    // specifies the last block number (inclusive)
+    pub to_block_num: u64,
+
+    // specifies the chain of the blocks
+    pub chain_id: Option<u64>,
+}
+
+impl ReportRangeParams {
+    // convert to hash map
+    pub fn to_hash_map(&self) -> HashMap<&'static str, u64> {
+        let mut params = HashMap::new();
+
+        params.insert("from_block_num", self.from_block_num);
+        params.insert("to_block_num", self.to_block_num);
+        if let Some(chain_id) = self.chain_id {
+            params.insert("chain_id", chain_id);
+        }
+
+        params
+    }
+}
+
+// HTTP Get `pipeline_id` parameter shared by the all fetch requests
+#[derive(Constructor, Debug, Deserialize)]
+pub struct PipelineParams {
+    // specifies the pipeline to fetch and prove the blocks
+    pub pipeline_id: Option<PipelineId>,
+}
+
+impl PipelineParams {
+    // return the specified pipeline id or the default one
+    pub fn pipeline_id(&self) -> PipelineId {
+        self.pipeline_id.unwrap_or(DEFAULT_PIPELINE_ID)
+    }
+
+    // convert to hash map, it's empty for the default pipeline
+    pub fn to_hash_map(&self) -> HashMap<&'static str, u64> {
+        let mut params = HashMap::new();
+
+        if let Some(pipeline_id) = self.pipeline_id {
+            params.insert("pipeline_id", pipeline_id as u64);
+        }
+
+        params
+    }
+}
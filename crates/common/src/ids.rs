@@ -0,0 +1,78 @@
+use derive_more::Constructor;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// chain id of the Ethereum mainnet, it's used if the chain of a pipeline is not specified
+pub const DEFAULT_CHAIN_ID: u64 = 1;
+
+// job id assigned by the fetch-service to a fetch request, it's carried through the fetch,
+// proving and report messages for correlating the blocks with the request; zero is unknown, e.g.
+// it's not echoed back by the provers
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize,
+)]
+#[serde(transparent)]
+pub struct JobId(u64);
+
+impl JobId {
+    // unknown job id
+    pub const UNKNOWN: Self = Self(0);
+
+    // return true if the job id is unknown
+    pub fn is_unknown(&self) -> bool {
+        *self == Self::UNKNOWN
+    }
+
+    // return true if the job ids are the same or either is unknown
+    pub fn matches(&self, other: Self) -> bool {
+        self.is_unknown() || other.is_unknown() || *self == other
+    }
+}
+
+impl From<u64> for JobId {
+    fn from(job_id: u64) -> Self {
+        Self(job_id)
+    }
+}
+
+impl From<JobId> for u64 {
+    fn from(job_id: JobId) -> Self {
+        job_id.0
+    }
+}
+
+impl fmt::Display for JobId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// key of a block across the chains, since the same block number could be proved for different
+// chains by multiple pipelines
+#[derive(
+    Clone,
+    Constructor,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Deserialize,
+    Serialize,
+)]
+pub struct BlockKey {
+    // chain id of the block
+    pub chain_id: u64,
+
+    // block number in the chain
+    pub block_number: u64,
+}
+
+impl fmt::Display for BlockKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.block_number, self.chain_id)
+    }
+}
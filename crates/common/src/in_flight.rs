@@ -0,0 +1,51 @@
+use crate::{ids::JobId, utils::PipelineId};
+use std::{collections::HashMap, sync::Mutex};
+
+// registry of the blocks dispatched to the proving cluster and waiting for the proof results, the
+// proving-clients register the blocks and the proof-service validates the received results by it
+#[derive(Debug, Default)]
+pub struct InFlightBlocks {
+    // job id of each in-flight block keyed by the pipeline and block number
+    job_ids: Mutex<HashMap<(PipelineId, u64), JobId>>,
+}
+
+impl InFlightBlocks {
+    // register a block dispatched to the proving cluster
+    pub fn register(&self, pipeline_id: PipelineId, job_id: JobId, block_number: u64) {
+        self.job_ids
+            .lock()
+            .expect("in-flight-blocks: lock is poisoned")
+            .insert((pipeline_id, block_number), job_id);
+    }
+
+    // unregister a block once its proof result is received
+    pub fn unregister(&self, pipeline_id: PipelineId, block_number: u64) {
+        self.job_ids
+            .lock()
+            .expect("in-flight-blocks: lock is poisoned")
+            .remove(&(pipeline_id, block_number));
+    }
+
+    // return the number of the blocks in flight
+    pub fn len(&self) -> usize {
+        self.job_ids
+            .lock()
+            .expect("in-flight-blocks: lock is poisoned")
+            .len()
+    }
+
+    // return true if no block is in flight
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // return true if the block is in flight, the job id is not checked if it's unknown since it
+    // may not be echoed by the provers
+    pub fn contains(&self, pipeline_id: PipelineId, job_id: JobId, block_number: u64) -> bool {
+        self.job_ids
+            .lock()
+            .expect("in-flight-blocks: lock is poisoned")
+            .get(&(pipeline_id, block_number))
+            .is_some_and(|in_flight_job_id| in_flight_job_id.matches(job_id))
+    }
+}
@@ -1,12 +1,119 @@
-use crate::utils::MAX_NUM_SUBBLOCKS;
-use anyhow::{Result, bail};
-use derive_more::Constructor;
+use crate::{
+    ids::DEFAULT_CHAIN_ID,
+    storage::{FsInputStorage, InputStorage},
+};
+use anyhow::{Result, bail, ensure};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::{
-    fs,
-    path::{Path, PathBuf},
+    collections::BTreeMap,
+    io::{Read, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
 };
+use tracing::warn;
+
+// zstd compression level for the dumped input files
+const INPUT_COMPRESSION_LEVEL: i32 = 3;
+
+// magic number prefix of a zstd frame, it's used for detecting the unversioned files dumped by
+// the previous versions
+const ZSTD_MAGIC_NUMBER: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+// magic number of the header of a dumped input file, it's followed by the little-endian u32 format
+// version and the zstd compressed input
+const INPUT_FILE_MAGIC_NUMBER: [u8; 4] = *b"EPIN";
+
+// format version of the dumped input files, it must be bumped if the serialization of the inputs
+// changes (e.g. a new ELF or stdin builder layout), so the archives of the older formats are refused
+// instead of deserialized into garbage
+pub const INPUT_FORMAT_VERSION: u32 = 1;
+
+// bytes of the header of a dumped input file
+const INPUT_FILE_HEADER_BYTES: usize = INPUT_FILE_MAGIC_NUMBER.len() + size_of::<u32>();
+
+// magic number prefix of the streamed proving inputs, the version is bumped if the layout changes
+const STREAM_MAGIC_NUMBER: [u8; 4] = *b"PIS1";
+
+// file name of the subblock public values
+const PUBLIC_VALUES_FILE: &str = "public_values.bin";
+
+// file name of the aggregator input
+const AGG_INPUT_FILE: &str = "final_aggregator_stdin_builder.bin";
 
-#[derive(Clone, Constructor, Debug)]
+// file name of the input manifest
+const MANIFEST_FILE: &str = "manifest.json";
+
+// default template of the input directory under the block entry
+pub const DEFAULT_INPUT_LAYOUT: &str = "gas{gas_threshold}";
+
+// default subblock gas threshold of the generated inputs
+pub const DEFAULT_SUBBLOCK_GAS_THRESHOLD: u64 = 10_000_000;
+
+// layout of the dumped input files of a block, the files are stored under `block{n}/` followed by
+// a directory rendered from the template, so the archives of different chains or subblock gas
+// settings could coexist in a storage, e.g. `block23264565/gas10000000/public_values.bin`
+#[derive(Clone, Debug)]
+pub struct InputLayout {
+    // template of the directory under the block entry, the `{chain_id}` and `{gas_threshold}`
+    // placeholders are replaced, e.g. `chain{chain_id}/gas{gas_threshold}`
+    template: String,
+
+    // chain id of the dumped blocks
+    chain_id: u64,
+
+    // subblock gas threshold of the dumped inputs
+    gas_threshold: u64,
+}
+
+impl InputLayout {
+    // create the layout and check the rendered directory is a valid relative path
+    pub fn new(template: String, chain_id: u64, gas_threshold: u64) -> Result<Self> {
+        let layout = Self {
+            template,
+            chain_id,
+            gas_threshold,
+        };
+        let dir = layout.render();
+        ensure!(
+            !dir.contains(['{', '}']),
+            "unknown placeholder in the input layout `{}`",
+            layout.template,
+        );
+        ensure!(
+            dir.split('/')
+                .all(|part| !part.is_empty() && part != "." && part != ".."),
+            "input layout `{}` is not a relative path",
+            layout.template,
+        );
+
+        Ok(layout)
+    }
+
+    // construct the key prefix of the input files of a block
+    pub fn block_key_prefix(&self, block_number: u64) -> String {
+        format!("{}/{}", block_entry(block_number), self.render())
+    }
+
+    // render the directory under the block entry
+    fn render(&self) -> String {
+        self.template
+            .replace("{chain_id}", &self.chain_id.to_string())
+            .replace("{gas_threshold}", &self.gas_threshold.to_string())
+    }
+}
+
+impl Default for InputLayout {
+    fn default() -> Self {
+        Self {
+            template: DEFAULT_INPUT_LAYOUT.to_string(),
+            chain_id: DEFAULT_CHAIN_ID,
+            gas_threshold: DEFAULT_SUBBLOCK_GAS_THRESHOLD,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ProvingInputs {
     // block number to prove
     pub block_number: u64,
@@ -19,71 +126,467 @@ pub struct ProvingInputs {
 
     // bincode serialized multiple subblock stdin builders
     pub subblock_inputs: Vec<Vec<u8>>,
+
+    // SHA-256 hex digest of the aggregator input, it's sent with the input so the provers could
+    // verify the received bytes
+    pub agg_input_digest: String,
+
+    // SHA-256 hex digests of the subblock inputs by subblock index
+    pub subblock_input_digests: Vec<String>,
+}
+
+// manifest of the dumped proving inputs of a block, it's written after the all input files, so a
+// corrupted or partially written archive could be detected when loading
+#[derive(Debug, Deserialize, Serialize)]
+pub struct InputManifest {
+    // block number of the dumped inputs
+    pub block_number: u64,
+
+    // number of the dumped subblock inputs
+    pub num_subblocks: usize,
+
+    // chain of the block, e.g. `ethereum`
+    pub chain: String,
+
+    // unix timestamp in seconds when the inputs are generated
+    pub generated_at: u64,
+
+    // SHA-256 hex digests of the stored files by file name
+    pub files: BTreeMap<String, String>,
+
+    // SHA-256 hex digests of the uncompressed inputs by file name, it's empty in the manifests
+    // written by the previous versions
+    #[serde(default)]
+    pub input_digests: BTreeMap<String, String>,
 }
 
 impl ProvingInputs {
-    // save the proving inputs to a directory
-    pub fn dump_to_dir(&self, dir: &Path) -> Result<()> {
-        let dir = block_dir(self.block_number, dir);
-        fs::create_dir_all(&dir)?;
+    // create the proving inputs and compute the digests of the aggregator and subblock inputs
+    pub fn new(
+        block_number: u64,
+        subblock_public_values: Vec<u8>,
+        agg_input: Vec<u8>,
+        subblock_inputs: Vec<Vec<u8>>,
+    ) -> Self {
+        let agg_input_digest = input_digest(&agg_input);
+        let subblock_input_digests = subblock_inputs
+            .iter()
+            .map(|input| input_digest(input))
+            .collect();
+
+        Self {
+            block_number,
+            subblock_public_values,
+            agg_input,
+            subblock_inputs,
+            agg_input_digest,
+            subblock_input_digests,
+        }
+    }
+
+    // check the aggregator and subblock inputs against their digests
+    pub fn verify_digests(&self) -> Result<()> {
+        let block_number = self.block_number;
+        let digest = input_digest(&self.agg_input);
+        ensure!(
+            digest == self.agg_input_digest,
+            "aggregator input of block {block_number} is corrupted: expected digest {}, got {digest}",
+            self.agg_input_digest,
+        );
+        ensure!(
+            self.subblock_inputs.len() == self.subblock_input_digests.len(),
+            "block {block_number} has {} subblock inputs but {} digests",
+            self.subblock_inputs.len(),
+            self.subblock_input_digests.len(),
+        );
+        for (i, (input, expected_digest)) in self
+            .subblock_inputs
+            .iter()
+            .zip(&self.subblock_input_digests)
+            .enumerate()
+        {
+            let digest = input_digest(input);
+            ensure!(
+                &digest == expected_digest,
+                "subblock input {i} of block {block_number} is corrupted: expected digest {expected_digest}, got {digest}",
+            );
+        }
+
+        Ok(())
+    }
+
+    // save the proving inputs and a manifest to a storage by the layout
+    pub async fn dump_to_storage(
+        &self,
+        storage: &dyn InputStorage,
+        layout: &InputLayout,
+        chain: &str,
+    ) -> Result<()> {
+        let prefix = layout.block_key_prefix(self.block_number);
+        let mut files = BTreeMap::new();
+        let mut input_digests = BTreeMap::new();
 
         // save the subblock public values
-        let file_path = dir.join("public_values.bin");
-        fs::write(file_path, &self.subblock_public_values)?;
+        let digest = write_input(
+            storage,
+            &prefix,
+            PUBLIC_VALUES_FILE,
+            &self.subblock_public_values,
+        )
+        .await?;
+        files.insert(PUBLIC_VALUES_FILE.to_string(), digest);
 
         // save the aggregator input
-        let file_path = dir.join("final_aggregator_stdin_builder.bin");
-        fs::write(file_path, &self.agg_input)?;
+        let digest = write_input(storage, &prefix, AGG_INPUT_FILE, &self.agg_input).await?;
+        files.insert(AGG_INPUT_FILE.to_string(), digest);
+        input_digests.insert(AGG_INPUT_FILE.to_string(), self.agg_input_digest.clone());
 
         // save the subblock inputs
-        for (i, input) in self.subblock_inputs.iter().enumerate() {
-            let file_path = dir.join(format!("subblock_stdin_builder_{i}.bin"));
-            fs::write(file_path, input)?;
+        for (i, (input, input_digest)) in self
+            .subblock_inputs
+            .iter()
+            .zip(&self.subblock_input_digests)
+            .enumerate()
+        {
+            let file_name = subblock_input_file(i);
+            let digest = write_input(storage, &prefix, &file_name, input).await?;
+            files.insert(file_name.clone(), digest);
+            input_digests.insert(file_name, input_digest.clone());
         }
 
-        Ok(())
+        // save the manifest at last
+        let manifest = InputManifest {
+            block_number: self.block_number,
+            num_subblocks: self.subblock_inputs.len(),
+            chain: chain.to_string(),
+            generated_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            files,
+            input_digests,
+        };
+        let key = format!("{prefix}/{MANIFEST_FILE}");
+        storage
+            .write(&key, serde_json::to_vec_pretty(&manifest)?)
+            .await
     }
 
-    // load the proving inputs from a directory
-    pub fn load_from_dir(block_number: u64, dir: &Path) -> Result<Self> {
-        let dir = block_dir(block_number, dir);
-        if !dir.exists() {
-            bail!("cannot read proving inputs from {dir:?} since it doesn't exist");
+    // load the proving inputs from a storage by the layout, the files and the uncompressed inputs
+    // are verified by the manifest if it exists, so the corrupted inputs are not dispatched for
+    // reproducing
+    pub async fn load_from_storage(
+        block_number: u64,
+        storage: &dyn InputStorage,
+        layout: &InputLayout,
+    ) -> Result<Self> {
+        let prefix = layout.block_key_prefix(block_number);
+
+        // load the manifest, it doesn't exist in the archives dumped by the previous versions
+        let manifest = load_manifest(storage, block_number, layout).await?;
+        if manifest.is_none() {
+            warn!("no input manifest of {prefix} in {storage:?}, skip verification");
+        }
+        if let Some(manifest) = &manifest {
+            ensure!(
+                manifest.block_number == block_number,
+                "input manifest of {prefix} has a wrong block number {}",
+                manifest.block_number,
+            );
         }
+        let manifest = manifest.as_ref();
 
-        // save the subblock public values
-        let file_path = dir.join("public_values.bin");
-        let subblock_public_values = fs::read(file_path)?;
+        // load the subblock public values
+        let Some(subblock_public_values) =
+            read_input(storage, &prefix, PUBLIC_VALUES_FILE, manifest).await?
+        else {
+            bail!("cannot read proving inputs of {prefix} from {storage:?} since it doesn't exist");
+        };
 
-        // save the aggregator input
-        let file_path = dir.join("final_aggregator_stdin_builder.bin");
-        let agg_input = fs::read(file_path)?;
+        // load the aggregator input
+        let Some(agg_input) = read_input(storage, &prefix, AGG_INPUT_FILE, manifest).await? else {
+            bail!("cannot read aggregator input of {prefix} from {storage:?}");
+        };
 
-        // save the subblock inputs
-        let mut subblock_inputs = Vec::with_capacity(MAX_NUM_SUBBLOCKS);
-        for i in 0..MAX_NUM_SUBBLOCKS {
-            let file_path = dir.join(format!("subblock_stdin_builder_{i}.bin"));
-            match fs::read(file_path) {
-                Ok(input) => subblock_inputs.push(input),
-                Err(_) => break,
+        // load the subblock inputs until the next index doesn't exist, the number of subblocks is
+        // not limited since it depends on the cluster which dumped the inputs
+        let mut subblock_inputs = Vec::with_capacity(manifest.map_or(0, |m| m.num_subblocks));
+        loop {
+            let file_name = subblock_input_file(subblock_inputs.len());
+            match read_input(storage, &prefix, &file_name, manifest).await? {
+                Some(input) => subblock_inputs.push(input),
+                None => break,
             }
         }
         assert!(
             !subblock_inputs.is_empty(),
             "must have one subblock at least",
         );
+        if let Some(manifest) = manifest {
+            ensure!(
+                manifest.num_subblocks == subblock_inputs.len(),
+                "input manifest of {prefix} expects {} subblocks, but {} subblocks are loaded",
+                manifest.num_subblocks,
+                subblock_inputs.len(),
+            );
+        }
+
+        let proving_inputs = ProvingInputs::new(
+            block_number,
+            subblock_public_values,
+            agg_input,
+            subblock_inputs,
+        );
+        if let Some(manifest) = manifest {
+            proving_inputs.verify_manifest_digests(manifest)?;
+        }
+
+        Ok(proving_inputs)
+    }
+
+    // check the computed input digests against the ones recorded in the manifest, the manifests
+    // written by the previous versions have no input digests
+    fn verify_manifest_digests(&self, manifest: &InputManifest) -> Result<()> {
+        let digests = [(AGG_INPUT_FILE.to_string(), &self.agg_input_digest)]
+            .into_iter()
+            .chain(
+                self.subblock_input_digests
+                    .iter()
+                    .enumerate()
+                    .map(|(i, digest)| (subblock_input_file(i), digest)),
+            );
+        for (file_name, digest) in digests {
+            if let Some(expected_digest) = manifest.input_digests.get(&file_name) {
+                ensure!(
+                    digest == expected_digest,
+                    "input digest mismatch of {file_name} of block {}: expected {expected_digest}, got {digest}",
+                    self.block_number,
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    // save the proving inputs to a local directory by the layout
+    pub async fn dump_to_dir(&self, dir: &Path, layout: &InputLayout, chain: &str) -> Result<()> {
+        let storage = FsInputStorage::new(dir.to_path_buf(), false);
+        self.dump_to_storage(&storage, layout, chain).await
+    }
+
+    // load the proving inputs from a local directory by the layout
+    pub async fn load_from_dir(
+        block_number: u64,
+        dir: &Path,
+        layout: &InputLayout,
+    ) -> Result<Self> {
+        let storage = FsInputStorage::new(dir.to_path_buf(), false);
+        Self::load_from_storage(block_number, &storage, layout).await
+    }
+
+    // stream the proving inputs to a writer with zstd compression, each component is written as a
+    // length-prefixed section directly from its bytes, so no serialized copy of the inputs is
+    // materialized
+    pub fn dump_to_writer<W: Write>(&self, writer: W) -> Result<()> {
+        let mut encoder = zstd::Encoder::new(writer, INPUT_COMPRESSION_LEVEL)?;
+        encoder.write_all(&STREAM_MAGIC_NUMBER)?;
+        encoder.write_all(&self.block_number.to_le_bytes())?;
+        write_section(&mut encoder, &self.subblock_public_values)?;
+        write_section(&mut encoder, &self.agg_input)?;
+        encoder.write_all(&(self.subblock_inputs.len() as u64).to_le_bytes())?;
+        for input in &self.subblock_inputs {
+            write_section(&mut encoder, input)?;
+        }
+        encoder.finish()?.flush()?;
 
-        Ok(ProvingInputs {
+        Ok(())
+    }
+
+    // load the proving inputs streamed by `dump_to_writer` from a reader, each component is
+    // decompressed into its own buffer without reading the whole stream first
+    pub fn load_from_reader<R: Read>(reader: R) -> Result<Self> {
+        let mut decoder = zstd::Decoder::new(reader)?;
+
+        let mut magic_number = [0; 4];
+        decoder.read_exact(&mut magic_number)?;
+        ensure!(
+            magic_number == STREAM_MAGIC_NUMBER,
+            "invalid proving inputs stream with magic number {magic_number:?}",
+        );
+
+        let block_number = read_u64(&mut decoder)?;
+        let subblock_public_values = read_section(&mut decoder)?;
+        let agg_input = read_section(&mut decoder)?;
+        let num_subblocks = read_u64(&mut decoder)?;
+        ensure!(
+            num_subblocks > 0,
+            "proving inputs stream of block {block_number} must have one subblock at least",
+        );
+        let subblock_inputs = (0..num_subblocks)
+            .map(|_| read_section(&mut decoder))
+            .collect::<Result<_>>()?;
+
+        Ok(ProvingInputs::new(
             block_number,
             subblock_public_values,
             agg_input,
             subblock_inputs,
-        })
+        ))
+    }
+}
+
+// return the SHA-256 hex digest of an input
+pub fn input_digest(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+// write a length-prefixed section of the streamed proving inputs
+fn write_section<W: Write>(writer: &mut W, data: &[u8]) -> Result<()> {
+    writer.write_all(&(data.len() as u64).to_le_bytes())?;
+    writer.write_all(data)?;
+
+    Ok(())
+}
+
+// read a length-prefixed section of the streamed proving inputs, the buffer grows with the read
+// bytes instead of being allocated by the untrusted length
+fn read_section<R: Read>(reader: &mut R) -> Result<Vec<u8>> {
+    let len = read_u64(reader)?;
+    let mut data = vec![];
+    reader.by_ref().take(len).read_to_end(&mut data)?;
+    ensure!(
+        data.len() as u64 == len,
+        "truncated proving inputs stream: expected {len} bytes, got {}",
+        data.len(),
+    );
+
+    Ok(data)
+}
+
+// read a little-endian u64 of the streamed proving inputs
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(u64::from_le_bytes(bytes))
+}
+
+// write an input with the versioned header and zstd compression, and return the SHA-256 hex
+// digest of the stored file
+async fn write_input(
+    storage: &dyn InputStorage,
+    prefix: &str,
+    file_name: &str,
+    data: &[u8],
+) -> Result<String> {
+    let mut file = Vec::with_capacity(INPUT_FILE_HEADER_BYTES);
+    file.extend_from_slice(&INPUT_FILE_MAGIC_NUMBER);
+    file.extend_from_slice(&INPUT_FORMAT_VERSION.to_le_bytes());
+    zstd::stream::copy_encode(data, &mut file, INPUT_COMPRESSION_LEVEL)?;
+
+    let digest = input_digest(&file);
+    storage
+        .write(&format!("{prefix}/{file_name}"), file)
+        .await?;
+
+    Ok(digest)
+}
+
+// read an input and verify it by the manifest if specified, the input of a mismatched format
+// version is refused; the unversioned files dumped by the previous versions are migrated, they're
+// decompressed if starting with the zstd magic number, otherwise returned as uncompressed inputs
+async fn read_input(
+    storage: &dyn InputStorage,
+    prefix: &str,
+    file_name: &str,
+    manifest: Option<&InputManifest>,
+) -> Result<Option<Vec<u8>>> {
+    // the memory-mapped files are decompressed without a copy of the compressed bytes
+    let data = storage.read_data(&format!("{prefix}/{file_name}")).await?;
+
+    if let Some(manifest) = manifest {
+        let expected_digest = manifest.files.get(file_name);
+        match (&data, expected_digest) {
+            (Some(data), Some(expected_digest)) => {
+                let digest = input_digest(data);
+                ensure!(
+                    &digest == expected_digest,
+                    "checksum mismatch of {prefix}/{file_name}: expected {expected_digest}, got {digest}",
+                );
+            }
+            (None, Some(_)) => bail!("missing input file {prefix}/{file_name} in the manifest"),
+            (Some(_), None) => {
+                bail!("unexpected input file {prefix}/{file_name} not in the manifest")
+            }
+            (None, None) => {}
+        }
     }
+
+    let Some(data) = data else {
+        return Ok(None);
+    };
+    if let Some(header) = data.strip_prefix(&INPUT_FILE_MAGIC_NUMBER) {
+        ensure!(
+            header.len() >= size_of::<u32>(),
+            "truncated header of input file {prefix}/{file_name}",
+        );
+        let (version, compressed) = header.split_at(size_of::<u32>());
+        let version = u32::from_le_bytes(version.try_into()?);
+        ensure!(
+            version == INPUT_FORMAT_VERSION,
+            "input file {prefix}/{file_name} has format version {version}, but version {INPUT_FORMAT_VERSION} is supported, the inputs must be dumped again by this version",
+        );
+        return Ok(Some(zstd::decode_all(compressed)?));
+    }
+
+    warn!("input file {prefix}/{file_name} has no format header, load it as a legacy file");
+    if !data.starts_with(&ZSTD_MAGIC_NUMBER) {
+        return Ok(Some(data.into_vec()));
+    }
+
+    Ok(Some(zstd::decode_all(&*data)?))
+}
+
+// construct the file name of an indexed subblock input
+fn subblock_input_file(index: usize) -> String {
+    format!("subblock_stdin_builder_{index}.bin")
+}
+
+// list the block numbers of the dumped proving inputs in a storage by ascending order
+pub async fn list_dumped_block_numbers(storage: &dyn InputStorage) -> Result<Vec<u64>> {
+    let mut block_numbers: Vec<u64> = storage
+        .list_entries()
+        .await?
+        .iter()
+        .filter_map(|name| name.strip_prefix("block")?.parse().ok())
+        .collect();
+    block_numbers.sort_unstable();
+
+    Ok(block_numbers)
+}
+
+// load the manifest of the dumped proving inputs of a block by the layout, return `None` if it
+// doesn't exist
+pub async fn load_manifest(
+    storage: &dyn InputStorage,
+    block_number: u64,
+    layout: &InputLayout,
+) -> Result<Option<InputManifest>> {
+    let key = format!("{}/{MANIFEST_FILE}", layout.block_key_prefix(block_number));
+    match storage.read(&key).await? {
+        Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
+        None => Ok(None),
+    }
+}
+
+// return the total bytes of the dumped proving inputs of a block
+pub async fn dumped_block_bytes(storage: &dyn InputStorage, block_number: u64) -> Result<u64> {
+    storage.entry_bytes(&block_entry(block_number)).await
+}
+
+// remove the dumped proving inputs of a block
+pub async fn remove_dumped_block(storage: &dyn InputStorage, block_number: u64) -> Result<()> {
+    storage.remove_entry(&block_entry(block_number)).await
 }
 
-// construct the block base directory
-fn block_dir(block_number: u64, dir: &Path) -> PathBuf {
-    dir.join(format!("block{}", block_number))
-        .join("gas10000000")
+// construct the top-level entry name of a block
+fn block_entry(block_number: u64) -> String {
+    format!("block{block_number}")
 }
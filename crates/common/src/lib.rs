@@ -1,6 +1,19 @@
+pub mod bus;
 pub mod channel;
 pub mod fetch;
+pub mod ids;
+pub mod in_flight;
 pub mod inputs;
 pub mod logger;
+pub mod metrics;
+pub mod pipeline_error;
+pub mod proof_store;
+pub mod readiness;
 pub mod report;
+pub mod runtime;
+pub mod shutdown;
+pub mod storage;
+pub mod summary;
+pub mod trace;
 pub mod utils;
+pub mod version;
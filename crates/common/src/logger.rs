@@ -1,18 +1,29 @@
-use std::{env, sync::Once};
-use tracing::Level;
+use crate::{channel::WatchReceiver, runtime::RuntimeState};
+use anyhow::{Result, anyhow};
+use std::{
+    env,
+    sync::{Once, OnceLock},
+};
+use tokio::{spawn, task::JoinHandle};
+use tracing::{Level, error, info};
 use tracing_forest::ForestLayer;
 use tracing_subscriber::{
     EnvFilter, Layer, Registry, filter::filter_fn, fmt::format::FmtSpan, layer::SubscriberExt,
-    util::SubscriberInitExt,
+    reload, util::SubscriberInitExt,
 };
 
 static INIT: Once = Once::new();
 
+// handle for replacing the log filter at runtime, it's set when the logger is initialized
+static LOG_FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
 pub fn setup_logger() {
     INIT.call_once(|| {
         let default_filter = "off";
         let env_filter =
             EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter));
+        let (env_filter, log_filter_handle) = reload::Layer::new(env_filter);
+        let _ = LOG_FILTER_HANDLE.set(log_filter_handle);
 
         let logger_type = env::var("RUST_LOGGER").unwrap_or_else(|_| "flat".to_string());
         match logger_type.as_str() {
@@ -31,15 +42,17 @@ pub fn setup_logger() {
                     .init();
             }
             "flat" => {
-                tracing_subscriber::fmt::Subscriber::builder()
-                    .compact()
-                    .with_ansi(false)
-                    .with_file(false)
-                    .with_target(false)
-                    .with_thread_names(false)
-                    .with_env_filter(env_filter)
-                    .with_span_events(FmtSpan::CLOSE)
-                    .finish()
+                Registry::default()
+                    .with(env_filter)
+                    .with(
+                        tracing_subscriber::fmt::layer()
+                            .compact()
+                            .with_ansi(false)
+                            .with_file(false)
+                            .with_target(false)
+                            .with_thread_names(false)
+                            .with_span_events(FmtSpan::CLOSE),
+                    )
                     .init();
             }
             _ => {
@@ -48,3 +61,39 @@ pub fn setup_logger() {
         }
     });
 }
+
+// check the log filter directives are valid, e.g. `info,scheduler=debug`
+pub fn check_log_filter(log_filter: &str) -> Result<()> {
+    EnvFilter::try_new(log_filter)?;
+
+    Ok(())
+}
+
+// replace the log filter of the logger at runtime
+pub fn set_log_filter(log_filter: &str) -> Result<()> {
+    let handle = LOG_FILTER_HANDLE
+        .get()
+        .ok_or_else(|| anyhow!("logger is not initialized"))?;
+    handle.reload(EnvFilter::try_new(log_filter)?)?;
+
+    Ok(())
+}
+
+// apply the log filter changed in the runtime state until the state channel is closed
+pub fn watch_log_filter(mut runtime_state: WatchReceiver<RuntimeState>) -> JoinHandle<()> {
+    spawn(async move {
+        let mut current_log_filter = runtime_state.latest().log_filter;
+        while let Some(state) = runtime_state.changed().await {
+            if state.log_filter == current_log_filter {
+                continue;
+            }
+            if let Some(log_filter) = &state.log_filter {
+                match set_log_filter(log_filter) {
+                    Ok(()) => info!("logger: changed the log filter to `{log_filter}`"),
+                    Err(e) => error!("logger: failed to change the log filter {e:?}"),
+                }
+            }
+            current_log_filter = state.log_filter;
+        }
+    })
+}
@@ -0,0 +1,107 @@
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex};
+
+// registry of the process metrics, the metrics are rendered in the prometheus text format
+#[derive(Debug, Default)]
+pub struct MetricsRegistry {
+    // counter values keyed by the metric name and the rendered labels
+    counters: Mutex<BTreeMap<String, BTreeMap<String, u64>>>,
+
+    // summaries of the observed values keyed by the metric name and the rendered labels, each
+    // summary is a pair of the sum and count of the values
+    summaries: Mutex<BTreeMap<String, BTreeMap<String, (f64, u64)>>>,
+
+    // gauge values keyed by the metric name and the rendered labels
+    gauges: Mutex<BTreeMap<String, BTreeMap<String, f64>>>,
+}
+
+impl MetricsRegistry {
+    // increase a counter of the specified labels by one
+    pub fn inc_counter(&self, name: &str, labels: &[(&str, &str)]) {
+        let mut counters = self
+            .counters
+            .lock()
+            .expect("metrics: counters lock poisoned");
+        *counters
+            .entry(name.to_string())
+            .or_default()
+            .entry(render_labels(labels))
+            .or_default() += 1;
+    }
+
+    // observe a value of the summary of the specified labels
+    pub fn observe(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        let mut summaries = self
+            .summaries
+            .lock()
+            .expect("metrics: summaries lock poisoned");
+        let (sum, count) = summaries
+            .entry(name.to_string())
+            .or_default()
+            .entry(render_labels(labels))
+            .or_default();
+        *sum += value;
+        *count += 1;
+    }
+
+    // set a gauge of the specified labels to the value
+    pub fn set_gauge(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        let mut gauges = self.gauges.lock().expect("metrics: gauges lock poisoned");
+        gauges
+            .entry(name.to_string())
+            .or_default()
+            .insert(render_labels(labels), value);
+    }
+
+    // render the all metrics in the prometheus text format
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+
+        let counters = self
+            .counters
+            .lock()
+            .expect("metrics: counters lock poisoned");
+        for (name, values) in counters.iter() {
+            let _ = writeln!(output, "# TYPE {name} counter");
+            for (labels, value) in values {
+                let _ = writeln!(output, "{name}{labels} {value}");
+            }
+        }
+
+        let summaries = self
+            .summaries
+            .lock()
+            .expect("metrics: summaries lock poisoned");
+        for (name, values) in summaries.iter() {
+            let _ = writeln!(output, "# TYPE {name} summary");
+            for (labels, (sum, count)) in values {
+                let _ = writeln!(output, "{name}_sum{labels} {sum}");
+                let _ = writeln!(output, "{name}_count{labels} {count}");
+            }
+        }
+
+        let gauges = self.gauges.lock().expect("metrics: gauges lock poisoned");
+        for (name, values) in gauges.iter() {
+            let _ = writeln!(output, "# TYPE {name} gauge");
+            for (labels, value) in values {
+                let _ = writeln!(output, "{name}{labels} {value}");
+            }
+        }
+
+        output
+    }
+}
+
+// render the labels as `{key="value",...}`, it's empty if no labels
+fn render_labels(labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+
+    let labels = labels
+        .iter()
+        .map(|(key, value)| format!("{key}=\"{}\"", value.replace('"', "\\\"")))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    format!("{{{labels}}}")
+}
@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// maximum number of the recent pipeline errors kept for the admin api
+const MAX_RECENT_PIPELINE_ERRORS: usize = 100;
+
+// kind of the pipeline errors
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineErrorKind {
+    // failed to send a message to the target thread
+    SendFailed,
+
+    // received a message type which has no route from the source thread
+    Unroutable,
+
+    // the receiving channel from the source thread is closed
+    ChannelClosed,
+}
+
+impl fmt::Display for PipelineErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SendFailed => write!(f, "send_failed"),
+            Self::Unroutable => write!(f, "unroutable"),
+            Self::ChannelClosed => write!(f, "channel_closed"),
+        }
+    }
+}
+
+// structured pipeline error event produced by the scheduler
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PipelineError {
+    // error kind
+    pub kind: PipelineErrorKind,
+
+    // thread where the message comes from
+    pub source: String,
+
+    // thread where the message is routed to, it's none if the message has no route
+    pub target: Option<String>,
+
+    // error details, e.g. the unroutable message
+    pub message: String,
+
+    // unix timestamp when the error occurred (in seconds)
+    pub timestamp_seconds: u64,
+}
+
+impl PipelineError {
+    pub fn new(
+        kind: PipelineErrorKind,
+        source: &str,
+        target: Option<&str>,
+        message: String,
+    ) -> Self {
+        let timestamp_seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+
+        Self {
+            kind,
+            source: source.to_string(),
+            target: target.map(str::to_string),
+            message,
+            timestamp_seconds,
+        }
+    }
+}
+
+impl fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} from {}", self.kind, self.source)?;
+        if let Some(target) = &self.target {
+            write!(f, " to {target}")?;
+        }
+
+        write!(f, ": {}", self.message)
+    }
+}
+
+// log of the recent pipeline errors served by the admin api
+#[derive(Debug, Default)]
+pub struct PipelineErrorLog {
+    // recent pipeline errors by ascending order of occurrence
+    errors: Mutex<VecDeque<PipelineError>>,
+}
+
+impl PipelineErrorLog {
+    // append a pipeline error and drop the oldest one if the log is full
+    pub fn push(&self, error: PipelineError) {
+        let mut errors = self
+            .errors
+            .lock()
+            .expect("pipeline-error-log: lock poisoned");
+        if errors.len() >= MAX_RECENT_PIPELINE_ERRORS {
+            errors.pop_front();
+        }
+        errors.push_back(error);
+    }
+
+    // return the recent pipeline errors by ascending order of occurrence
+    pub fn recent(&self) -> Vec<PipelineError> {
+        let errors = self
+            .errors
+            .lock()
+            .expect("pipeline-error-log: lock poisoned");
+        errors.iter().cloned().collect()
+    }
+}
@@ -0,0 +1,66 @@
+use crate::{
+    ids::JobId,
+    storage::{InputStorage, open_input_storage},
+};
+use anyhow::{Result, bail};
+use sha2::{Digest, Sha256};
+use std::{fmt, path::Path, sync::Arc};
+
+// store of the received proofs on a local directory or an object store, the proofs are keyed by
+// block number and job id, e.g. `block23264565/job3.bin`, and referenced by the reports instead of
+// carrying the proof bytes; the reports carry the proof hash for checking the bytes loaded back
+pub struct ProofStore {
+    // base directory or object store URL of the proofs
+    location: String,
+
+    // storage for writing the proof files
+    storage: Arc<dyn InputStorage>,
+}
+
+impl ProofStore {
+    // open the proof store by the location, it could be a local directory or an object store URL
+    // prefixed with `s3://` or `gs://`
+    pub fn open(location: &Path) -> Result<Self> {
+        let storage = open_input_storage(location)?;
+        let location = location.to_string_lossy().trim_end_matches('/').to_string();
+
+        Ok(Self { location, storage })
+    }
+
+    // write a proof and return its storage reference
+    pub async fn save(&self, job_id: JobId, block_number: u64, proof: Vec<u8>) -> Result<String> {
+        let key = format!("block{block_number}/job{job_id}.bin");
+        self.storage.write(&key, proof).await?;
+
+        Ok(format!("{}/{key}", self.location))
+    }
+
+    // read a persisted proof by its storage reference returned by `save`, it returns none if the
+    // proof is not found
+    pub async fn load(&self, proof_uri: &str) -> Result<Option<Vec<u8>>> {
+        let Some(key) = proof_uri
+            .strip_prefix(&self.location)
+            .and_then(|key| key.strip_prefix('/'))
+        else {
+            bail!(
+                "proof {proof_uri} is not in the proof store {}",
+                self.location
+            );
+        };
+
+        self.storage.read(key).await
+    }
+}
+
+// return the SHA-256 hex digest of the proof bytes
+pub fn proof_hash(proof: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(proof))
+}
+
+impl fmt::Debug for ProofStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProofStore")
+            .field("location", &self.location)
+            .finish_non_exhaustive()
+    }
+}
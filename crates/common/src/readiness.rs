@@ -0,0 +1,86 @@
+use crate::{
+    channel::{WatchChannel, WatchReceiver},
+    runtime::RuntimeState,
+};
+use anyhow::{Result, bail};
+use derive_more::Constructor;
+use std::{
+    env,
+    os::{
+        linux::net::SocketAddrExt,
+        unix::net::{SocketAddr, UnixDatagram},
+    },
+};
+use tracing::info;
+
+// env variable of the systemd notification socket, it's set by systemd for `Type=notify` units
+const NOTIFY_SOCKET_ENV: &str = "NOTIFY_SOCKET";
+
+// readiness check of a component initializing in the process, it's pending in the runtime state
+// until passed, and the process is ready once the all pending checks passed
+#[derive(Clone, Constructor, Debug)]
+pub struct ReadinessCheck {
+    // name of the check, e.g. the component name
+    name: String,
+
+    // runtime state of the process holding the pending checks
+    runtime_state: WatchChannel<RuntimeState>,
+}
+
+impl ReadinessCheck {
+    // mark the check pending until passed, e.g. the component is initializing or reconnecting to
+    // its dependencies
+    pub fn set_pending(&self) {
+        self.update(true);
+    }
+
+    // pass the check, e.g. the component is connected to its dependencies
+    pub fn pass(&self) {
+        self.update(false);
+    }
+
+    // set the check pending or passed, the subscribers are only notified if changed
+    fn update(&self, pending: bool) {
+        let changed = self.runtime_state.send_if_modified(|state| {
+            if pending {
+                state.pending_checks.insert(self.name.clone())
+            } else {
+                state.pending_checks.remove(&self.name)
+            }
+        });
+        if changed && !pending {
+            info!("readiness: passed the {} check", self.name);
+        }
+    }
+}
+
+// wait for the all registered checks passed, it returns immediately if the process is ready
+pub async fn wait_ready(runtime_state: &mut WatchReceiver<RuntimeState>) {
+    if runtime_state.latest().is_ready() {
+        return;
+    }
+    while let Some(state) = runtime_state.changed().await {
+        if state.is_ready() {
+            return;
+        }
+    }
+}
+
+// notify systemd the process is ready by the notification socket, it returns false if not run by
+// systemd with `Type=notify`
+pub fn notify_systemd_ready() -> Result<bool> {
+    let Some(socket_path) = env::var_os(NOTIFY_SOCKET_ENV) else {
+        return Ok(false);
+    };
+    let socket_path = socket_path.to_string_lossy();
+
+    // a socket path starting with `@` is in the abstract namespace
+    let addr = match socket_path.strip_prefix('@') {
+        Some(name) => SocketAddr::from_abstract_name(name)?,
+        None if socket_path.starts_with('/') => SocketAddr::from_pathname(&*socket_path)?,
+        None => bail!("unsupported systemd notification socket `{socket_path}`"),
+    };
+    UnixDatagram::unbound()?.send_to_addr(b"READY=1", &addr)?;
+
+    Ok(true)
+}
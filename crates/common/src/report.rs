@@ -1,63 +1,556 @@
-use anyhow::Result;
+use crate::{
+    ids::{BlockKey, JobId},
+    inputs::ProvingInputs,
+    proof_store,
+    trace::{TraceId, block_span},
+};
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+use rusqlite::{Connection, OptionalExtension, params};
 use serde::{Deserialize, Serialize};
-use std::{fmt, fs::OpenOptions, io::Write, path::Path};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Debug},
+    fs::OpenOptions,
+    io::Write,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::{sync::RwLock, task::spawn_blocking};
+use tracing::Span;
+
+// pipeline stage where a block failed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureStage {
+    // failed to fetch, execute or load the block data
+    Fetching,
+
+    // rejected before proving, e.g. the inputs exceed the size limit
+    Validation,
+
+    // failed in the proving cluster
+    Proving,
+}
+
+impl fmt::Display for FailureStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fetching => write!(f, "fetching"),
+            Self::Validation => write!(f, "validation"),
+            Self::Proving => write!(f, "proving"),
+        }
+    }
+}
+
+// error code of a proving failure reported by the provers
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProvingErrorCode {
+    // prover ran out of memory
+    Oom,
+
+    // constraints are not satisfied by the execution trace
+    ConstraintFailure,
+
+    // proving exceeded the time limit of the prover
+    Timeout,
+
+    // proving inputs failed to decode
+    InputDecodeError,
+
+    // proof failed to verify by the proof service
+    VerificationFailure,
+}
+
+impl ProvingErrorCode {
+    // identify if the block could be proved by retrying, the resource limits of the provers are
+    // transient but the invalid inputs and proofs are not
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Oom | Self::Timeout)
+    }
+}
+
+impl fmt::Display for ProvingErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Oom => write!(f, "oom"),
+            Self::ConstraintFailure => write!(f, "constraint_failure"),
+            Self::Timeout => write!(f, "timeout"),
+            Self::InputDecodeError => write!(f, "input_decode_error"),
+            Self::VerificationFailure => write!(f, "verification_failure"),
+        }
+    }
+}
+
+// proving result of a subblock reported by the provers
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvedSubblock {
+    // index of the subblock in the block
+    pub subblock_index: u32,
+
+    // proving cycles of the subblock
+    pub cycles: u64,
+
+    // milliseconds of proving the subblock, it's zero if not reported
+    pub proving_milliseconds: u64,
+
+    // id of the prover which proved the subblock, it's empty if not reported
+    pub prover_id: String,
+}
+
+// header of the csv columns of the block reports
+pub const CSV_HEADER: &str = "block_number,job_id,success,cycles,emulated_cycles,proving_seconds,data_fetch_seconds,rpc_fetch_seconds,host_execution_seconds,serialization_seconds,emulation_seconds,input_bytes,gas_used,blob_count,blob_gas_used,subblock_gas_used,subblock_input_bytes,emulated_subblock_cycles,proved_subblock_cycles,proved_subblock_seconds,aggregation_seconds,failure_stage,proof_uri,proving_error_code,chain_id,trace_id,proof_bytes,gas_limit,tx_count,proving_khz,proof_hash,reported_at";
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct BlockProvingReport {
     // identify if proving is success
     pub success: bool,
 
+    // job id of the fetch request assigned by the fetch-service
+    pub job_id: JobId,
+
+    // trace id of the block generated when the report is initialized
+    pub trace_id: TraceId,
+
+    // chain id of the block
+    pub chain_id: u64,
+
     // block number
     pub block_number: u64,
 
     // emulation cycles
     pub cycles: u64,
 
-    // milliseconds of proving time
+    // milliseconds of proving time, it's the end-to-end time reported by the provers, or derived
+    // from the slowest subblock and the aggregation if only the breakdown is reported
     pub proving_milliseconds: u64,
 
+    // milliseconds of aggregating the subblock proofs, it's zero if not reported
+    pub aggregation_milliseconds: u64,
+
     // milliseconds of fetching and preparing block input data
     pub data_fetch_milliseconds: u64,
 
-    // bincode serialized proof bytes
+    // milliseconds of the rpc requests for fetching the block header and the state, the time of
+    // the concurrent requests is summed
+    pub rpc_fetch_milliseconds: u64,
+
+    // milliseconds of executing the block on the host for generating the subblock output,
+    // excluding the state rpc requests made during the execution
+    pub host_execution_milliseconds: u64,
+
+    // milliseconds of serializing the subblock and aggregator stdin, excluding the emulation
+    pub serialization_milliseconds: u64,
+
+    // milliseconds of emulating the subblock and aggregator inputs, it's zero if the inputs are
+    // not emulated
+    pub emulation_milliseconds: u64,
+
+    // bincode serialized proof bytes, it's none if the proof is persisted by the proof service
     pub proof: Option<Vec<u8>>,
+
+    // storage reference of the proof persisted by the proof service, e.g.
+    // `proofs/block23264565/job3.bin`
+    pub proof_uri: Option<String>,
+
+    // bytes of the serialized proof, it's kept if the proof is persisted by the proof service
+    pub proof_bytes: u64,
+
+    // SHA-256 hex digest of the serialized proof, it's kept if the proof is persisted by the proof
+    // service, so the proof loaded from the storage could be checked; it's none if not proved
+    pub proof_hash: Option<String>,
+
+    // emulated cycles of each subblock, it's empty if the inputs are not emulated
+    pub emulated_subblock_cycles: Vec<u64>,
+
+    // emulated cycles of the aggregator, it's zero if the inputs are not emulated
+    pub emulated_agg_cycles: u64,
+
+    // identify if the report is a dry-run estimation without proving
+    pub is_estimation: bool,
+
+    // gas limit of the block
+    pub gas_limit: u64,
+
+    // gas used of the block
+    pub gas_used: u64,
+
+    // number of the transactions in the block
+    pub tx_count: u64,
+
+    // blob gas used of the block, it's zero if the block carries no EIP-4844 blobs
+    pub blob_gas_used: u64,
+
+    // number of the EIP-4844 blobs carried by the block
+    pub blob_count: u64,
+
+    // bytes of the serialized subblock public values
+    pub public_values_bytes: u64,
+
+    // bytes of the serialized aggregator input
+    pub agg_input_bytes: u64,
+
+    // bytes of each serialized subblock input
+    pub subblock_input_bytes: Vec<u64>,
+
+    // gas used by each subblock, it's empty if the inputs are loaded from the dumped files
+    pub subblock_gas_used: Vec<u64>,
+
+    // bytes of the parent state serialized in each subblock input, it's empty if the inputs are
+    // loaded from the dumped files
+    pub subblock_parent_state_bytes: Vec<u64>,
+
+    // proving result of each subblock reported by the provers, it's empty if not reported
+    pub proved_subblocks: Vec<ProvedSubblock>,
+
+    // reason of the failure before proving, e.g. the inputs exceed the size limit
+    pub failure_reason: Option<String>,
+
+    // pipeline stage where the block failed, it's none if the block succeeded
+    pub failure_stage: Option<FailureStage>,
+
+    // error code of a proving failure reported by the provers, it's none if not reported
+    pub proving_error_code: Option<ProvingErrorCode>,
+
+    // unix seconds of reporting the block, it's zero for the reports stored before it's recorded
+    #[serde(default)]
+    pub reported_at: u64,
 }
 
 impl fmt::Display for BlockProvingReport {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "Block #{} | success: {} | cycles: {} | proving: {} ms | data_fetch: {} ms",
+            "Block #{} | chain: {} | job: {} | success: {} | cycles: {} | emulated_cycles: {} | proving: {} ms | data_fetch: {} ms",
             self.block_number,
+            self.chain_id,
+            self.job_id,
             self.success,
             self.cycles,
+            self.emulated_cycles(),
             self.proving_milliseconds,
             self.data_fetch_milliseconds,
-        )
+        )?;
+
+        if self.host_execution_milliseconds > 0 {
+            write!(
+                f,
+                " | rpc_fetch: {} ms | host_execution: {} ms | serialization: {} ms | emulation: {} ms",
+                self.rpc_fetch_milliseconds,
+                self.host_execution_milliseconds,
+                self.serialization_milliseconds,
+                self.emulation_milliseconds,
+            )?;
+        }
+        if self.input_bytes() > 0 {
+            write!(f, " | input: {} bytes", self.input_bytes())?;
+        }
+        if let Some(khz) = self.proving_khz() {
+            write!(f, " | speed: {khz:.1} kHz")?;
+        }
+        if self.gas_used > 0 {
+            write!(
+                f,
+                " | gas_used: {} | gas_limit: {} | txs: {}",
+                self.gas_used, self.gas_limit, self.tx_count,
+            )?;
+        }
+        if self.blob_count > 0 {
+            write!(
+                f,
+                " | blobs: {} | blob_gas_used: {}",
+                self.blob_count, self.blob_gas_used,
+            )?;
+        }
+        if !self.subblock_gas_used.is_empty() {
+            write!(f, " | subblock_gas_used: {:?}", self.subblock_gas_used)?;
+        }
+        let parent_state_bytes: u64 = self.subblock_parent_state_bytes.iter().sum();
+        if parent_state_bytes > 0 {
+            write!(f, " | parent_state: {parent_state_bytes} bytes")?;
+        }
+        if let Some(imbalance) = self.subblock_gas_imbalance() {
+            write!(f, " | subblock_gas_imbalance: {imbalance:.2}")?;
+        }
+        if !self.proved_subblocks.is_empty() {
+            write!(
+                f,
+                " | proved_subblock_cycles: {:?} | proved_subblock_proving: {:?} ms",
+                self.proved_subblock_cycles(),
+                self.proved_subblock_milliseconds(),
+            )?;
+        }
+        if self.aggregation_milliseconds > 0 {
+            write!(f, " | aggregation: {} ms", self.aggregation_milliseconds)?;
+        }
+        if self.proof_bytes > 0 {
+            write!(f, " | proof_bytes: {}", self.proof_bytes)?;
+        }
+        if let Some(proof_uri) = &self.proof_uri {
+            write!(f, " | proof: {proof_uri}")?;
+        }
+        if let Some(proof_hash) = &self.proof_hash {
+            write!(f, " | proof_hash: {proof_hash}")?;
+        }
+        if self.is_estimation {
+            write!(f, " | estimation")?;
+        }
+        if let Some(failure_stage) = self.failure_stage {
+            write!(f, " | failure_stage: {failure_stage}")?;
+        }
+        if let Some(proving_error_code) = self.proving_error_code {
+            write!(f, " | proving_error_code: {proving_error_code}")?;
+        }
+        if let Some(failure_reason) = &self.failure_reason {
+            write!(f, " | failure: {failure_reason}")?;
+        }
+
+        Ok(())
     }
 }
 
 impl BlockProvingReport {
-    // initialize a report after fetching block data
-    pub fn new(block_number: u64, data_fetch_milliseconds: u64) -> Self {
+    // initialize a report before fetching block data
+    pub fn new(job_id: JobId, block_key: BlockKey) -> Self {
         Self {
-            block_number,
-            data_fetch_milliseconds,
+            job_id,
+            trace_id: TraceId::generate(),
+            chain_id: block_key.chain_id,
+            block_number: block_key.block_number,
             ..Default::default()
         }
     }
 
-    // set proving success
-    pub fn on_proving_success(&mut self, cycles: u64, proving_milliseconds: u64, proof: Vec<u8>) {
+    // return the span of the block for instrumenting the processing in each component
+    pub fn span(&self) -> Span {
+        block_span(self.trace_id, self.job_id, self.block_number)
+    }
+
+    // return the key of the block across the chains
+    pub fn block_key(&self) -> BlockKey {
+        BlockKey::new(self.chain_id, self.block_number)
+    }
+
+    // set the time of fetching and preparing block input data
+    pub fn on_data_fetched(&mut self, data_fetch_milliseconds: u64) {
+        self.data_fetch_milliseconds = data_fetch_milliseconds;
+    }
+
+    // set the time of each stage of fetching and preparing block input data
+    pub fn on_fetch_stages_timed(
+        &mut self,
+        rpc_fetch_milliseconds: u64,
+        host_execution_milliseconds: u64,
+        serialization_milliseconds: u64,
+        emulation_milliseconds: u64,
+    ) {
+        self.rpc_fetch_milliseconds = rpc_fetch_milliseconds;
+        self.host_execution_milliseconds = host_execution_milliseconds;
+        self.serialization_milliseconds = serialization_milliseconds;
+        self.emulation_milliseconds = emulation_milliseconds;
+    }
+
+    // set the gas, transaction and blob metadata of the fetched block
+    pub fn on_block_fetched(
+        &mut self,
+        gas_limit: u64,
+        gas_used: u64,
+        tx_count: u64,
+        blob_gas_used: u64,
+        blob_count: u64,
+    ) {
+        self.gas_limit = gas_limit;
+        self.gas_used = gas_used;
+        self.tx_count = tx_count;
+        self.blob_gas_used = blob_gas_used;
+        self.blob_count = blob_count;
+    }
+
+    // set the gas used by each subblock
+    pub fn on_subblocks_split(&mut self, subblock_gas_used: Vec<u64>) {
+        self.subblock_gas_used = subblock_gas_used;
+    }
+
+    // set the bytes of the parent state serialized in each subblock input
+    pub fn on_parent_states_measured(&mut self, subblock_parent_state_bytes: Vec<u64>) {
+        self.subblock_parent_state_bytes = subblock_parent_state_bytes;
+    }
+
+    // return the ratio of the maximum subblock gas to the average, `1.0` means a balanced split
+    pub fn subblock_gas_imbalance(&self) -> Option<f64> {
+        let max_gas_used = *self.subblock_gas_used.iter().max()?;
+        let total_gas_used: u64 = self.subblock_gas_used.iter().sum();
+        if total_gas_used == 0 {
+            return None;
+        }
+
+        let avg_gas_used = total_gas_used as f64 / self.subblock_gas_used.len() as f64;
+        Some(max_gas_used as f64 / avg_gas_used)
+    }
+
+    // set the emulated cycles of subblocks and aggregator
+    pub fn on_inputs_emulated(&mut self, subblock_cycles: Vec<u64>, agg_cycles: u64) {
+        self.emulated_subblock_cycles = subblock_cycles;
+        self.emulated_agg_cycles = agg_cycles;
+    }
+
+    // set the serialized sizes of the proving inputs
+    pub fn on_inputs_measured(&mut self, proving_inputs: &ProvingInputs) {
+        self.public_values_bytes = proving_inputs.subblock_public_values.len() as u64;
+        self.agg_input_bytes = proving_inputs.agg_input.len() as u64;
+        self.subblock_input_bytes = proving_inputs
+            .subblock_inputs
+            .iter()
+            .map(|input| input.len() as u64)
+            .collect();
+    }
+
+    // return the total bytes of the serialized proving inputs
+    pub fn input_bytes(&self) -> u64 {
+        self.public_values_bytes
+            + self.agg_input_bytes
+            + self.subblock_input_bytes.iter().sum::<u64>()
+    }
+
+    // set the dry-run estimation result
+    pub fn on_estimated(&mut self) {
+        self.success = true;
+        self.is_estimation = true;
+        self.cycles = self.emulated_cycles();
+    }
+
+    // set the block is rejected before proving
+    pub fn on_rejected(&mut self, failure_reason: String) {
+        self.success = false;
+        self.failure_stage = Some(FailureStage::Validation);
+        self.failure_reason = Some(failure_reason);
+    }
+
+    // set the block data is failed to fetch or load, the block is never proved
+    pub fn on_fetching_failure(&mut self, failure_reason: String) {
+        self.success = false;
+        self.failure_stage = Some(FailureStage::Fetching);
+        self.failure_reason = Some(failure_reason);
+    }
+
+    // set the time of reporting the block
+    pub fn on_reported(&mut self) {
+        self.reported_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+    }
+
+    // return the total emulated cycles of subblocks and aggregator
+    pub fn emulated_cycles(&self) -> u64 {
+        self.emulated_subblock_cycles.iter().sum::<u64>() + self.emulated_agg_cycles
+    }
+
+    // set proving success, the proof is either carried by the bytes or referenced by the storage
+    // uri if it's persisted; the proof size and hash are measured from the bytes if not reported
+    pub fn on_proving_success(
+        &mut self,
+        cycles: u64,
+        proving_milliseconds: u64,
+        proof: Option<Vec<u8>>,
+        proof_uri: Option<String>,
+        proof_bytes: u64,
+        proof_hash: Option<String>,
+    ) {
         self.success = true;
         self.cycles = cycles;
         self.proving_milliseconds = proving_milliseconds;
-        self.proof = Some(proof);
+        self.proof_bytes = match &proof {
+            Some(proof) if proof_bytes == 0 => proof.len() as u64,
+            _ => proof_bytes,
+        };
+        self.proof_hash = proof_hash.or_else(|| proof.as_deref().map(proof_store::proof_hash));
+        self.proof = proof;
+        self.proof_uri = proof_uri;
+    }
+
+    // return the proving speed in kHz (cycles per proving millisecond), it's none if the block
+    // is not proved or the time is not reported
+    pub fn proving_khz(&self) -> Option<f64> {
+        (self.success && !self.is_estimation && self.proving_milliseconds > 0)
+            .then(|| self.cycles as f64 / self.proving_milliseconds as f64)
     }
 
-    // set proving failure
-    pub fn on_proving_failure(&mut self) {
+    // set the proving result of each subblock and the aggregation time reported by the provers,
+    // the proving time is derived from the breakdown if the provers don't report it
+    pub fn on_subblocks_proved(
+        &mut self,
+        mut proved_subblocks: Vec<ProvedSubblock>,
+        aggregation_milliseconds: u64,
+    ) {
+        proved_subblocks.sort_by_key(|subblock| subblock.subblock_index);
+        self.proved_subblocks = proved_subblocks;
+        self.aggregation_milliseconds = aggregation_milliseconds;
+
+        if self.proving_milliseconds == 0 {
+            let max_subblock_milliseconds = self
+                .proved_subblock_milliseconds()
+                .into_iter()
+                .max()
+                .unwrap_or_default();
+            self.proving_milliseconds = max_subblock_milliseconds + aggregation_milliseconds;
+        }
+    }
+
+    // return the proving cycles of each subblock reported by the provers
+    pub fn proved_subblock_cycles(&self) -> Vec<u64> {
+        self.proved_subblocks
+            .iter()
+            .map(|subblock| subblock.cycles)
+            .collect()
+    }
+
+    // return the proving milliseconds of each subblock reported by the provers
+    pub fn proved_subblock_milliseconds(&self) -> Vec<u64> {
+        self.proved_subblocks
+            .iter()
+            .map(|subblock| subblock.proving_milliseconds)
+            .collect()
+    }
+
+    // set proving failure with the error code and message reported by the provers
+    pub fn on_proving_failure(
+        &mut self,
+        proving_error_code: Option<ProvingErrorCode>,
+        failure_reason: Option<String>,
+    ) {
         self.success = false;
+        self.failure_stage = Some(FailureStage::Proving);
+        self.proving_error_code = proving_error_code;
+        self.failure_reason = failure_reason;
+    }
+
+    // convert to a JSON object, the derived values are added and the proof bytes are left out
+    // since the size is recorded by `proof_bytes`
+    pub fn to_json_value(&self) -> Result<serde_json::Value> {
+        let mut value = serde_json::to_value(self)?;
+        if let Some(object) = value.as_object_mut() {
+            object.remove("proof");
+            object.insert("emulated_cycles".to_string(), self.emulated_cycles().into());
+            object.insert("input_bytes".to_string(), self.input_bytes().into());
+            object.insert("proving_khz".to_string(), self.proving_khz().into());
+        }
+
+        Ok(value)
+    }
+
+    // append the report as a JSON object line of `to_json_value`
+    pub fn append_to_ndjson<P: AsRef<Path>>(&self, ndjson_file_path: P) -> Result<()> {
+        let value = self.to_json_value()?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(ndjson_file_path)?;
+        writeln!(file, "{value}")?;
+
+        Ok(())
     }
 
     pub fn append_to_csv<P: AsRef<Path>>(&self, csv_file_path: P) -> Result<()> {
@@ -70,22 +563,299 @@ impl BlockProvingReport {
             .open(file_path)?;
 
         if !file_exists {
-            writeln!(
-                file,
-                "block_number,success,cycles,proving_seconds,data_fetch_seconds",
-            )?;
+            writeln!(file, "{CSV_HEADER}")?;
         }
+        writeln!(file, "{}", self.to_csv_row())?;
+
+        Ok(())
+    }
 
-        writeln!(
-            file,
-            "{},{},{},{},{}",
+    // convert to a csv row of the `CSV_HEADER` columns
+    pub fn to_csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
             self.block_number,
+            self.job_id,
             self.success,
             self.cycles,
+            self.emulated_cycles(),
             self.proving_milliseconds as f64 / 1000.0,
             self.data_fetch_milliseconds as f64 / 1000.0,
-        )?;
+            self.rpc_fetch_milliseconds as f64 / 1000.0,
+            self.host_execution_milliseconds as f64 / 1000.0,
+            self.serialization_milliseconds as f64 / 1000.0,
+            self.emulation_milliseconds as f64 / 1000.0,
+            self.input_bytes(),
+            self.gas_used,
+            self.blob_count,
+            self.blob_gas_used,
+            join_csv_list(&self.subblock_gas_used),
+            join_csv_list(&self.subblock_input_bytes),
+            join_csv_list(&self.emulated_subblock_cycles),
+            join_csv_list(&self.proved_subblock_cycles()),
+            self.proved_subblock_milliseconds()
+                .iter()
+                .map(|milliseconds| (*milliseconds as f64 / 1000.0).to_string())
+                .collect::<Vec<_>>()
+                .join(";"),
+            self.aggregation_milliseconds as f64 / 1000.0,
+            self.failure_stage
+                .map(|stage| stage.to_string())
+                .unwrap_or_default(),
+            self.proof_uri.as_deref().unwrap_or_default(),
+            self.proving_error_code
+                .map(|code| code.to_string())
+                .unwrap_or_default(),
+            self.chain_id,
+            self.trace_id,
+            self.proof_bytes,
+            self.gas_limit,
+            self.tx_count,
+            self.proving_khz()
+                .map(|khz| format!("{khz:.3}"))
+                .unwrap_or_default(),
+            self.proof_hash.as_deref().unwrap_or_default(),
+            self.reported_at,
+        )
+    }
+}
+
+// join a list of values by `;` for a single csv column
+fn join_csv_list(values: &[u64]) -> String {
+    values
+        .iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+// store of the block reports for querying the history, the latest report of a block replaces the
+// previous ones, e.g. of a reproduced block; the proof bytes are not stored
+#[async_trait]
+pub trait ReportStore: Debug + Send + Sync {
+    // insert a block report
+    async fn insert(&self, report: BlockProvingReport) -> Result<()>;
+
+    // return the latest report of a block, it's none if the block is not reported
+    async fn get(&self, block_key: BlockKey) -> Result<Option<BlockProvingReport>>;
+
+    // return the reports of the blocks in the range (inclusive) of a chain by the block number
+    async fn range(
+        &self,
+        chain_id: u64,
+        from_block_number: u64,
+        to_block_number: u64,
+    ) -> Result<Vec<BlockProvingReport>>;
+
+    // return the reports of a chain reported in the time range (inclusive) by the unix seconds,
+    // ordered by the block number
+    async fn reported_between(
+        &self,
+        chain_id: u64,
+        from_timestamp: u64,
+        to_timestamp: u64,
+    ) -> Result<Vec<BlockProvingReport>>;
+}
+
+// open a report store by the URL, it could be `memory://` for the in-process store or a SQLite
+// database file path prefixed with `sqlite://`, e.g. `sqlite://proving_reports.db`
+pub fn open_report_store(url: &str) -> Result<Arc<dyn ReportStore>> {
+    let store: Arc<dyn ReportStore> = match url.split_once("://") {
+        Some(("memory", _)) => Arc::new(MemoryReportStore::default()),
+        Some(("sqlite", path)) => Arc::new(SqliteReportStore::open(Path::new(path))?),
+        Some((scheme, _)) => bail!("unsupported report store scheme `{scheme}`"),
+        None => bail!("invalid report store URL `{url}`"),
+    };
+
+    Ok(store)
+}
+
+// in-process report store, the reports are lost on restart
+#[derive(Debug, Default)]
+pub struct MemoryReportStore {
+    // reports by the chain id and block number
+    reports: RwLock<BTreeMap<BlockKey, BlockProvingReport>>,
+}
+
+#[async_trait]
+impl ReportStore for MemoryReportStore {
+    async fn insert(&self, mut report: BlockProvingReport) -> Result<()> {
+        report.proof = None;
+        self.reports
+            .write()
+            .await
+            .insert(report.block_key(), report);
 
         Ok(())
     }
+
+    async fn get(&self, block_key: BlockKey) -> Result<Option<BlockProvingReport>> {
+        Ok(self.reports.read().await.get(&block_key).cloned())
+    }
+
+    async fn range(
+        &self,
+        chain_id: u64,
+        from_block_number: u64,
+        to_block_number: u64,
+    ) -> Result<Vec<BlockProvingReport>> {
+        let from = BlockKey::new(chain_id, from_block_number);
+        let to = BlockKey::new(chain_id, to_block_number);
+        if from > to {
+            return Ok(vec![]);
+        }
+
+        Ok(self
+            .reports
+            .read()
+            .await
+            .range(from..=to)
+            .map(|(_, report)| report.clone())
+            .collect())
+    }
+
+    async fn reported_between(
+        &self,
+        chain_id: u64,
+        from_timestamp: u64,
+        to_timestamp: u64,
+    ) -> Result<Vec<BlockProvingReport>> {
+        Ok(self
+            .reports
+            .read()
+            .await
+            .values()
+            .filter(|report| {
+                report.chain_id == chain_id
+                    && (from_timestamp..=to_timestamp).contains(&report.reported_at)
+            })
+            .cloned()
+            .collect())
+    }
+}
+
+// report store by a SQLite database file, the reports are kept as json by the chain id and block
+// number, and indexed by the reporting time
+#[derive(Debug)]
+pub struct SqliteReportStore {
+    // connection shared by the blocking queries
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteReportStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS block_reports (
+                chain_id INTEGER NOT NULL,
+                block_number INTEGER NOT NULL,
+                report TEXT NOT NULL,
+                PRIMARY KEY (chain_id, block_number)
+            )",
+            [],
+        )?;
+        connection.execute(
+            "CREATE INDEX IF NOT EXISTS block_reports_reported_at
+            ON block_reports (chain_id, json_extract(report, '$.reported_at'))",
+            [],
+        )?;
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    // run a query on the connection in a blocking thread
+    async fn query<T: Send + 'static>(
+        &self,
+        query: impl FnOnce(&Connection) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let connection = self.connection.clone();
+        spawn_blocking(move || {
+            let connection = connection
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            query(&connection)
+        })
+        .await?
+    }
+}
+
+#[async_trait]
+impl ReportStore for SqliteReportStore {
+    async fn insert(&self, mut report: BlockProvingReport) -> Result<()> {
+        report.proof = None;
+        let json = serde_json::to_string(&report)?;
+        self.query(move |connection| {
+            connection.execute(
+                "INSERT OR REPLACE INTO block_reports (chain_id, block_number, report)
+                VALUES (?1, ?2, ?3)",
+                params![report.chain_id, report.block_number, json],
+            )?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn get(&self, block_key: BlockKey) -> Result<Option<BlockProvingReport>> {
+        self.query(move |connection| {
+            let json: Option<String> = connection
+                .query_row(
+                    "SELECT report FROM block_reports WHERE chain_id = ?1 AND block_number = ?2",
+                    params![block_key.chain_id, block_key.block_number],
+                    |row| row.get(0),
+                )
+                .optional()?;
+
+            Ok(json.map(|json| serde_json::from_str(&json)).transpose()?)
+        })
+        .await
+    }
+
+    async fn range(
+        &self,
+        chain_id: u64,
+        from_block_number: u64,
+        to_block_number: u64,
+    ) -> Result<Vec<BlockProvingReport>> {
+        self.query(move |connection| {
+            let mut statement = connection.prepare(
+                "SELECT report FROM block_reports
+                WHERE chain_id = ?1 AND block_number BETWEEN ?2 AND ?3
+                ORDER BY block_number",
+            )?;
+            let rows = statement.query_map(
+                params![chain_id, from_block_number, to_block_number],
+                |row| row.get::<_, String>(0),
+            )?;
+
+            rows.map(|json| -> Result<BlockProvingReport> { Ok(serde_json::from_str(&json?)?) })
+                .collect()
+        })
+        .await
+    }
+
+    async fn reported_between(
+        &self,
+        chain_id: u64,
+        from_timestamp: u64,
+        to_timestamp: u64,
+    ) -> Result<Vec<BlockProvingReport>> {
+        self.query(move |connection| {
+            let mut statement = connection.prepare(
+                "SELECT report FROM block_reports
+                WHERE chain_id = ?1 AND json_extract(report, '$.reported_at') BETWEEN ?2 AND ?3
+                ORDER BY block_number",
+            )?;
+            let rows = statement
+                .query_map(params![chain_id, from_timestamp, to_timestamp], |row| {
+                    row.get::<_, String>(0)
+                })?;
+
+            rows.map(|json| -> Result<BlockProvingReport> { Ok(serde_json::from_str(&json?)?) })
+                .collect()
+        })
+        .await
+    }
 }
@@ -0,0 +1,83 @@
+use crate::utils::PipelineId;
+use derive_more::Constructor;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::LazyLock,
+};
+use tokio::sync::Notify;
+
+// notification of the requested reloads of the config file, it's notified by the admin requests
+// of the fetch-service and waited by the config reloader of the binary
+static CONFIG_RELOAD: LazyLock<Notify> = LazyLock::new(Notify::new);
+
+// state of the all pipelines switched by the admin requests
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineState {
+    // fetching and proving blocks
+    #[default]
+    Running,
+
+    // stopped fetching new blocks and dispatching them to the proving cluster
+    Paused,
+
+    // stopped fetching new blocks but proving the blocks already fetched
+    Draining,
+}
+
+// proving grpc urls of a pipeline
+#[derive(Clone, Constructor, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvingUrls {
+    // aggregator proving grpc url
+    pub agg_url: String,
+
+    // subblock proving grpc urls
+    pub subblock_urls: Vec<String>,
+}
+
+// runtime configuration and status of the process, it's updated by the admin requests of the
+// fetch-service or the reloaded config file and broadcast to the subsystems by a watch channel, so the subsystems observe the
+// latest value without the point-to-point messages
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuntimeState {
+    // state of the all pipelines
+    pub pipeline_state: PipelineState,
+
+    // maximum number of blocks dispatched for proving but not reported in a pipeline before the
+    // scheduler holds the new fetch messages; disabled if zero
+    pub max_in_flight_blocks: usize,
+
+    // log filter directives applied at runtime, e.g. `info,scheduler=debug`; the filter of the
+    // `RUST_LOG` environment variable is kept if none
+    pub log_filter: Option<String>,
+
+    // maximum waiting seconds of the proving-clients for proving complete or any progress of the
+    // proving block; the configured timeout of the proving-clients is kept if none
+    pub proving_timeout_seconds: Option<u64>,
+
+    // proving grpc urls by pipeline id, a proving-client reconnects the proving cluster once its
+    // urls changed; the configured urls are kept if a pipeline is not listed
+    pub proving_urls: BTreeMap<PipelineId, ProvingUrls>,
+
+    // names of the readiness checks not passed yet, e.g. the proving-clients not connected to the
+    // proving cluster; the process is ready if empty
+    pub pending_checks: BTreeSet<String>,
+}
+
+impl RuntimeState {
+    // identify if the all readiness checks of the process passed
+    pub fn is_ready(&self) -> bool {
+        self.pending_checks.is_empty()
+    }
+}
+
+// request reloading the config file, a request while the reloader is busy is kept for it
+pub fn request_config_reload() {
+    CONFIG_RELOAD.notify_one();
+}
+
+// wait for a request of reloading the config file
+pub async fn config_reload_requested() {
+    CONFIG_RELOAD.notified().await;
+}
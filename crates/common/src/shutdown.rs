@@ -0,0 +1,73 @@
+use std::sync::LazyLock;
+use tokio::sync::watch::Sender;
+
+// phase of the ordered shutdown of the process, it's advanced by the binary on `Ctrl+C` or
+// `SIGTERM` and waited by the components stopping in the phase
+static SHUTDOWN_PHASE: LazyLock<Sender<ShutdownPhase>> =
+    LazyLock::new(|| Sender::new(ShutdownPhase::Running));
+
+// number of the components not flushed yet, e.g. the reporter persisting the queued reports
+static PENDING_FLUSHES: LazyLock<Sender<usize>> = LazyLock::new(|| Sender::new(0));
+
+// phases of the ordered shutdown, a phase is never moved back
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ShutdownPhase {
+    // serving normally
+    #[default]
+    Running,
+
+    // stopped accepting the HTTP requests and dispatching new blocks, the blocks in flight are
+    // still proved and their results received
+    Draining,
+
+    // the blocks in flight are proved or the drain deadline passed, the proving services stop and
+    // the queued reports are flushed before exiting
+    Flushing,
+}
+
+// guard of a component flushing on shutdown, the component is flushed once it's dropped
+#[derive(Debug)]
+pub struct FlushGuard(());
+
+impl Drop for FlushGuard {
+    fn drop(&mut self) {
+        PENDING_FLUSHES.send_modify(|pending| *pending -= 1);
+    }
+}
+
+// advance the shutdown to a phase, it's ignored if the phase is already reached
+pub fn advance_shutdown(phase: ShutdownPhase) {
+    SHUTDOWN_PHASE.send_if_modified(|current| {
+        let is_advanced = phase > *current;
+        if is_advanced {
+            *current = phase;
+        }
+        is_advanced
+    });
+}
+
+// return the current shutdown phase
+pub fn shutdown_phase() -> ShutdownPhase {
+    *SHUTDOWN_PHASE.borrow()
+}
+
+// wait until the shutdown reaches a phase
+pub async fn shutdown_reached(phase: ShutdownPhase) {
+    let mut receiver = SHUTDOWN_PHASE.subscribe();
+    // the sender is static and never dropped
+    let _ = receiver.wait_for(|current| *current >= phase).await;
+}
+
+// register a component flushing on shutdown, it's waited by `wait_flushed` until the returned
+// guard is dropped
+pub fn flush_guard() -> FlushGuard {
+    PENDING_FLUSHES.send_modify(|pending| *pending += 1);
+    FlushGuard(())
+}
+
+// wait until the all registered components are flushed
+pub async fn wait_flushed() {
+    let mut receiver = PENDING_FLUSHES.subscribe();
+    // the sender is static and never dropped
+    let _ = receiver.wait_for(|pending| *pending == 0).await;
+}
@@ -0,0 +1,287 @@
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+use derive_more::Constructor;
+use futures::TryStreamExt;
+use memmap2::Mmap;
+use object_store::{
+    ObjectStore, PutPayload, aws::AmazonS3Builder, gcp::GoogleCloudStorageBuilder,
+    path::Path as ObjectPath,
+};
+use reqwest::Url;
+use std::{
+    fmt::Debug,
+    fs::{self, File},
+    io::ErrorKind,
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::task::spawn_blocking;
+
+// storage for dumping and loading the proving inputs, the keys are `/` separated relative paths,
+// e.g. `block23264565/gas10000000/public_values.bin`
+#[async_trait]
+pub trait InputStorage: Debug + Send + Sync {
+    // write the data to the specified key
+    async fn write(&self, key: &str, data: Vec<u8>) -> Result<()>;
+
+    // read the data of the specified key, return `None` if it doesn't exist
+    async fn read(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    // read the data of the specified key without copying it into memory if supported, e.g. a
+    // memory-mapped local file; return `None` if it doesn't exist
+    async fn read_data(&self, key: &str) -> Result<Option<InputData>> {
+        Ok(self.read(key).await?.map(InputData::Owned))
+    }
+
+    // list the names of the top-level entries
+    async fn list_entries(&self) -> Result<Vec<String>>;
+
+    // return the total bytes of the data under the specified top-level entry
+    async fn entry_bytes(&self, entry: &str) -> Result<u64>;
+
+    // remove the specified top-level entry and all the data under it
+    async fn remove_entry(&self, entry: &str) -> Result<()>;
+}
+
+// open an input storage by the location, it could be a local directory or an object store URL
+// prefixed with `s3://` or `gs://`, the object store credentials are read from the environment
+// variables, e.g. `AWS_ACCESS_KEY_ID` or `GOOGLE_SERVICE_ACCOUNT`
+pub fn open_input_storage(location: &Path) -> Result<Arc<dyn InputStorage>> {
+    let location_str = location.to_string_lossy();
+    let storage: Arc<dyn InputStorage> = match location_str.split_once("://") {
+        Some(("s3", _)) => {
+            let url = Url::parse(&location_str)?;
+            let store = AmazonS3Builder::from_env().with_url(url.as_str()).build()?;
+            Arc::new(ObjectInputStorage::new(Arc::new(store), url.path().into()))
+        }
+        Some(("gs", _)) => {
+            let url = Url::parse(&location_str)?;
+            let store = GoogleCloudStorageBuilder::from_env()
+                .with_url(url.as_str())
+                .build()?;
+            Arc::new(ObjectInputStorage::new(Arc::new(store), url.path().into()))
+        }
+        Some((scheme, _)) => bail!("unsupported input storage scheme `{scheme}`"),
+        None => Arc::new(FsInputStorage::new(location.to_path_buf(), false)),
+    };
+
+    Ok(storage)
+}
+
+// open an input storage by the location like `open_input_storage`, the files of a local directory
+// are memory-mapped when read by `read_data`, so a large input is decompressed from the page cache
+// instead of a copy in memory; the mapped files must not be rewritten while loading
+pub fn open_mapped_input_storage(location: &Path) -> Result<Arc<dyn InputStorage>> {
+    if location.to_string_lossy().contains("://") {
+        return open_input_storage(location);
+    }
+
+    Ok(Arc::new(FsInputStorage::new(location.to_path_buf(), true)))
+}
+
+// data read from an input storage, either owned or memory-mapped
+#[derive(Debug)]
+pub enum InputData {
+    Owned(Vec<u8>),
+    Mapped(Mmap),
+}
+
+impl InputData {
+    // convert to the owned bytes, the mapped data is copied
+    pub fn into_vec(self) -> Vec<u8> {
+        match self {
+            Self::Owned(data) => data,
+            Self::Mapped(mmap) => mmap.to_vec(),
+        }
+    }
+}
+
+impl Deref for InputData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Self::Owned(data) => data,
+            Self::Mapped(mmap) => mmap,
+        }
+    }
+}
+
+// input storage on the local filesystem, the blocking file operations run on the blocking threads
+#[derive(Constructor, Debug)]
+pub struct FsInputStorage {
+    // base directory of the input files
+    base_dir: PathBuf,
+
+    // identify if the files are memory-mapped when read by `read_data`
+    mmap: bool,
+}
+
+#[async_trait]
+impl InputStorage for FsInputStorage {
+    async fn write(&self, key: &str, data: Vec<u8>) -> Result<()> {
+        let file_path = self.base_dir.join(key);
+        spawn_blocking(move || -> Result<()> {
+            if let Some(dir) = file_path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(file_path, data)?;
+
+            Ok(())
+        })
+        .await?
+    }
+
+    async fn read(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let file_path = self.base_dir.join(key);
+        spawn_blocking(move || -> Result<Option<Vec<u8>>> {
+            match fs::read(file_path) {
+                Ok(data) => Ok(Some(data)),
+                Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        })
+        .await?
+    }
+
+    async fn read_data(&self, key: &str) -> Result<Option<InputData>> {
+        if !self.mmap {
+            return Ok(self.read(key).await?.map(InputData::Owned));
+        }
+
+        let file_path = self.base_dir.join(key);
+        spawn_blocking(move || -> Result<Option<InputData>> {
+            let file = match File::open(file_path) {
+                Ok(file) => file,
+                Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+                Err(e) => return Err(e.into()),
+            };
+            // SAFETY: the input files are not modified after dumped, the caller of
+            // `open_mapped_input_storage` ensures the files are not rewritten while mapped
+            let mmap = unsafe { Mmap::map(&file)? };
+
+            Ok(Some(InputData::Mapped(mmap)))
+        })
+        .await?
+    }
+
+    async fn list_entries(&self) -> Result<Vec<String>> {
+        let base_dir = self.base_dir.clone();
+        spawn_blocking(move || -> Result<Vec<String>> {
+            let mut entries = vec![];
+            for entry in fs::read_dir(base_dir)? {
+                if let Some(name) = entry?.file_name().to_str() {
+                    entries.push(name.to_string());
+                }
+            }
+
+            Ok(entries)
+        })
+        .await?
+    }
+
+    async fn entry_bytes(&self, entry: &str) -> Result<u64> {
+        let entry_dir = self.base_dir.join(entry);
+        spawn_blocking(move || dir_bytes(&entry_dir)).await?
+    }
+
+    async fn remove_entry(&self, entry: &str) -> Result<()> {
+        let entry_dir = self.base_dir.join(entry);
+        spawn_blocking(move || -> Result<()> {
+            match fs::remove_dir_all(entry_dir) {
+                Ok(()) => Ok(()),
+                Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+                Err(e) => Err(e.into()),
+            }
+        })
+        .await?
+    }
+}
+
+// return the total bytes of the files in a directory recursively
+fn dir_bytes(dir: &Path) -> Result<u64> {
+    let mut bytes = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        bytes += if metadata.is_dir() {
+            dir_bytes(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+
+    Ok(bytes)
+}
+
+// input storage on an object store, e.g. S3 or GCS bucket
+#[derive(Constructor, Debug)]
+pub struct ObjectInputStorage {
+    // object store client
+    store: Arc<dyn ObjectStore>,
+
+    // key prefix of the input objects in the bucket
+    prefix: ObjectPath,
+}
+
+impl ObjectInputStorage {
+    // convert a relative key to the object path
+    fn object_path(&self, key: &str) -> ObjectPath {
+        key.split('/')
+            .fold(self.prefix.clone(), |path, part| path.child(part))
+    }
+}
+
+#[async_trait]
+impl InputStorage for ObjectInputStorage {
+    async fn write(&self, key: &str, data: Vec<u8>) -> Result<()> {
+        self.store
+            .put(&self.object_path(key), PutPayload::from(data))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn read(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match self.store.get(&self.object_path(key)).await {
+            Ok(result) => Ok(Some(result.bytes().await?.to_vec())),
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn list_entries(&self) -> Result<Vec<String>> {
+        let result = self.store.list_with_delimiter(Some(&self.prefix)).await?;
+        let entries = result
+            .common_prefixes
+            .iter()
+            .filter_map(|path| path.filename().map(str::to_string))
+            .collect();
+
+        Ok(entries)
+    }
+
+    async fn entry_bytes(&self, entry: &str) -> Result<u64> {
+        let objects: Vec<_> = self
+            .store
+            .list(Some(&self.object_path(entry)))
+            .try_collect()
+            .await?;
+
+        Ok(objects.iter().map(|object| object.size).sum())
+    }
+
+    async fn remove_entry(&self, entry: &str) -> Result<()> {
+        let objects: Vec<_> = self
+            .store
+            .list(Some(&self.object_path(entry)))
+            .try_collect()
+            .await?;
+        for object in objects {
+            self.store.delete(&object.location).await?;
+        }
+
+        Ok(())
+    }
+}
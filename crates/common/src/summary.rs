@@ -0,0 +1,163 @@
+use crate::report::BlockProvingReport;
+use anyhow::Result;
+use serde::Serialize;
+use std::{fmt, fs::OpenOptions, io::Write, path::Path};
+
+// summary of a proving campaign folded from a set of block reports, the proving times and cycles
+// are measured over the proved blocks, and the dry-run estimations are not counted
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ProvingCampaignSummary {
+    // number of the reported blocks
+    pub block_count: u64,
+
+    // number of the proved blocks
+    pub success_count: u64,
+
+    // ratio of the proved blocks to the reported ones, it's zero if no block is reported
+    pub success_rate: f64,
+
+    // lowest reported block number, it's zero if no block is reported
+    pub first_block_number: u64,
+
+    // highest reported block number, it's zero if no block is reported
+    pub last_block_number: u64,
+
+    // median proving milliseconds
+    pub proving_p50_milliseconds: u64,
+
+    // 90th percentile of the proving milliseconds
+    pub proving_p90_milliseconds: u64,
+
+    // 99th percentile of the proving milliseconds
+    pub proving_p99_milliseconds: u64,
+
+    // average proving milliseconds
+    pub avg_proving_milliseconds: u64,
+
+    // average proving cycles
+    pub avg_cycles: u64,
+
+    // total proving cycles
+    pub total_cycles: u64,
+
+    // total gas used of the proved blocks
+    pub total_gas_used: u64,
+}
+
+impl ProvingCampaignSummary {
+    // fold the block reports into a summary
+    pub fn from_reports<'a>(reports: impl IntoIterator<Item = &'a BlockProvingReport>) -> Self {
+        let mut summary = Self::default();
+        let mut proving_milliseconds = vec![];
+        for report in reports {
+            if report.is_estimation {
+                continue;
+            }
+
+            let block_number = report.block_number;
+            if summary.block_count == 0 {
+                summary.first_block_number = block_number;
+                summary.last_block_number = block_number;
+            } else {
+                summary.first_block_number = summary.first_block_number.min(block_number);
+                summary.last_block_number = summary.last_block_number.max(block_number);
+            }
+            summary.block_count += 1;
+
+            if report.success {
+                summary.success_count += 1;
+                summary.total_cycles += report.cycles;
+                summary.total_gas_used += report.gas_used;
+                proving_milliseconds.push(report.proving_milliseconds);
+            }
+        }
+
+        if summary.block_count > 0 {
+            summary.success_rate = summary.success_count as f64 / summary.block_count as f64;
+        }
+        if summary.success_count > 0 {
+            summary.avg_cycles = summary.total_cycles / summary.success_count;
+            summary.avg_proving_milliseconds =
+                proving_milliseconds.iter().sum::<u64>() / summary.success_count;
+        }
+
+        proving_milliseconds.sort_unstable();
+        summary.proving_p50_milliseconds = percentile(&proving_milliseconds, 50);
+        summary.proving_p90_milliseconds = percentile(&proving_milliseconds, 90);
+        summary.proving_p99_milliseconds = percentile(&proving_milliseconds, 99);
+
+        summary
+    }
+
+    // return the summary as a json object
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    // append the summary to a csv file, the header is written if the file is new
+    pub fn append_to_csv<P: AsRef<Path>>(&self, csv_file_path: P) -> Result<()> {
+        let file_path = csv_file_path.as_ref();
+        let file_exists = file_path.exists();
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file_path)?;
+
+        if !file_exists {
+            writeln!(
+                file,
+                "block_count,success_count,success_rate,first_block_number,last_block_number,proving_p50_seconds,proving_p90_seconds,proving_p99_seconds,avg_proving_seconds,avg_cycles,total_cycles,total_gas_used",
+            )?;
+        }
+
+        writeln!(
+            file,
+            "{},{},{:.4},{},{},{},{},{},{},{},{},{}",
+            self.block_count,
+            self.success_count,
+            self.success_rate,
+            self.first_block_number,
+            self.last_block_number,
+            self.proving_p50_milliseconds as f64 / 1000.0,
+            self.proving_p90_milliseconds as f64 / 1000.0,
+            self.proving_p99_milliseconds as f64 / 1000.0,
+            self.avg_proving_milliseconds as f64 / 1000.0,
+            self.avg_cycles,
+            self.total_cycles,
+            self.total_gas_used,
+        )?;
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for ProvingCampaignSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Blocks #{}..#{} | blocks: {} | proved: {} | success_rate: {:.1}% | proving p50/p90/p99: {}/{}/{} ms | avg_proving: {} ms | avg_cycles: {} | total_gas_used: {}",
+            self.first_block_number,
+            self.last_block_number,
+            self.block_count,
+            self.success_count,
+            self.success_rate * 100.0,
+            self.proving_p50_milliseconds,
+            self.proving_p90_milliseconds,
+            self.proving_p99_milliseconds,
+            self.avg_proving_milliseconds,
+            self.avg_cycles,
+            self.total_gas_used,
+        )
+    }
+}
+
+// return the nearest-rank percentile of the sorted values, it's zero if there's no value
+pub fn percentile(sorted_values: &[u64], percent: usize) -> u64 {
+    if sorted_values.is_empty() {
+        return 0;
+    }
+
+    let rank = (sorted_values.len() * percent).div_ceil(100).max(1);
+    sorted_values[rank - 1]
+}
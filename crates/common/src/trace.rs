@@ -0,0 +1,87 @@
+use crate::ids::JobId;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+use std::{
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hasher},
+    str::FromStr,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::{Span, info_span};
+
+// sequence mixed into the generated trace ids, so the ids generated at the same time differ
+static TRACE_ID_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+// trace id of a block's journey through the pipeline, it's generated when the block is fetched and
+// carried by the block messages and the grpc metadata, so the logs of the fetcher, scheduler,
+// proving-client, proof-service and reporter could be correlated; zero is unknown, e.g. it's not
+// echoed back by the provers; it's serialized as the 32 hex digits, since the json values could
+// not hold the 128-bit numbers
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TraceId(u128);
+
+impl TraceId {
+    // unknown trace id
+    pub const UNKNOWN: Self = Self(0);
+
+    // generate a random trace id
+    pub fn generate() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or_default();
+        let sequence = TRACE_ID_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+
+        // the randomly seeded hasher spreads the time and sequence to the all bits
+        let random_u64 = |salt: u64| {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u64(nanos);
+            hasher.write_u64(sequence);
+            hasher.write_u64(salt);
+            hasher.finish()
+        };
+
+        Self(((random_u64(0) as u128) << 64) | random_u64(1) as u128)
+    }
+
+    // return true if the trace id is unknown
+    pub fn is_unknown(&self) -> bool {
+        *self == Self::UNKNOWN
+    }
+}
+
+impl fmt::Display for TraceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:032x}", self.0)
+    }
+}
+
+impl FromStr for TraceId {
+    type Err = std::num::ParseIntError;
+
+    // parse the 32 hex digits of a trace id
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        u128::from_str_radix(s, 16).map(Self)
+    }
+}
+
+impl Serialize for TraceId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TraceId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+// return the span of a block in a pipeline component, the spans of the same block share the trace
+// id field
+pub fn block_span(trace_id: TraceId, job_id: JobId, block_number: u64) -> Span {
+    info_span!("block", %trace_id, %job_id, block_number)
+}
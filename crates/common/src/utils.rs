@@ -1,8 +1,21 @@
 use reqwest::Url;
 use std::net::SocketAddr;
+use tokio::task::JoinHandle;
 
-// maximum number of subblocks for proving
-pub const MAX_NUM_SUBBLOCKS: usize = 7;
+// default maximum number of subblocks for proving, it's configurable by the number of subblock
+// provers available in the cluster
+pub const DEFAULT_MAX_NUM_SUBBLOCKS: usize = 7;
+
+// id of a pipeline of a fetcher and a proving-client, multiple pipelines run under one scheduler,
+// e.g. one per chain or proving cluster
+pub type PipelineId = u32;
+
+// id of the default pipeline configured by the cli arguments
+pub const DEFAULT_PIPELINE_ID: PipelineId = 0;
+
+// starter of a restartable task of a pipeline component, it's called again by the supervisor for
+// restarting the task after a panic
+pub type TaskStarter = Box<dyn Fn() -> JoinHandle<()> + Send + Sync>;
 
 // convert a socket address to an url
 // - addr: socket address
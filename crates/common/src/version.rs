@@ -0,0 +1,65 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{fmt, fs, path::Path, sync::OnceLock};
+
+// build info of the process, it's set by the binary on startup and served by the services
+static BUILD_INFO: OnceLock<BuildInfo> = OnceLock::new();
+
+// build and deployment info of a process, it's logged on startup and served by the `/version`
+// request for comparing the proving numbers across the deployments
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BuildInfo {
+    // package version of the binary
+    pub version: String,
+
+    // git commit sha of the build, suffixed by `-dirty` if built with uncommitted changes
+    pub git_commit: String,
+
+    // build timestamp in RFC 3339
+    pub build_timestamp: String,
+
+    // pico-sdk version with the locked git revision
+    pub pico_sdk_version: String,
+
+    // rsp executor version with the locked git revision
+    pub rsp_version: String,
+
+    // SHA-256 digest of the subblock ELF in use; none if the ELF couldn't be read
+    pub subblock_elf_digest: Option<String>,
+
+    // SHA-256 digest of the aggregator ELF in use; none if the ELF couldn't be read
+    pub agg_elf_digest: Option<String>,
+}
+
+impl fmt::Display for BuildInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digest = |digest: &Option<String>| digest.clone().unwrap_or_else(|| "-".to_string());
+        write!(
+            f,
+            "version {} (commit {}, built {}), pico-sdk {}, rsp {}, subblock elf {}, aggregator elf {}",
+            self.version,
+            self.git_commit,
+            self.build_timestamp,
+            self.pico_sdk_version,
+            self.rsp_version,
+            digest(&self.subblock_elf_digest),
+            digest(&self.agg_elf_digest),
+        )
+    }
+}
+
+// set the build info of the process, it's ignored if already set
+pub fn set_build_info(build_info: BuildInfo) {
+    let _ = BUILD_INFO.set(build_info);
+}
+
+// return the build info of the process, it's none if not set by the binary
+pub fn build_info() -> Option<&'static BuildInfo> {
+    BUILD_INFO.get()
+}
+
+// return the hex SHA-256 digest of a file, e.g. an ELF
+pub fn file_digest(file_path: &Path) -> Result<String> {
+    Ok(format!("{:x}", Sha256::digest(fs::read(file_path)?)))
+}
@@ -0,0 +1,116 @@
+use anyhow::{Result, ensure};
+use serde::Deserialize;
+use std::{collections::HashSet, fs, path::Path, time::Duration};
+
+// default seconds of waiting for a container to stop before it's killed on restarting
+const DEFAULT_STOP_TIMEOUT_SECONDS: u64 = 10;
+
+// default maximum seconds of waiting for the restarted containers to be healthy
+const DEFAULT_HEALTH_TIMEOUT_SECONDS: u64 = 300;
+
+// default seconds between the health checks of the restarted containers
+const DEFAULT_HEALTH_POLL_SECONDS: u64 = 2;
+
+// default seconds of waiting after the containers are healthy, e.g. for the grpc services to listen
+const DEFAULT_SETTLE_SECONDS: u64 = 10;
+
+// default timeout seconds of a request to the docker engine api
+const DEFAULT_REQUEST_TIMEOUT_SECONDS: u64 = 120;
+
+// docker host running the prover containers
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DockerHostConfig {
+    // docker engine api address, e.g. `unix:///var/run/docker.sock` or `tcp://172.1.1.1:2375`;
+    // the local defaults of `DOCKER_HOST` or the unix socket are used if not specified
+    pub url: Option<String>,
+
+    // names of the prover containers on the host, e.g. the aggregator and subblock workers
+    pub containers: Vec<String>,
+}
+
+// config of controlling the prover containers of a proving cluster by the docker engine api
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DockerControlConfig {
+    // docker hosts of the proving cluster
+    pub hosts: Vec<DockerHostConfig>,
+
+    // seconds of waiting for a container to stop before it's killed on restarting
+    pub stop_timeout_seconds: Option<u64>,
+
+    // maximum seconds of waiting for the restarted containers to be healthy, the containers
+    // without a health check are healthy once running
+    pub health_timeout_seconds: Option<u64>,
+
+    // seconds between the health checks of the restarted containers
+    pub health_poll_seconds: Option<u64>,
+
+    // seconds of waiting after the containers are healthy before the proving cluster is used
+    pub settle_seconds: Option<u64>,
+
+    // timeout seconds of a request to the docker engine api
+    pub request_timeout_seconds: Option<u64>,
+}
+
+impl DockerControlConfig {
+    // return the waiting time for a container to stop before it's killed
+    pub fn stop_timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.stop_timeout_seconds
+                .unwrap_or(DEFAULT_STOP_TIMEOUT_SECONDS),
+        )
+    }
+
+    // return the maximum waiting time for the restarted containers to be healthy
+    pub fn health_timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.health_timeout_seconds
+                .unwrap_or(DEFAULT_HEALTH_TIMEOUT_SECONDS),
+        )
+    }
+
+    // return the interval between the health checks
+    pub fn health_poll_interval(&self) -> Duration {
+        Duration::from_secs(
+            self.health_poll_seconds
+                .unwrap_or(DEFAULT_HEALTH_POLL_SECONDS)
+                .max(1),
+        )
+    }
+
+    // return the waiting time after the containers are healthy
+    pub fn settle_time(&self) -> Duration {
+        Duration::from_secs(self.settle_seconds.unwrap_or(DEFAULT_SETTLE_SECONDS))
+    }
+
+    // return the timeout seconds of a docker engine api request
+    pub fn request_timeout_seconds(&self) -> u64 {
+        self.request_timeout_seconds
+            .unwrap_or(DEFAULT_REQUEST_TIMEOUT_SECONDS)
+    }
+}
+
+// load the docker control config from a json file, a container is only listed once per host
+pub fn load_docker_control_config(file_path: &Path) -> Result<DockerControlConfig> {
+    let config: DockerControlConfig = serde_json::from_slice(&fs::read(file_path)?)?;
+    ensure!(!config.hosts.is_empty(), "no docker host is configured");
+
+    for host in &config.hosts {
+        let url = host.url.as_deref().unwrap_or("local");
+        ensure!(
+            !host.containers.is_empty(),
+            "no container is configured on docker host {url}",
+        );
+
+        let mut names = HashSet::new();
+        for container in &host.containers {
+            ensure!(
+                names.insert(container.as_str()),
+                "duplicate container {container} on docker host {url}",
+            );
+        }
+    }
+
+    Ok(config)
+}
@@ -0,0 +1,226 @@
+use crate::config::DockerControlConfig;
+use anyhow::{Context, Result, bail};
+use bollard::{
+    API_DEFAULT_VERSION, Docker,
+    container::{InspectContainerOptions, ListContainersOptions, RestartContainerOptions},
+    models::{ContainerState, ContainerStateStatusEnum, HealthStatusEnum},
+};
+use futures::future::try_join_all;
+use std::{collections::HashMap, time::Duration};
+use tokio::time::{Instant, sleep};
+use tracing::info;
+
+// status of a configured prover container
+#[derive(Clone, Debug)]
+pub struct ContainerStatus {
+    // docker host of the container
+    pub host: String,
+
+    // container name
+    pub name: String,
+
+    // container state, e.g. `running` or `exited`; none if the container doesn't exist
+    pub state: Option<String>,
+
+    // human readable status, e.g. `Up 2 hours (healthy)`
+    pub status: Option<String>,
+}
+
+// docker host connected by the engine api
+#[derive(Debug)]
+struct DockerHost {
+    // docker engine api address for logging
+    url: String,
+
+    // docker engine api client
+    docker: Docker,
+
+    // names of the prover containers on the host
+    containers: Vec<String>,
+}
+
+// control of the prover containers of a proving cluster by the docker engine api
+#[derive(Debug)]
+pub struct DockerControl {
+    // docker control config
+    config: DockerControlConfig,
+
+    // connected docker hosts
+    hosts: Vec<DockerHost>,
+}
+
+impl DockerControl {
+    // connect to the configured docker hosts, the docker engines are only requested on use
+    pub fn connect(config: DockerControlConfig) -> Result<Self> {
+        let timeout = config.request_timeout_seconds();
+        let hosts = config
+            .hosts
+            .iter()
+            .map(|host| {
+                let docker = match host.url.as_deref() {
+                    None => Docker::connect_with_local_defaults()
+                        .map(|docker| docker.with_timeout(Duration::from_secs(timeout))),
+                    Some(url) if url.starts_with("unix://") => {
+                        Docker::connect_with_socket(url, timeout, API_DEFAULT_VERSION)
+                    }
+                    Some(url) => Docker::connect_with_http(url, timeout, API_DEFAULT_VERSION),
+                };
+                let url = host.url.clone().unwrap_or_else(|| "local".to_string());
+                let docker =
+                    docker.with_context(|| format!("failed to connect to docker host {url}"))?;
+
+                Ok(DockerHost {
+                    url,
+                    docker,
+                    containers: host.containers.clone(),
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { config, hosts })
+    }
+
+    // list the configured prover containers, the missing ones are listed without a state
+    pub async fn list_containers(&self) -> Result<Vec<ContainerStatus>> {
+        let mut statuses = vec![];
+        for host in &self.hosts {
+            let options = ListContainersOptions {
+                all: true,
+                filters: HashMap::from([("name".to_string(), host.containers.clone())]),
+                ..Default::default()
+            };
+            let summaries = host
+                .docker
+                .list_containers(Some(options))
+                .await
+                .with_context(|| {
+                    format!("failed to list containers on docker host {}", host.url)
+                })?;
+
+            // the name filter matches by substring, the containers are matched by the exact names
+            for name in &host.containers {
+                let summary = summaries.iter().find(|summary| {
+                    summary.names.as_ref().is_some_and(|names| {
+                        names
+                            .iter()
+                            .any(|summary_name| summary_name.trim_start_matches('/') == name)
+                    })
+                });
+                statuses.push(ContainerStatus {
+                    host: host.url.clone(),
+                    name: name.clone(),
+                    state: summary.and_then(|summary| summary.state.clone()),
+                    status: summary.and_then(|summary| summary.status.clone()),
+                });
+            }
+        }
+
+        Ok(statuses)
+    }
+
+    // restart all prover containers and wait for them to be healthy, it fails if any container
+    // couldn't be restarted or isn't healthy in time
+    pub async fn restart_containers(&self) -> Result<()> {
+        let stop_timeout = self.config.stop_timeout().as_secs() as isize;
+        let restarts = self.hosts.iter().flat_map(|host| {
+            host.containers.iter().map(move |name| async move {
+                info!(
+                    "docker-control: restarting container {name} on {}",
+                    host.url
+                );
+                host.docker
+                    .restart_container(name, Some(RestartContainerOptions { t: stop_timeout }))
+                    .await
+                    .with_context(|| {
+                        format!(
+                            "failed to restart container {name} on docker host {}",
+                            host.url
+                        )
+                    })
+            })
+        });
+        try_join_all(restarts).await?;
+
+        self.wait_healthy().await?;
+
+        let settle_time = self.config.settle_time();
+        if !settle_time.is_zero() {
+            info!(
+                "docker-control: waiting {} s for the containers to settle",
+                settle_time.as_secs(),
+            );
+            sleep(settle_time).await;
+        }
+
+        Ok(())
+    }
+
+    // wait for all prover containers to be healthy, the containers without a health check are
+    // healthy once running
+    async fn wait_healthy(&self) -> Result<()> {
+        let health_timeout = self.config.health_timeout();
+        let deadline = Instant::now() + health_timeout;
+        loop {
+            let mut pending = vec![];
+            for host in &self.hosts {
+                for name in &host.containers {
+                    let response = host
+                        .docker
+                        .inspect_container(name, None::<InspectContainerOptions>)
+                        .await
+                        .with_context(|| {
+                            format!(
+                                "failed to inspect container {name} on docker host {}",
+                                host.url,
+                            )
+                        })?;
+                    let is_healthy = response
+                        .state
+                        .as_ref()
+                        .map(is_container_healthy)
+                        .transpose()
+                        .with_context(|| {
+                            format!("container {name} on docker host {} failed", host.url)
+                        })?
+                        .unwrap_or_default();
+                    if !is_healthy {
+                        pending.push(format!("{name}@{}", host.url));
+                    }
+                }
+            }
+
+            if pending.is_empty() {
+                info!("docker-control: all containers are healthy");
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                bail!(
+                    "containers {} are not healthy in {} s",
+                    pending.join(", "),
+                    health_timeout.as_secs(),
+                );
+            }
+            sleep(self.config.health_poll_interval()).await;
+        }
+    }
+}
+
+// identify if a container is healthy by its state, it fails if the container stopped or its health
+// check failed; false if it's still starting
+fn is_container_healthy(state: &ContainerState) -> Result<bool> {
+    match state.status {
+        Some(ContainerStateStatusEnum::RUNNING) => {}
+        Some(ContainerStateStatusEnum::EXITED | ContainerStateStatusEnum::DEAD) => bail!(
+            "container exited with code {:?} {}",
+            state.exit_code,
+            state.error.as_deref().unwrap_or_default(),
+        ),
+        _ => return Ok(false),
+    }
+
+    match state.health.as_ref().and_then(|health| health.status) {
+        Some(HealthStatusEnum::STARTING) => Ok(false),
+        Some(HealthStatusEnum::UNHEALTHY) => bail!("container health check failed"),
+        _ => Ok(true),
+    }
+}
@@ -0,0 +1,150 @@
+use crate::{
+    config::ClusterConfig,
+    outbox::{ProofStatus, Submission},
+};
+use anyhow::{Result, bail};
+use derive_more::Constructor;
+use reqwest::{Client, RequestBuilder, Response, StatusCode, Url, header::CONTENT_TYPE};
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fmt, time::Duration};
+
+// response of registering a cluster
+#[derive(Debug, Deserialize)]
+struct RegisteredCluster {
+    // id of the registered cluster
+    id: u64,
+}
+
+// body of a proof status request, the details are only sent with the proved or failed status
+#[derive(Debug, Serialize)]
+struct ProofStatusRequest<'a> {
+    block_number: u64,
+    cluster_id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proving_time: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proving_cycles: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proof: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    verifier_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+}
+
+// request rejected by the ethproofs API, it's not retried since the same request would be rejected
+// again
+#[derive(Clone, Constructor, Debug)]
+pub struct RejectedRequest {
+    // response status, a client error
+    pub status: StatusCode,
+
+    // error message responded
+    pub message: String,
+}
+
+impl fmt::Display for RejectedRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ethproofs rejected the request {}: {}",
+            self.status, self.message
+        )
+    }
+}
+
+impl Error for RejectedRequest {}
+
+// client of the ethproofs API authorized by the API key
+pub struct EthproofsApi {
+    // HTTP client
+    client: Client,
+
+    // base URL of the API, ending with `/` so the paths are joined under it
+    api_url: Url,
+
+    // API key sent as the bearer token
+    api_key: String,
+}
+
+impl EthproofsApi {
+    pub fn new(api_url: &str, api_key: String, timeout: Duration) -> Result<Self> {
+        let api_url = Url::parse(&format!("{}/", api_url.trim_end_matches('/')))?;
+        let client = Client::builder().timeout(timeout).build()?;
+
+        Ok(Self {
+            client,
+            api_url,
+            api_key,
+        })
+    }
+
+    // register a cluster and return its id
+    pub async fn register_cluster(&self, cluster: &ClusterConfig) -> Result<u64> {
+        let response = check_status(self.post("clusters", cluster)?.send().await?).await?;
+        let registered: RegisteredCluster = serde_json::from_slice(&response.bytes().await?)?;
+
+        Ok(registered.id)
+    }
+
+    // submit the proof status of a block proved by the cluster, a rejected request is returned as
+    // `RejectedRequest`
+    pub async fn submit(
+        &self,
+        cluster_id: u64,
+        verifier_id: Option<&str>,
+        submission: &Submission,
+    ) -> Result<()> {
+        let request = ProofStatusRequest {
+            block_number: submission.block_number,
+            cluster_id,
+            proving_time: submission.proving_time,
+            proving_cycles: submission.proving_cycles,
+            proof: submission.proof.as_deref(),
+            verifier_id: verifier_id.filter(|_| submission.status == ProofStatus::Proved),
+            error: submission.error.as_deref(),
+        };
+        let path = format!("proofs/{}", submission.status);
+        check_status(self.post(&path, &request)?.send().await?).await?;
+
+        Ok(())
+    }
+
+    // create a json post request of a path under the API URL
+    fn post(&self, path: &str, body: &impl Serialize) -> Result<RequestBuilder> {
+        Ok(self
+            .client
+            .post(self.api_url.join(path)?)
+            .bearer_auth(&self.api_key)
+            .header(CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec(body)?))
+    }
+}
+
+impl fmt::Debug for EthproofsApi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EthproofsApi")
+            .field("api_url", &self.api_url.as_str())
+            .finish_non_exhaustive()
+    }
+}
+
+// check the response status, a client error other than the timeout or rate limit is returned as
+// `RejectedRequest`, and the others are retryable
+async fn check_status(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let message = response.text().await.unwrap_or_default();
+    if status.is_client_error()
+        && !matches!(
+            status,
+            StatusCode::REQUEST_TIMEOUT | StatusCode::TOO_MANY_REQUESTS
+        )
+    {
+        return Err(RejectedRequest::new(status, message).into());
+    }
+    bail!("ethproofs responded {status}: {message}");
+}
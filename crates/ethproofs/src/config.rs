@@ -0,0 +1,144 @@
+use anyhow::{Result, ensure};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+// default base URL of the ethproofs API
+const DEFAULT_API_URL: &str = "https://ethproofs.org/api/v0/";
+
+// default SQLite database file of the pending submissions
+const DEFAULT_OUTBOX_PATH: &str = "ethproofs_outbox.db";
+
+// default seconds of waiting before retrying a failed submission, it's doubled by each attempt
+const DEFAULT_RETRY_BASE_SECONDS: u64 = 5;
+
+// default maximum seconds of waiting before retrying a failed submission
+const DEFAULT_RETRY_MAX_SECONDS: u64 = 600;
+
+// default timeout seconds of a request to the ethproofs API
+const DEFAULT_REQUEST_TIMEOUT_SECONDS: u64 = 60;
+
+// machines of the same instance type in the cluster
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClusterMachine {
+    // instance type of the machines, e.g. `g6.16xlarge`
+    pub instance_type: String,
+
+    // number of the machines
+    pub instance_count: u32,
+}
+
+// cluster configuration registered on ethproofs, it's sent as the body of the cluster creation
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClusterConfig {
+    // display name of the cluster
+    pub nickname: String,
+
+    // description of the cluster
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    // hardware summary of the cluster, e.g. `8x RTX 4090`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hardware: Option<String>,
+
+    // cycle type of the zkVM, e.g. `Pico`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cycle_type: Option<String>,
+
+    // proof type of the zkVM, e.g. `Pico STARK`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof_type: Option<String>,
+
+    // machines of the cluster by instance type
+    pub configuration: Vec<ClusterMachine>,
+}
+
+// config of submitting the proving progress of the blocks to ethproofs
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EthproofsConfig {
+    // base URL of the ethproofs API; `https://ethproofs.org/api/v0/` if not specified
+    pub api_url: Option<String>,
+
+    // id of a cluster already registered on ethproofs; the cluster is registered by `cluster` if
+    // not specified
+    pub cluster_id: Option<u64>,
+
+    // cluster configuration registered on startup, it's registered again once changed
+    pub cluster: Option<ClusterConfig>,
+
+    // verifier id of the proofs, e.g. the verification key digest of the aggregator program
+    pub verifier_id: Option<String>,
+
+    // SQLite database file of the pending submissions, they're retried after a restart
+    pub outbox_path: Option<PathBuf>,
+
+    // seconds of waiting before retrying a failed submission, it's doubled by each attempt
+    pub retry_base_seconds: Option<u64>,
+
+    // maximum seconds of waiting before retrying a failed submission
+    pub retry_max_seconds: Option<u64>,
+
+    // timeout seconds of a request to the ethproofs API
+    pub request_timeout_seconds: Option<u64>,
+}
+
+impl EthproofsConfig {
+    // return the base URL of the ethproofs API
+    pub fn api_url(&self) -> &str {
+        self.api_url.as_deref().unwrap_or(DEFAULT_API_URL)
+    }
+
+    // return the database file of the pending submissions
+    pub fn outbox_path(&self) -> PathBuf {
+        self.outbox_path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_OUTBOX_PATH))
+    }
+
+    // return the waiting time before retrying a submission failed by the attempts
+    pub fn retry_delay(&self, attempts: u32) -> Duration {
+        let base = self
+            .retry_base_seconds
+            .unwrap_or(DEFAULT_RETRY_BASE_SECONDS);
+        let max = self.retry_max_seconds.unwrap_or(DEFAULT_RETRY_MAX_SECONDS);
+        let seconds = base
+            .saturating_mul(1 << attempts.saturating_sub(1).min(16))
+            .min(max);
+
+        Duration::from_secs(seconds.max(1))
+    }
+
+    // return the timeout of an ethproofs API request
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.request_timeout_seconds
+                .unwrap_or(DEFAULT_REQUEST_TIMEOUT_SECONDS),
+        )
+    }
+}
+
+// load the ethproofs config from a json file, either a registered cluster id or the cluster
+// configuration is required
+pub fn load_ethproofs_config(file_path: &Path) -> Result<EthproofsConfig> {
+    let config: EthproofsConfig = serde_json::from_slice(&fs::read(file_path)?)?;
+    ensure!(
+        config.cluster_id.is_some() || config.cluster.is_some(),
+        "either `cluster_id` or `cluster` must be configured",
+    );
+    if let Some(cluster) = &config.cluster {
+        ensure!(
+            !cluster.configuration.is_empty(),
+            "no machine is configured for cluster {}",
+            cluster.nickname,
+        );
+    }
+
+    Ok(config)
+}
@@ -0,0 +1,4 @@
+pub mod api;
+pub mod config;
+pub mod outbox;
+pub mod submitter;
@@ -0,0 +1,260 @@
+use anyhow::Result;
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::task::spawn_blocking;
+
+// proof status of a block submitted to ethproofs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofStatus {
+    // block is picked by the cluster and waiting for proving
+    Queued,
+
+    // block is proving by the cluster
+    Proving,
+
+    // block is proved with the proof
+    Proved,
+
+    // block is failed to prove
+    Failed,
+}
+
+impl fmt::Display for ProofStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Queued => write!(f, "queued"),
+            Self::Proving => write!(f, "proving"),
+            Self::Proved => write!(f, "proved"),
+            Self::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+// pending submission of the proof status of a block, the cluster id is added on sending since
+// the cluster may be not registered yet
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Submission {
+    // block number
+    pub block_number: u64,
+
+    // proof status of the block
+    pub status: ProofStatus,
+
+    // milliseconds of proving the block, only for the proved status
+    pub proving_time: Option<u64>,
+
+    // proving cycles of the block, only for the proved status
+    pub proving_cycles: Option<u64>,
+
+    // base64 encoded proof, only for the proved status
+    pub proof: Option<String>,
+
+    // reason of the failure, only for the failed status
+    pub error: Option<String>,
+}
+
+impl Submission {
+    // create a submission of a status without details, i.e. queued or proving
+    pub fn new(block_number: u64, status: ProofStatus) -> Self {
+        Self {
+            block_number,
+            status,
+            proving_time: None,
+            proving_cycles: None,
+            proof: None,
+            error: None,
+        }
+    }
+}
+
+// submission taken from the outbox with its attempts
+#[derive(Clone, Debug)]
+pub struct PendingSubmission {
+    // row id in the outbox
+    pub id: i64,
+
+    // submission to send
+    pub submission: Submission,
+
+    // number of the failed attempts
+    pub attempts: u32,
+}
+
+// durable outbox of the submissions in a SQLite database, they're sent in order per block and
+// kept until accepted, so no status is lost by an ethproofs outage or a restart
+pub struct SubmissionOutbox {
+    // connection shared by the blocking queries
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SubmissionOutbox {
+    pub fn open(path: &Path) -> Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS submissions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                block_number INTEGER NOT NULL,
+                submission TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                next_attempt_at INTEGER NOT NULL DEFAULT 0
+            )",
+            [],
+        )?;
+        connection.execute(
+            "CREATE INDEX IF NOT EXISTS submissions_block_number
+            ON submissions (block_number, id)",
+            [],
+        )?;
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS clusters (
+                config_digest TEXT PRIMARY KEY,
+                cluster_id INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    // append a submission to the outbox
+    pub async fn push(&self, submission: Submission) -> Result<()> {
+        let json = serde_json::to_string(&submission)?;
+        self.query(move |connection| {
+            connection.execute(
+                "INSERT INTO submissions (block_number, submission) VALUES (?1, ?2)",
+                params![submission.block_number, json],
+            )?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    // return the earliest submission due for sending, a submission waits for the earlier ones of
+    // the same block so the statuses are sent in order
+    pub async fn next_due(&self) -> Result<Option<PendingSubmission>> {
+        let now = unix_milliseconds();
+        self.query(move |connection| {
+            let row: Option<(i64, String, u32)> = connection
+                .query_row(
+                    "SELECT id, submission, attempts FROM submissions AS pending
+                    WHERE next_attempt_at <= ?1 AND NOT EXISTS (
+                        SELECT 1 FROM submissions AS earlier
+                        WHERE earlier.block_number = pending.block_number
+                            AND earlier.id < pending.id
+                    )
+                    ORDER BY id LIMIT 1",
+                    params![now],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .optional()?;
+
+            row.map(|(id, json, attempts)| {
+                Ok(PendingSubmission {
+                    id,
+                    submission: serde_json::from_str(&json)?,
+                    attempts,
+                })
+            })
+            .transpose()
+        })
+        .await
+    }
+
+    // remove a submission accepted or rejected by ethproofs
+    pub async fn remove(&self, id: i64) -> Result<()> {
+        self.query(move |connection| {
+            connection.execute("DELETE FROM submissions WHERE id = ?1", params![id])?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    // postpone a failed submission for the retry delay
+    pub async fn postpone(&self, id: i64, attempts: u32, delay: Duration) -> Result<()> {
+        let next_attempt_at = unix_milliseconds().saturating_add(delay.as_millis() as u64);
+        self.query(move |connection| {
+            connection.execute(
+                "UPDATE submissions SET attempts = ?2, next_attempt_at = ?3 WHERE id = ?1",
+                params![id, attempts, next_attempt_at],
+            )?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    // return the number of the pending submissions
+    pub async fn pending_count(&self) -> Result<u64> {
+        self.query(|connection| {
+            Ok(connection.query_row("SELECT COUNT(*) FROM submissions", [], |row| row.get(0))?)
+        })
+        .await
+    }
+
+    // return the id of the cluster registered by the config digest
+    pub async fn cluster_id(&self, config_digest: String) -> Result<Option<u64>> {
+        self.query(move |connection| {
+            Ok(connection
+                .query_row(
+                    "SELECT cluster_id FROM clusters WHERE config_digest = ?1",
+                    params![config_digest],
+                    |row| row.get(0),
+                )
+                .optional()?)
+        })
+        .await
+    }
+
+    // record the id of the cluster registered by the config digest
+    pub async fn save_cluster_id(&self, config_digest: String, cluster_id: u64) -> Result<()> {
+        self.query(move |connection| {
+            connection.execute(
+                "INSERT OR REPLACE INTO clusters (config_digest, cluster_id) VALUES (?1, ?2)",
+                params![config_digest, cluster_id],
+            )?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    // run a query on the connection in a blocking thread
+    async fn query<T: Send + 'static>(
+        &self,
+        query: impl FnOnce(&Connection) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let connection = self.connection.clone();
+        spawn_blocking(move || {
+            let connection = connection
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            query(&connection)
+        })
+        .await?
+    }
+}
+
+impl fmt::Debug for SubmissionOutbox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SubmissionOutbox").finish_non_exhaustive()
+    }
+}
+
+// return the current unix time in milliseconds
+fn unix_milliseconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
@@ -0,0 +1,286 @@
+use crate::{
+    api::{EthproofsApi, RejectedRequest},
+    config::{ClusterConfig, EthproofsConfig},
+    outbox::{PendingSubmission, ProofStatus, Submission, SubmissionOutbox},
+};
+use anyhow::{Context, Result, bail};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use common::{
+    proof_store::ProofStore,
+    report::BlockProvingReport,
+    shutdown::{ShutdownPhase, shutdown_reached},
+};
+use messages::{BlockStage, StatusMsg};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use tokio::{
+    select, spawn,
+    sync::Notify,
+    task::JoinHandle,
+    time::{Duration, sleep},
+};
+use tracing::{debug, error, info, warn};
+
+// interval of checking the outbox for the submissions due for retrying
+const OUTBOX_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// submitter of the proving progress of the blocks to ethproofs, the statuses are queued by the
+// reporter into the durable outbox and sent in background with retries
+#[derive(Debug)]
+pub struct EthproofsSubmitter {
+    // ethproofs config
+    config: EthproofsConfig,
+
+    // ethproofs API client
+    api: EthproofsApi,
+
+    // durable outbox of the pending submissions
+    outbox: SubmissionOutbox,
+
+    // store for loading the proofs persisted by the proof service; the proofs are carried by the
+    // reports if not specified
+    proof_store: Option<Arc<ProofStore>>,
+
+    // notified once a submission is queued
+    queued: Notify,
+}
+
+impl EthproofsSubmitter {
+    // create the API client and open the outbox, the pending submissions of the last run are sent
+    // once started
+    pub fn open(
+        config: EthproofsConfig,
+        api_key: String,
+        proof_store: Option<Arc<ProofStore>>,
+    ) -> Result<Self> {
+        let api = EthproofsApi::new(config.api_url(), api_key, config.request_timeout())?;
+        let outbox_path = config.outbox_path();
+        let outbox = SubmissionOutbox::open(&outbox_path)
+            .with_context(|| format!("failed to open the ethproofs outbox {outbox_path:?}"))?;
+
+        Ok(Self {
+            config,
+            api,
+            outbox,
+            proof_store,
+            queued: Notify::new(),
+        })
+    }
+
+    pub fn run(self: Arc<Self>) -> JoinHandle<()> {
+        info!("ethproofs-submitter: start");
+
+        spawn(async move {
+            select! {
+                () = self.submit_all() => {}
+                // the pending submissions are kept in the outbox for the next run
+                () = shutdown_reached(ShutdownPhase::Flushing) => {
+                    match self.outbox.pending_count().await {
+                        Ok(count) => info!(
+                            "ethproofs-submitter: kept {count} pending submissions for the restart"
+                        ),
+                        Err(e) => error!(
+                            "ethproofs-submitter: failed to count the pending submissions {e:?}"
+                        ),
+                    }
+                }
+            }
+            info!("ethproofs-submitter: stopped");
+        })
+    }
+
+    // queue the status of a block entering a stage, it's queued once dispatched to the
+    // proving-client and proving once sent to the proving cluster
+    pub async fn on_status(&self, status_msg: &StatusMsg) {
+        let status = match status_msg.stage {
+            BlockStage::Dispatched => ProofStatus::Queued,
+            BlockStage::Proving => ProofStatus::Proving,
+            _ => return,
+        };
+        self.queue(Submission::new(status_msg.block_number, status))
+            .await;
+    }
+
+    // queue the proved or failed status of a block report with the proof and timing, the
+    // estimation reports are skipped
+    pub async fn on_report(&self, report: &BlockProvingReport) {
+        if report.is_estimation {
+            return;
+        }
+
+        let submission = if report.success {
+            let proof = match self.load_proof(report).await {
+                Ok(proof) => proof,
+                Err(e) => {
+                    error!(
+                        "ethproofs-submitter: failed to load the proof of block {} {e:?}",
+                        report.block_number,
+                    );
+                    return;
+                }
+            };
+            Submission {
+                proving_time: Some(report.proving_milliseconds),
+                proving_cycles: Some(report.cycles),
+                proof: Some(STANDARD.encode(proof)),
+                ..Submission::new(report.block_number, ProofStatus::Proved)
+            }
+        } else {
+            Submission {
+                error: Some(failure_reason(report)),
+                ..Submission::new(report.block_number, ProofStatus::Failed)
+            }
+        };
+        self.queue(submission).await;
+    }
+
+    // append a submission to the outbox and wake up the sending
+    async fn queue(&self, submission: Submission) {
+        let (block_number, status) = (submission.block_number, submission.status);
+        match self.outbox.push(submission).await {
+            Ok(()) => self.queued.notify_one(),
+            Err(e) => error!(
+                "ethproofs-submitter: failed to queue the {status} status of block {block_number} {e:?}"
+            ),
+        }
+    }
+
+    // return the proof bytes of a proved block, carried by the report or persisted in the proof
+    // store
+    async fn load_proof(&self, report: &BlockProvingReport) -> Result<Vec<u8>> {
+        if let Some(proof) = &report.proof {
+            return Ok(proof.clone());
+        }
+
+        let (Some(proof_uri), Some(proof_store)) = (&report.proof_uri, &self.proof_store) else {
+            bail!("the proof is neither carried by the report nor persisted in the proof store");
+        };
+        proof_store
+            .load(proof_uri)
+            .await?
+            .with_context(|| format!("proof {proof_uri} is not found"))
+    }
+
+    // send the due submissions in order after the cluster is registered, it never returns
+    async fn submit_all(&self) {
+        let cluster_id = self.cluster_id().await;
+        loop {
+            match self.outbox.next_due().await {
+                Ok(Some(pending)) => self.submit(cluster_id, pending).await,
+                Ok(None) => {
+                    select! {
+                        () = self.queued.notified() => {}
+                        () = sleep(OUTBOX_POLL_INTERVAL) => {}
+                    }
+                }
+                Err(e) => {
+                    error!("ethproofs-submitter: failed to read the outbox {e:?}");
+                    sleep(OUTBOX_POLL_INTERVAL).await;
+                }
+            }
+        }
+    }
+
+    // send a submission, it's removed once accepted or rejected, and postponed with the backoff
+    // on the other failures
+    async fn submit(&self, cluster_id: u64, pending: PendingSubmission) {
+        let PendingSubmission {
+            id,
+            submission,
+            attempts,
+        } = pending;
+        let (block_number, status) = (submission.block_number, submission.status);
+        let verifier_id = self.config.verifier_id.as_deref();
+        let result = match self.api.submit(cluster_id, verifier_id, &submission).await {
+            Ok(()) => {
+                debug!(
+                    "ethproofs-submitter: submitted the {status} status of block {block_number}"
+                );
+                self.outbox.remove(id).await
+            }
+            Err(e) if e.is::<RejectedRequest>() => {
+                error!(
+                    "ethproofs-submitter: dropped the {status} status of block {block_number} {e:?}"
+                );
+                self.outbox.remove(id).await
+            }
+            Err(e) => {
+                let attempts = attempts + 1;
+                let delay = self.config.retry_delay(attempts);
+                warn!(
+                    "ethproofs-submitter: failed to submit the {status} status of block {block_number} by {attempts} attempts, retrying in {} s {e:?}",
+                    delay.as_secs(),
+                );
+                self.outbox.postpone(id, attempts, delay).await
+            }
+        };
+        if let Err(e) = result {
+            error!("ethproofs-submitter: failed to update the outbox {e:?}");
+            sleep(OUTBOX_POLL_INTERVAL).await;
+        }
+    }
+
+    // return the id of the cluster, the configured cluster is registered unless it's registered
+    // by the same configuration before; the registration is retried until succeeded
+    async fn cluster_id(&self) -> u64 {
+        if let Some(cluster_id) = self.config.cluster_id {
+            return cluster_id;
+        }
+
+        let cluster =
+            self.config.cluster.as_ref().expect(
+                "ethproofs-submitter: either `cluster_id` or `cluster` is checked on loading",
+            );
+        let mut attempts = 0;
+        loop {
+            match self.register_cluster(cluster).await {
+                Ok(cluster_id) => return cluster_id,
+                Err(e) => {
+                    attempts += 1;
+                    let delay = self.config.retry_delay(attempts);
+                    warn!(
+                        "ethproofs-submitter: failed to register the cluster {}, retrying in {} s {e:?}",
+                        cluster.nickname,
+                        delay.as_secs(),
+                    );
+                    sleep(delay).await;
+                }
+            }
+        }
+    }
+
+    // register the cluster if its configuration is not registered before, and return its id
+    async fn register_cluster(&self, cluster: &ClusterConfig) -> Result<u64> {
+        let config_digest = format!("{:x}", Sha256::digest(serde_json::to_vec(cluster)?));
+        if let Some(cluster_id) = self.outbox.cluster_id(config_digest.clone()).await? {
+            info!(
+                "ethproofs-submitter: using the registered cluster {} of id {cluster_id}",
+                cluster.nickname,
+            );
+            return Ok(cluster_id);
+        }
+
+        let cluster_id = self.api.register_cluster(cluster).await?;
+        self.outbox
+            .save_cluster_id(config_digest, cluster_id)
+            .await?;
+        info!(
+            "ethproofs-submitter: registered the cluster {} of id {cluster_id}",
+            cluster.nickname,
+        );
+
+        Ok(cluster_id)
+    }
+}
+
+// return the failure reason of a failed block report
+fn failure_reason(report: &BlockProvingReport) -> String {
+    let reason = match report.failure_stage {
+        Some(failure_stage) => format!("failed in the {failure_stage} stage"),
+        None => "failed".to_string(),
+    };
+    match report.proving_error_code {
+        Some(proving_error_code) => format!("{reason} with {proving_error_code}"),
+        None => reason,
+    }
+}
@@ -0,0 +1,367 @@
+use crate::ws::ReportWatcher;
+use anyhow::{Result, bail};
+use common::{
+    fetch::{
+        BackfillBlockRangeParams, EstimateBlockParams, HTTP_BACKFILL_BLOCK_RANGE_PATH,
+        HTTP_DRAIN_PATH, HTTP_ESTIMATE_BLOCK_PATH, HTTP_PAUSE_PATH, HTTP_PROOF_PATH,
+        HTTP_PROVE_BLOCK_BY_NUMBER_PATH, HTTP_PROVE_LATEST_BLOCK_PATH, HTTP_READYZ_PATH,
+        HTTP_RELOAD_CONFIG_PATH, HTTP_REPORT_PATH, HTTP_REPORTS_PATH,
+        HTTP_REPRODUCE_BLOCK_BY_LIST_PATH, HTTP_REPRODUCE_BLOCK_BY_NUMBER_PATH,
+        HTTP_REPRODUCE_LATEST_BLOCK_PATH, HTTP_RESUME_PATH, HTTP_RUNTIME_STATE_PATH,
+        HTTP_SET_LOG_FILTER_PATH, HTTP_SET_MAX_IN_FLIGHT_BLOCKS_PATH, HTTP_VERSION_PATH,
+        PipelineParams, ProveBlockByNumberParams, ProveLatestBlockParams, ReportParams,
+        ReportRangeParams, ReproduceBlockByListParams, ReproduceBlockByNumberParams,
+        ReproduceLatestBlockParams,
+    },
+    ids::JobId,
+    report::BlockProvingReport,
+    runtime::RuntimeState,
+    utils::PipelineId,
+    version::BuildInfo,
+};
+use reqwest::{Client, RequestBuilder, Response, StatusCode, Url};
+use serde::{Serialize, de::DeserializeOwned};
+use std::time::Duration;
+use tracing::info;
+
+// default fetch service HTTP URL
+pub const DEFAULT_HTTP_URL: &str = "http://127.0.0.1:8080";
+
+// default fetch service websocket URL
+pub const DEFAULT_WS_URL: &str = "ws://127.0.0.1:8080";
+
+// default timeout seconds of a HTTP request, including downloading the response
+const DEFAULT_REQUEST_TIMEOUT_SECONDS: u64 = 60;
+
+// default timeout seconds of connecting to the fetch service
+const DEFAULT_CONNECT_TIMEOUT_SECONDS: u64 = 10;
+
+// builder of a fetch client, the unset options are the defaults of a local fetch service
+#[derive(Clone, Debug)]
+pub struct FetchClientBuilder {
+    // fetch service HTTP URL
+    http_url: Url,
+
+    // fetch service websocket URL
+    ws_url: Url,
+
+    // timeout of a HTTP request
+    request_timeout: Duration,
+
+    // timeout of connecting to the fetch service
+    connect_timeout: Duration,
+
+    // pipeline of the fetch requests; the default pipeline if not specified
+    pipeline_id: Option<PipelineId>,
+
+    // key sent as the `authorization: Bearer <key>` header of the requests; no header if not
+    // specified
+    auth_key: Option<String>,
+}
+
+impl Default for FetchClientBuilder {
+    fn default() -> Self {
+        Self {
+            http_url: DEFAULT_HTTP_URL
+                .parse()
+                .expect("fetch-client: invalid default HTTP URL"),
+            ws_url: DEFAULT_WS_URL
+                .parse()
+                .expect("fetch-client: invalid default websocket URL"),
+            request_timeout: Duration::from_secs(DEFAULT_REQUEST_TIMEOUT_SECONDS),
+            connect_timeout: Duration::from_secs(DEFAULT_CONNECT_TIMEOUT_SECONDS),
+            pipeline_id: None,
+            auth_key: None,
+        }
+    }
+}
+
+impl FetchClientBuilder {
+    // set the fetch service HTTP URL
+    pub fn http_url(mut self, http_url: Url) -> Self {
+        self.http_url = http_url;
+        self
+    }
+
+    // set the fetch service websocket URL
+    pub fn ws_url(mut self, ws_url: Url) -> Self {
+        self.ws_url = ws_url;
+        self
+    }
+
+    // set the timeout of a HTTP request
+    pub fn request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.request_timeout = request_timeout;
+        self
+    }
+
+    // set the timeout of connecting to the fetch service
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    // set the pipeline of the fetch requests
+    pub fn pipeline_id(mut self, pipeline_id: PipelineId) -> Self {
+        self.pipeline_id = Some(pipeline_id);
+        self
+    }
+
+    // set the key of the `authorization: Bearer <key>` header
+    pub fn auth_key(mut self, auth_key: impl Into<String>) -> Self {
+        self.auth_key = Some(auth_key.into());
+        self
+    }
+
+    // build the fetch client
+    pub fn build(self) -> Result<FetchClient> {
+        let client = Client::builder()
+            .timeout(self.request_timeout)
+            .connect_timeout(self.connect_timeout)
+            .build()?;
+
+        Ok(FetchClient {
+            http_url: self.http_url,
+            ws_url: self.ws_url,
+            pipeline_id: self.pipeline_id,
+            auth_key: self.auth_key,
+            client,
+        })
+    }
+}
+
+// client of the fetch service HTTP and websocket APIs, the fetch requests return the assigned job
+// ids which tag the block reports received by `watch_reports`
+#[derive(Clone, Debug)]
+pub struct FetchClient {
+    // fetch service HTTP URL
+    http_url: Url,
+
+    // fetch service websocket URL
+    ws_url: Url,
+
+    // pipeline of the fetch requests; the default pipeline if not specified
+    pipeline_id: Option<PipelineId>,
+
+    // key of the `authorization: Bearer <key>` header; no header if not specified
+    auth_key: Option<String>,
+
+    // HTTP client shared by the requests
+    client: Client,
+}
+
+impl FetchClient {
+    // return a builder of the client
+    pub fn builder() -> FetchClientBuilder {
+        FetchClientBuilder::default()
+    }
+
+    // return the fetch service HTTP URL
+    pub fn http_url(&self) -> &Url {
+        &self.http_url
+    }
+
+    // return the fetch service websocket URL
+    pub fn ws_url(&self) -> &Url {
+        &self.ws_url
+    }
+
+    // request proving blocks from a block number
+    pub async fn prove_block_by_number(&self, params: &ProveBlockByNumberParams) -> Result<JobId> {
+        self.submit_job(HTTP_PROVE_BLOCK_BY_NUMBER_PATH, &params.to_hash_map())
+            .await
+    }
+
+    // request proving the latest blocks
+    pub async fn prove_latest_block(&self, params: &ProveLatestBlockParams) -> Result<JobId> {
+        self.submit_job(HTTP_PROVE_LATEST_BLOCK_PATH, &params.to_hash_map())
+            .await
+    }
+
+    // request reproducing the dumped blocks from a block number
+    pub async fn reproduce_block_by_number(
+        &self,
+        params: &ReproduceBlockByNumberParams,
+    ) -> Result<JobId> {
+        self.submit_job(HTTP_REPRODUCE_BLOCK_BY_NUMBER_PATH, &params.to_hash_map())
+            .await
+    }
+
+    // request reproducing the most recently dumped blocks
+    pub async fn reproduce_latest_block(
+        &self,
+        params: &ReproduceLatestBlockParams,
+    ) -> Result<JobId> {
+        self.submit_job(HTTP_REPRODUCE_LATEST_BLOCK_PATH, &params.to_hash_map())
+            .await
+    }
+
+    // request reproducing a list of the dumped blocks
+    pub async fn reproduce_block_by_list(
+        &self,
+        params: &ReproduceBlockByListParams,
+    ) -> Result<JobId> {
+        self.submit_job(HTTP_REPRODUCE_BLOCK_BY_LIST_PATH, &params.to_hash_map())
+            .await
+    }
+
+    // request backfilling a range of blocks with checkpointing
+    pub async fn backfill_block_range(&self, params: &BackfillBlockRangeParams) -> Result<JobId> {
+        self.submit_job(HTTP_BACKFILL_BLOCK_RANGE_PATH, &params.to_hash_map())
+            .await
+    }
+
+    // request estimating blocks in the dry-run mode without proving
+    pub async fn estimate_block(&self, params: &EstimateBlockParams) -> Result<JobId> {
+        self.submit_job(HTTP_ESTIMATE_BLOCK_PATH, &params.to_hash_map())
+            .await
+    }
+
+    // pause the all pipelines
+    pub async fn pause(&self) -> Result<()> {
+        self.send(self.get(HTTP_PAUSE_PATH)?).await?;
+        Ok(())
+    }
+
+    // resume the all pipelines
+    pub async fn resume(&self) -> Result<()> {
+        self.send(self.get(HTTP_RESUME_PATH)?).await?;
+        Ok(())
+    }
+
+    // drain the all pipelines
+    pub async fn drain(&self) -> Result<()> {
+        self.send(self.get(HTTP_DRAIN_PATH)?).await?;
+        Ok(())
+    }
+
+    // return the runtime configuration and status of the instance
+    pub async fn runtime_state(&self) -> Result<RuntimeState> {
+        self.send_json(self.get(HTTP_RUNTIME_STATE_PATH)?).await
+    }
+
+    // set the maximum number of blocks dispatched but not reported in a pipeline
+    pub async fn set_max_in_flight_blocks(&self, count: usize) -> Result<()> {
+        let request = self
+            .get(HTTP_SET_MAX_IN_FLIGHT_BLOCKS_PATH)?
+            .query(&[("count", count)]);
+        self.send(request).await?;
+
+        Ok(())
+    }
+
+    // set the log filter directives of the instance
+    pub async fn set_log_filter(&self, filter: &str) -> Result<()> {
+        let request = self
+            .get(HTTP_SET_LOG_FILTER_PATH)?
+            .query(&[("filter", filter)]);
+        self.send(request).await?;
+
+        Ok(())
+    }
+
+    // request reloading the config file of the instance
+    pub async fn reload_config(&self) -> Result<()> {
+        self.send(self.get(HTTP_RELOAD_CONFIG_PATH)?).await?;
+        Ok(())
+    }
+
+    // return the build info of the instance
+    pub async fn version(&self) -> Result<BuildInfo> {
+        self.send_json(self.get(HTTP_VERSION_PATH)?).await
+    }
+
+    // identify if the instance passed the all readiness checks
+    pub async fn is_ready(&self) -> Result<bool> {
+        let response = self.get(HTTP_READYZ_PATH)?.send().await?;
+
+        Ok(response.status().is_success())
+    }
+
+    // return the report of a block, it's none if not reported
+    pub async fn report(&self, params: &ReportParams) -> Result<Option<BlockProvingReport>> {
+        let request = self.get(HTTP_REPORT_PATH)?.query(&params.to_hash_map());
+        let Some(response) = self.send_optional(request).await? else {
+            return Ok(None);
+        };
+
+        Ok(Some(serde_json::from_slice(&response.bytes().await?)?))
+    }
+
+    // return the reports of a block range (inclusive)
+    pub async fn reports(&self, params: &ReportRangeParams) -> Result<Vec<BlockProvingReport>> {
+        let request = self.get(HTTP_REPORTS_PATH)?.query(&params.to_hash_map());
+        self.send_json(request).await
+    }
+
+    // return the persisted proof bytes of a block, it's none if not found
+    pub async fn proof(&self, params: &ReportParams) -> Result<Option<Vec<u8>>> {
+        let request = self.get(HTTP_PROOF_PATH)?.query(&params.to_hash_map());
+        let Some(response) = self.send_optional(request).await? else {
+            return Ok(None);
+        };
+
+        Ok(Some(response.bytes().await?.to_vec()))
+    }
+
+    // connect a watcher of the block reports, it should be connected before sending the fetch
+    // requests so no report is missed
+    pub async fn watch_reports(&self) -> Result<ReportWatcher> {
+        ReportWatcher::connect_with_auth(&self.ws_url, self.auth_key.as_deref()).await
+    }
+
+    // send a fetch request of the pipeline and return the assigned job id
+    async fn submit_job(&self, path: &str, params: &impl Serialize) -> Result<JobId> {
+        let pipeline_params = PipelineParams::new(self.pipeline_id).to_hash_map();
+        let request = self.get(path)?.query(params).query(&pipeline_params);
+        let job_id = self.send(request).await?.text().await?;
+
+        Ok(job_id.trim().parse::<u64>()?.into())
+    }
+
+    // create a HTTP Get request of the path
+    fn get(&self, path: &str) -> Result<RequestBuilder> {
+        let url = self.http_url.join(path)?;
+        info!("sending HTTP request: url = {url}");
+
+        let request = self.client.get(url);
+        Ok(match &self.auth_key {
+            Some(auth_key) => request.bearer_auth(auth_key),
+            None => request,
+        })
+    }
+
+    // send a request and parse the json response
+    async fn send_json<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        let response = self.send(request).await?;
+
+        Ok(serde_json::from_slice(&response.bytes().await?)?)
+    }
+
+    // send a request, it returns none if not found
+    async fn send_optional(&self, request: RequestBuilder) -> Result<Option<Response>> {
+        let response = request.send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        Ok(Some(check_status(response).await?))
+    }
+
+    // send a request and check the response status
+    async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        check_status(request.send().await?).await
+    }
+}
+
+// check the response status, the error message responded by the fetch service is returned if
+// not successful
+async fn check_status(response: Response) -> Result<Response> {
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_default();
+        bail!("fetch service responded {status}: {message}");
+    }
+
+    Ok(response)
+}
@@ -1,50 +1,86 @@
+use crate::client::FetchClient;
 use anyhow::Result;
-use common::fetch::{
-    HTTP_PROVE_BLOCK_BY_NUMBER_PATH, HTTP_PROVE_LATEST_BLOCK_PATH,
-    HTTP_REPRODUCE_BLOCK_BY_NUMBER_PATH, ProveBlockByNumberParams, ProveLatestBlockParams,
-    ReproduceBlockByNumberParams,
+use common::{
+    fetch::{
+        BackfillBlockRangeParams, EstimateBlockParams, ProveBlockByNumberParams,
+        ProveLatestBlockParams, ReportParams, ReproduceBlockByListParams,
+        ReproduceBlockByNumberParams, ReproduceLatestBlockParams,
+    },
+    ids::JobId,
+    report::BlockProvingReport,
 };
-use reqwest::{Client, Url};
-use tracing::info;
+use reqwest::Url;
 
-// send a http request:
+// send a http request and return the assigned job id:
 // `http://HTTP_URL/prove_block_by_number?start_block_num=START_BLOCK_NUM&count=COUNT`
 pub async fn prove_block_by_number(
     http_url: &Url,
     params: &ProveBlockByNumberParams,
-) -> Result<()> {
-    let url = http_url.join(HTTP_PROVE_BLOCK_BY_NUMBER_PATH)?;
-    let params = params.to_hash_map();
-
-    info!("sending HTTP request: url = {url}, params = {params:?}");
-    Client::new().get(url).query(&params).send().await?;
-
-    Ok(())
+) -> Result<JobId> {
+    client(http_url)?.prove_block_by_number(params).await
 }
 
-// send a http request:
+// send a http request and return the assigned job id:
 // `http://HTTP_URL/prove_latest_block?count=COUNT`
-pub async fn prove_latest_block(http_url: &Url, params: &ProveLatestBlockParams) -> Result<()> {
-    let url = http_url.join(HTTP_PROVE_LATEST_BLOCK_PATH)?;
-    let params = params.to_hash_map();
-
-    info!("sending HTTP request: url = {url}, params = {params:?}");
-    Client::new().get(url).query(&params).send().await?;
-
-    Ok(())
+pub async fn prove_latest_block(http_url: &Url, params: &ProveLatestBlockParams) -> Result<JobId> {
+    client(http_url)?.prove_latest_block(params).await
 }
 
-// send a http request:
+// send a http request and return the assigned job id:
 // `http://HTTP_URL/reproduce_block_by_number?start_block_num=START_BLOCK_NUM&count=COUNT`
 pub async fn reproduce_block_by_number(
     http_url: &Url,
     params: &ReproduceBlockByNumberParams,
-) -> Result<()> {
-    let url = http_url.join(HTTP_REPRODUCE_BLOCK_BY_NUMBER_PATH)?;
-    let params = params.to_hash_map();
+) -> Result<JobId> {
+    client(http_url)?.reproduce_block_by_number(params).await
+}
 
-    info!("sending HTTP request: url = {url}, params = {params:?}");
-    Client::new().get(url).query(&params).send().await?;
+// send a http request and return the assigned job id:
+// `http://HTTP_URL/reproduce_latest_block?count=COUNT`
+pub async fn reproduce_latest_block(
+    http_url: &Url,
+    params: &ReproduceLatestBlockParams,
+) -> Result<JobId> {
+    client(http_url)?.reproduce_latest_block(params).await
+}
+
+// send a http request and return the assigned job id:
+// `http://HTTP_URL/reproduce_block_by_list?block_nums=BLOCK_NUM1,BLOCK_NUM2`
+pub async fn reproduce_block_by_list(
+    http_url: &Url,
+    params: &ReproduceBlockByListParams,
+) -> Result<JobId> {
+    client(http_url)?.reproduce_block_by_list(params).await
+}
+
+// send a http request and return the assigned job id:
+// `http://HTTP_URL/backfill_block_range?start_block_num=START_BLOCK_NUM&end_block_num=END_BLOCK_NUM`
+pub async fn backfill_block_range(
+    http_url: &Url,
+    params: &BackfillBlockRangeParams,
+) -> Result<JobId> {
+    client(http_url)?.backfill_block_range(params).await
+}
+
+// send a http request and return the assigned job id:
+// `http://HTTP_URL/estimate_block?start_block_num=START_BLOCK_NUM&count=COUNT`
+pub async fn estimate_block(http_url: &Url, params: &EstimateBlockParams) -> Result<JobId> {
+    client(http_url)?.estimate_block(params).await
+}
+
+// send a http request and return the report of a block, it's none if not reported:
+// `http://HTTP_URL/report?block_number=BLOCK_NUMBER&chain_id=CHAIN_ID`
+pub async fn report(http_url: &Url, params: &ReportParams) -> Result<Option<BlockProvingReport>> {
+    client(http_url)?.report(params).await
+}
+
+// send a http request and return the persisted proof bytes of a block, it's none if not found:
+// `http://HTTP_URL/proof?block_number=BLOCK_NUMBER&chain_id=CHAIN_ID`
+pub async fn proof(http_url: &Url, params: &ReportParams) -> Result<Option<Vec<u8>>> {
+    client(http_url)?.proof(params).await
+}
 
-    Ok(())
+// create a fetch client of the HTTP URL with the default options
+fn client(http_url: &Url) -> Result<FetchClient> {
+    FetchClient::builder().http_url(http_url.clone()).build()
 }
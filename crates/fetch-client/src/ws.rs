@@ -1,99 +1,199 @@
 use anyhow::Result;
-use common::report::BlockProvingReport;
-use futures::{SinkExt, StreamExt};
+use common::{report::BlockProvingReport, summary::ProvingCampaignSummary};
+use futures::{SinkExt, StreamExt, stream::SplitStream};
 use reqwest::Url;
-use std::path::PathBuf;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 use tokio::{
+    net::TcpStream,
     select, spawn,
     sync::oneshot,
+    task::JoinHandle,
     time::{Duration, sleep},
 };
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
 use tracing::{error, info};
-use tungstenite::{Bytes, protocol::Message};
+use tungstenite::{
+    Bytes, client::IntoClientRequest, http::header::AUTHORIZATION, protocol::Message,
+};
 
 // interval seconds for sending a websocket ping message
 const WS_PING_INTERVAL: u64 = 15;
 
-// wait proving complete for the specified number of requested blocks on a websocket connection
-// - ws_url: websocket URL to connect
-// - block_count: number of blocks to wait for complete
-// - report_path: csv file to append the block reports if it's specified
-pub async fn wait_for_proving_complete(
-    ws_url: &Url,
-    mut block_count: usize,
-    report_path: &Option<PathBuf>,
-) -> Result<()> {
-    let url = ws_url.as_str();
-    info!("websocket-client: connecting to {url}");
-
-    let (ws_stream, ws_resp) = tokio_tungstenite::connect_async(url).await?;
-    info!(
-        "websocket-client: connected with status {}",
-        ws_resp.status(),
-    );
-
-    // split to a websocket sender and receiver
-    let (mut ws_sender, mut ws_receiver) = ws_stream.split();
-
-    // create a oneshot channel for graceful shutdown
-    let (exit_sender, mut exit_receiver) = oneshot::channel();
-
-    // send ping messages at intervals to keep the websocket connection alive
-    let ping_thread = spawn(async move {
-        let ping_interval = Duration::from_secs(WS_PING_INTERVAL);
-        let ping_msg = Message::Ping(Bytes::new());
-
-        loop {
-            select! {
-                _ = sleep(ping_interval) => {
-                    if let Err(e) = ws_sender.send(ping_msg.clone()).await {
-                        error!("websocket-client: failed to send ping message {e}");
+// watcher of the block reports on a websocket connection, it should be connected before sending
+// the fetch requests so no report is missed
+pub struct ReportWatcher {
+    // receiver of the websocket messages
+    ws_receiver: SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>,
+
+    // sender of the exit message to the websocket ping thread
+    exit_sender: oneshot::Sender<()>,
+
+    // thread sending the websocket ping messages
+    ping_thread: JoinHandle<()>,
+}
+
+impl ReportWatcher {
+    // connect to the websocket URL and keep the connection alive by the ping messages
+    pub async fn connect(ws_url: &Url) -> Result<Self> {
+        Self::connect_with_auth(ws_url, None).await
+    }
+
+    // connect to the websocket URL with the key of the `authorization: Bearer <key>` header if
+    // specified
+    pub async fn connect_with_auth(ws_url: &Url, auth_key: Option<&str>) -> Result<Self> {
+        let url = ws_url.as_str();
+        info!("websocket-client: connecting to {url}");
+
+        let mut request = url.into_client_request()?;
+        if let Some(auth_key) = auth_key {
+            request
+                .headers_mut()
+                .insert(AUTHORIZATION, format!("Bearer {auth_key}").parse()?);
+        }
+        let (ws_stream, ws_resp) = tokio_tungstenite::connect_async(request).await?;
+        info!(
+            "websocket-client: connected with status {}",
+            ws_resp.status(),
+        );
+
+        // split to a websocket sender and receiver
+        let (mut ws_sender, ws_receiver) = ws_stream.split();
+
+        // create a oneshot channel for graceful shutdown
+        let (exit_sender, mut exit_receiver) = oneshot::channel();
+
+        // send ping messages at intervals to keep the websocket connection alive
+        let ping_thread = spawn(async move {
+            let ping_interval = Duration::from_secs(WS_PING_INTERVAL);
+            let ping_msg = Message::Ping(Bytes::new());
+
+            loop {
+                select! {
+                    _ = sleep(ping_interval) => {
+                        if let Err(e) = ws_sender.send(ping_msg.clone()).await {
+                            error!("websocket-client: failed to send ping message {e}");
+                            break;
+                        }
+                    }
+                    _ = &mut exit_receiver => {
+                        info!("websocket-client: sending a Close meesage before exit");
+                        let _ = ws_sender.send(Message::Close(None)).await;
                         break;
                     }
                 }
-                _ = &mut exit_receiver => {
-                    info!("websocket-client: sending a Close meesage before exit");
-                    let _ = ws_sender.send(Message::Close(None)).await;
-                    break;
-                }
             }
-        }
-    });
+        });
 
-    // wait for receiving the proving reports of requested number of blocks
-    while let Some(msg) = ws_receiver.next().await {
-        match msg? {
-            Message::Binary(data) => {
+        Ok(Self {
+            ws_receiver,
+            exit_sender,
+            ping_thread,
+        })
+    }
+
+    // receive the next block report, the block status and error messages are logged; it returns
+    // none if the connection is closed by the server
+    pub async fn next_report(&mut self) -> Result<Option<BlockProvingReport>> {
+        while let Some(msg) = self.ws_receiver.next().await {
+            match msg? {
                 // decode the returned block proving report
-                let report: BlockProvingReport = bincode::deserialize(&data)?;
-
-                if let Some(csv_file_path) = report_path {
-                    // append the proving result to the csv file
-                    report.append_to_csv(csv_file_path)?;
-                } else {
-                    // output the proving result if the csv file is not specified
-                    info!("websocket-client: received proving result {report}");
+                Message::Binary(data) => return Ok(Some(bincode::deserialize(&data)?)),
+                Message::Text(status) => {
+                    // the block status and error messages show the progress before the reports
+                    info!("websocket-client: received block status {status}");
                 }
-
-                // for simplicity we only check the returned number
-                if block_count <= 1 {
+                Message::Close(frame) => {
+                    info!("websocket-client: closed by server {frame:?}");
                     break;
                 }
-                block_count -= 1;
+                msg => info!("websocket-client: received other message {msg:?}"),
             }
-            Message::Close(frame) => {
-                info!("websocket-client: closed by server {frame:?}");
-                break;
-            }
-            msg => info!("websocket-client: received other message {msg:?}"),
         }
+
+        Ok(None)
     }
 
-    // send a exit message to the websocket ping thread
-    let _ = exit_sender.send(());
-    let _ = ping_thread.await;
+    // close the websocket connection
+    pub async fn close(self) {
+        // send a exit message to the websocket ping thread
+        let _ = self.exit_sender.send(());
+        let _ = self.ping_thread.await;
+
+        info!("websocket-client: disconnected");
+    }
+}
+
+// wait proving complete for the specified number of requested blocks on a websocket connection
+// - ws_url: websocket URL to connect
+// - block_count: number of blocks to wait for complete
+// - report_path: file to append the block reports if it's specified, it's in NDJSON if the
+//   extension is `ndjson` or `jsonl`, otherwise in CSV
+// - summary_path: file to save the summary of the received reports if it's specified, it's in
+//   JSON if the extension is `json`, otherwise appended in CSV
+pub async fn wait_for_proving_complete(
+    ws_url: &Url,
+    block_count: usize,
+    report_path: &Option<PathBuf>,
+    summary_path: &Option<PathBuf>,
+) -> Result<()> {
+    let mut watcher = ReportWatcher::connect(ws_url).await?;
 
-    info!("websocket-client: disconnected");
+    // wait for receiving the proving reports of requested number of blocks, for simplicity we
+    // only check the returned number
+    let mut reports = vec![];
+    while reports.len() < block_count.max(1) {
+        let Some(mut report) = watcher.next_report().await? else {
+            break;
+        };
+        append_report(&report, report_path)?;
+        report.proof = None;
+        reports.push(report);
+    }
+    watcher.close().await;
+
+    // summarize the received reports
+    let summary = ProvingCampaignSummary::from_reports(&reports);
+    info!("websocket-client: proving summary {summary}");
+    if let Some(summary_path) = summary_path {
+        save_summary(&summary, summary_path)?;
+    }
 
     Ok(())
 }
+
+// append the proving result to the report file, it's in NDJSON if the extension is `ndjson` or
+// `jsonl` otherwise in CSV; the result is logged if the report file is not specified
+pub fn append_report(report: &BlockProvingReport, report_path: &Option<PathBuf>) -> Result<()> {
+    match report_path {
+        Some(report_path) if is_ndjson_path(report_path) => report.append_to_ndjson(report_path),
+        Some(report_path) => report.append_to_csv(report_path),
+        None => {
+            info!("websocket-client: received proving result {report}");
+            Ok(())
+        }
+    }
+}
+
+// save the summary to a file, it's in JSON if the extension is `json` otherwise appended in CSV
+fn save_summary(summary: &ProvingCampaignSummary, summary_path: &Path) -> Result<()> {
+    if summary_path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        fs::write(summary_path, summary.to_json()?)?;
+    } else {
+        summary.append_to_csv(summary_path)?;
+    }
+
+    Ok(())
+}
+
+// identify if the report file is in NDJSON by the extension
+fn is_ndjson_path(report_path: &Path) -> bool {
+    report_path
+        .extension()
+        .is_some_and(|extension| extension == "ndjson" || extension == "jsonl")
+}
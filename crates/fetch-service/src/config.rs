@@ -1,9 +1,26 @@
+use crate::job_queue::RedisJobQueue;
+use common::utils::PipelineId;
 use derive_more::Constructor;
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 
 // fetch service configuration
 #[derive(Constructor, Debug)]
 pub struct FetchServiceConfig {
     // fetch service address to bind
     pub addr: SocketAddr,
+
+    // ids of the pipelines which could be requested
+    pub pipeline_ids: Vec<PipelineId>,
+
+    // number of the latest blocks of the default pipeline proved once the service is ready, so
+    // no request is required to begin proving; nothing is requested if not specified
+    pub prove_latest_on_start: Option<u64>,
+
+    // identify if only the reproducing requests are served in the reproduce mode, the proving,
+    // backfilling and estimating requests are not routed since no rpc node is connected
+    pub is_reproduce_only: bool,
+
+    // job queue shared by the orchestrator replicas, the fetch requests are pushed to it instead
+    // of the local pipelines and the job ids are assigned by it; none if not shared
+    pub job_queue: Option<Arc<RedisJobQueue>>,
 }
@@ -1,32 +1,205 @@
 use crate::service::FetchService;
-use anyhow::Result;
-use common::fetch::{
-    ProveBlockByNumberParams, ProveLatestBlockParams, ReproduceBlockByNumberParams,
+use anyhow::{Result, anyhow, ensure};
+use common::{
+    fetch::{
+        BackfillBlockRangeParams, EstimateBlockParams, ProveBlockByNumberParams,
+        ProveLatestBlockParams, ReportParams, ReportRangeParams, ReproduceBlockByListParams,
+        ReproduceBlockByNumberParams, ReproduceLatestBlockParams, SetLogFilterParams,
+        SetMaxInFlightBlocksParams,
+    },
+    ids::DEFAULT_CHAIN_ID,
+    logger::check_log_filter,
+    proof_store::proof_hash,
+    report::BlockProvingReport,
+    runtime::{PipelineState, request_config_reload},
+    utils::PipelineId,
 };
+use messages::{BlockKey, BlockMsg, ControlMsg, FetchJob, JobId};
 use std::sync::Arc;
 
+// maximum number of blocks queried by a `reports` request
+const MAX_REPORT_RANGE_BLOCKS: u64 = 10_000;
+
 impl FetchService {
-    // handle `prove_block_by_number` HTTP Get requests
-    pub fn prove_block_by_number(self: Arc<Self>, params: ProveBlockByNumberParams) -> Result<()> {
-        self.comm_sender.send(params.into())?;
+    // handle `pause`, `resume` and `drain` HTTP Get admin requests
+    pub fn send_control(self: Arc<Self>, control_msg: ControlMsg) -> Result<()> {
+        self.comm_sender.send(BlockMsg::Control(control_msg))?;
+
+        let pipeline_state = match control_msg {
+            ControlMsg::Pause => PipelineState::Paused,
+            ControlMsg::Resume => PipelineState::Running,
+            ControlMsg::Drain => PipelineState::Draining,
+        };
+        self.runtime_state
+            .send_modify(|state| state.pipeline_state = pipeline_state);
 
         Ok(())
     }
 
-    // handle `prove_latest_block` HTTP Get request
-    pub fn prove_latest_block(self: Arc<Self>, params: ProveLatestBlockParams) -> Result<()> {
-        self.comm_sender.send(params.into())?;
+    // handle `report` HTTP Get requests, it returns none if the block is not reported
+    pub async fn report(
+        self: Arc<Self>,
+        params: ReportParams,
+    ) -> Result<Option<BlockProvingReport>> {
+        let chain_id = params.chain_id.unwrap_or(DEFAULT_CHAIN_ID);
+        self.report_store
+            .get(BlockKey::new(chain_id, params.block_number))
+            .await
+    }
+
+    // handle `proof` HTTP Get requests, it returns none if the block is not reported or its proof
+    // is not persisted; the loaded bytes are checked against the proof hash of the report
+    pub async fn proof(self: Arc<Self>, params: ReportParams) -> Result<Option<Vec<u8>>> {
+        let proof_store = self
+            .proof_store
+            .clone()
+            .ok_or_else(|| anyhow!("proofs are not persisted"))?;
+        let block_number = params.block_number;
+        let Some(report) = self.report(params).await? else {
+            return Ok(None);
+        };
+        let Some(proof_uri) = &report.proof_uri else {
+            return Ok(None);
+        };
+        let Some(proof) = proof_store.load(proof_uri).await? else {
+            return Ok(None);
+        };
+        if let Some(expected_hash) = &report.proof_hash {
+            let hash = proof_hash(&proof);
+            ensure!(
+                &hash == expected_hash,
+                "proof hash {hash} of block {block_number} is not consistent with the report {expected_hash}",
+            );
+        }
+
+        Ok(Some(proof))
+    }
+
+    // handle `reports` HTTP Get requests
+    pub async fn reports(
+        self: Arc<Self>,
+        params: ReportRangeParams,
+    ) -> Result<Vec<BlockProvingReport>> {
+        let (from, to) = (params.from_block_num, params.to_block_num);
+        ensure!(from <= to, "invalid block range {from}..={to}");
+        ensure!(
+            to - from < MAX_REPORT_RANGE_BLOCKS,
+            "block range {from}..={to} exceeds the limit of {MAX_REPORT_RANGE_BLOCKS} blocks",
+        );
+
+        let chain_id = params.chain_id.unwrap_or(DEFAULT_CHAIN_ID);
+        self.report_store.range(chain_id, from, to).await
+    }
+
+    // handle `set_max_in_flight_blocks` HTTP Get admin requests
+    pub fn set_max_in_flight_blocks(self: Arc<Self>, params: SetMaxInFlightBlocksParams) {
+        self.runtime_state
+            .send_modify(|state| state.max_in_flight_blocks = params.count);
+    }
+
+    // handle `set_log_filter` HTTP Get admin requests, the filter is checked before applying
+    pub fn set_log_filter(self: Arc<Self>, params: SetLogFilterParams) -> Result<()> {
+        check_log_filter(&params.filter)?;
+        self.runtime_state
+            .send_modify(|state| state.log_filter = Some(params.filter));
 
         Ok(())
     }
 
-    // handle `reproduce_block_by_number` HTTP Get requests
-    pub fn reproduce_block_by_number(
+    // handle `reload_config` HTTP Get admin requests, the reloaded settings are applied to the
+    // runtime state by the config reloader
+    pub fn reload_config(self: Arc<Self>) {
+        request_config_reload();
+    }
+
+    // handle `prove_block_by_number` HTTP Get requests, return the assigned job id
+    pub async fn prove_block_by_number(
+        self: Arc<Self>,
+        params: ProveBlockByNumberParams,
+        pipeline_id: PipelineId,
+    ) -> Result<JobId> {
+        let job_id = self.assign_job_id(pipeline_id).await?;
+        self.submit_job(FetchJob::new(job_id, pipeline_id, params).into())
+            .await?;
+
+        Ok(job_id)
+    }
+
+    // handle `prove_latest_block` HTTP Get request, return the assigned job id
+    pub async fn prove_latest_block(
+        self: Arc<Self>,
+        params: ProveLatestBlockParams,
+        pipeline_id: PipelineId,
+    ) -> Result<JobId> {
+        let job_id = self.assign_job_id(pipeline_id).await?;
+        self.submit_job(FetchJob::new(job_id, pipeline_id, params).into())
+            .await?;
+
+        Ok(job_id)
+    }
+
+    // handle `reproduce_block_by_number` HTTP Get requests, return the assigned job id
+    pub async fn reproduce_block_by_number(
         self: Arc<Self>,
         params: ReproduceBlockByNumberParams,
-    ) -> Result<()> {
-        self.comm_sender.send(params.into())?;
+        pipeline_id: PipelineId,
+    ) -> Result<JobId> {
+        let job_id = self.assign_job_id(pipeline_id).await?;
+        self.submit_job(FetchJob::new(job_id, pipeline_id, params).into())
+            .await?;
 
-        Ok(())
+        Ok(job_id)
+    }
+
+    // handle `reproduce_latest_block` HTTP Get requests, return the assigned job id
+    pub async fn reproduce_latest_block(
+        self: Arc<Self>,
+        params: ReproduceLatestBlockParams,
+        pipeline_id: PipelineId,
+    ) -> Result<JobId> {
+        let job_id = self.assign_job_id(pipeline_id).await?;
+        self.submit_job(FetchJob::new(job_id, pipeline_id, params).into())
+            .await?;
+
+        Ok(job_id)
+    }
+
+    // handle `reproduce_block_by_list` HTTP Get requests, return the assigned job id
+    pub async fn reproduce_block_by_list(
+        self: Arc<Self>,
+        params: ReproduceBlockByListParams,
+        pipeline_id: PipelineId,
+    ) -> Result<JobId> {
+        let job_id = self.assign_job_id(pipeline_id).await?;
+        self.submit_job(FetchJob::new(job_id, pipeline_id, params).try_into()?)
+            .await?;
+
+        Ok(job_id)
+    }
+
+    // handle `backfill_block_range` HTTP Get requests, return the assigned job id
+    pub async fn backfill_block_range(
+        self: Arc<Self>,
+        params: BackfillBlockRangeParams,
+        pipeline_id: PipelineId,
+    ) -> Result<JobId> {
+        let job_id = self.assign_job_id(pipeline_id).await?;
+        self.submit_job(FetchJob::new(job_id, pipeline_id, params).try_into()?)
+            .await?;
+
+        Ok(job_id)
+    }
+
+    // handle `estimate_block` HTTP Get requests, return the assigned job id
+    pub async fn estimate_block(
+        self: Arc<Self>,
+        params: EstimateBlockParams,
+        pipeline_id: PipelineId,
+    ) -> Result<JobId> {
+        let job_id = self.assign_job_id(pipeline_id).await?;
+        self.submit_job(FetchJob::new(job_id, pipeline_id, params).into())
+            .await?;
+
+        Ok(job_id)
     }
 }
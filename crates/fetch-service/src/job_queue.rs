@@ -0,0 +1,618 @@
+use anyhow::{Result, anyhow, bail, ensure};
+use common::{
+    report::BlockProvingReport,
+    shutdown::{ShutdownPhase, shutdown_reached},
+    utils::PipelineId,
+};
+use derive_more::Constructor;
+use messages::{BlockMsg, BlockMsgReceiver, BlockMsgSender, FetchMsg, JobId};
+use redis::{
+    AsyncCommands, RedisResult, Value,
+    aio::MultiplexedConnection,
+    streams::{
+        StreamAutoClaimOptions, StreamAutoClaimReply, StreamClaimOptions, StreamId,
+        StreamPendingCountReply, StreamReadOptions, StreamReadReply,
+    },
+};
+use std::{sync::Arc, time::Duration};
+use tokio::{
+    select, spawn,
+    sync::Mutex,
+    task::JoinHandle,
+    time::{MissedTickBehavior, interval},
+};
+use tracing::{debug, error, info, warn};
+
+// default redis stream of the shared fetch jobs
+pub const DEFAULT_JOB_QUEUE_STREAM: &str = "eth-proofs:jobs";
+
+// default consumer group of the replicas sharing the fetch jobs
+pub const DEFAULT_JOB_QUEUE_GROUP: &str = "eth-proofs";
+
+// default maximum number of the jobs claimed by a replica at the same time
+pub const DEFAULT_JOB_QUEUE_MAX_CLAIMS: usize = 4;
+
+// default seconds of a claimed job not renewed before it's claimed by another replica
+pub const DEFAULT_JOB_QUEUE_CLAIM_TIMEOUT_SECONDS: u64 = 120;
+
+// field name of the json fetch job in a stream entry
+const JOB_FIELD: &str = "job";
+
+// suffix of the key of the job id counter, the key is `<stream>:next-job-id`
+const JOB_ID_KEY_SUFFIX: &str = "next-job-id";
+
+// interval of claiming the queued jobs if a replica could claim more
+const CLAIM_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// number of the entries pushed by a redis pipeline
+const PUSH_BATCH_SIZE: usize = 1_000;
+
+// maximum number of the blocks queued by a fetch request
+const MAX_QUEUED_BLOCKS: u64 = 1_000_000;
+
+// config of the redis job queue shared by the orchestrator replicas
+#[derive(Clone, Constructor, Debug)]
+pub struct JobQueueConfig {
+    // redis URL, e.g. `redis://127.0.0.1:6379`
+    pub url: String,
+
+    // redis stream of the queued jobs, it's created with the consumer group if not existing
+    pub stream: String,
+
+    // consumer group of the replicas, a queued job is claimed by only one replica of the group
+    pub group: String,
+
+    // consumer name of this replica in the group, it must be unique among the replicas
+    pub consumer: String,
+
+    // maximum number of the jobs claimed by this replica at the same time
+    pub max_claims: usize,
+
+    // duration of a claimed job not renewed before it's claimed by another replica, e.g. the
+    // claiming replica crashed
+    pub claim_timeout: Duration,
+}
+
+// job claimed from the queue
+#[derive(Clone, Debug)]
+pub struct QueuedJob {
+    // id of the stream entry, it's acked once the job is completed
+    pub entry_id: String,
+
+    // fetch job sent to the local pipeline
+    pub fetch_msg: FetchMsg,
+}
+
+// job queue of the fetch requests shared by the orchestrator replicas by a redis stream and a
+// consumer group; the requests are pushed by any replica and claimed by one of them, a claimed job
+// is kept pending in the group until acked, so the jobs of a crashed replica are claimed by the
+// others after the claim timeout and no leader is required
+#[derive(Debug)]
+pub struct RedisJobQueue {
+    // job queue config
+    config: JobQueueConfig,
+
+    // shared connection of the queue commands, no blocking command is sent by it
+    connection: MultiplexedConnection,
+}
+
+impl RedisJobQueue {
+    // connect the redis server and create the stream and consumer group if not existing
+    pub async fn connect(config: JobQueueConfig) -> Result<Self> {
+        let client = redis::Client::open(config.url.as_str())?;
+        let mut connection = client.get_multiplexed_async_connection().await?;
+
+        // the group reads the stream from the beginning, so the jobs pushed before the group is
+        // created are not missed
+        let created: RedisResult<()> = connection
+            .xgroup_create_mkstream(&config.stream, &config.group, "0")
+            .await;
+        if let Err(e) = created
+            && e.code() != Some("BUSYGROUP")
+        {
+            return Err(e.into());
+        }
+
+        Ok(Self { config, connection })
+    }
+
+    // return the job queue config
+    pub fn config(&self) -> &JobQueueConfig {
+        &self.config
+    }
+
+    // return the interval of renewing the claimed jobs, it's a third of the claim timeout so a
+    // failed renewal is retried before the timeout
+    pub fn renew_interval(&self) -> Duration {
+        (self.config.claim_timeout / 3).max(Duration::from_secs(1))
+    }
+
+    // assign a new job id unique among the replicas, it starts from 1 since 0 means unassigned
+    pub async fn next_job_id(&self) -> Result<JobId> {
+        let key = format!("{}:{JOB_ID_KEY_SUFFIX}", self.config.stream);
+        let job_id: u64 = self.connection.clone().incr(key, 1).await?;
+
+        Ok(job_id.into())
+    }
+
+    // push a fetch job to the queue, it's split into the single block jobs if the blocks are
+    // known, so the blocks of a request are proved by the replicas in parallel
+    pub async fn push(&self, fetch_msg: FetchMsg) -> Result<()> {
+        let mut connection = self.connection.clone();
+        for jobs in split_job(fetch_msg)?.chunks(PUSH_BATCH_SIZE) {
+            let mut pipe = redis::pipe();
+            for job in jobs {
+                pipe.xadd(
+                    &self.config.stream,
+                    "*",
+                    &[(JOB_FIELD, serde_json::to_string(job)?)],
+                )
+                .ignore();
+            }
+            let () = pipe.query_async(&mut connection).await?;
+        }
+
+        Ok(())
+    }
+
+    // claim up to the count of jobs, the jobs not renewed over the claim timeout are claimed
+    // before the new ones; the undecodable entries are acked and skipped
+    pub async fn claim(&self, count: usize) -> Result<Vec<QueuedJob>> {
+        let JobQueueConfig {
+            stream,
+            group,
+            consumer,
+            claim_timeout,
+            ..
+        } = &self.config;
+        let mut connection = self.connection.clone();
+
+        let reply: StreamAutoClaimReply = connection
+            .xautoclaim_options(
+                stream,
+                group,
+                consumer,
+                claim_timeout.as_millis() as u64,
+                "0-0",
+                StreamAutoClaimOptions::default().count(count),
+            )
+            .await?;
+        let mut entries = reply.claimed;
+        if entries.len() < count {
+            let reply: Option<StreamReadReply> = connection
+                .xread_options(
+                    &[stream],
+                    &[">"],
+                    &StreamReadOptions::default()
+                        .group(group, consumer)
+                        .count(count - entries.len()),
+                )
+                .await?;
+            entries.extend(
+                reply
+                    .into_iter()
+                    .flat_map(|reply| reply.keys)
+                    .flat_map(|key| key.ids),
+            );
+        }
+
+        let mut jobs = vec![];
+        for entry in entries {
+            match decode_job(&entry) {
+                Ok(fetch_msg) => jobs.push(QueuedJob {
+                    entry_id: entry.id,
+                    fetch_msg,
+                }),
+                Err(e) => {
+                    error!(
+                        "job-queue: dropped the undecodable entry {} {e:?}",
+                        entry.id
+                    );
+                    self.ack(&entry.id).await?;
+                }
+            }
+        }
+
+        Ok(jobs)
+    }
+
+    // renew the claims of the jobs held by this replica, so they're not claimed by the others;
+    // the jobs already claimed by another replica are returned as lost
+    pub async fn renew(&self, entry_ids: &[String]) -> Result<Vec<String>> {
+        let JobQueueConfig {
+            stream,
+            group,
+            consumer,
+            ..
+        } = &self.config;
+        let mut connection = self.connection.clone();
+
+        // check the owners first since a claim is renewed regardless of the owner
+        let mut pipe = redis::pipe();
+        for entry_id in entry_ids {
+            pipe.xpending_count(stream, group, entry_id, entry_id, 1);
+        }
+        let replies: Vec<StreamPendingCountReply> = pipe.query_async(&mut connection).await?;
+        let (owned, lost): (Vec<_>, Vec<_>) =
+            entry_ids.iter().zip(replies).partition(|(_, reply)| {
+                reply
+                    .ids
+                    .first()
+                    .is_some_and(|pending| &pending.consumer == consumer)
+            });
+
+        let owned: Vec<&String> = owned.into_iter().map(|(entry_id, _)| entry_id).collect();
+        if !owned.is_empty() {
+            let _: Value = connection
+                .xclaim_options(
+                    stream,
+                    group,
+                    consumer,
+                    0,
+                    &owned,
+                    StreamClaimOptions::default().with_justid(),
+                )
+                .await?;
+        }
+
+        Ok(lost
+            .into_iter()
+            .map(|(entry_id, _)| entry_id.clone())
+            .collect())
+    }
+
+    // ack a completed job and remove it from the stream
+    pub async fn ack(&self, entry_id: &str) -> Result<()> {
+        let () = redis::pipe()
+            .atomic()
+            .xack(&self.config.stream, &self.config.group, &[entry_id])
+            .ignore()
+            .xdel(&self.config.stream, &[entry_id])
+            .ignore()
+            .query_async(&mut self.connection.clone())
+            .await?;
+
+        Ok(())
+    }
+}
+
+// job claimed by this replica and waiting for its reports
+#[derive(Debug)]
+struct ClaimedJob {
+    // id of the stream entry
+    entry_id: String,
+
+    // job id of the fetch request
+    job_id: JobId,
+
+    // block number of a single block job, it's none for the latest blocks
+    block_number: Option<u64>,
+
+    // number of the block reports not received yet
+    remaining_reports: u64,
+}
+
+impl ClaimedJob {
+    fn new(entry_id: String, fetch_msg: &FetchMsg) -> Self {
+        let (block_number, remaining_reports) = match fetch_msg {
+            FetchMsg::ProveFromStart {
+                start_block_number,
+                count,
+                ..
+            }
+            | FetchMsg::ReproduceFromStart {
+                start_block_number,
+                count,
+                ..
+            }
+            | FetchMsg::Estimate {
+                start_block_number,
+                count,
+                ..
+            } if *count == 1 => (Some(*start_block_number), 1),
+            FetchMsg::ReproduceByList { block_numbers, .. } if block_numbers.len() == 1 => {
+                (Some(block_numbers[0]), 1)
+            }
+            FetchMsg::ProveLatest { count, .. } => (None, *count),
+            FetchMsg::ProveFromStart { count, .. }
+            | FetchMsg::ReproduceFromStart { count, .. }
+            | FetchMsg::ReproduceLatest { count, .. }
+            | FetchMsg::Estimate { count, .. } => (None, *count),
+            FetchMsg::ReproduceByList { block_numbers, .. } => (None, block_numbers.len() as u64),
+            FetchMsg::Backfill {
+                start_block_number,
+                end_block_number,
+                ..
+            } => (
+                None,
+                (end_block_number + 1).saturating_sub(*start_block_number),
+            ),
+        };
+
+        Self {
+            entry_id,
+            job_id: fetch_msg.job_id(),
+            block_number,
+            remaining_reports,
+        }
+    }
+
+    // identify if a block report belongs to the job
+    fn matches(&self, report: &BlockProvingReport) -> bool {
+        report.job_id == self.job_id
+            && self
+                .block_number
+                .is_none_or(|block_number| block_number == report.block_number)
+    }
+}
+
+// consumer of the shared job queue, it claims the queued jobs for the local pipelines and acks
+// them once the all block reports are received; a job not completed by a crashed replica is
+// claimed again by the others, so a block could be proved more than once but is never lost
+#[derive(Constructor, Debug)]
+pub struct JobQueueConsumer {
+    // shared job queue
+    queue: Arc<RedisJobQueue>,
+
+    // ids of the local pipelines
+    pipeline_ids: Vec<PipelineId>,
+
+    // communication sender of the claimed jobs to the local pipelines
+    comm_sender: Arc<BlockMsgSender>,
+
+    // communication receiver of the block reports
+    comm_receiver: Arc<Mutex<BlockMsgReceiver>>,
+}
+
+impl JobQueueConsumer {
+    pub fn run(self: Arc<Self>) -> JoinHandle<()> {
+        info!("job-queue: start");
+
+        spawn(async move {
+            let mut comm_receiver = self.comm_receiver.lock().await;
+            let mut claim_ticker = interval(CLAIM_POLL_INTERVAL);
+            claim_ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            let mut renew_ticker = interval(self.queue.renew_interval());
+            renew_ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            // jobs claimed by this replica and not completed
+            let mut claimed_jobs = vec![];
+            // identify if the process is shutting down, no new job is claimed and the claimed jobs
+            // are still renewed until the process exits
+            let mut is_draining = false;
+            loop {
+                let can_claim = !is_draining && claimed_jobs.len() < self.queue.config().max_claims;
+                select! {
+                    msg = comm_receiver.recv() => {
+                        let Some(msg) = msg else {
+                            break;
+                        };
+                        if let BlockMsg::Report(report) = msg {
+                            self.on_report(&mut claimed_jobs, &report).await;
+                        }
+                    }
+                    _ = claim_ticker.tick(), if can_claim => {
+                        self.claim_jobs(&mut claimed_jobs).await;
+                    }
+                    _ = renew_ticker.tick(), if !claimed_jobs.is_empty() => {
+                        self.renew_jobs(&mut claimed_jobs).await;
+                    }
+                    () = shutdown_reached(ShutdownPhase::Draining), if !is_draining => {
+                        info!(
+                            "job-queue: stopped claiming jobs for shutdown, {} claimed jobs are claimed by the other replicas if not completed",
+                            claimed_jobs.len(),
+                        );
+                        is_draining = true;
+                    }
+                }
+            }
+            info!("job-queue: stopped");
+        })
+    }
+
+    // claim the queued jobs up to the maximum claims and send them to the local pipelines
+    async fn claim_jobs(&self, claimed_jobs: &mut Vec<ClaimedJob>) {
+        let count = self
+            .queue
+            .config()
+            .max_claims
+            .saturating_sub(claimed_jobs.len());
+        let jobs = match self.queue.claim(count).await {
+            Ok(jobs) => jobs,
+            Err(e) => {
+                error!("job-queue: failed to claim the queued jobs {e:?}");
+                return;
+            }
+        };
+
+        for QueuedJob {
+            entry_id,
+            fetch_msg,
+        } in jobs
+        {
+            // a job of this replica is claimed again if its renewal timed out
+            if claimed_jobs.iter().any(|job| job.entry_id == entry_id) {
+                continue;
+            }
+            let (job_id, pipeline_id) = (fetch_msg.job_id(), fetch_msg.pipeline_id());
+            if !self.pipeline_ids.contains(&pipeline_id) {
+                error!(
+                    "job-queue: dropped job {job_id} of the unknown pipeline {pipeline_id}, the replicas must run the same pipelines"
+                );
+                if let Err(e) = self.queue.ack(&entry_id).await {
+                    error!("job-queue: failed to ack entry {entry_id} {e:?}");
+                }
+                continue;
+            }
+
+            info!("job-queue: claimed job {job_id} of entry {entry_id} for pipeline {pipeline_id}");
+            let claimed_job = ClaimedJob::new(entry_id, &fetch_msg);
+            match self.comm_sender.send(BlockMsg::Fetch(fetch_msg)) {
+                Ok(()) => claimed_jobs.push(claimed_job),
+                Err(e) => error!("job-queue: failed to send job {job_id} {e:?}"),
+            }
+        }
+    }
+
+    // renew the claimed jobs, the jobs claimed by another replica are no longer tracked
+    async fn renew_jobs(&self, claimed_jobs: &mut Vec<ClaimedJob>) {
+        let entry_ids: Vec<String> = claimed_jobs
+            .iter()
+            .map(|job| job.entry_id.clone())
+            .collect();
+        match self.queue.renew(&entry_ids).await {
+            Ok(lost_entry_ids) => {
+                for entry_id in lost_entry_ids {
+                    warn!(
+                        "job-queue: entry {entry_id} is claimed by another replica after the claim timeout"
+                    );
+                    claimed_jobs.retain(|job| job.entry_id != entry_id);
+                }
+            }
+            Err(e) => error!("job-queue: failed to renew the claimed jobs {e:?}"),
+        }
+    }
+
+    // count a block report of the claimed jobs, the job is acked once the all reports are received
+    async fn on_report(&self, claimed_jobs: &mut Vec<ClaimedJob>, report: &BlockProvingReport) {
+        let Some(index) = claimed_jobs.iter().position(|job| job.matches(report)) else {
+            return;
+        };
+
+        let claimed_job = &mut claimed_jobs[index];
+        claimed_job.remaining_reports = claimed_job.remaining_reports.saturating_sub(1);
+        if claimed_job.remaining_reports > 0 {
+            return;
+        }
+
+        // the job is claimed again by another replica if failed to ack
+        let claimed_job = claimed_jobs.swap_remove(index);
+        match self.queue.ack(&claimed_job.entry_id).await {
+            Ok(()) => debug!(
+                "job-queue: completed job {} of entry {}",
+                claimed_job.job_id, claimed_job.entry_id,
+            ),
+            Err(e) => error!(
+                "job-queue: failed to ack job {} of entry {} {e:?}",
+                claimed_job.job_id, claimed_job.entry_id,
+            ),
+        }
+    }
+}
+
+// split a fetch job into the queued jobs, the known blocks are split into the single block jobs
+// and the latest blocks are queued as a whole since they're only known by fetching; a backfill is
+// queued as the single block proving jobs, the queue replaces its checkpoint
+fn split_job(fetch_msg: FetchMsg) -> Result<Vec<FetchMsg>> {
+    let jobs = match fetch_msg {
+        FetchMsg::ProveFromStart {
+            job_id,
+            pipeline_id,
+            start_block_number,
+            count,
+        } => single_block_jobs(start_block_number, count, |block_number| {
+            FetchMsg::ProveFromStart {
+                job_id,
+                pipeline_id,
+                start_block_number: block_number,
+                count: 1,
+            }
+        })?,
+        FetchMsg::ReproduceFromStart {
+            job_id,
+            pipeline_id,
+            start_block_number,
+            count,
+        } => single_block_jobs(start_block_number, count, |block_number| {
+            FetchMsg::ReproduceFromStart {
+                job_id,
+                pipeline_id,
+                start_block_number: block_number,
+                count: 1,
+            }
+        })?,
+        FetchMsg::Estimate {
+            job_id,
+            pipeline_id,
+            start_block_number,
+            count,
+        } => single_block_jobs(start_block_number, count, |block_number| {
+            FetchMsg::Estimate {
+                job_id,
+                pipeline_id,
+                start_block_number: block_number,
+                count: 1,
+            }
+        })?,
+        FetchMsg::Backfill {
+            job_id,
+            pipeline_id,
+            start_block_number,
+            end_block_number,
+        } => {
+            ensure!(
+                start_block_number <= end_block_number,
+                "invalid block range {start_block_number}..={end_block_number}",
+            );
+            let count = (end_block_number - start_block_number).saturating_add(1);
+            single_block_jobs(start_block_number, count, |block_number| {
+                FetchMsg::ProveFromStart {
+                    job_id,
+                    pipeline_id,
+                    start_block_number: block_number,
+                    count: 1,
+                }
+            })?
+        }
+        FetchMsg::ReproduceByList {
+            job_id,
+            pipeline_id,
+            block_numbers,
+        } => {
+            ensure!(
+                block_numbers.len() as u64 <= MAX_QUEUED_BLOCKS,
+                "{} blocks exceed the limit of {MAX_QUEUED_BLOCKS} queued blocks",
+                block_numbers.len(),
+            );
+            block_numbers
+                .into_iter()
+                .map(|block_number| FetchMsg::ReproduceByList {
+                    job_id,
+                    pipeline_id,
+                    block_numbers: vec![block_number],
+                })
+                .collect()
+        }
+        fetch_msg @ FetchMsg::ProveLatest { .. } => vec![fetch_msg],
+        FetchMsg::ReproduceLatest { .. } => {
+            bail!("the latest dumped blocks are only reproduced by the local pipelines")
+        }
+    };
+
+    Ok(jobs)
+}
+
+// create the single block jobs of the blocks starting from a block number
+fn single_block_jobs(
+    start_block_number: u64,
+    count: u64,
+    job: impl Fn(u64) -> FetchMsg,
+) -> Result<Vec<FetchMsg>> {
+    ensure!(
+        count <= MAX_QUEUED_BLOCKS,
+        "{count} blocks exceed the limit of {MAX_QUEUED_BLOCKS} queued blocks",
+    );
+    let end_block_number = start_block_number
+        .checked_add(count)
+        .ok_or_else(|| anyhow!("block number {start_block_number} + {count} overflows"))?;
+
+    Ok((start_block_number..end_block_number).map(job).collect())
+}
+
+// decode the fetch job of a stream entry
+fn decode_job(entry: &StreamId) -> Result<FetchMsg> {
+    let Some(job) = entry.get::<String>(JOB_FIELD) else {
+        bail!("field `{JOB_FIELD}` is missing");
+    };
+
+    Ok(serde_json::from_str(&job)?)
+}
@@ -1,4 +1,5 @@
 pub mod config;
 pub mod http;
+pub mod job_queue;
 pub mod service;
 pub mod ws;
@@ -1,52 +1,156 @@
 use crate::config::FetchServiceConfig;
+use anyhow::{Result, ensure};
 use axum::{
-    Router,
+    Json, Router,
     extract::{Query, State, WebSocketUpgrade},
-    http::StatusCode,
-    response::IntoResponse,
+    http::{StatusCode, header::CONTENT_TYPE},
+    response::{IntoResponse, Response},
     routing::get,
 };
-use common::fetch::{
-    HTTP_PROVE_BLOCK_BY_NUMBER_PATH, HTTP_PROVE_LATEST_BLOCK_PATH,
-    HTTP_REPRODUCE_BLOCK_BY_NUMBER_PATH, ProveBlockByNumberParams, ProveLatestBlockParams,
-    ReproduceBlockByNumberParams,
+use common::{
+    channel::WatchChannel,
+    fetch::{
+        BackfillBlockRangeParams, EstimateBlockParams, HTTP_BACKFILL_BLOCK_RANGE_PATH,
+        HTTP_DRAIN_PATH, HTTP_ESTIMATE_BLOCK_PATH, HTTP_METRICS_PATH, HTTP_PAUSE_PATH,
+        HTTP_PIPELINE_ERRORS_PATH, HTTP_PROOF_PATH, HTTP_PROVE_BLOCK_BY_NUMBER_PATH,
+        HTTP_PROVE_LATEST_BLOCK_PATH, HTTP_READYZ_PATH, HTTP_RELOAD_CONFIG_PATH, HTTP_REPORT_PATH,
+        HTTP_REPORTS_PATH, HTTP_REPRODUCE_BLOCK_BY_LIST_PATH, HTTP_REPRODUCE_BLOCK_BY_NUMBER_PATH,
+        HTTP_REPRODUCE_LATEST_BLOCK_PATH, HTTP_RESUME_PATH, HTTP_RUNTIME_STATE_PATH,
+        HTTP_SET_LOG_FILTER_PATH, HTTP_SET_MAX_IN_FLIGHT_BLOCKS_PATH, HTTP_VERSION_PATH,
+        PipelineParams, ProveBlockByNumberParams, ProveLatestBlockParams, ReportParams,
+        ReportRangeParams, ReproduceBlockByListParams, ReproduceBlockByNumberParams,
+        ReproduceLatestBlockParams, SetLogFilterParams, SetMaxInFlightBlocksParams,
+    },
+    metrics::MetricsRegistry,
+    pipeline_error::PipelineErrorLog,
+    proof_store::ProofStore,
+    readiness::{notify_systemd_ready, wait_ready},
+    report::ReportStore,
+    runtime::RuntimeState,
+    shutdown::{ShutdownPhase, shutdown_reached},
+    utils::{DEFAULT_PIPELINE_ID, PipelineId},
+    version::build_info,
 };
-use derive_more::Constructor;
-use messages::BlockMsgSender;
-use std::sync::Arc;
-use tokio::{net::TcpListener, signal::ctrl_c, spawn, task::JoinHandle};
+use messages::{BlockMsg, BlockMsgSender, ControlMsg, FetchMsg, JobId};
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+use tokio::{net::TcpListener, spawn, task::JoinHandle};
 use tracing::{error, info};
 
 // fetch http and websocket service
-#[derive(Constructor, Debug)]
+#[derive(Debug)]
 pub struct FetchService {
     // fetch service configuration
     pub config: FetchServiceConfig,
 
     // communication sender for coordinating with the main scheduler
     pub comm_sender: Arc<BlockMsgSender>,
+
+    // registry of the process metrics
+    pub metrics: Arc<MetricsRegistry>,
+
+    // log of the recent pipeline errors
+    pub pipeline_error_log: Arc<PipelineErrorLog>,
+
+    // runtime configuration and status updated by the admin requests
+    pub runtime_state: WatchChannel<RuntimeState>,
+
+    // store of the block reports for the history requests, it's shared with the reporter
+    pub report_store: Arc<dyn ReportStore>,
+
+    // store of the persisted proofs for the proof requests, it's shared with the proof service and
+    // none if the proofs are carried by the reports
+    pub proof_store: Option<Arc<ProofStore>>,
+
+    // next job id assigned to a fetch request, it starts from 1 since 0 means unassigned
+    next_job_id: AtomicU64,
 }
 
 impl FetchService {
+    pub fn new(
+        config: FetchServiceConfig,
+        comm_sender: Arc<BlockMsgSender>,
+        metrics: Arc<MetricsRegistry>,
+        pipeline_error_log: Arc<PipelineErrorLog>,
+        runtime_state: WatchChannel<RuntimeState>,
+        report_store: Arc<dyn ReportStore>,
+        proof_store: Option<Arc<ProofStore>>,
+    ) -> Self {
+        Self {
+            config,
+            comm_sender,
+            metrics,
+            pipeline_error_log,
+            runtime_state,
+            report_store,
+            proof_store,
+            next_job_id: AtomicU64::new(1),
+        }
+    }
+
+    // check the requested pipeline exists and assign a new job id to a fetch request, the job id
+    // is assigned by the shared job queue if enabled so it's unique among the replicas
+    pub async fn assign_job_id(&self, pipeline_id: PipelineId) -> Result<JobId> {
+        ensure!(
+            self.config.pipeline_ids.contains(&pipeline_id),
+            "unknown pipeline {pipeline_id}",
+        );
+
+        match &self.config.job_queue {
+            Some(job_queue) => job_queue.next_job_id().await,
+            None => Ok(self.next_job_id.fetch_add(1, Ordering::Relaxed).into()),
+        }
+    }
+
+    // send a fetch job to the local pipelines, or push it to the shared job queue if enabled; the
+    // latest dumped blocks are always reproduced locally since the dumps are local
+    pub async fn submit_job(&self, msg: BlockMsg) -> Result<()> {
+        match (&self.config.job_queue, msg) {
+            (Some(job_queue), BlockMsg::Fetch(fetch_msg))
+                if !matches!(fetch_msg, FetchMsg::ReproduceLatest { .. }) =>
+            {
+                job_queue.push(fetch_msg).await
+            }
+            (_, msg) => Ok(self.comm_sender.send(msg)?),
+        }
+    }
+
     pub fn run(self: Arc<Self>) -> JoinHandle<()> {
         info!("fetch-service: start");
 
         let addr = self.config.addr;
         spawn(async move {
-            // create the router for http and websocket service
-            let router = Router::new()
+            // create the router for http and websocket service, the requests of fetching the
+            // blocks from the rpc node are not routed in the reproduce mode
+            let mut router = Router::new()
                 // root path is used for websocket, it notifies the proving result to client
-                .route("/", get(ws_handler))
-                // HTTP Get request path for proving blocks by the specified block number
-                // It supports two parameters:
-                // - start_block_num: it specifies the `start` block number to prove
-                // - count: it's optional and `1` is the default value, it specifies the number of blocks to prove
-                .route(HTTP_PROVE_BLOCK_BY_NUMBER_PATH, get(prove_block_by_number))
-                // HTTP Get request path for proving latest blocks
-                // It supports one parameter:
-                // - count: it's optional and `1` is the default value, it specifies the number of latest blocks
-                //   to prove
-                .route(HTTP_PROVE_LATEST_BLOCK_PATH, get(prove_latest_block))
+                .route("/", get(ws_handler));
+            if !self.config.is_reproduce_only {
+                router = router
+                    // HTTP Get request path for proving blocks by the specified block number
+                    // It supports two parameters:
+                    // - start_block_num: it specifies the `start` block number to prove
+                    // - count: it's optional and `1` is the default value, it specifies the number of blocks to prove
+                    .route(HTTP_PROVE_BLOCK_BY_NUMBER_PATH, get(prove_block_by_number))
+                    // HTTP Get request path for proving latest blocks
+                    // It supports one parameter:
+                    // - count: it's optional and `1` is the default value, it specifies the number of latest blocks
+                    //   to prove
+                    .route(HTTP_PROVE_LATEST_BLOCK_PATH, get(prove_latest_block))
+                    // HTTP Get request path for backfilling a range of blocks with checkpointing
+                    // It supports two parameters:
+                    // - start_block_num: it specifies the first block number to backfill
+                    // - end_block_num: it specifies the last block number to backfill (inclusive)
+                    .route(HTTP_BACKFILL_BLOCK_RANGE_PATH, get(backfill_block_range))
+                    // HTTP Get request path for estimating blocks in a dry-run mode without proving
+                    // It supports two parameters:
+                    // - start_block_num: it specifies the `start` block number to estimate
+                    // - count: it's optional and `1` is the default value, it specifies the number of blocks to estimate
+                    .route(HTTP_ESTIMATE_BLOCK_PATH, get(estimate_block));
+            }
+            let router = router
                 // HTTP Get request path for reproducing blocks by the specified block number
                 // It supports two parameters:
                 // - start_block_num: it specifies the `start` block number to reproduce
@@ -55,7 +159,52 @@ impl FetchService {
                     HTTP_REPRODUCE_BLOCK_BY_NUMBER_PATH,
                     get(reproduce_block_by_number),
                 )
-                .with_state(self);
+                // HTTP Get request path for reproducing the most recently dumped blocks
+                // It supports one parameter:
+                // - count: it's optional and `1` is the default value, it specifies the number of the
+                //   most recently dumped blocks to reproduce
+                .route(
+                    HTTP_REPRODUCE_LATEST_BLOCK_PATH,
+                    get(reproduce_latest_block),
+                )
+                // HTTP Get request path for reproducing blocks by a list of block numbers
+                // It supports one parameter:
+                // - block_nums: it specifies the block numbers to reproduce separated by comma
+                .route(
+                    HTTP_REPRODUCE_BLOCK_BY_LIST_PATH,
+                    get(reproduce_block_by_list),
+                )
+                // HTTP Get admin request paths for pausing, resuming and draining the all pipelines
+                .route(HTTP_PAUSE_PATH, get(pause))
+                .route(HTTP_RESUME_PATH, get(resume))
+                .route(HTTP_DRAIN_PATH, get(drain))
+                // HTTP Get admin request paths for the runtime configuration and status
+                .route(HTTP_RUNTIME_STATE_PATH, get(runtime_state))
+                .route(
+                    HTTP_SET_MAX_IN_FLIGHT_BLOCKS_PATH,
+                    get(set_max_in_flight_blocks),
+                )
+                .route(HTTP_SET_LOG_FILTER_PATH, get(set_log_filter))
+                .route(HTTP_RELOAD_CONFIG_PATH, get(reload_config))
+                // HTTP Get request path for the readiness probe
+                .route(HTTP_READYZ_PATH, get(readyz))
+                // HTTP Get request path for the process metrics in the prometheus text format
+                .route(HTTP_METRICS_PATH, get(metrics))
+                // HTTP Get request path for the build info in json
+                .route(HTTP_VERSION_PATH, get(version))
+                // HTTP Get request path for the recent pipeline errors in json
+                .route(HTTP_PIPELINE_ERRORS_PATH, get(pipeline_errors))
+                // HTTP Get request paths for the history of the block reports
+                .route(HTTP_REPORT_PATH, get(report))
+                .route(HTTP_REPORTS_PATH, get(reports))
+                // HTTP Get request path for the persisted proof bytes of a block
+                .route(HTTP_PROOF_PATH, get(proof))
+                .with_state(self.clone());
+
+            // delay listening until the components are initialized, so no request is routed to a
+            // half-initialized process
+            info!("fetch-service: waiting for the readiness checks");
+            wait_ready(&mut self.runtime_state.subscribe()).await;
 
             // listen on the specified socket address
             let listener = TcpListener::bind(addr)
@@ -63,6 +212,30 @@ impl FetchService {
                 .expect("fetch-service: failed to listening on {addr}");
             info!("fetch-service: listening on {addr}");
 
+            // notify systemd the process is ready if run with `Type=notify`
+            match notify_systemd_ready() {
+                Ok(true) => info!("fetch-service: notified systemd of the readiness"),
+                Ok(false) => {}
+                Err(e) => error!("fetch-service: failed to notify systemd of the readiness {e:?}"),
+            }
+
+            // start proving the latest blocks if requested on launch
+            if let Some(count) = self.config.prove_latest_on_start {
+                let params = ProveLatestBlockParams::new(Some(count));
+                match self
+                    .clone()
+                    .prove_latest_block(params, DEFAULT_PIPELINE_ID)
+                    .await
+                {
+                    Ok(job_id) => info!(
+                        "fetch-service: started proving the latest blocks on launch by job {job_id}"
+                    ),
+                    Err(e) => error!(
+                        "fetch-service: failed to start proving the latest blocks on launch {e:?}"
+                    ),
+                }
+            }
+
             // start the service
             axum::serve(listener, router)
                 .with_graceful_shutdown(shutdown_signal())
@@ -90,43 +263,257 @@ async fn ws_handler(
 async fn prove_block_by_number(
     State(service): State<Arc<FetchService>>,
     Query(params): Query<ProveBlockByNumberParams>,
+    Query(pipeline): Query<PipelineParams>,
 ) -> impl IntoResponse {
-    info!("fetch-service: received prove_block_by_number with params {params:?}");
+    info!("fetch-service: received prove_block_by_number with params {params:?}, {pipeline:?}");
 
-    service.prove_block_by_number(params).map_or_else(
-        |e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
-        |_| (StatusCode::OK, "OK".to_string()),
-    )
+    service
+        .prove_block_by_number(params, pipeline.pipeline_id())
+        .await
+        .map_or_else(
+            |e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            |job_id| (StatusCode::OK, job_id.to_string()),
+        )
 }
 
 // handle `prove_latest_block` HTTP Get request
 async fn prove_latest_block(
     State(service): State<Arc<FetchService>>,
     Query(params): Query<ProveLatestBlockParams>,
+    Query(pipeline): Query<PipelineParams>,
 ) -> impl IntoResponse {
-    info!("fetch-service: received prove_latest_block with params {params:?}");
+    info!("fetch-service: received prove_latest_block with params {params:?}, {pipeline:?}");
 
-    service.prove_latest_block(params).map_or_else(
-        |e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
-        |_| (StatusCode::OK, "OK".to_string()),
-    )
+    service
+        .prove_latest_block(params, pipeline.pipeline_id())
+        .await
+        .map_or_else(
+            |e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            |job_id| (StatusCode::OK, job_id.to_string()),
+        )
 }
 
 // handle `reproduce_block_by_number` HTTP Get request
 async fn reproduce_block_by_number(
     State(service): State<Arc<FetchService>>,
     Query(params): Query<ReproduceBlockByNumberParams>,
+    Query(pipeline): Query<PipelineParams>,
+) -> impl IntoResponse {
+    info!("fetch-service: received reproduce_block_by_number with params {params:?}, {pipeline:?}");
+
+    service
+        .reproduce_block_by_number(params, pipeline.pipeline_id())
+        .await
+        .map_or_else(
+            |e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            |job_id| (StatusCode::OK, job_id.to_string()),
+        )
+}
+
+// handle `reproduce_latest_block` HTTP Get request
+async fn reproduce_latest_block(
+    State(service): State<Arc<FetchService>>,
+    Query(params): Query<ReproduceLatestBlockParams>,
+    Query(pipeline): Query<PipelineParams>,
+) -> impl IntoResponse {
+    info!("fetch-service: received reproduce_latest_block with params {params:?}, {pipeline:?}");
+
+    service
+        .reproduce_latest_block(params, pipeline.pipeline_id())
+        .await
+        .map_or_else(
+            |e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            |job_id| (StatusCode::OK, job_id.to_string()),
+        )
+}
+
+// handle `reproduce_block_by_list` HTTP Get request
+async fn reproduce_block_by_list(
+    State(service): State<Arc<FetchService>>,
+    Query(params): Query<ReproduceBlockByListParams>,
+    Query(pipeline): Query<PipelineParams>,
 ) -> impl IntoResponse {
-    info!("fetch-service: received reproduce_block_by_number with params {params:?}");
+    info!("fetch-service: received reproduce_block_by_list with params {params:?}, {pipeline:?}");
 
-    service.reproduce_block_by_number(params).map_or_else(
+    service
+        .reproduce_block_by_list(params, pipeline.pipeline_id())
+        .await
+        .map_or_else(
+            |e| (StatusCode::BAD_REQUEST, e.to_string()),
+            |job_id| (StatusCode::OK, job_id.to_string()),
+        )
+}
+
+// handle `backfill_block_range` HTTP Get request
+async fn backfill_block_range(
+    State(service): State<Arc<FetchService>>,
+    Query(params): Query<BackfillBlockRangeParams>,
+    Query(pipeline): Query<PipelineParams>,
+) -> impl IntoResponse {
+    info!("fetch-service: received backfill_block_range with params {params:?}, {pipeline:?}");
+
+    service
+        .backfill_block_range(params, pipeline.pipeline_id())
+        .await
+        .map_or_else(
+            |e| (StatusCode::BAD_REQUEST, e.to_string()),
+            |job_id| (StatusCode::OK, job_id.to_string()),
+        )
+}
+
+// handle `estimate_block` HTTP Get request
+async fn estimate_block(
+    State(service): State<Arc<FetchService>>,
+    Query(params): Query<EstimateBlockParams>,
+    Query(pipeline): Query<PipelineParams>,
+) -> impl IntoResponse {
+    info!("fetch-service: received estimate_block with params {params:?}, {pipeline:?}");
+
+    service
+        .estimate_block(params, pipeline.pipeline_id())
+        .await
+        .map_or_else(
+            |e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            |job_id| (StatusCode::OK, job_id.to_string()),
+        )
+}
+
+// handle `pause` HTTP Get request
+async fn pause(State(service): State<Arc<FetchService>>) -> impl IntoResponse {
+    send_control(service, ControlMsg::Pause)
+}
+
+// handle `resume` HTTP Get request
+async fn resume(State(service): State<Arc<FetchService>>) -> impl IntoResponse {
+    send_control(service, ControlMsg::Resume)
+}
+
+// handle `drain` HTTP Get request
+async fn drain(State(service): State<Arc<FetchService>>) -> impl IntoResponse {
+    send_control(service, ControlMsg::Drain)
+}
+
+// send a control message to the all pipelines
+fn send_control(service: Arc<FetchService>, control_msg: ControlMsg) -> (StatusCode, String) {
+    info!("fetch-service: received a {control_msg} request");
+
+    service.send_control(control_msg).map_or_else(
         |e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
         |_| (StatusCode::OK, "OK".to_string()),
     )
 }
 
-// graceful shutdown for `Ctrl+C`
+// handle `runtime_state` HTTP Get request
+async fn runtime_state(State(service): State<Arc<FetchService>>) -> impl IntoResponse {
+    Json(service.runtime_state.latest())
+}
+
+// handle `version` HTTP Get request
+async fn version() -> Response {
+    match build_info() {
+        Some(build_info) => Json(build_info).into_response(),
+        None => (StatusCode::NOT_FOUND, "build info is not set".to_string()).into_response(),
+    }
+}
+
+// handle `set_max_in_flight_blocks` HTTP Get request
+async fn set_max_in_flight_blocks(
+    State(service): State<Arc<FetchService>>,
+    Query(params): Query<SetMaxInFlightBlocksParams>,
+) -> impl IntoResponse {
+    info!(
+        "fetch-service: received a request of setting the max in-flight blocks to {}",
+        params.count,
+    );
+    service.set_max_in_flight_blocks(params);
+
+    (StatusCode::OK, "OK".to_string())
+}
+
+// handle `set_log_filter` HTTP Get request
+async fn set_log_filter(
+    State(service): State<Arc<FetchService>>,
+    Query(params): Query<SetLogFilterParams>,
+) -> impl IntoResponse {
+    info!(
+        "fetch-service: received a request of setting the log filter to `{}`",
+        params.filter,
+    );
+    service.set_log_filter(params).map_or_else(
+        |e| (StatusCode::BAD_REQUEST, e.to_string()),
+        |_| (StatusCode::OK, "OK".to_string()),
+    )
+}
+
+// handle `reload_config` HTTP Get request
+async fn reload_config(State(service): State<Arc<FetchService>>) -> impl IntoResponse {
+    info!("fetch-service: received a request of reloading the config file");
+    service.reload_config();
+
+    (StatusCode::OK, "OK".to_string())
+}
+
+// handle `readyz` HTTP Get request
+async fn readyz(State(service): State<Arc<FetchService>>) -> impl IntoResponse {
+    let state = service.runtime_state.latest();
+    if state.is_ready() {
+        (StatusCode::OK, "OK".to_string())
+    } else {
+        let pending_checks: Vec<_> = state.pending_checks.into_iter().collect();
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("pending checks: {}", pending_checks.join(",")),
+        )
+    }
+}
+
+// handle `metrics` HTTP Get request
+async fn metrics(State(service): State<Arc<FetchService>>) -> impl IntoResponse {
+    service.metrics.render()
+}
+
+// handle `pipeline_errors` HTTP Get request
+async fn pipeline_errors(State(service): State<Arc<FetchService>>) -> impl IntoResponse {
+    Json(service.pipeline_error_log.recent())
+}
+
+// handle `report` HTTP Get request
+async fn report(
+    State(service): State<Arc<FetchService>>,
+    Query(params): Query<ReportParams>,
+) -> Response {
+    match service.report(params).await {
+        Ok(Some(report)) => Json(report).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "block is not reported".to_string()).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+// handle `reports` HTTP Get request
+async fn reports(
+    State(service): State<Arc<FetchService>>,
+    Query(params): Query<ReportRangeParams>,
+) -> Response {
+    match service.reports(params).await {
+        Ok(reports) => Json(reports).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+// handle `proof` HTTP Get request
+async fn proof(
+    State(service): State<Arc<FetchService>>,
+    Query(params): Query<ReportParams>,
+) -> Response {
+    match service.proof(params).await {
+        Ok(Some(proof)) => ([(CONTENT_TYPE, "application/octet-stream")], proof).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "proof is not found".to_string()).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+// graceful shutdown once the process starts draining, the new requests are no longer accepted
 async fn shutdown_signal() {
-    ctrl_c().await.expect("failed to install Ctrl+C handler");
-    info!("Ctrl+C signal received");
+    shutdown_reached(ShutdownPhase::Draining).await;
+    info!("fetch-service: stop accepting requests for shutdown");
 }
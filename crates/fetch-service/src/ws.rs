@@ -12,6 +12,15 @@ use tokio::{spawn, sync::mpsc::unbounded_channel};
 use tracing::{info, warn};
 
 impl FetchService {
+    // register a watcher of the block reports, status and error messages broadcast by the reporter
+    pub fn watch(&self) -> Result<SingleUnboundedChannel<BlockMsg>> {
+        let channel = SingleUnboundedChannel::default();
+        self.comm_sender
+            .send(BlockMsg::Watch(WatchMsg::new(channel.sender())))?;
+
+        Ok(channel)
+    }
+
     // handle websocket messages
     pub async fn handle_ws(self: Arc<Self>, socket: WebSocket) -> Result<()> {
         info!("fetch-service: received a websocket in handle_ws");
@@ -20,13 +29,7 @@ impl FetchService {
         let (mut ws_sender, mut ws_receiver) = socket.split();
 
         info!("fetch-service: registering a block proving monitor to receive block reports");
-        let proved_receiver = {
-            let channel = SingleUnboundedChannel::default();
-            let msg = BlockMsg::Watch(WatchMsg::new(channel.sender()));
-            self.comm_sender.send(msg)?;
-
-            channel.receiver()
-        };
+        let proved_receiver = self.watch()?.receiver();
 
         info!("fetch-service: sending a websocket welcome message");
         ws_sender
@@ -41,16 +44,31 @@ impl FetchService {
         let msg_sender_clone = msg_sender.clone();
         let proved_receiving_handle = spawn(async move {
             let mut proved_receiver = proved_receiver.lock().await;
-            while let Some(BlockMsg::Report(report)) = proved_receiver.recv().await {
-                // serialize block report
-                let report_bytes = bincode::serialize(&report)
-                    .expect("fetch-service: failed to serialize block report in websocket");
+            while let Some(msg) = proved_receiver.recv().await {
+                let ws_msg = match msg {
+                    // serialize block report as a binary message
+                    BlockMsg::Report(report) => {
+                        let report_bytes = bincode::serialize(&report)
+                            .expect("fetch-service: failed to serialize block report in websocket");
+                        Message::Binary(report_bytes.into())
+                    }
+                    // serialize block status as a json text message
+                    BlockMsg::Status(status_msg) => {
+                        let status_json = serde_json::to_string(&status_msg)
+                            .expect("fetch-service: failed to serialize block status in websocket");
+                        Message::Text(status_json.into())
+                    }
+                    // serialize block error as a json text message
+                    BlockMsg::Error(error_msg) => {
+                        let error_json = serde_json::to_string(&error_msg)
+                            .expect("fetch-service: failed to serialize block error in websocket");
+                        Message::Text(error_json.into())
+                    }
+                    _ => break,
+                };
 
-                // send serialized block report to websocket sender thread
-                if msg_sender_clone
-                    .send(Message::Binary(report_bytes.into()))
-                    .is_err()
-                {
+                // send serialized message to websocket sender thread
+                if msg_sender_clone.send(ws_msg).is_err() {
                     warn!("fetch-service: websocket connection may be closed");
                     break;
                 }
@@ -0,0 +1,353 @@
+use crate::{
+    config::BlockFetcherConfig, proving_queue::ProvingQueue, subblock_executor::SubblockExecutor,
+};
+use anyhow::Result;
+use common::report::BlockProvingReport;
+use messages::{BlockKey, FetchMsg, FetchMsgReceiver, JobId, ProvingMsg};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, fs, path::Path, sync::Arc, time::Instant};
+use tokio::{spawn, sync::Mutex, task::JoinHandle};
+use tracing::{Instrument, error, info, warn};
+
+// checkpoint of a backfill campaign persisted after each reported block
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct BackfillCheckpoint {
+    // job id of the backfill request assigned by the fetch-service
+    #[serde(default)]
+    pub job_id: JobId,
+
+    // first block number of the backfill range
+    pub start_block_number: u64,
+
+    // last block number of the backfill range (inclusive)
+    pub end_block_number: u64,
+
+    // last block number of the range up to which the all blocks are reported, the failed ones
+    // are kept in the failed blocks
+    #[serde(alias = "last_submitted_block_number")]
+    pub last_reported_block_number: Option<u64>,
+
+    // failed blocks to be retried when the campaign is resumed
+    #[serde(default)]
+    pub failed_block_numbers: BTreeSet<u64>,
+}
+
+impl BackfillCheckpoint {
+    // load the checkpoint from a file, return `None` if it doesn't exist
+    pub fn load(file_path: &Path) -> Result<Option<Self>> {
+        if !file_path.exists() {
+            return Ok(None);
+        }
+
+        let data = fs::read(file_path)?;
+        Ok(Some(serde_json::from_slice(&data)?))
+    }
+
+    // save the checkpoint to a file, it's written to a temporary file first to avoid a partially
+    // written checkpoint
+    pub fn save(&self, file_path: &Path) -> Result<()> {
+        let tmp_file_path = file_path.with_extension("tmp");
+        fs::write(&tmp_file_path, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(tmp_file_path, file_path)?;
+
+        Ok(())
+    }
+
+    // return the next block number of the range to fetch
+    pub fn next_block_number(&self) -> u64 {
+        self.last_reported_block_number
+            .map_or(self.start_block_number, |block_number| block_number + 1)
+    }
+
+    // identify if the same block range is requested
+    fn is_same_range(&self, start_block_number: u64, end_block_number: u64) -> bool {
+        self.start_block_number == start_block_number && self.end_block_number == end_block_number
+    }
+}
+
+// progress of the running backfill campaign
+#[derive(Debug)]
+struct BackfillProgress {
+    // checkpoint persisted when a block is reported
+    checkpoint: BackfillCheckpoint,
+
+    // blocks submitted to the proving-client but not reported yet
+    pending_block_numbers: BTreeSet<u64>,
+
+    // next block number of the range to submit
+    next_submitting_block_number: u64,
+}
+
+impl BackfillProgress {
+    // advance the checkpoint to the block before the first block of the range not reported yet
+    fn advance(&mut self) {
+        let next_block_number = self.checkpoint.next_block_number();
+        let first_unreported_block_number = self
+            .pending_block_numbers
+            .range(next_block_number..)
+            .next()
+            .copied()
+            .unwrap_or(self.next_submitting_block_number)
+            .min(self.next_submitting_block_number);
+        if first_unreported_block_number > next_block_number {
+            self.checkpoint.last_reported_block_number = Some(first_unreported_block_number - 1);
+        }
+    }
+
+    // identify if the all blocks of the range are submitted and reported
+    fn is_completed(&self) -> bool {
+        self.next_submitting_block_number > self.checkpoint.end_block_number
+            && self.pending_block_numbers.is_empty()
+    }
+}
+
+// sub block fetcher for backfilling a large range of historical blocks, the progress is persisted
+// to a checkpoint file when the blocks are reported and resumed after a restart, the failed blocks
+// are retried on resuming
+pub struct BackfillingFetcher {
+    // fetcher configuration
+    config: Arc<BlockFetcherConfig>,
+
+    // receiving fetch messages
+    fetch_receiver: Arc<Mutex<FetchMsgReceiver>>,
+
+    // queue for sending proving messages to the proving-client thread
+    proving_queue: Arc<ProvingQueue>,
+
+    // executor for generating subblock and aggregation inputs
+    subblock_executor: Arc<SubblockExecutor>,
+
+    // progress of the running backfill campaign, it's none if no campaign is running
+    progress: std::sync::Mutex<Option<BackfillProgress>>,
+}
+
+impl BackfillingFetcher {
+    pub fn new(
+        config: Arc<BlockFetcherConfig>,
+        fetch_receiver: Arc<Mutex<FetchMsgReceiver>>,
+        proving_queue: Arc<ProvingQueue>,
+        subblock_executor: Arc<SubblockExecutor>,
+    ) -> Self {
+        Self {
+            config,
+            fetch_receiver,
+            proving_queue,
+            subblock_executor,
+            progress: std::sync::Mutex::new(None),
+        }
+    }
+
+    pub fn run(self: Arc<Self>) -> JoinHandle<()> {
+        info!("backfilling-fetcher: start");
+
+        spawn(async move {
+            // resume the unfinished backfill campaign
+            let checkpoint_path = &self.config.backfill_checkpoint_path;
+            match BackfillCheckpoint::load(checkpoint_path) {
+                Ok(Some(checkpoint)) => {
+                    info!("backfilling-fetcher: resuming from the checkpoint {checkpoint:?}");
+                    self.backfill(checkpoint).await;
+                }
+                Ok(None) => {}
+                Err(e) => error!(
+                    "backfilling-fetcher: failed to load the checkpoint {checkpoint_path:?} {e:?}",
+                ),
+            }
+
+            let mut fetch_receiver = self.fetch_receiver.lock().await;
+            while let Some(msg) = fetch_receiver.recv().await {
+                match msg {
+                    FetchMsg::Backfill {
+                        job_id,
+                        start_block_number,
+                        end_block_number,
+                        ..
+                    } => {
+                        info!(
+                            "backfilling-fetcher: received backfill message of job_id = {job_id}, start_block_number = {start_block_number}, end_block_number = {end_block_number}",
+                        );
+
+                        // resume from the checkpoint if the same range was interrupted
+                        let checkpoint = match BackfillCheckpoint::load(checkpoint_path) {
+                            Ok(Some(checkpoint))
+                                if checkpoint
+                                    .is_same_range(start_block_number, end_block_number) =>
+                            {
+                                info!(
+                                    "backfilling-fetcher: resuming from the checkpoint {checkpoint:?}",
+                                );
+                                // the resumed blocks are reported under the new job id
+                                BackfillCheckpoint {
+                                    job_id,
+                                    ..checkpoint
+                                }
+                            }
+                            _ => BackfillCheckpoint {
+                                job_id,
+                                start_block_number,
+                                end_block_number,
+                                last_reported_block_number: None,
+                                failed_block_numbers: BTreeSet::new(),
+                            },
+                        };
+                        self.backfill(checkpoint).await;
+                    }
+                    _ => error!("backfilling-fetcher: received a wrong message {msg:?}"),
+                }
+            }
+        })
+    }
+
+    // backfill the failed blocks and then the rest blocks of the range from the checkpoint, the
+    // checkpoint is updated when the blocks are reported
+    async fn backfill(&self, checkpoint: BackfillCheckpoint) {
+        let job_id = checkpoint.job_id;
+        let retried_block_numbers = checkpoint.failed_block_numbers.clone();
+        let failed_block_numbers = retried_block_numbers.clone();
+        let block_numbers = (checkpoint.next_block_number()..=checkpoint.end_block_number)
+            .filter(move |block_number| !failed_block_numbers.contains(block_number));
+        *self.progress() = Some(BackfillProgress {
+            next_submitting_block_number: checkpoint.next_block_number(),
+            checkpoint,
+            pending_block_numbers: BTreeSet::new(),
+        });
+
+        if !retried_block_numbers.is_empty() {
+            info!("backfilling-fetcher: retrying the failed blocks {retried_block_numbers:?}");
+        }
+        let block_numbers = retried_block_numbers
+            .into_iter()
+            .map(|block_number| (block_number, true))
+            .chain(block_numbers.map(|block_number| (block_number, false)));
+        for (block_number, is_retried) in block_numbers {
+            // the retried blocks are before the range cursor, or skipped by the range
+            if let Some(progress) = self.progress().as_mut() {
+                progress.pending_block_numbers.insert(block_number);
+                if !is_retried {
+                    progress.next_submitting_block_number = block_number + 1;
+                }
+            }
+
+            info!("backfilling-fetcher: starting for fetching block {block_number}");
+            if let Err(e) = self.fetch_block(job_id, block_number).await {
+                error!("backfilling-fetcher: failed to fetch block-{block_number} {e:?}");
+
+                // the failure report of the block is not routed back to the fetcher
+                self.on_block_reported(job_id, block_number, false);
+                continue;
+            }
+            info!("backfilling-fetcher: succeeded for fetching block {block_number}");
+        }
+
+        // the campaign may be already completed if the all blocks failed before dispatch
+        if let Some(progress) = self.progress().as_mut() {
+            progress.next_submitting_block_number = progress.checkpoint.end_block_number + 1;
+            progress.advance();
+        }
+        self.try_complete();
+    }
+
+    // advance the checkpoint of the running campaign after a block is reported or failed, the
+    // failed block is kept in the checkpoint for retrying on resuming
+    pub fn on_block_reported(&self, job_id: JobId, block_number: u64, success: bool) {
+        {
+            let mut progress = self.progress();
+            let Some(progress) = progress
+                .as_mut()
+                .filter(|progress| progress.checkpoint.job_id == job_id)
+            else {
+                return;
+            };
+            if !progress.pending_block_numbers.remove(&block_number) {
+                return;
+            }
+
+            if success {
+                progress
+                    .checkpoint
+                    .failed_block_numbers
+                    .remove(&block_number);
+            } else {
+                progress
+                    .checkpoint
+                    .failed_block_numbers
+                    .insert(block_number);
+            }
+            progress.advance();
+
+            let checkpoint_path = &self.config.backfill_checkpoint_path;
+            if let Err(e) = progress.checkpoint.save(checkpoint_path) {
+                warn!(
+                    "backfilling-fetcher: failed to save the checkpoint of block {block_number} {e:?}"
+                );
+            }
+        }
+
+        self.try_complete();
+    }
+
+    // finish the running campaign if the all blocks are reported, the checkpoint is kept if any
+    // block failed so they're retried on resuming
+    fn try_complete(&self) {
+        let mut progress = self.progress();
+        if !progress
+            .as_ref()
+            .is_some_and(BackfillProgress::is_completed)
+        {
+            return;
+        }
+
+        let Some(BackfillProgress { checkpoint, .. }) = progress.take() else {
+            return;
+        };
+        let checkpoint_path = &self.config.backfill_checkpoint_path;
+        if !checkpoint.failed_block_numbers.is_empty() {
+            warn!(
+                "backfilling-fetcher: completed backfilling {checkpoint:?} with failed blocks, they're retried on resuming from {checkpoint_path:?}",
+            );
+            return;
+        }
+
+        info!("backfilling-fetcher: completed backfilling {checkpoint:?}");
+        if let Err(e) = fs::remove_file(checkpoint_path) {
+            warn!("backfilling-fetcher: failed to remove the checkpoint {checkpoint_path:?} {e}");
+        }
+    }
+
+    // lock the progress of the running campaign
+    fn progress(&self) -> std::sync::MutexGuard<'_, Option<BackfillProgress>> {
+        self.progress
+            .lock()
+            .expect("backfilling-fetcher: progress lock is poisoned")
+    }
+
+    // fetch a specified block by number
+    async fn fetch_block(&self, job_id: JobId, block_number: u64) -> Result<()> {
+        let mut fetch_report =
+            BlockProvingReport::new(job_id, BlockKey::new(self.config.chain_id, block_number));
+        let span = fetch_report.span();
+
+        async move {
+            // wait until the proving backlog is below the limit
+            self.proving_queue.wait_for_admission(&fetch_report).await;
+
+            // generate proving inputs of the specified block number
+            let start_time = Instant::now();
+            let proving_inputs = match self
+                .subblock_executor
+                .generate_inputs(block_number, &mut fetch_report)
+                .await
+            {
+                Ok(proving_inputs) => proving_inputs,
+                Err(e) => return Err(self.proving_queue.send_failure(fetch_report, e)),
+            };
+            fetch_report.on_data_fetched(start_time.elapsed().as_millis() as u64);
+
+            // send the proving message
+            self.proving_queue
+                .send(ProvingMsg::new(fetch_report, proving_inputs))
+        }
+        .instrument(span)
+        .await
+    }
+}
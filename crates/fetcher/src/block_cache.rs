@@ -0,0 +1,47 @@
+use anyhow::Result;
+use derive_more::Constructor;
+use rsp_client_executor::io::SubblockHostOutput;
+use std::{fs, path::PathBuf};
+use tracing::warn;
+
+// on-disk cache of the fetched block data and execution witnesses keyed by block hash, it avoids
+// refetching the same block from the rpc node for retries, reproduction and comparison
+#[derive(Constructor, Debug)]
+pub struct BlockCache {
+    // base directory of the cached files
+    dir: PathBuf,
+}
+
+impl BlockCache {
+    // return the cached subblock host output of the block hash if it exists
+    pub fn get(&self, block_hash: &str) -> Option<SubblockHostOutput> {
+        let file_path = self.file_path(block_hash);
+        let data = fs::read(&file_path).ok()?;
+
+        match bincode::deserialize(&data) {
+            Ok(output) => Some(output),
+            Err(e) => {
+                warn!("block-cache: ignore the corrupted cache file {file_path:?} {e}");
+                None
+            }
+        }
+    }
+
+    // save the subblock host output of the block hash, it's written to a temporary file first to
+    // avoid a partially written cache file
+    pub fn put(&self, block_hash: &str, output: &SubblockHostOutput) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        let file_path = self.file_path(block_hash);
+        let tmp_file_path = file_path.with_extension("tmp");
+        fs::write(&tmp_file_path, bincode::serialize(output)?)?;
+        fs::rename(tmp_file_path, file_path)?;
+
+        Ok(())
+    }
+
+    // construct the cache file path of the block hash
+    fn file_path(&self, block_hash: &str) -> PathBuf {
+        self.dir.join(format!("{block_hash}.bin"))
+    }
+}
@@ -1,6 +1,8 @@
+use crate::{dump_retention::DumpRetentionPolicy, rpc_fixture::RpcFixtureMode};
+use common::inputs::InputLayout;
 use derive_more::Constructor;
 use reqwest::Url;
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 
 // block fetcher configuration
 #[derive(Constructor, Debug)]
@@ -8,22 +10,84 @@ pub struct BlockFetcherConfig {
     // identify if should check the generated inputs by emulation
     pub is_input_emulated: bool,
 
-    // base directory for saving input files; nothing will be saved if not specified
+    // base directory or object store URL (`s3://` or `gs://`) for saving input files; nothing will
+    // be saved if not specified
     pub input_dump_dir: Option<PathBuf>,
 
-    // base directory for reproducing blocks by loading input files; it could be the same directory
-    // as `input_dump_dir`
+    // retention policy of the dumped input files in `input_dump_dir`
+    pub dump_retention_policy: DumpRetentionPolicy,
+
+    // base directory or object store URL for reproducing blocks by loading input files; it could be
+    // the same location as `input_dump_dir`
     pub input_load_dir: Option<PathBuf>,
 
-    // http url of rpc node
-    pub rpc_http_url: Url,
+    // identify if the input files in a local `input_load_dir` are memory-mapped when loading, so
+    // the compressed files are not read into memory; the files must not be rewritten while loading
+    pub input_load_mmap: bool,
+
+    // layout of the dumped and loaded input files of each block
+    pub input_layout: InputLayout,
+
+    // http url of rpc node; none in the reproduce mode, which only reproduces the dumped inputs
+    // without fetching the blocks
+    pub rpc_http_url: Option<Url>,
+
+    // websocket url of rpc node; none in the reproduce mode
+    pub rpc_ws_url: Option<Url>,
 
-    // websocket url of rpc node
-    pub rpc_ws_url: Url,
+    // http url of the rpc node for fetching the block execution witnesses, e.g. a node with the
+    // debug and state proof apis enabled; `rpc_http_url` is used if not specified
+    pub witness_rpc_url: Option<Url>,
+
+    // mode of recording or replaying the http rpc responses for deterministic tests; the rpc
+    // responses are neither recorded nor replayed if not specified
+    pub rpc_fixture_mode: Option<RpcFixtureMode>,
+
+    // directory of the rpc fixture files of each block
+    pub rpc_fixture_dir: PathBuf,
 
     // subblock elf file path
     pub subblock_elf_path: PathBuf,
 
     // aggregator elf file path
     pub agg_elf_path: PathBuf,
+
+    // base directory for caching the fetched block data and execution witnesses keyed by block
+    // hash; nothing will be cached if not specified
+    pub block_cache_dir: Option<PathBuf>,
+
+    // maximum number of blocks whose inputs are prepared ahead of the proving-client consumption
+    // in prove-from-start mode
+    pub prefetch_block_count: usize,
+
+    // maximum time of a prefetched block holding its permit without a report or failure, the
+    // permit is released after it so the prefetching is not stalled by a lost block
+    pub prefetch_permit_timeout: Duration,
+
+    // maximum number of latest blocks fetched in each websocket subscription batch, the
+    // subscription is renewed after each batch for receiving new fetch requests; batching is
+    // disabled if it's zero
+    pub max_blocks_per_batch: usize,
+
+    // number of blocks the latest fetcher waits behind the chain head before proving a block,
+    // avoiding wasted work on blocks reorged within a few slots; the head is proved if zero
+    pub confirmations: u64,
+
+    // maximum number of blocks submitted to the proving-client but not reported, the inputs of new
+    // blocks are delayed to generate when the backlog exceeds it; it's disabled if zero
+    pub max_proving_queue_depth: usize,
+
+    // maximum bytes of each serialized subblock or aggregator input, the blocks exceeding it are
+    // rejected with a failure report before sending to the proving cluster
+    pub max_input_bytes: usize,
+
+    // maximum number of subblocks of a block, the blocks split into more subblocks are rejected
+    // since the cluster has no enough subblock provers
+    pub max_num_subblocks: usize,
+
+    // checkpoint file path for resuming the backfill campaign after a restart
+    pub backfill_checkpoint_path: PathBuf,
+
+    // chain id of the fetched blocks for keying the blocks across the chains
+    pub chain_id: u64,
 }
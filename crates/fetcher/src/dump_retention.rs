@@ -0,0 +1,123 @@
+use anyhow::Result;
+use common::{
+    inputs::{
+        InputLayout, dumped_block_bytes, list_dumped_block_numbers, load_manifest,
+        remove_dumped_block,
+    },
+    storage::InputStorage,
+};
+use derive_more::Constructor;
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    spawn,
+    task::JoinHandle,
+    time::{Duration, interval},
+};
+use tracing::{error, info};
+
+// interval of enforcing the retention policy (in seconds)
+const DUMP_RETENTION_INTERVAL_SECONDS: u64 = 600;
+
+// retention policy of the dumped proving inputs
+#[derive(Clone, Constructor, Debug)]
+pub struct DumpRetentionPolicy {
+    // maximum age of the dumped blocks (in seconds); unlimited if not specified
+    pub max_age_seconds: Option<u64>,
+
+    // maximum total bytes of the dumped blocks; unlimited if not specified
+    pub max_total_bytes: Option<u64>,
+
+    // number of the most recent blocks which are always kept
+    pub keep_latest_count: usize,
+}
+
+impl DumpRetentionPolicy {
+    // identify if any limit is specified
+    pub fn is_enabled(&self) -> bool {
+        self.max_age_seconds.is_some() || self.max_total_bytes.is_some()
+    }
+}
+
+// background task for removing the dumped blocks by the retention policy, the oldest blocks are
+// removed first and the most recent blocks are always kept
+#[derive(Constructor)]
+pub struct DumpRetention {
+    // retention policy
+    policy: DumpRetentionPolicy,
+
+    // storage of the dumped input files
+    input_dump_storage: Arc<dyn InputStorage>,
+
+    // layout of the dumped input files for locating the manifests
+    input_layout: InputLayout,
+}
+
+impl DumpRetention {
+    pub fn run(self: Arc<Self>) -> JoinHandle<()> {
+        info!("dump-retention: start with policy {:?}", self.policy);
+
+        spawn(async move {
+            let mut ticker = interval(Duration::from_secs(DUMP_RETENTION_INTERVAL_SECONDS));
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.enforce().await {
+                    error!("dump-retention: failed to enforce the retention policy {e:?}");
+                }
+            }
+        })
+    }
+
+    // remove the dumped blocks exceeding the maximum age or total bytes
+    async fn enforce(&self) -> Result<()> {
+        let storage = self.input_dump_storage.as_ref();
+        let block_numbers = list_dumped_block_numbers(storage).await?;
+        let num_removable = block_numbers
+            .len()
+            .saturating_sub(self.policy.keep_latest_count);
+
+        // collect the bytes of the all blocks for calculating the total bytes
+        let mut block_bytes = Vec::with_capacity(block_numbers.len());
+        for block_number in &block_numbers {
+            block_bytes.push(dumped_block_bytes(storage, *block_number).await?);
+        }
+        let mut total_bytes: u64 = block_bytes.iter().sum();
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let mut removed_count = 0;
+        for (block_number, bytes) in block_numbers
+            .into_iter()
+            .zip(block_bytes)
+            .take(num_removable)
+        {
+            let is_oversized = self
+                .policy
+                .max_total_bytes
+                .is_some_and(|max_total_bytes| total_bytes > max_total_bytes);
+            let is_expired = match self.policy.max_age_seconds {
+                Some(max_age_seconds) => load_manifest(storage, block_number, &self.input_layout)
+                    .await?
+                    .is_some_and(|manifest| {
+                        now.saturating_sub(manifest.generated_at) > max_age_seconds
+                    }),
+                None => false,
+            };
+            if !is_oversized && !is_expired {
+                continue;
+            }
+
+            remove_dumped_block(storage, block_number).await?;
+            total_bytes -= bytes;
+            removed_count += 1;
+            info!("dump-retention: removed the dumped block {block_number} of {bytes} bytes");
+        }
+
+        info!(
+            "dump-retention: removed {removed_count} dumped blocks, the remaining total is {total_bytes} bytes",
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use crate::subblock_executor::SubblockExecutor;
+use anyhow::Result;
+use common::report::BlockProvingReport;
+use derive_more::Constructor;
+use messages::{BlockKey, BlockMsg, BlockMsgSender, FetchMsg, FetchMsgReceiver, JobId};
+use std::{sync::Arc, time::Instant};
+use tokio::{spawn, sync::Mutex, task::JoinHandle};
+use tracing::{Instrument, error, info};
+
+// sub block fetcher for estimating blocks in a dry-run mode, the inputs are generated and emulated
+// but never dispatched to the proving-client, the estimation reports are sent to the reporter
+#[derive(Constructor)]
+pub struct EstimatingFetcher {
+    // chain id of the estimated blocks
+    chain_id: u64,
+
+    // receiving fetch messages
+    fetch_receiver: Arc<Mutex<FetchMsgReceiver>>,
+
+    // sending estimation reports to the reporter thread
+    report_sender: Arc<BlockMsgSender>,
+
+    // executor for generating and emulating subblock and aggregation inputs
+    subblock_executor: Arc<SubblockExecutor>,
+}
+
+impl EstimatingFetcher {
+    pub fn run(self: Arc<Self>) -> JoinHandle<()> {
+        info!("estimating-fetcher: start");
+
+        spawn(async move {
+            let mut fetch_receiver = self.fetch_receiver.lock().await;
+            while let Some(msg) = fetch_receiver.recv().await {
+                match msg {
+                    FetchMsg::Estimate {
+                        job_id,
+                        start_block_number,
+                        count,
+                        ..
+                    } => {
+                        info!(
+                            "estimating-fetcher: received estimate message of job_id = {job_id}, start_block_number = {start_block_number}, count = {count}",
+                        );
+
+                        for block_number in start_block_number..start_block_number + count {
+                            match self.estimate_block(job_id, block_number).await {
+                                Ok(()) => info!(
+                                    "estimating-fetcher: succeeded for estimating block {block_number}",
+                                ),
+                                Err(e) => error!(
+                                    "estimating-fetcher: failed to estimate block-{block_number} {e:?}",
+                                ),
+                            }
+                        }
+                    }
+                    _ => error!("estimating-fetcher: received a wrong message {msg:?}"),
+                }
+            }
+        })
+    }
+
+    // estimate a specified block by number and send the estimation report
+    async fn estimate_block(&self, job_id: JobId, block_number: u64) -> Result<()> {
+        let mut report =
+            BlockProvingReport::new(job_id, BlockKey::new(self.chain_id, block_number));
+        let span = report.span();
+
+        async move {
+            info!("estimating-fetcher: starting for estimating block {block_number}");
+
+            // generate and emulate proving inputs of the specified block number
+            let start_time = Instant::now();
+            if let Err(e) = self
+                .subblock_executor
+                .estimate_inputs(block_number, &mut report)
+                .await
+            {
+                // send a failure report so the clients always receive a report per block
+                if report.failure_stage.is_none() {
+                    report.on_fetching_failure(format!("{e:#}"));
+                }
+                self.report_sender.send(BlockMsg::Report(report))?;
+
+                return Err(e);
+            }
+            report.on_data_fetched(start_time.elapsed().as_millis() as u64);
+            info!("estimating-fetcher: {report}");
+
+            // send the estimation report
+            self.report_sender.send(BlockMsg::Report(report))?;
+
+            Ok(())
+        }
+        .instrument(span)
+        .await
+    }
+}
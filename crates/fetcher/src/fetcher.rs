@@ -1,13 +1,30 @@
 use crate::{
-    config::BlockFetcherConfig, proving_from_start::ProvingFromStartFetcher,
-    proving_latest::ProvingLatestFetcher, reproducing_from_start::ReproducingFromStartFetcher,
-    subblock_executor::SubblockExecutor,
+    backfilling::BackfillingFetcher,
+    config::BlockFetcherConfig,
+    dump_retention::DumpRetention,
+    estimating::EstimatingFetcher,
+    proving_from_start::ProvingFromStartFetcher,
+    proving_latest::ProvingLatestFetcher,
+    proving_queue::ProvingQueue,
+    reproducing_from_start::ReproducingFromStartFetcher,
+    rpc_fixture::{RpcFixture, RpcFixtureMode},
+    subblock_executor::{SubblockExecutor, connect_http},
+    witness_provider::RpcReplayWitnessProvider,
 };
-use common::channel::SingleUnboundedChannel;
-use messages::{BlockMsg, BlockMsgEndpoint, FetchMsg, FetchMsgSender};
-use std::sync::Arc;
-use tokio::{spawn, task::JoinHandle};
-use tracing::{error, info};
+use alloy_provider::{Provider, RootProvider};
+use common::{
+    channel::SingleUnboundedChannel,
+    readiness::ReadinessCheck,
+    storage::{open_input_storage, open_mapped_input_storage},
+    utils::TaskStarter,
+};
+use messages::{BlockMsg, BlockMsgEndpoint, ControlMsg, FetchMsg, FetchMsgSender};
+use std::{sync::Arc, time::Duration};
+use tokio::{spawn, task::JoinHandle, time::sleep};
+use tracing::{error, info, warn};
+
+// retry interval of checking the rpc connectivity (in seconds)
+const RPC_CHECK_INTERVAL_SECONDS: u64 = 2;
 
 // main block fetcher for dispatching different types of fetch messages
 pub struct BlockFetcher {
@@ -20,52 +37,196 @@ pub struct BlockFetcher {
     // sending fetch messages of `prove-latest` type to the specified fetcher
     proving_latest_msg_sender: Arc<FetchMsgSender>,
 
-    // sending fetch messages of `reproduce-from-start` type to the specified fetcher
+    // sending fetch messages of `reproduce-from-start`, `reproduce-latest` and `reproduce-by-list`
+    // types to the specified fetcher
     reproducing_from_start_msg_sender: Arc<FetchMsgSender>,
 
+    // sending fetch messages of `backfill` type to the specified fetcher
+    backfilling_msg_sender: Arc<FetchMsgSender>,
+
+    // sending fetch messages of `estimate` type to the specified fetcher
+    estimating_msg_sender: Arc<FetchMsgSender>,
+
+    // sub fetchers generating the inputs of the blocks fetched from the rpc node, none in the
+    // reproduce mode without the rpc node
+    rpc_fetchers: Option<RpcFetchers>,
+
+    // reproducing blocks by a start block number, the most recently dumped blocks or a list of
+    // block numbers
+    reproducing_from_start_fetcher: Arc<ReproducingFromStartFetcher>,
+
+    // queue of the blocks submitted to the proving-client for admission control
+    proving_queue: Arc<ProvingQueue>,
+
+    // removing the dumped input files by the retention policy; disabled if not specified
+    dump_retention: Option<Arc<DumpRetention>>,
+
+    // chain id of the fetched blocks
+    chain_id: u64,
+
+    // rpc provider for checking the connectivity before fetching, it's none if the rpc responses
+    // are replayed without a live node
+    rpc_provider: Option<RootProvider>,
+
+    // readiness check passed once the rpc node is connected
+    readiness: ReadinessCheck,
+}
+
+// sub fetchers generating the inputs of the blocks fetched from the rpc node
+struct RpcFetchers {
     // fetching blocks by a start block number and a count specified the number of blocks
     proving_from_start_fetcher: Arc<ProvingFromStartFetcher>,
 
     // fetching latest blocks by a count specified the number of blocks
     proving_latest_fetcher: Arc<ProvingLatestFetcher>,
 
-    // reproducing blocks by a start block number and a count specified the number of blocks
-    reproducing_from_start_fetcher: Arc<ReproducingFromStartFetcher>,
+    // backfilling a range of blocks with checkpointing
+    backfilling_fetcher: Arc<BackfillingFetcher>,
+
+    // estimating blocks in a dry-run mode without proving
+    estimating_fetcher: Arc<EstimatingFetcher>,
 }
 
 impl BlockFetcher {
-    pub fn new(config: Arc<BlockFetcherConfig>, comm_endpoint: Arc<BlockMsgEndpoint>) -> Arc<Self> {
-        // create the subblock executor
-        let subblock_executor = Arc::new(SubblockExecutor::new(config.clone()));
+    pub fn new(
+        config: Arc<BlockFetcherConfig>,
+        comm_endpoint: Arc<BlockMsgEndpoint>,
+        readiness: ReadinessCheck,
+    ) -> Arc<Self> {
+        // open the storages for dumping and loading input files, the loaded files are
+        // memory-mapped if enabled
+        let [input_dump_storage, input_load_storage] = [
+            (&config.input_dump_dir, false),
+            (&config.input_load_dir, config.input_load_mmap),
+        ]
+        .map(|(location, mmap)| {
+            location.as_ref().map(|location| {
+                let storage = if mmap {
+                    open_mapped_input_storage(location)
+                } else {
+                    open_input_storage(location)
+                };
+                storage.unwrap_or_else(|e| {
+                    panic!("fetcher: failed to open the input storage {location:?}: {e}")
+                })
+            })
+        });
 
-        // create channels for communication with the sub fetchers
+        // create the retention task of the dumped input files if any limit is specified
+        let dump_retention = input_dump_storage
+            .clone()
+            .filter(|_| config.dump_retention_policy.is_enabled())
+            .map(|storage| {
+                Arc::new(DumpRetention::new(
+                    config.dump_retention_policy.clone(),
+                    storage,
+                    config.input_layout.clone(),
+                ))
+            });
+
+        // open the rpc fixtures for recording or replaying the rpc responses
+        let rpc_fixture = config.rpc_fixture_mode.map(|mode| {
+            RpcFixture::new(mode, config.rpc_fixture_dir.clone()).unwrap_or_else(|e| {
+                panic!(
+                    "fetcher: failed to open the rpc fixtures {:?}: {e}",
+                    config.rpc_fixture_dir,
+                )
+            })
+        });
+
+        // create the rpc provider for checking the connectivity unless replaying the fixtures or
+        // reproducing without the rpc node
+        let rpc_provider = config
+            .rpc_http_url
+            .clone()
+            .filter(|_| config.rpc_fixture_mode != Some(RpcFixtureMode::Replay))
+            .map(|rpc_http_url| connect_http(rpc_fixture.as_ref(), rpc_http_url));
+
+        // create the proving queue shared by the sub fetchers for admission control
+        let proving_queue = Arc::new(ProvingQueue::new(
+            comm_endpoint.clone_sender(),
+            config.max_proving_queue_depth,
+            config.max_input_bytes,
+        ));
+
+        // create channels for communication with the sub fetchers, the latest fetcher keeps the
+        // channel for polling new requests while fetching
         let [
             (proving_from_start_msg_sender, proving_from_start_msg_receiver),
-            (proving_latest_msg_sender, proving_latest_msg_receiver),
             (reproducing_from_start_msg_sender, reproducing_from_start_msg_receiver),
-        ] = [0, 1, 2].map(|_| {
+            (backfilling_msg_sender, backfilling_msg_receiver),
+            (estimating_msg_sender, estimating_msg_receiver),
+        ] = [0, 1, 2, 3].map(|_| {
             let channel = SingleUnboundedChannel::default();
             (channel.sender(), channel.receiver())
         });
+        let proving_latest_msg_channel = SingleUnboundedChannel::default();
+        let proving_latest_msg_sender = proving_latest_msg_channel.sender();
 
-        // initialize sub fetchers
-        let proving_from_start_fetcher = ProvingFromStartFetcher::new(
-            proving_from_start_msg_receiver,
-            comm_endpoint.clone_sender(),
-            subblock_executor.clone(),
-        )
-        .into();
-        let proving_latest_fetcher = ProvingLatestFetcher::new(
-            config.clone(),
-            proving_latest_msg_receiver,
-            comm_endpoint.clone_sender(),
-            subblock_executor,
-        )
-        .into();
+        // initialize the sub fetchers of the rpc node, they're not created in the reproduce mode
+        // since the dumped inputs are loaded instead of generated
+        let rpc_fetchers = config
+            .rpc_http_url
+            .clone()
+            .zip(config.rpc_ws_url.clone())
+            .map(|(rpc_http_url, rpc_ws_url)| {
+                // create the witness provider replaying blocks via rpc
+                let witness_rpc_url = config
+                    .witness_rpc_url
+                    .clone()
+                    .unwrap_or_else(|| rpc_http_url.clone());
+                let witness_provider = Arc::new(RpcReplayWitnessProvider::new(
+                    witness_rpc_url.clone(),
+                    connect_http(rpc_fixture.as_ref(), witness_rpc_url),
+                ));
+
+                // create the subblock executor
+                let subblock_executor = Arc::new(SubblockExecutor::new(
+                    config.clone(),
+                    rpc_http_url,
+                    input_dump_storage,
+                    witness_provider,
+                    rpc_fixture,
+                ));
+
+                RpcFetchers {
+                    proving_from_start_fetcher: ProvingFromStartFetcher::new(
+                        config.clone(),
+                        proving_from_start_msg_receiver,
+                        proving_queue.clone(),
+                        subblock_executor.clone(),
+                    )
+                    .into(),
+                    proving_latest_fetcher: ProvingLatestFetcher::new(
+                        config.clone(),
+                        rpc_ws_url,
+                        proving_latest_msg_channel,
+                        proving_queue.clone(),
+                        subblock_executor.clone(),
+                    )
+                    .into(),
+                    backfilling_fetcher: BackfillingFetcher::new(
+                        config.clone(),
+                        backfilling_msg_receiver,
+                        proving_queue.clone(),
+                        subblock_executor.clone(),
+                    )
+                    .into(),
+                    estimating_fetcher: EstimatingFetcher::new(
+                        config.chain_id,
+                        estimating_msg_receiver,
+                        comm_endpoint.clone_sender(),
+                        subblock_executor,
+                    )
+                    .into(),
+                }
+            });
         let reproducing_from_start_fetcher = ReproducingFromStartFetcher::new(
-            config,
+            config.chain_id,
+            input_load_storage,
+            config.input_layout.clone(),
             reproducing_from_start_msg_receiver,
-            comm_endpoint.clone_sender(),
+            proving_queue.clone(),
         )
         .into();
 
@@ -74,31 +235,109 @@ impl BlockFetcher {
             proving_from_start_msg_sender,
             proving_latest_msg_sender,
             reproducing_from_start_msg_sender,
-            proving_from_start_fetcher,
-            proving_latest_fetcher,
+            rpc_fetchers,
             reproducing_from_start_fetcher,
+            backfilling_msg_sender,
+            estimating_msg_sender,
+            proving_queue,
+            dump_retention,
+            chain_id: config.chain_id,
+            rpc_provider,
+            readiness,
         }
         .into()
     }
 
     pub fn run(self: Arc<Self>) -> Vec<JoinHandle<()>> {
-        info!("fetcher: start");
+        self.tasks().iter().map(|(_, start)| start()).collect()
+    }
+
+    // return the named starters of the sub fetcher and main fetcher threads, they could be
+    // restarted by a supervisor since the channels are kept across restarts
+    pub fn tasks(self: Arc<Self>) -> Vec<(&'static str, TaskStarter)> {
+        let reproducing_from_start_fetcher = self.reproducing_from_start_fetcher.clone();
+
+        let mut tasks: Vec<(&'static str, TaskStarter)> = vec![(
+            "reproducing-from-start-fetcher",
+            Box::new(move || reproducing_from_start_fetcher.clone().run()),
+        )];
+        if let Some(rpc_fetchers) = &self.rpc_fetchers {
+            let proving_from_start_fetcher = rpc_fetchers.proving_from_start_fetcher.clone();
+            let proving_latest_fetcher = rpc_fetchers.proving_latest_fetcher.clone();
+            let backfilling_fetcher = rpc_fetchers.backfilling_fetcher.clone();
+            let estimating_fetcher = rpc_fetchers.estimating_fetcher.clone();
+            let rpc_tasks: [(&'static str, TaskStarter); 4] = [
+                (
+                    "proving-from-start-fetcher",
+                    Box::new(move || proving_from_start_fetcher.clone().run()),
+                ),
+                (
+                    "proving-latest-fetcher",
+                    Box::new(move || proving_latest_fetcher.clone().run()),
+                ),
+                (
+                    "backfilling-fetcher",
+                    Box::new(move || backfilling_fetcher.clone().run()),
+                ),
+                (
+                    "estimating-fetcher",
+                    Box::new(move || estimating_fetcher.clone().run()),
+                ),
+            ];
+            tasks.extend(rpc_tasks);
+        }
+        if let Some(dump_retention) = self.dump_retention.clone() {
+            tasks.push((
+                "dump-retention",
+                Box::new(move || dump_retention.clone().run()),
+            ));
+        }
+        tasks.push(("fetcher", Box::new(move || self.clone().run_main())));
+
+        tasks
+    }
 
-        // start the sub fetcher threads
-        let mut handles = vec![];
-        handles.push(self.proving_from_start_fetcher.clone().run());
-        handles.push(self.proving_latest_fetcher.clone().run());
-        handles.push(self.reproducing_from_start_fetcher.clone().run());
+    // start the main fetcher thread for dispatching the fetch messages to the sub fetchers
+    fn run_main(self: Arc<Self>) -> JoinHandle<()> {
+        info!("fetcher: start");
 
         let comm_endpoint = self.comm_endpoint.clone();
         let proving_from_start_msg_sender = self.proving_from_start_msg_sender.clone();
         let proving_latest_msg_sender = self.proving_latest_msg_sender.clone();
         let reproducing_from_start_msg_sender = self.reproducing_from_start_msg_sender.clone();
+        let backfilling_msg_sender = self.backfilling_msg_sender.clone();
+        let estimating_msg_sender = self.estimating_msg_sender.clone();
+        let proving_from_start_fetcher = self
+            .rpc_fetchers
+            .as_ref()
+            .map(|rpc_fetchers| rpc_fetchers.proving_from_start_fetcher.clone());
+        let backfilling_fetcher = self
+            .rpc_fetchers
+            .as_ref()
+            .map(|rpc_fetchers| rpc_fetchers.backfilling_fetcher.clone());
+        let proving_queue = self.proving_queue.clone();
+
+        spawn(async move {
+            // check the rpc connectivity before dispatching the fetch messages
+            self.wait_rpc_connected().await;
 
-        // start the main fetcher thread
-        handles.push(spawn(async move {
             while let Ok(msg) = comm_endpoint.recv().await {
                 match msg {
+                    // only the dumped inputs are reproduced in the reproduce mode, the other
+                    // requests are dropped since the blocks can't be fetched without the rpc node
+                    BlockMsg::Fetch(fetch_msg)
+                        if self.rpc_fetchers.is_none()
+                            && !matches!(
+                                fetch_msg,
+                                FetchMsg::ReproduceFromStart { .. }
+                                    | FetchMsg::ReproduceLatest { .. }
+                                    | FetchMsg::ReproduceByList { .. }
+                            ) =>
+                    {
+                        error!(
+                            "fetcher: dropped the fetch message {fetch_msg:?} since no rpc node is connected in the reproduce mode"
+                        );
+                    }
                     BlockMsg::Fetch(fetch_msg) => match fetch_msg {
                         FetchMsg::ProveFromStart { .. } => {
                             proving_from_start_msg_sender.send(fetch_msg).expect(
@@ -108,17 +347,85 @@ impl BlockFetcher {
                         FetchMsg::ProveLatest { .. } => proving_latest_msg_sender
                             .send(fetch_msg)
                             .expect("fetcher: failed to send a message to proving-latest-fetcher thread"),
-                        FetchMsg::ReproduceFromStart { .. } => {
+                        FetchMsg::ReproduceFromStart { .. }
+                        | FetchMsg::ReproduceLatest { .. }
+                        | FetchMsg::ReproduceByList { .. } => {
                             reproducing_from_start_msg_sender.send(fetch_msg).expect(
                                 "fetcher: failed to send a message to reproducing-from-start-fetcher thread",
                             )
                         }
+                        FetchMsg::Backfill { .. } => backfilling_msg_sender
+                            .send(fetch_msg)
+                            .expect("fetcher: failed to send a message to backfilling-fetcher thread"),
+                        FetchMsg::Estimate { .. } => estimating_msg_sender
+                            .send(fetch_msg)
+                            .expect("fetcher: failed to send a message to estimating-fetcher thread"),
                     },
+                    BlockMsg::Report(report) => {
+                        // the block report is the backpressure signal for prefetching blocks and
+                        // admitting new blocks
+                        if let Some(proving_from_start_fetcher) = &proving_from_start_fetcher {
+                            proving_from_start_fetcher.on_block_reported(report.block_number);
+                        }
+                        proving_queue.on_block_reported();
+
+                        // the backfill checkpoint is advanced by the reported blocks
+                        if let Some(backfilling_fetcher) = &backfilling_fetcher {
+                            backfilling_fetcher.on_block_reported(
+                                report.job_id,
+                                report.block_number,
+                                report.success,
+                            );
+                        }
+                    }
+                    BlockMsg::Error(error_msg) => {
+                        // a failed block may not be reported, its prefetch permit is released
+                        if let Some(proving_from_start_fetcher) = &proving_from_start_fetcher {
+                            proving_from_start_fetcher.on_block_reported(error_msg.block_number);
+                        }
+                        if let Some(backfilling_fetcher) = &backfilling_fetcher {
+                            backfilling_fetcher.on_block_reported(
+                                error_msg.job_id,
+                                error_msg.block_number,
+                                false,
+                            );
+                        }
+                    }
+                    BlockMsg::Control(control_msg) => {
+                        // a drain also stops fetching new blocks, the fetched blocks are completed
+                        // by the proving-client
+                        info!("fetcher: received a {control_msg} control message");
+                        proving_queue.set_paused(control_msg != ControlMsg::Resume);
+                    }
                     _ => error!("fetcher: received a wrong message {msg:?}"),
                 }
             }
-        }));
+        })
+    }
 
-        handles
+    // wait for the rpc node connected and serving the chain of the fetched blocks, then pass the
+    // readiness check
+    async fn wait_rpc_connected(&self) {
+        if let Some(rpc_provider) = &self.rpc_provider {
+            loop {
+                match rpc_provider.get_chain_id().await {
+                    Ok(chain_id) if chain_id == self.chain_id => break,
+                    Ok(chain_id) => error!(
+                        "fetcher: rpc node serves chain {chain_id} instead of {}",
+                        self.chain_id,
+                    ),
+                    Err(e) => warn!("fetcher: failed to connect the rpc node {e:?}"),
+                }
+                info!(
+                    "fetcher: retrying the rpc connectivity check in {RPC_CHECK_INTERVAL_SECONDS}s"
+                );
+                sleep(Duration::from_secs(RPC_CHECK_INTERVAL_SECONDS)).await;
+            }
+            info!(
+                "fetcher: connected to the rpc node of chain {}",
+                self.chain_id
+            );
+        }
+        self.readiness.pass();
     }
 }
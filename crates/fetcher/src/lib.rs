@@ -1,6 +1,14 @@
+pub mod backfilling;
+pub mod block_cache;
 pub mod config;
+pub mod dump_retention;
+pub mod estimating;
 pub mod fetcher;
 pub mod proving_from_start;
 pub mod proving_latest;
+pub mod proving_queue;
 pub mod reproducing_from_start;
+pub mod rpc_fixture;
+pub mod rpc_timer;
 pub mod subblock_executor;
+pub mod witness_provider;
@@ -1,27 +1,68 @@
-use crate::subblock_executor::SubblockExecutor;
+use crate::{
+    config::BlockFetcherConfig, proving_queue::ProvingQueue, subblock_executor::SubblockExecutor,
+};
 use anyhow::Result;
 use common::report::BlockProvingReport;
-use derive_more::Constructor;
-use messages::{BlockMsg, BlockMsgSender, FetchMsg, FetchMsgReceiver, ProvingMsg};
-use std::{sync::Arc, time::Instant};
-use tokio::{spawn, sync::Mutex, task::JoinHandle};
-use tracing::{error, info};
+use dashmap::DashMap;
+use futures::{StreamExt, stream};
+use messages::{BlockKey, FetchMsg, FetchMsgReceiver, JobId, ProvingMsg};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    spawn,
+    sync::{Mutex, OwnedSemaphorePermit, Semaphore},
+    task::JoinHandle,
+    time::timeout,
+};
+use tracing::{Instrument, error, info, warn};
+
+// interval of checking the expired prefetch permits while waiting for a permit
+const PREFETCH_PERMIT_CHECK_INTERVAL: Duration = Duration::from_secs(10);
 
 // sub block fetcher for fetching blocks by a start block number and a count specified requested
 // number of blocks
-#[derive(Constructor)]
 pub struct ProvingFromStartFetcher {
+    // fetcher configuration
+    config: Arc<BlockFetcherConfig>,
+
     // receiving fetch messages
     fetch_receiver: Arc<Mutex<FetchMsgReceiver>>,
 
-    // sending proving messages to the proving-client thread
-    proving_sender: Arc<BlockMsgSender>,
+    // queue for sending proving messages to the proving-client thread
+    proving_queue: Arc<ProvingQueue>,
 
     // executor for generating subblock and aggregation inputs
     subblock_executor: Arc<SubblockExecutor>,
+
+    // limiting the number of prefetched blocks which are not reported by the proving-client
+    prefetch_semaphore: Arc<Semaphore>,
+
+    // holding the prefetch permits of the blocks in progress with the time of acquiring, a permit
+    // is released when the block is reported or failed, or expired by the prefetch permit timeout
+    prefetch_permits: DashMap<u64, (OwnedSemaphorePermit, Instant)>,
 }
 
 impl ProvingFromStartFetcher {
+    pub fn new(
+        config: Arc<BlockFetcherConfig>,
+        fetch_receiver: Arc<Mutex<FetchMsgReceiver>>,
+        proving_queue: Arc<ProvingQueue>,
+        subblock_executor: Arc<SubblockExecutor>,
+    ) -> Self {
+        let prefetch_semaphore = Arc::new(Semaphore::new(config.prefetch_block_count.max(1)));
+
+        Self {
+            config,
+            fetch_receiver,
+            proving_queue,
+            subblock_executor,
+            prefetch_semaphore,
+            prefetch_permits: DashMap::new(),
+        }
+    }
+
     pub fn run(self: Arc<Self>) -> JoinHandle<()> {
         info!("proving-from-start-fetcher: start");
 
@@ -30,24 +71,41 @@ impl ProvingFromStartFetcher {
             while let Some(msg) = fetch_receiver.recv().await {
                 match msg {
                     FetchMsg::ProveFromStart {
+                        job_id,
                         start_block_number,
                         count,
+                        ..
                     } => {
                         info!(
-                            "proving-from-start-fetcher: received from-start fetch message of start_block_number = {start_block_number}, count = {count}",
+                            "proving-from-start-fetcher: received from-start fetch message of job_id = {job_id}, start_block_number = {start_block_number}, count = {count}",
                         );
-                        for block_number in start_block_number..start_block_number + count {
-                            info!(
-                                "proving-from-start-fetcher: starting for fetching block {block_number}"
-                            );
-                            if let Err(e) = self.fetch_block(block_number).await {
-                                error!(
-                                    "proving-from-start-fetcher: failed to fetch block-{block_number} {e:?}",
-                                );
+
+                        // prepare the inputs of next blocks ahead, the results are returned in
+                        // order of block number
+                        let fetcher = &self;
+                        let prefetch_block_count = self.config.prefetch_block_count.max(1);
+                        let mut fetched_blocks =
+                            stream::iter(start_block_number..start_block_number + count)
+                                .map(|block_number| async move {
+                                    (
+                                        block_number,
+                                        fetcher.fetch_block(job_id, block_number).await,
+                                    )
+                                })
+                                .buffered(prefetch_block_count);
+
+                        while let Some((block_number, result)) = fetched_blocks.next().await {
+                            match result.and_then(|msg| self.proving_queue.send(msg)) {
+                                Ok(()) => info!(
+                                    "proving-from-start-fetcher: succeeded for fetching block {block_number}",
+                                ),
+                                Err(e) => {
+                                    error!(
+                                        "proving-from-start-fetcher: failed to fetch block-{block_number} {e:?}",
+                                    );
+                                    self.on_block_reported(block_number);
+                                }
                             }
-                            info!(
-                                "proving-from-start-fetcher: succeeded for fetching block {block_number}",
-                            );
                         }
                     }
                     _ => error!("proving-from-start-fetcher: received a wrong message {msg:?}"),
@@ -56,20 +114,78 @@ impl ProvingFromStartFetcher {
         })
     }
 
-    // fetch a specified block by number
-    async fn fetch_block(&self, block_number: u64) -> Result<()> {
-        // generate proving inputs of the specified block number
-        let start_time = Instant::now();
-        let proving_inputs = self.subblock_executor.generate_inputs(block_number).await?;
-        let data_fetch_milliseconds = start_time.elapsed().as_millis() as u64;
+    // release the prefetch permit of a block after the proving-client reports it, or any stage
+    // fails it
+    pub fn on_block_reported(&self, block_number: u64) {
+        if self.prefetch_permits.remove(&block_number).is_some() {
+            info!(
+                "proving-from-start-fetcher: released the prefetch permit of block {block_number}"
+            );
+        }
+    }
+
+    // wait for a prefetch permit, the permits held over the timeout are released while waiting
+    // since their blocks may never be reported
+    async fn acquire_prefetch_permit(&self) -> Result<OwnedSemaphorePermit> {
+        loop {
+            let permit = self.prefetch_semaphore.clone().acquire_owned();
+            match timeout(PREFETCH_PERMIT_CHECK_INTERVAL, permit).await {
+                Ok(permit) => return Ok(permit?),
+                Err(_) => self.release_expired_permits(),
+            }
+        }
+    }
+
+    // release the prefetch permits held over the prefetch permit timeout
+    fn release_expired_permits(&self) {
+        let permit_timeout = self.config.prefetch_permit_timeout;
+        self.prefetch_permits.retain(|block_number, (_, acquired_at)| {
+            let is_expired = acquired_at.elapsed() >= permit_timeout;
+            if is_expired {
+                warn!(
+                    "proving-from-start-fetcher: released the expired prefetch permit of block {block_number} not reported in {} s",
+                    permit_timeout.as_secs(),
+                );
+            }
+            !is_expired
+        });
+    }
+
+    // fetch a specified block by number and return the proving message
+    async fn fetch_block(&self, job_id: JobId, block_number: u64) -> Result<ProvingMsg> {
+        let mut fetch_report =
+            BlockProvingReport::new(job_id, BlockKey::new(self.config.chain_id, block_number));
+        let span = fetch_report.span();
+
+        async move {
+            // wait until the number of blocks ahead of the proving-client is below the limit
+            let permit = self.acquire_prefetch_permit().await?;
+
+            // wait until the proving backlog is below the limit
+            self.proving_queue.wait_for_admission(&fetch_report).await;
+            info!("proving-from-start-fetcher: starting for fetching block {block_number}");
 
-        // create a block report
-        let fetch_report = BlockProvingReport::new(block_number, data_fetch_milliseconds);
+            // generate proving inputs of the specified block number
+            let start_time = Instant::now();
+            let proving_inputs = match self
+                .subblock_executor
+                .generate_inputs(block_number, &mut fetch_report)
+                .await
+            {
+                Ok(proving_inputs) => proving_inputs,
+                Err(e) => return Err(self.proving_queue.send_failure(fetch_report, e)),
+            };
+            fetch_report.on_data_fetched(start_time.elapsed().as_millis() as u64);
 
-        // send the proving message
-        let msg = BlockMsg::Proving(ProvingMsg::new(fetch_report, proving_inputs));
-        self.proving_sender.send(msg)?;
+            // hold the permit until the block is reported, the permit of the same block fetched
+            // again is kept and the new one is released
+            self.prefetch_permits
+                .entry(block_number)
+                .or_insert((permit, Instant::now()));
 
-        Ok(())
+            Ok(ProvingMsg::new(fetch_report, proving_inputs))
+        }
+        .instrument(span)
+        .await
     }
 }
@@ -1,20 +1,26 @@
-use crate::{config::BlockFetcherConfig, subblock_executor::SubblockExecutor};
+use crate::{
+    config::BlockFetcherConfig, proving_queue::ProvingQueue, subblock_executor::SubblockExecutor,
+};
 use alloy_provider::{Provider, ProviderBuilder, WsConnect};
 use anyhow::Result;
-use common::report::BlockProvingReport;
+use common::{channel::SingleUnboundedChannel, report::BlockProvingReport};
 use derive_more::Constructor;
 use futures::StreamExt;
-use messages::{BlockMsg, BlockMsgSender, FetchMsg, FetchMsgReceiver, ProvingMsg};
+use messages::{BlockKey, FetchMsg, JobId, ProvingMsg};
+use reqwest::Url;
 use std::{sync::Arc, time::Instant};
 use tokio::{
     spawn,
-    sync::{Mutex, mpsc::error::TryRecvError},
     task::JoinHandle,
+    time::{Duration, sleep},
 };
-use tracing::{error, info};
+use tracing::{Instrument, error, info, warn};
+
+// initial backoff for reconnecting to the rpc websocket (in milliseconds)
+const RPC_WS_INITIAL_RECONNECT_BACKOFF_MILLISECONDS: u64 = 500;
 
-// maximum fetch number of blocks in each batch
-const NUM_BLOCKS_PER_BATCH: usize = 10;
+// maximum backoff for reconnecting to the rpc websocket (in seconds)
+const RPC_WS_MAX_RECONNECT_BACKOFF_SECONDS: u64 = 30;
 
 // sub block fetcher for fetching the latest blocks by a count specified requested number of blocks
 #[derive(Constructor)]
@@ -22,11 +28,14 @@ pub struct ProvingLatestFetcher {
     // fetcher configuration
     config: Arc<BlockFetcherConfig>,
 
-    // receiving fetch messages
-    fetch_receiver: Arc<Mutex<FetchMsgReceiver>>,
+    // websocket url of the rpc node for subscribing the latest blocks
+    rpc_ws_url: Url,
+
+    // channel of the fetch messages, it's polled without waiting while fetching the blocks
+    fetch_channel: SingleUnboundedChannel<FetchMsg>,
 
-    // sending proving messages to the proving-client thread
-    proving_sender: Arc<BlockMsgSender>,
+    // queue for sending proving messages to the proving-client thread
+    proving_queue: Arc<ProvingQueue>,
 
     // executor for generating subblock and aggregation inputs
     subblock_executor: Arc<SubblockExecutor>,
@@ -37,22 +46,40 @@ impl ProvingLatestFetcher {
         info!("proving-latest-fetcher: start");
 
         spawn(async move {
-            let mut fetch_receiver = self.fetch_receiver.lock().await;
+            // save the total remaining number of latest blocks, it's kept across batches and
+            // websocket reconnections
+            let mut remaining_count = 0;
+
+            // save the job id of the most recent latest request, the remaining blocks are
+            // reported under it
+            let mut job_id = JobId::UNKNOWN;
+
+            // save the last fetched block number for catching up the blocks missed during a
+            // websocket outage
+            let mut last_block_number: Option<u64> = None;
+
             loop {
                 // save the processed fetch number in the current batch
                 let mut batch_fetch_count = 0;
 
-                // save the total remaining number of latest blocks
-                let mut remaining_count = 0;
-
                 // handle latest block fetch message and update remaining count if necessary
                 let new_count = if remaining_count == 0 {
+                    // the previous request is completed, no blocks need to be caught up
+                    last_block_number = None;
+
                     info!(
                         "proving-latest-fetcher: waiting for a request fetch number for the latest blocks",
                     );
 
-                    match fetch_receiver.recv().await {
-                        Some(FetchMsg::ProveLatest { count }) => count,
+                    match self.fetch_channel.recv().await {
+                        Ok(FetchMsg::ProveLatest {
+                            job_id: new_job_id,
+                            count,
+                            ..
+                        }) => {
+                            job_id = new_job_id;
+                            count
+                        }
                         msg => {
                             error!(
                                 "proving-latest-fetcher: fetch receiver received an unexpected message {msg:?}",
@@ -64,9 +91,16 @@ impl ProvingLatestFetcher {
                     info!(
                         "proving-latest-fetcher: try to receive a new fetch number for the latest blocks",
                     );
-                    match fetch_receiver.try_recv() {
-                        Ok(FetchMsg::ProveLatest { count }) => count,
-                        Err(TryRecvError::Empty) => {
+                    match self.fetch_channel.try_recv() {
+                        Ok(Some(FetchMsg::ProveLatest {
+                            job_id: new_job_id,
+                            count,
+                            ..
+                        })) => {
+                            job_id = new_job_id;
+                            count
+                        }
+                        Ok(None) => {
                             // received no message and return the same remaining count
                             remaining_count
                         }
@@ -82,7 +116,7 @@ impl ProvingLatestFetcher {
                 // set the remaining count to the maximum value compared with new request
                 remaining_count = remaining_count.max(new_count);
                 info!(
-                    "proving-latest-fetcher: received latest fetch message of count {new_count} and update remaining count to {remaining_count}",
+                    "proving-latest-fetcher: received latest fetch message of job_id = {job_id}, count {new_count} and update remaining count to {remaining_count}",
                 );
 
                 if remaining_count == 0 {
@@ -90,59 +124,134 @@ impl ProvingLatestFetcher {
                     continue;
                 }
 
-                // initialize a websocket rpc connection for receiving latest blocks
-                let ws_conn = WsConnect::new(self.config.rpc_ws_url.as_str());
-                let provider = ProviderBuilder::new()
-                    .connect_ws(ws_conn)
-                    .await
-                    .expect("proving-latest-fetcher: failed to connect to rpc websocket URL");
-                let subscription = provider
-                    .subscribe_blocks()
-                    .await
-                    .expect("proving-latest-fetcher: failed to subscribe the latest blocks");
+                // initialize a websocket rpc connection for receiving latest blocks, reconnect
+                // with exponential backoff if the connection or subscription fails
+                let mut reconnect_backoff =
+                    Duration::from_millis(RPC_WS_INITIAL_RECONNECT_BACKOFF_MILLISECONDS);
+                let (_provider, subscription) = loop {
+                    let ws_conn = WsConnect::new(self.rpc_ws_url.as_str());
+                    let result = async {
+                        let provider = ProviderBuilder::new().connect_ws(ws_conn).await?;
+                        let subscription = provider.subscribe_blocks().await?;
+                        anyhow::Ok((provider, subscription))
+                    }
+                    .await;
+
+                    match result {
+                        Ok(result) => break result,
+                        Err(e) => {
+                            warn!(
+                                "proving-latest-fetcher: failed to subscribe the latest blocks {e:?}, reconnecting in {reconnect_backoff:?}",
+                            );
+                            sleep(reconnect_backoff).await;
+                            reconnect_backoff = (reconnect_backoff * 2)
+                                .min(Duration::from_secs(RPC_WS_MAX_RECONNECT_BACKOFF_SECONDS));
+                        }
+                    }
+                };
                 let mut latest_block_receiver = subscription.into_stream();
 
                 // handle the new block notification from the websocket rpc
                 while let Some(header) = latest_block_receiver.next().await {
-                    let block_number = header.number;
+                    let head_block_number = header.number;
                     info!(
-                        "proving-latest-fetcher: rpc websocket connection received a new block {block_number}",
+                        "proving-latest-fetcher: rpc websocket connection received a new block {head_block_number}",
                     );
 
-                    if let Err(e) = self.fetch_block(block_number).await {
-                        error!(
-                            "proving-latest-fetcher: failed to fetch block-{block_number} {e:?}",
+                    // only prove the blocks with enough confirmations behind the head
+                    let Some(latest_block_number) =
+                        head_block_number.checked_sub(self.config.confirmations)
+                    else {
+                        continue;
+                    };
+
+                    // catch up the blocks missed since the last fetched block
+                    let start_block_number = match last_block_number {
+                        Some(last) if latest_block_number <= last => {
+                            info!(
+                                "proving-latest-fetcher: skip block {latest_block_number} since block {last} is already fetched",
+                            );
+                            continue;
+                        }
+                        Some(last) => last + 1,
+                        None => latest_block_number,
+                    };
+
+                    for block_number in start_block_number..=latest_block_number {
+                        if block_number < latest_block_number {
+                            info!(
+                                "proving-latest-fetcher: catching up the missed block {block_number}",
+                            );
+                        }
+
+                        if let Err(e) = self.fetch_block(job_id, block_number).await {
+                            error!(
+                                "proving-latest-fetcher: failed to fetch block-{block_number} {e:?}",
+                            );
+                        }
+                        info!(
+                            "proving-latest-fetcher: succeeded for fetching block {block_number}"
                         );
-                    }
-                    info!("proving-latest-fetcher: succeeded for fetching block {block_number}");
 
-                    batch_fetch_count += 1;
-                    remaining_count -= 1;
+                        last_block_number = Some(block_number);
+                        batch_fetch_count += 1;
+                        remaining_count -= 1;
+
+                        if remaining_count == 0 {
+                            break;
+                        }
+                    }
 
                     // exit the current fetching batch if no remaining blocks or reaching the
                     // maximum number of blocks per batch
-                    if remaining_count == 0 || batch_fetch_count >= NUM_BLOCKS_PER_BATCH {
+                    if remaining_count == 0 || self.is_batch_full(batch_fetch_count) {
                         break;
                     }
                 }
+
+                if remaining_count > 0 && !self.is_batch_full(batch_fetch_count) {
+                    warn!(
+                        "proving-latest-fetcher: rpc websocket subscription ended with remaining count {remaining_count}, resubscribing",
+                    );
+                }
             }
         })
     }
 
+    // identify if the current batch reaches the maximum number of blocks, it's never full if
+    // batching is disabled
+    fn is_batch_full(&self, batch_fetch_count: usize) -> bool {
+        let max_blocks_per_batch = self.config.max_blocks_per_batch;
+        max_blocks_per_batch > 0 && batch_fetch_count >= max_blocks_per_batch
+    }
+
     // fetch a specified block by number
-    async fn fetch_block(&self, block_number: u64) -> Result<()> {
-        // generate proving inputs of the specified block number
-        let start_time = Instant::now();
-        let proving_inputs = self.subblock_executor.generate_inputs(block_number).await?;
-        let data_fetch_milliseconds = start_time.elapsed().as_millis() as u64;
+    async fn fetch_block(&self, job_id: JobId, block_number: u64) -> Result<()> {
+        let mut fetch_report =
+            BlockProvingReport::new(job_id, BlockKey::new(self.config.chain_id, block_number));
+        let span = fetch_report.span();
 
-        // create a block report
-        let fetch_report = BlockProvingReport::new(block_number, data_fetch_milliseconds);
+        async move {
+            // wait until the proving backlog is below the limit
+            self.proving_queue.wait_for_admission(&fetch_report).await;
 
-        // send the proving message
-        let msg = BlockMsg::Proving(ProvingMsg::new(fetch_report, proving_inputs));
-        self.proving_sender.send(msg)?;
+            // generate proving inputs of the specified block number
+            let start_time = Instant::now();
+            let proving_inputs = match self
+                .subblock_executor
+                .generate_inputs(block_number, &mut fetch_report)
+                .await
+            {
+                Ok(proving_inputs) => proving_inputs,
+                Err(e) => return Err(self.proving_queue.send_failure(fetch_report, e)),
+            };
+            fetch_report.on_data_fetched(start_time.elapsed().as_millis() as u64);
 
-        Ok(())
+            // send the proving message
+            self.proving_queue
+                .send(ProvingMsg::new(fetch_report, proving_inputs))
+        }
+        .instrument(span)
+        .await
     }
 }
@@ -0,0 +1,242 @@
+use anyhow::{Error, Result, bail};
+use common::report::{BlockProvingReport, FailureStage};
+use messages::{BlockMsg, BlockMsgSender, BlockStage, ErrorMsg, ProvingMsg, StatusMsg};
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+// queue of the proving messages sent to the proving-client, it tracks the number of blocks which
+// are submitted but not reported yet, and delays the admission of new blocks when the backlog
+// exceeds the limit; the blocks whose inputs exceed the size limit are rejected with a failure
+// report instead of failing inside the grpc requests; the admission is also stopped while the
+// pipeline is paused or drained by a control message; the stage transitions of the blocks are
+// sent as status messages for the websocket watchers, and the failures are sent as error messages
+pub struct ProvingQueue {
+    // sending proving messages to the proving-client thread
+    proving_sender: Arc<BlockMsgSender>,
+
+    // maximum number of blocks submitted but not reported; admission control is disabled if it's
+    // zero
+    max_queue_depth: usize,
+
+    // maximum bytes of each serialized subblock or aggregator input
+    max_input_bytes: usize,
+
+    // current number of blocks submitted but not reported
+    queue_depth: AtomicUsize,
+
+    // identify if the admission of new blocks is stopped by a pause or drain
+    paused: AtomicBool,
+
+    // notifying the waiting fetchers when a block is reported or the pipeline is resumed
+    reported_notify: Notify,
+}
+
+impl ProvingQueue {
+    pub fn new(
+        proving_sender: Arc<BlockMsgSender>,
+        max_queue_depth: usize,
+        max_input_bytes: usize,
+    ) -> Self {
+        Self {
+            proving_sender,
+            max_queue_depth,
+            max_input_bytes,
+            queue_depth: AtomicUsize::new(0),
+            paused: AtomicBool::new(false),
+            reported_notify: Notify::new(),
+        }
+    }
+
+    // wait until the pipeline is not paused and the queue depth is below the limit before
+    // generating inputs of a new block, the queue slot of the block is reserved once admitted and
+    // released when the block is reported or failed before dispatch
+    pub async fn wait_for_admission(&self, report: &BlockProvingReport) {
+        let block_number = report.block_number;
+        self.send_status(report, BlockStage::Queued);
+        loop {
+            // register for the notification before checking the state to avoid missing a wakeup
+            let reported = self.reported_notify.notified();
+
+            if self.is_paused() {
+                info!("proving-queue: delaying block {block_number} since the pipeline is paused");
+                reported.await;
+                continue;
+            }
+
+            // reserve the slot atomically, so the concurrent fetchers can't pass the check
+            // together and exceed the limit
+            let reserved =
+                self.queue_depth
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |depth| {
+                        (self.max_queue_depth == 0 || depth < self.max_queue_depth)
+                            .then_some(depth + 1)
+                    });
+            let Err(queue_depth) = reserved else {
+                self.send_status(report, BlockStage::Fetching);
+                return;
+            };
+
+            info!(
+                "proving-queue: delaying block {block_number} since the queue depth {queue_depth} reaches the limit {}",
+                self.max_queue_depth,
+            );
+            reported.await;
+        }
+    }
+
+    // stop or continue admitting new blocks
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::SeqCst);
+        if !paused {
+            self.reported_notify.notify_waiters();
+        }
+    }
+
+    // identify if the admission of new blocks is stopped
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    // send a proving message of an admitted block to the proving-client, a failure report is sent
+    // to the reporter instead if the inputs exceed the size limit, and the queue slot of the block
+    // is released since it won't be reported by the proving-client
+    pub fn send(&self, mut proving_msg: ProvingMsg) -> Result<()> {
+        let report = &mut proving_msg.fetch_report;
+        let block_number = report.block_number;
+
+        // measure the serialized input sizes
+        report.on_inputs_measured(&proving_msg.proving_inputs);
+        self.send_status(report, BlockStage::InputsReady);
+        info!(
+            "proving-queue: block {block_number} has public values {} bytes, aggregator input {} bytes and subblock inputs {:?} bytes",
+            report.public_values_bytes, report.agg_input_bytes, report.subblock_input_bytes,
+        );
+
+        // reject the block if any input exceeds the size limit
+        let max_input_bytes = self.max_input_bytes as u64;
+        let oversized_input = report
+            .subblock_input_bytes
+            .iter()
+            .enumerate()
+            .map(|(i, bytes)| (format!("subblock input {i}"), *bytes))
+            .chain([("aggregator input".to_string(), report.agg_input_bytes)])
+            .find(|(_, bytes)| *bytes > max_input_bytes);
+        if let Some((input_name, bytes)) = oversized_input {
+            let failure_reason = format!(
+                "{input_name} of {bytes} bytes exceeds the limit of {max_input_bytes} bytes"
+            );
+            report.on_rejected(failure_reason.clone());
+            self.release_slot();
+            self.send_status(report, BlockStage::Failed);
+            self.send_error(
+                report,
+                BlockStage::InputsReady,
+                failure_reason.clone(),
+                false,
+            );
+            self.proving_sender
+                .send(BlockMsg::Report(proving_msg.fetch_report))?;
+
+            bail!("rejected block {block_number} since {failure_reason}");
+        }
+
+        // the dispatched status is sent ahead of the proving message by the same channel, so it's
+        // routed before the proving status of the proving-client in the block lifecycle
+        self.send_status(report, BlockStage::Dispatched);
+        if let Err(e) = self.proving_sender.send(BlockMsg::Proving(proving_msg)) {
+            self.release_slot();
+            return Err(e.into());
+        }
+
+        Ok(())
+    }
+
+    // send a failure report of a block failed before proving to the reporter and return the
+    // error, so the clients waiting for a number of blocks always receive a report per block; a
+    // fetching failure is retryable since it's usually caused by the RPC node, but a rejection in
+    // validation is not; the queue slot of the admitted block is released
+    pub fn send_failure(&self, mut report: BlockProvingReport, e: Error) -> Error {
+        self.release_slot();
+
+        // keep the failure stage if the block is already rejected in validation
+        if report.failure_stage.is_none() {
+            report.on_fetching_failure(format!("{e:#}"));
+        }
+        self.send_status(&report, BlockStage::Failed);
+        let (stage, retryable) = match report.failure_stage {
+            Some(FailureStage::Validation) => (BlockStage::InputsReady, false),
+            _ => (BlockStage::Fetching, true),
+        };
+        self.send_error(&report, stage, format!("{e:#}"), retryable);
+        if let Err(send_err) = self.proving_sender.send(BlockMsg::Report(report)) {
+            warn!("proving-queue: failed to send the failure report {send_err:?}");
+        }
+
+        e
+    }
+
+    // send a status message of a block entering the stage to the reporter
+    pub fn send_status(&self, report: &BlockProvingReport, stage: BlockStage) {
+        self.send_status_msg(StatusMsg::new(
+            report.job_id,
+            report.block_number,
+            stage,
+            report.trace_id,
+        ));
+    }
+
+    // send a status message to the reporter
+    fn send_status_msg(&self, status_msg: StatusMsg) {
+        let stage = status_msg.stage;
+        let block_number = status_msg.block_number;
+        if let Err(e) = self.proving_sender.send(BlockMsg::Status(status_msg)) {
+            warn!("proving-queue: failed to send the {stage} status of block {block_number} {e:?}");
+        }
+    }
+
+    // send an error message of a block failed in the stage to the scheduler
+    fn send_error(
+        &self,
+        report: &BlockProvingReport,
+        stage: BlockStage,
+        error: String,
+        retryable: bool,
+    ) {
+        let block_number = report.block_number;
+        let error_msg = ErrorMsg::new(
+            report.job_id,
+            block_number,
+            stage,
+            error,
+            retryable,
+            report.trace_id,
+        );
+        if let Err(e) = self.proving_sender.send(BlockMsg::Error(error_msg)) {
+            warn!("proving-queue: failed to send the error of block {block_number} {e:?}");
+        }
+    }
+
+    // decrease the queue depth after the proving-client reports a block
+    pub fn on_block_reported(&self) {
+        self.release_slot();
+    }
+
+    // release the queue slot of a block and wake up the waiting fetchers
+    fn release_slot(&self) {
+        let _ = self
+            .queue_depth
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |depth| {
+                Some(depth.saturating_sub(1))
+            });
+        self.reported_notify.notify_waiters();
+    }
+
+    // return the current number of blocks submitted but not reported
+    pub fn queue_depth(&self) -> usize {
+        self.queue_depth.load(Ordering::SeqCst)
+    }
+}
@@ -1,24 +1,34 @@
-use crate::config::BlockFetcherConfig;
+use crate::proving_queue::ProvingQueue;
 use anyhow::Result;
-use common::{inputs::ProvingInputs, report::BlockProvingReport};
+use common::{
+    inputs::{InputLayout, ProvingInputs, list_dumped_block_numbers},
+    report::BlockProvingReport,
+    storage::InputStorage,
+};
 use derive_more::Constructor;
-use messages::{BlockMsg, BlockMsgSender, FetchMsg, FetchMsgReceiver, ProvingMsg};
+use messages::{BlockKey, FetchMsg, FetchMsgReceiver, JobId, ProvingMsg};
 use std::{sync::Arc, time::Instant};
 use tokio::{spawn, sync::Mutex, task::JoinHandle};
-use tracing::{error, info};
+use tracing::{Instrument, error, info};
 
 // sub block fetcher for reproducing blocks by a start block number and a count specified requested
-// number of blocks
+// number of blocks, by the most recently dumped blocks, or by a list of block numbers
 #[derive(Constructor)]
 pub struct ReproducingFromStartFetcher {
-    // fetcher configuration
-    config: Arc<BlockFetcherConfig>,
+    // chain id of the reproduced blocks
+    chain_id: u64,
+
+    // storage for reproducing blocks by loading input files
+    input_load_storage: Option<Arc<dyn InputStorage>>,
+
+    // layout of the loaded input files
+    input_layout: InputLayout,
 
     // receiving fetch messages
     fetch_receiver: Arc<Mutex<FetchMsgReceiver>>,
 
-    // sending proving messages to the proving-client thread
-    proving_sender: Arc<BlockMsgSender>,
+    // queue for sending proving messages to the proving-client thread
+    proving_queue: Arc<ProvingQueue>,
 }
 
 impl ReproducingFromStartFetcher {
@@ -28,53 +38,101 @@ impl ReproducingFromStartFetcher {
         spawn(async move {
             let mut fetch_receiver = self.fetch_receiver.lock().await;
             while let Some(msg) = fetch_receiver.recv().await {
-                match msg {
+                info!("reproducing-from-start-fetcher: received reproduce fetch message {msg:?}");
+
+                // collect the block numbers to reproduce
+                let (job_id, block_numbers) = match msg {
                     FetchMsg::ReproduceFromStart {
+                        job_id,
                         start_block_number,
                         count,
-                    } => {
-                        info!(
-                            "reproducing-from-start-fetcher: received from-start fetch message of start_block_number = {start_block_number}, count = {count}",
-                        );
-                        for block_number in start_block_number..start_block_number + count {
-                            info!(
-                                "reproducing-from-start-fetcher: starting for fetching block {block_number}"
-                            );
-                            match self.load_block(block_number) {
-                                Ok(()) => info!(
-                                    "reproducing-from-start-fetcher: succeeded for fetching block {block_number}",
-                                ),
-                                Err(e) => error!(
-                                    "reproducing-from-start-fetcher: failed to fetch block-{block_number} {e:?}",
-                                ),
+                        ..
+                    } => (
+                        job_id,
+                        (start_block_number..start_block_number + count).collect(),
+                    ),
+                    FetchMsg::ReproduceLatest { job_id, count, .. } => {
+                        match self.latest_block_numbers(count).await {
+                            Ok(block_numbers) => (job_id, block_numbers),
+                            Err(e) => {
+                                error!(
+                                    "reproducing-from-start-fetcher: failed to list the dumped blocks {e:?}",
+                                );
+                                continue;
                             }
                         }
                     }
-                    _ => error!("reproducing-from-start-fetcher: received a wrong message {msg:?}"),
+                    FetchMsg::ReproduceByList {
+                        job_id,
+                        block_numbers,
+                        ..
+                    } => (job_id, block_numbers),
+                    _ => {
+                        error!("reproducing-from-start-fetcher: received a wrong message {msg:?}");
+                        continue;
+                    }
+                };
+
+                for block_number in block_numbers {
+                    info!(
+                        "reproducing-from-start-fetcher: starting for fetching block {block_number}"
+                    );
+                    match self.load_block(job_id, block_number).await {
+                        Ok(()) => info!(
+                            "reproducing-from-start-fetcher: succeeded for fetching block {block_number}",
+                        ),
+                        Err(e) => error!(
+                            "reproducing-from-start-fetcher: failed to fetch block-{block_number} {e:?}",
+                        ),
+                    }
                 }
             }
         })
     }
 
+    // return the block numbers of the most recently dumped blocks by ascending order
+    async fn latest_block_numbers(&self, count: u64) -> Result<Vec<u64>> {
+        let mut block_numbers = list_dumped_block_numbers(self.input_load_storage()).await?;
+        let skipped = block_numbers.len().saturating_sub(count as usize);
+
+        Ok(block_numbers.split_off(skipped))
+    }
+
     // load a specified block by number
-    fn load_block(&self, block_number: u64) -> Result<()> {
-        // generate proving inputs of the specified block number
-        let input_load_dir = self
-            .config
-            .input_load_dir
-            .as_ref()
-            .expect("reproducing-from-start-fetcher: `input_load_dir` in unset");
-        let start_time = Instant::now();
-        let proving_inputs = ProvingInputs::load_from_dir(block_number, input_load_dir)?;
-        let data_fetch_milliseconds = start_time.elapsed().as_millis() as u64;
-
-        // create a block report
-        let fetch_report = BlockProvingReport::new(block_number, data_fetch_milliseconds);
-
-        // send the proving message
-        let msg = BlockMsg::Proving(ProvingMsg::new(fetch_report, proving_inputs));
-        self.proving_sender.send(msg)?;
-
-        Ok(())
+    async fn load_block(&self, job_id: JobId, block_number: u64) -> Result<()> {
+        let mut fetch_report =
+            BlockProvingReport::new(job_id, BlockKey::new(self.chain_id, block_number));
+        let span = fetch_report.span();
+
+        async move {
+            // wait until the proving backlog is below the limit
+            self.proving_queue.wait_for_admission(&fetch_report).await;
+
+            // generate proving inputs of the specified block number
+            let start_time = Instant::now();
+            let proving_inputs = match ProvingInputs::load_from_storage(
+                block_number,
+                self.input_load_storage(),
+                &self.input_layout,
+            )
+            .await
+            {
+                Ok(proving_inputs) => proving_inputs,
+                Err(e) => return Err(self.proving_queue.send_failure(fetch_report, e)),
+            };
+            fetch_report.on_data_fetched(start_time.elapsed().as_millis() as u64);
+
+            // send the proving message
+            self.proving_queue
+                .send(ProvingMsg::new(fetch_report, proving_inputs))
+        }
+        .instrument(span)
+        .await
+    }
+
+    fn input_load_storage(&self) -> &dyn InputStorage {
+        self.input_load_storage
+            .as_deref()
+            .expect("reproducing-from-start-fetcher: `input_load_dir` in unset")
     }
 }
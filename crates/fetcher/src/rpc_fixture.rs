@@ -0,0 +1,329 @@
+use crate::rpc_timer::RpcTimerLayer;
+use alloy_json_rpc::{RequestPacket, Response, ResponsePacket, SerializedRequest};
+use alloy_provider::RootProvider;
+use alloy_rpc_client::{ClientBuilder, RpcClient};
+use alloy_transport::{TransportError, TransportErrorKind, TransportFut};
+use anyhow::{Result, bail};
+use reqwest::Url;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+use tracing::info;
+
+// mode of the rpc fixtures
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RpcFixtureMode {
+    // forward the rpc requests to the node and capture the responses into the fixture files
+    Record,
+
+    // serve the rpc requests from the fixture files without a live node
+    Replay,
+}
+
+impl FromStr for RpcFixtureMode {
+    type Err = anyhow::Error;
+
+    fn from_str(mode: &str) -> Result<Self> {
+        match mode {
+            "record" => Ok(Self::Record),
+            "replay" => Ok(Self::Replay),
+            _ => bail!("unsupported rpc fixture mode `{mode}`, expected `record` or `replay`"),
+        }
+    }
+}
+
+// rpc fixtures keyed by the method and parameters of the requests, the recorded responses are
+// flushed to a fixture file of each block, and the all fixture files in the directory are loaded
+// for replaying
+#[derive(Debug)]
+pub struct RpcFixture {
+    // fixture mode
+    mode: RpcFixtureMode,
+
+    // directory of the fixture files
+    dir: PathBuf,
+
+    // json rpc responses keyed by the method and parameters
+    responses: Mutex<HashMap<String, Value>>,
+}
+
+impl RpcFixture {
+    pub fn new(mode: RpcFixtureMode, dir: PathBuf) -> Result<Arc<Self>> {
+        let mut responses = HashMap::new();
+        match mode {
+            RpcFixtureMode::Record => fs::create_dir_all(&dir)?,
+            RpcFixtureMode::Replay => {
+                for entry in fs::read_dir(&dir)? {
+                    let file_path = entry?.path();
+                    if file_path.extension().is_some_and(|ext| ext == "json") {
+                        let data = fs::read(&file_path)?;
+                        let fixture: HashMap<String, Value> = serde_json::from_slice(&data)?;
+                        responses.extend(fixture);
+                    }
+                }
+                info!(
+                    "rpc-fixture: loaded {} responses from {dir:?}",
+                    responses.len()
+                );
+            }
+        }
+
+        Ok(Arc::new(Self {
+            mode,
+            dir,
+            responses: Mutex::new(responses),
+        }))
+    }
+
+    // create a timed http provider recording to or replaying from the fixtures
+    pub fn connect_http(self: &Arc<Self>, rpc_url: Url) -> RootProvider {
+        let client = match self.mode {
+            RpcFixtureMode::Record => ClientBuilder::default()
+                .layer(RpcTimerLayer)
+                .layer(RpcFixtureLayer {
+                    fixture: self.clone(),
+                })
+                .http(rpc_url),
+            RpcFixtureMode::Replay => RpcClient::new(
+                RpcTimerLayer.layer(RpcFixtureReplayTransport {
+                    fixture: self.clone(),
+                }),
+                true,
+            ),
+        };
+
+        RootProvider::new(client)
+    }
+
+    // write the recorded responses to the fixture file of a block and clear them, the responses
+    // are shared by the all blocks, so the blocks must be fetched one by one for recording
+    pub fn flush(&self, block_number: u64) -> Result<()> {
+        if self.mode != RpcFixtureMode::Record {
+            return Ok(());
+        }
+
+        let responses = std::mem::take(
+            &mut *self
+                .responses
+                .lock()
+                .expect("rpc-fixture: responses lock is poisoned"),
+        );
+        let file_path = self.dir.join(format!("block{block_number}.json"));
+        fs::write(&file_path, serde_json::to_vec_pretty(&responses)?)?;
+        info!(
+            "rpc-fixture: recorded {} responses to {file_path:?}",
+            responses.len()
+        );
+
+        Ok(())
+    }
+
+    // save the responses of the recorded requests
+    fn record(&self, requests: &[(Value, String)], response_packet: &ResponsePacket) {
+        let mut responses = self
+            .responses
+            .lock()
+            .expect("rpc-fixture: responses lock is poisoned");
+        for response in packet_responses(response_packet) {
+            let Ok(id) = serde_json::to_value(&response.id) else {
+                continue;
+            };
+            let Some((_, key)) = requests.iter().find(|(request_id, _)| *request_id == id) else {
+                continue;
+            };
+            if let Ok(value) = serde_json::to_value(response) {
+                responses.insert(key.clone(), value);
+            }
+        }
+    }
+
+    // return the recorded responses of the requests
+    fn replay(&self, request_packet: &RequestPacket) -> Result<ResponsePacket, TransportError> {
+        let responses = self
+            .responses
+            .lock()
+            .expect("rpc-fixture: responses lock is poisoned");
+        let replay_request = |request: &SerializedRequest| {
+            let key = fixture_key(request);
+            let mut value = responses.get(&key).cloned().ok_or_else(|| {
+                TransportErrorKind::custom_str(&format!("no rpc fixture of {key}"))
+            })?;
+            value["id"] = serde_json::to_value(request.id()).map_err(TransportError::ser_err)?;
+
+            // the raw value payload could only be deserialized from a string
+            let text = value.to_string();
+            serde_json::from_str::<Response>(&text).map_err(|e| TransportError::deser_err(e, text))
+        };
+
+        match request_packet {
+            RequestPacket::Single(request) => Ok(ResponsePacket::Single(replay_request(request)?)),
+            RequestPacket::Batch(requests) => Ok(ResponsePacket::Batch(
+                requests
+                    .iter()
+                    .map(replay_request)
+                    .collect::<Result<_, _>>()?,
+            )),
+        }
+    }
+}
+
+// tower layer of the transport for recording the rpc responses
+#[derive(Clone, Debug)]
+struct RpcFixtureLayer {
+    fixture: Arc<RpcFixture>,
+}
+
+impl<S> Layer<S> for RpcFixtureLayer {
+    type Service = RpcFixtureRecordService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RpcFixtureRecordService {
+            inner,
+            fixture: self.fixture.clone(),
+        }
+    }
+}
+
+// transport service forwarding the rpc requests and recording the responses
+#[derive(Clone, Debug)]
+struct RpcFixtureRecordService<S> {
+    inner: S,
+    fixture: Arc<RpcFixture>,
+}
+
+impl<S> Service<RequestPacket> for RpcFixtureRecordService<S>
+where
+    S: Service<RequestPacket, Response = ResponsePacket, Error = TransportError>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request_packet: RequestPacket) -> Self::Future {
+        let requests: Vec<_> = serialized_requests(&request_packet)
+            .iter()
+            .filter_map(|request| {
+                let id = serde_json::to_value(request.id()).ok()?;
+                Some((id, fixture_key(request)))
+            })
+            .collect();
+        let fixture = self.fixture.clone();
+        let response_future = self.inner.call(request_packet);
+
+        Box::pin(async move {
+            let response_packet = response_future.await?;
+            fixture.record(&requests, &response_packet);
+
+            Ok(response_packet)
+        })
+    }
+}
+
+// transport serving the rpc requests from the fixtures
+#[derive(Clone, Debug)]
+struct RpcFixtureReplayTransport {
+    fixture: Arc<RpcFixture>,
+}
+
+impl Service<RequestPacket> for RpcFixtureReplayTransport {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request_packet: RequestPacket) -> Self::Future {
+        let result = self.fixture.replay(&request_packet);
+
+        Box::pin(async move { result })
+    }
+}
+
+// return the serialized requests of a packet
+fn serialized_requests(request_packet: &RequestPacket) -> &[SerializedRequest] {
+    match request_packet {
+        RequestPacket::Single(request) => std::slice::from_ref(request),
+        RequestPacket::Batch(requests) => requests,
+    }
+}
+
+// return the responses of a packet
+fn packet_responses(response_packet: &ResponsePacket) -> &[Response] {
+    match response_packet {
+        ResponsePacket::Single(response) => std::slice::from_ref(response),
+        ResponsePacket::Batch(responses) => responses,
+    }
+}
+
+// construct the fixture key by the method and parameters of a request
+fn fixture_key(request: &SerializedRequest) -> String {
+    let params = request.params().map_or("null", |params| params.get());
+
+    format!("{}:{params}", request.method())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_json_rpc::{Id, Request};
+    use alloy_primitives::U64;
+    use alloy_provider::Provider;
+    use serde_json::json;
+
+    // params of the replayed request
+    const PARAMS: (&str, &str) = ("0x0000000000000000000000000000000000000001", "latest");
+
+    // create an empty fixture directory of a test
+    fn fixture_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rpc-fixture-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    #[tokio::test]
+    async fn replay_recorded_response() {
+        let dir = fixture_dir("replay");
+        let request = Request::new("eth_getBalance", Id::Number(7), PARAMS)
+            .serialize()
+            .unwrap();
+        let fixture = json!({
+            fixture_key(&request): {"jsonrpc": "2.0", "id": 7, "result": "0x2a"},
+        });
+        fs::write(dir.join("block1.json"), fixture.to_string()).unwrap();
+
+        let rpc_fixture = RpcFixture::new(RpcFixtureMode::Replay, dir.clone()).unwrap();
+        let provider = rpc_fixture.connect_http("http://127.0.0.1:1".parse().unwrap());
+        let balance: U64 = provider
+            .client()
+            .request("eth_getBalance", PARAMS)
+            .await
+            .unwrap();
+        assert_eq!(balance, U64::from(42));
+
+        // a request not in the fixtures fails instead of reaching the node
+        let result: Result<U64, _> = provider.client().request("eth_chainId", ()).await;
+        assert!(result.is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,83 @@
+use alloy_json_rpc::{RequestPacket, ResponsePacket};
+use alloy_transport::{TransportError, TransportFut};
+use std::{
+    future::Future,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tower::{Layer, Service};
+
+tokio::task_local! {
+    // accumulated rpc time (in nanoseconds) of the requests made by the current timed future
+    static RPC_NANOSECONDS: Arc<AtomicU64>;
+}
+
+// run a future and return its output with the total time of the rpc requests it made by the
+// timed providers, the time of the concurrent requests is summed, and the requests made by the
+// tasks spawned from the future are not counted
+pub async fn timed_rpc<F: Future>(future: F) -> (F::Output, Duration) {
+    let rpc_nanoseconds = Arc::new(AtomicU64::new(0));
+    let output = RPC_NANOSECONDS.scope(rpc_nanoseconds.clone(), future).await;
+
+    (
+        output,
+        Duration::from_nanos(rpc_nanoseconds.load(Ordering::SeqCst)),
+    )
+}
+
+// tower layer of the transport for timing the rpc requests
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RpcTimerLayer;
+
+impl<S> Layer<S> for RpcTimerLayer {
+    type Service = RpcTimerService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RpcTimerService { inner }
+    }
+}
+
+// transport service adding the time of each rpc request to the timed future which made it
+#[derive(Clone, Debug)]
+pub struct RpcTimerService<S> {
+    inner: S,
+}
+
+impl<S> Service<RequestPacket> for RpcTimerService<S>
+where
+    S: Service<RequestPacket, Response = ResponsePacket, Error = TransportError>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request_packet: RequestPacket) -> Self::Future {
+        // the request is called by the task of the timed future, the response may be awaited
+        // anywhere
+        let rpc_nanoseconds = RPC_NANOSECONDS.try_with(Arc::clone).ok();
+        let start_time = Instant::now();
+        let response_future = self.inner.call(request_packet);
+
+        Box::pin(async move {
+            let result = response_future.await;
+            if let Some(rpc_nanoseconds) = rpc_nanoseconds {
+                rpc_nanoseconds.fetch_add(start_time.elapsed().as_nanos() as u64, Ordering::SeqCst);
+            }
+
+            result
+        })
+    }
+}
@@ -1,43 +1,176 @@
-use crate::config::BlockFetcherConfig;
-use alloy_provider::RootProvider;
-use anyhow::Result;
-use common::inputs::ProvingInputs;
+use crate::{
+    block_cache::BlockCache,
+    config::BlockFetcherConfig,
+    rpc_fixture::RpcFixture,
+    rpc_timer::{RpcTimerLayer, timed_rpc},
+    witness_provider::WitnessProvider,
+};
+use alloy_primitives::B256;
+use alloy_provider::{Provider, RootProvider};
+use alloy_rpc_client::ClientBuilder;
+use anyhow::{Error, Result, anyhow, ensure};
+use common::{inputs::ProvingInputs, report::BlockProvingReport, storage::InputStorage};
 use itertools::Itertools;
 use pico_sdk::{HashableKey, client::DefaultProverClient};
-use rsp_client_executor::{ChainVariant, io::SubblockHostOutput};
-use rsp_host_executor::HostExecutor;
-use std::{fs, sync::Arc};
-use tracing::info;
+use reqwest::Url;
+use rsp_client_executor::io::SubblockHostOutput;
+use std::{
+    fs,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::{error, info, warn};
+
+// chain name recorded in the manifest of dumped inputs
+const CHAIN_NAME: &str = "ethereum";
+
+// blob gas consumed by each EIP-4844 blob
+const GAS_PER_BLOB: u64 = 131_072;
 
 // subblock executor for generating subblock and aggregation inputs
 pub struct SubblockExecutor {
     // fetcher configuration
     config: Arc<BlockFetcherConfig>,
 
-    // rsp-subblock executor
-    executor: HostExecutor<RootProvider>,
+    // rpc provider for querying the block hash
+    provider: RootProvider,
+
+    // provider of the block execution witnesses for generating the subblock output
+    witness_provider: Arc<dyn WitnessProvider>,
+
+    // on-disk cache of the subblock host outputs; nothing will be cached if not specified
+    block_cache: Option<BlockCache>,
+
+    // storage for saving input files; nothing will be saved if not specified
+    input_dump_storage: Option<Arc<dyn InputStorage>>,
+
+    // rpc fixtures for recording or replaying the rpc responses; disabled if not specified
+    rpc_fixture: Option<Arc<RpcFixture>>,
 }
 
 impl SubblockExecutor {
-    pub fn new(config: Arc<BlockFetcherConfig>) -> Self {
-        // create rsp-subblock executor
-        let provider = RootProvider::new_http(config.rpc_http_url.clone());
-        let executor = HostExecutor::new(provider);
+    pub fn new(
+        config: Arc<BlockFetcherConfig>,
+        rpc_http_url: Url,
+        input_dump_storage: Option<Arc<dyn InputStorage>>,
+        witness_provider: Arc<dyn WitnessProvider>,
+        rpc_fixture: Option<Arc<RpcFixture>>,
+    ) -> Self {
+        let provider = connect_http(rpc_fixture.as_ref(), rpc_http_url);
 
-        Self { config, executor }
+        let block_cache = config.block_cache_dir.clone().map(BlockCache::new);
+
+        Self {
+            config,
+            provider,
+            witness_provider,
+            block_cache,
+            input_dump_storage,
+            rpc_fixture,
+        }
     }
 
-    // generate subblock and aggregation inputs
-    pub async fn generate_inputs(&self, block_number: u64) -> Result<ProvingInputs> {
-        // fetch eth block data and generate the subblock output
-        info!(
-            "subblock-executor: fetching and generating subblock output for block {block_number}",
-        );
-        let subblock_output = self
-            .executor
-            .execute_subblock(block_number, ChainVariant::Ethereum, None)
+    // generate subblock and aggregation inputs, the emulated cycles are recorded to the fetch
+    // report if the inputs are emulated
+    pub async fn generate_inputs(
+        &self,
+        block_number: u64,
+        fetch_report: &mut BlockProvingReport,
+    ) -> Result<ProvingInputs> {
+        let proving_inputs = self
+            .generate(block_number, fetch_report, self.config.is_input_emulated)
             .await?;
 
+        // save proving inputs to the storage, the block is still proved if failed to dump since
+        // the dump is only for reproducing
+        if let Some(storage) = &self.input_dump_storage
+            && let Err(e) = proving_inputs
+                .dump_to_storage(storage.as_ref(), &self.config.input_layout, CHAIN_NAME)
+                .await
+        {
+            error!(
+                "subblock-executor: failed to dump the proving inputs of block {block_number} {e:?}"
+            );
+        }
+
+        Ok(proving_inputs)
+    }
+
+    // generate and emulate subblock and aggregation inputs for a dry-run estimation, the cycles,
+    // gas and input sizes are recorded to the report, and the inputs are not dumped
+    pub async fn estimate_inputs(
+        &self,
+        block_number: u64,
+        report: &mut BlockProvingReport,
+    ) -> Result<()> {
+        let proving_inputs = self.generate(block_number, report, true).await?;
+        report.on_inputs_measured(&proving_inputs);
+        report.on_estimated();
+
+        Ok(())
+    }
+
+    // generate subblock and aggregation inputs and emulate them if specified
+    async fn generate(
+        &self,
+        block_number: u64,
+        fetch_report: &mut BlockProvingReport,
+        is_input_emulated: bool,
+    ) -> Result<ProvingInputs> {
+        // fetch the block header and record the gas and blob metadata
+        let rpc_fetch_start_time = Instant::now();
+        let block = self
+            .provider
+            .get_block_by_number(block_number.into())
+            .await?
+            .ok_or_else(|| anyhow!("block {block_number} is not found"))?;
+        let rpc_fetch_time = rpc_fetch_start_time.elapsed();
+        let blob_gas_used = block.header.blob_gas_used.unwrap_or_default();
+        fetch_report.on_block_fetched(
+            block.header.gas_limit,
+            block.header.gas_used,
+            block.transactions.len() as u64,
+            blob_gas_used,
+            blob_gas_used / GAS_PER_BLOB,
+        );
+
+        // fetch eth block data and generate the subblock output, the state is fetched lazily
+        // during the host execution, so the time of the state rpc requests is moved from the host
+        // execution to the rpc fetch
+        let host_execution_start_time = Instant::now();
+        let (subblock_output, state_rpc_time) =
+            timed_rpc(self.execute_subblock(block_number, block.header.hash)).await;
+        let subblock_output = subblock_output?;
+        let host_execution_time = host_execution_start_time
+            .elapsed()
+            .saturating_sub(state_rpc_time);
+        let rpc_fetch_time = rpc_fetch_time + state_rpc_time;
+        if let Some(rpc_fixture) = &self.rpc_fixture {
+            rpc_fixture.flush(block_number)?;
+        }
+
+        // record the gas used by each subblock for identifying the imbalanced splits
+        fetch_report.on_subblocks_split(subblock_gas_used(&subblock_output, block.header.gas_used));
+
+        // record the parent state sizes which dominate the overlapping subblock inputs
+        fetch_report.on_parent_states_measured(
+            subblock_output
+                .subblock_parent_states
+                .iter()
+                .map(|parent_state| parent_state.len() as u64)
+                .collect(),
+        );
+
+        // reject the block if it's split into more subblocks than the cluster could prove
+        let num_subblocks = subblock_output.subblock_inputs.len();
+        if num_subblocks > self.config.max_num_subblocks {
+            let e = anyhow!(
+                "block {block_number} is split into {num_subblocks} subblocks which exceeds the maximum {}",
+                self.config.max_num_subblocks,
+            );
+            return Err(self.reject(fetch_report, e));
+        }
+
         // create subblock and aggregation prover clients
         let subblock_elf = fs::read(&self.config.subblock_elf_path)?;
         let agg_elf = fs::read(&self.config.agg_elf_path)?;
@@ -47,53 +180,162 @@ impl SubblockExecutor {
 
         // generate the subblock inputs
         info!("subblock-executor: generating subblock inputs for block {block_number}");
-        let subblock_inputs = generate_subblock_inputs(
-            self.config.is_input_emulated,
-            &subblock_output,
-            subblock_prover_client,
-        );
+        let serialization_start_time = Instant::now();
+        let (subblock_inputs, subblock_cycles, subblock_emulation_time) =
+            generate_subblock_inputs(is_input_emulated, &subblock_output, subblock_prover_client);
 
         // generate the subblock public values
         let subblock_public_values = generate_subblock_public_values(&subblock_output);
 
         // generate the aggregation input
         info!("subblock-executor: generating aggregator input for block {block_number}");
-        let agg_input = generate_agg_input(
-            self.config.is_input_emulated,
+        let (agg_input, agg_cycles, agg_public_values, agg_emulation_time) = generate_agg_input(
+            is_input_emulated,
             &subblock_output,
             agg_prover_client,
             subblock_vk_hash,
             &subblock_public_values,
         );
 
+        if is_input_emulated {
+            info!(
+                "subblock-executor: emulated block {block_number} with subblock cycles {subblock_cycles:?} and aggregator cycles {agg_cycles}",
+            );
+            fetch_report.on_inputs_emulated(subblock_cycles, agg_cycles);
+
+            // fail fast before dispatching to the cluster if the emulated result mismatches
+            if let Err(e) = verify_block_hash(
+                block_number,
+                block.header.hash,
+                block.header.state_root,
+                &agg_public_values,
+            ) {
+                return Err(self.reject(fetch_report, e));
+            }
+        }
+
         let subblock_public_values = bincode::serialize(&subblock_public_values)
             .expect("subblock-executor: failed to serialize subblock public values");
 
-        let proving_inputs = ProvingInputs::new(
+        // record the time of each stage, the serialization time excludes the emulation
+        let emulation_time = subblock_emulation_time + agg_emulation_time;
+        let serialization_time = serialization_start_time
+            .elapsed()
+            .saturating_sub(emulation_time);
+        fetch_report.on_fetch_stages_timed(
+            rpc_fetch_time.as_millis() as u64,
+            host_execution_time.as_millis() as u64,
+            serialization_time.as_millis() as u64,
+            emulation_time.as_millis() as u64,
+        );
+
+        Ok(ProvingInputs::new(
             block_number,
             subblock_public_values,
             agg_input,
             subblock_inputs,
-        );
+        ))
+    }
+
+    // mark the fetch report of a block rejected in validation and return the error, the failure
+    // report is sent by the caller
+    fn reject(&self, fetch_report: &mut BlockProvingReport, e: Error) -> Error {
+        fetch_report.on_rejected(format!("{e:#}"));
+
+        e
+    }
+
+    // fetch eth block data and generate the subblock output, the output is loaded from or saved to
+    // the block cache if it's enabled
+    async fn execute_subblock(
+        &self,
+        block_number: u64,
+        block_hash: B256,
+    ) -> Result<SubblockHostOutput> {
+        let Some(block_cache) = &self.block_cache else {
+            info!(
+                "subblock-executor: fetching and generating subblock output for block {block_number}",
+            );
+            return self.witness_provider.execute_subblock(block_number).await;
+        };
 
-        if let Some(dir) = &self.config.input_dump_dir {
-            // save proving inputs to the directory
-            proving_inputs
-                .dump_to_dir(dir)
-                .expect("subblock-executor: failed to dump the block proving inputs");
+        // the cache is keyed by block hash, so a reorged block is never loaded from the cache
+        let block_hash = block_hash.to_string();
+
+        if let Some(subblock_output) = block_cache.get(&block_hash) {
+            info!(
+                "subblock-executor: loaded subblock output of block {block_number} from cache {block_hash}",
+            );
+            return Ok(subblock_output);
         }
 
-        Ok(proving_inputs)
+        info!(
+            "subblock-executor: fetching and generating subblock output for block {block_number}",
+        );
+        let subblock_output = self.witness_provider.execute_subblock(block_number).await?;
+        if let Err(e) = block_cache.put(&block_hash, &subblock_output) {
+            warn!(
+                "subblock-executor: failed to cache subblock output of block {block_number} {e:?}"
+            );
+        }
+
+        Ok(subblock_output)
+    }
+}
+
+// create a timed http provider of the rpc url, the rpc responses are recorded or replayed if the
+// fixtures are specified
+pub fn connect_http(rpc_fixture: Option<&Arc<RpcFixture>>, rpc_url: Url) -> RootProvider {
+    match rpc_fixture {
+        Some(rpc_fixture) => rpc_fixture.connect_http(rpc_url),
+        None => RootProvider::new(ClientBuilder::default().layer(RpcTimerLayer).http(rpc_url)),
     }
 }
 
-// generate the subblock inputs
+// verify the block hash committed by the emulated aggregator against the on-chain block header,
+// the block hash covers the post-state root, so a mismatch means the computed state diverges
+fn verify_block_hash(
+    block_number: u64,
+    block_hash: B256,
+    state_root: B256,
+    agg_public_values: &[u8],
+) -> Result<()> {
+    let committed_hash: B256 = bincode::deserialize(agg_public_values)?;
+    ensure!(
+        committed_hash == block_hash,
+        "emulated hash {committed_hash} of block {block_number} mismatches the on-chain hash {block_hash} with state root {state_root}",
+    );
+    info!("subblock-executor: verified the emulated hash of block {block_number}");
+
+    Ok(())
+}
+
+// calculate the gas used by each subblock, it's the difference between the starting gas used of
+// the current subblock and the next one, and the last subblock ends with the block gas used
+fn subblock_gas_used(subblock_output: &SubblockHostOutput, block_gas_used: u64) -> Vec<u64> {
+    let starting_gas_used: Vec<_> = subblock_output
+        .subblock_inputs
+        .iter()
+        .map(|input| input.starting_gas_used)
+        .chain([block_gas_used])
+        .collect();
+
+    starting_gas_used
+        .windows(2)
+        .map(|gas| gas[1].saturating_sub(gas[0]))
+        .collect()
+}
+
+// generate the subblock inputs and return the emulated cycles of each subblock and the total
+// emulation time, the cycles are empty if emulation is disabled
 fn generate_subblock_inputs(
     is_input_emulated: bool,
     subblock_output: &SubblockHostOutput,
     subblock_prover_client: DefaultProverClient,
-) -> Vec<Vec<u8>> {
-    subblock_output
+) -> (Vec<Vec<u8>>, Vec<u64>, Duration) {
+    let mut subblock_cycles = vec![];
+    let mut emulation_time = Duration::ZERO;
+    let subblock_inputs = subblock_output
         .subblock_inputs
         .iter()
         .zip_eq(subblock_output.subblock_parent_states.iter())
@@ -105,14 +347,19 @@ fn generate_subblock_inputs(
 
             // emulate the subblock with generated stdin builder if the flag is specified
             if is_input_emulated {
-                subblock_prover_client.emulate(stdin_builder.clone());
+                let emulation_start_time = Instant::now();
+                let (cycles, _) = subblock_prover_client.emulate(stdin_builder.clone());
+                emulation_time += emulation_start_time.elapsed();
+                subblock_cycles.push(cycles);
             }
 
             // serialize the stdin builder
             bincode::serialize(&stdin_builder)
                 .expect("subblock-executor: failed to serialize subblock stdin builder")
         })
-        .collect()
+        .collect();
+
+    (subblock_inputs, subblock_cycles, emulation_time)
 }
 
 // generate the subblock public values
@@ -135,14 +382,15 @@ fn generate_subblock_public_values(subblock_output: &SubblockHostOutput) -> Vec<
     public_values
 }
 
-// generate the aggregation input
+// generate the aggregation input and return the emulated cycles, public values and emulation time,
+// the cycles are zero and the public values are empty if emulation is disabled
 fn generate_agg_input(
     is_input_emulated: bool,
     subblock_output: &SubblockHostOutput,
     agg_prover_client: DefaultProverClient,
     subblock_vk_hash: [u32; 8],
     subblock_public_values: &Vec<Vec<u8>>,
-) -> Vec<u8> {
+) -> (Vec<u8>, u64, Vec<u8>, Duration) {
     // generate aggregator stdin builder
     let mut stdin_builder = agg_prover_client.new_stdin_builder();
     stdin_builder.write::<Vec<Vec<u8>>>(subblock_public_values);
@@ -151,11 +399,17 @@ fn generate_agg_input(
     stdin_builder.write(&subblock_output.agg_input.parent_header().state_root);
 
     // emulate the aggregator with generated stdin builder if the flag is specified
+    let (mut agg_cycles, mut agg_public_values) = (0, vec![]);
+    let mut emulation_time = Duration::ZERO;
     if is_input_emulated {
-        agg_prover_client.emulate(stdin_builder.clone());
+        let emulation_start_time = Instant::now();
+        (agg_cycles, agg_public_values) = agg_prover_client.emulate(stdin_builder.clone());
+        emulation_time = emulation_start_time.elapsed();
     }
 
     // serialize the stdin builder
-    bincode::serialize(&stdin_builder)
-        .expect("subblock-executor: failed to serialize aggregator stdin builder")
+    let agg_input = bincode::serialize(&stdin_builder)
+        .expect("subblock-executor: failed to serialize aggregator stdin builder");
+
+    (agg_input, agg_cycles, agg_public_values, emulation_time)
 }
@@ -0,0 +1,49 @@
+use alloy_provider::RootProvider;
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Url;
+use rsp_client_executor::{ChainVariant, io::SubblockHostOutput};
+use rsp_host_executor::HostExecutor;
+use std::fmt::{self, Debug};
+
+// provider of the block execution witnesses for generating the subblock output, it allows the
+// inputs to be generated from different witness sources
+#[async_trait]
+pub trait WitnessProvider: Debug + Send + Sync {
+    // fetch the witnesses of a block and generate the subblock output
+    async fn execute_subblock(&self, block_number: u64) -> Result<SubblockHostOutput>;
+}
+
+// witness provider replaying the block via the generic rpc methods, e.g. `eth_getProof`
+pub struct RpcReplayWitnessProvider {
+    // http url of the rpc node
+    rpc_url: Url,
+
+    // rsp-subblock executor
+    executor: HostExecutor<RootProvider>,
+}
+
+impl RpcReplayWitnessProvider {
+    pub fn new(rpc_url: Url, provider: RootProvider) -> Self {
+        let executor = HostExecutor::new(provider);
+
+        Self { rpc_url, executor }
+    }
+}
+
+impl Debug for RpcReplayWitnessProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RpcReplayWitnessProvider")
+            .field("rpc_url", &self.rpc_url.as_str())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl WitnessProvider for RpcReplayWitnessProvider {
+    async fn execute_subblock(&self, block_number: u64) -> Result<SubblockHostOutput> {
+        self.executor
+            .execute_subblock(block_number, ChainVariant::Ethereum, None)
+            .await
+    }
+}
@@ -1,12 +1,29 @@
+pub mod lifecycle;
+
+use anyhow::{Error, Result, bail, ensure};
 use common::{
-    channel::{DuplexUnboundedEndpoint, UnboundedReceiver, UnboundedSender},
-    fetch::{ProveBlockByNumberParams, ProveLatestBlockParams, ReproduceBlockByNumberParams},
+    channel::{
+        ChannelReceiver, ChannelSender, DuplexBoundedEndpoint, DuplexUnboundedEndpoint, Receiver,
+        Sender, UnboundedReceiver, UnboundedSender,
+    },
+    fetch::{
+        BackfillBlockRangeParams, EstimateBlockParams, ProveBlockByNumberParams,
+        ProveLatestBlockParams, ReproduceBlockByListParams, ReproduceBlockByNumberParams,
+        ReproduceLatestBlockParams,
+    },
     inputs::ProvingInputs,
+    pipeline_error::PipelineError,
     report::BlockProvingReport,
+    trace::TraceId,
+    utils::PipelineId,
 };
 use derive_more::Constructor;
 use proof_proto::CompleteProvingRequest;
-use std::sync::Arc;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use std::{fmt, sync::Arc};
+
+pub use common::ids::{BlockKey, JobId};
 
 // default value of `count` parameter
 const DEFAULT_PARAM_COUNT: u64 = 1;
@@ -28,11 +45,143 @@ pub enum BlockMsg {
 
     // block report message
     Report(ReportMsg),
+
+    // pipeline control message
+    Control(ControlMsg),
+
+    // block lifecycle status message
+    Status(StatusMsg),
+
+    // block failure message emitted by any stage
+    Error(ErrorMsg),
+}
+
+impl BlockMsg {
+    // return the message kind for routing
+    pub fn kind(&self) -> BlockMsgKind {
+        match self {
+            Self::Watch(_) => BlockMsgKind::Watch,
+            Self::Fetch(_) => BlockMsgKind::Fetch,
+            Self::Proving(_) => BlockMsgKind::Proving,
+            Self::Proved(_) => BlockMsgKind::Proved,
+            Self::Report(_) => BlockMsgKind::Report,
+            Self::Control(_) => BlockMsgKind::Control,
+            Self::Status(_) => BlockMsgKind::Status,
+            Self::Error(_) => BlockMsgKind::Error,
+        }
+    }
+
+    // return the pipeline id for routing the message to a pipeline, it's none if the message is
+    // routed by the pipeline of the source thread
+    pub fn pipeline_id(&self) -> Option<PipelineId> {
+        match self {
+            Self::Fetch(fetch_msg) => Some(fetch_msg.pipeline_id()),
+            Self::Proved(proved_msg) => Some(proved_msg.pipeline_id),
+            _ => None,
+        }
+    }
+
+    // return the trace id of the block carried by the message, it's none if the message is not
+    // for a single block or the trace id is not propagated
+    pub fn trace_id(&self) -> Option<TraceId> {
+        let trace_id = match self {
+            Self::Proving(proving_msg) => proving_msg.fetch_report.trace_id,
+            Self::Proved(proved_msg) => proved_msg.trace_id.parse().ok()?,
+            Self::Report(report) => report.trace_id,
+            Self::Status(status_msg) => status_msg.trace_id,
+            Self::Error(error_msg) => error_msg.trace_id,
+            Self::Watch(_) | Self::Fetch(_) | Self::Control(_) => return None,
+        };
+
+        (!trace_id.is_unknown()).then_some(trace_id)
+    }
+
+    // encode the message for transmitting over a message bus, the watch messages are not
+    // transmittable since they carry a local websocket sender
+    pub fn encode(self) -> Result<Vec<u8>> {
+        let encoded_msg = match self {
+            Self::Watch(_) => bail!("watch message is not transmittable"),
+            Self::Fetch(fetch_msg) => EncodedBlockMsg::Fetch(fetch_msg),
+            Self::Proving(proving_msg) => EncodedBlockMsg::Proving(proving_msg),
+            Self::Proved(proved_msg) => EncodedBlockMsg::Proved(proved_msg.encode_to_vec()),
+            Self::Report(report) => EncodedBlockMsg::Report(report),
+            Self::Control(control_msg) => EncodedBlockMsg::Control(control_msg),
+            Self::Status(status_msg) => EncodedBlockMsg::Status(status_msg),
+            Self::Error(error_msg) => EncodedBlockMsg::Error(error_msg),
+        };
+
+        Ok(bincode::serialize(&encoded_msg)?)
+    }
+
+    // decode a message received from a message bus
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let msg = match bincode::deserialize(bytes)? {
+            EncodedBlockMsg::Fetch(fetch_msg) => Self::Fetch(fetch_msg),
+            EncodedBlockMsg::Proving(proving_msg) => Self::Proving(proving_msg),
+            EncodedBlockMsg::Proved(proved_msg) => {
+                Self::Proved(ProvedMsg::decode(proved_msg.as_slice())?)
+            }
+            EncodedBlockMsg::Report(report) => Self::Report(report),
+            EncodedBlockMsg::Control(control_msg) => Self::Control(control_msg),
+            EncodedBlockMsg::Status(status_msg) => Self::Status(status_msg),
+            EncodedBlockMsg::Error(error_msg) => Self::Error(error_msg),
+        };
+
+        Ok(msg)
+    }
+}
+
+// block message encoded by bincode for a message bus, the proof results are embedded as the
+// protobuf bytes since the generated types don't implement serde
+#[derive(Deserialize, Serialize)]
+enum EncodedBlockMsg {
+    Fetch(FetchMsg),
+    Proving(ProvingMsg),
+    Proved(Vec<u8>),
+    Report(ReportMsg),
+    Control(ControlMsg),
+    Status(StatusMsg),
+    Error(ErrorMsg),
+}
+
+// kind of the block messages, the scheduler routes the messages by kind
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BlockMsgKind {
+    Watch,
+    Fetch,
+    Proving,
+    Proved,
+    Report,
+    Control,
+    Status,
+    Error,
 }
 
-impl From<ProveBlockByNumberParams> for BlockMsg {
-    fn from(params: ProveBlockByNumberParams) -> Self {
+impl fmt::Display for BlockMsgKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Watch => write!(f, "watch"),
+            Self::Fetch => write!(f, "fetch"),
+            Self::Proving => write!(f, "proving"),
+            Self::Proved => write!(f, "proved"),
+            Self::Report => write!(f, "report"),
+            Self::Control => write!(f, "control"),
+            Self::Status => write!(f, "status"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl From<FetchJob<ProveBlockByNumberParams>> for BlockMsg {
+    fn from(job: FetchJob<ProveBlockByNumberParams>) -> Self {
+        let FetchJob {
+            job_id,
+            pipeline_id,
+            params,
+        } = job;
         let fetch_msg = FetchMsg::ProveFromStart {
+            job_id,
+            pipeline_id,
             start_block_number: params.start_block_num,
             count: params.count.unwrap_or(DEFAULT_PARAM_COUNT),
         };
@@ -41,9 +190,16 @@ impl From<ProveBlockByNumberParams> for BlockMsg {
     }
 }
 
-impl From<ProveLatestBlockParams> for BlockMsg {
-    fn from(params: ProveLatestBlockParams) -> Self {
+impl From<FetchJob<ProveLatestBlockParams>> for BlockMsg {
+    fn from(job: FetchJob<ProveLatestBlockParams>) -> Self {
+        let FetchJob {
+            job_id,
+            pipeline_id,
+            params,
+        } = job;
         let fetch_msg = FetchMsg::ProveLatest {
+            job_id,
+            pipeline_id,
             count: params.count.unwrap_or(DEFAULT_PARAM_COUNT),
         };
 
@@ -51,9 +207,100 @@ impl From<ProveLatestBlockParams> for BlockMsg {
     }
 }
 
-impl From<ReproduceBlockByNumberParams> for BlockMsg {
-    fn from(params: ReproduceBlockByNumberParams) -> Self {
+impl From<FetchJob<ReproduceBlockByNumberParams>> for BlockMsg {
+    fn from(job: FetchJob<ReproduceBlockByNumberParams>) -> Self {
+        let FetchJob {
+            job_id,
+            pipeline_id,
+            params,
+        } = job;
         let fetch_msg = FetchMsg::ReproduceFromStart {
+            job_id,
+            pipeline_id,
+            start_block_number: params.start_block_num,
+            count: params.count.unwrap_or(DEFAULT_PARAM_COUNT),
+        };
+
+        Self::Fetch(fetch_msg)
+    }
+}
+
+impl From<FetchJob<ReproduceLatestBlockParams>> for BlockMsg {
+    fn from(job: FetchJob<ReproduceLatestBlockParams>) -> Self {
+        let FetchJob {
+            job_id,
+            pipeline_id,
+            params,
+        } = job;
+        let fetch_msg = FetchMsg::ReproduceLatest {
+            job_id,
+            pipeline_id,
+            count: params.count.unwrap_or(DEFAULT_PARAM_COUNT),
+        };
+
+        Self::Fetch(fetch_msg)
+    }
+}
+
+impl TryFrom<FetchJob<ReproduceBlockByListParams>> for BlockMsg {
+    type Error = Error;
+
+    fn try_from(job: FetchJob<ReproduceBlockByListParams>) -> Result<Self> {
+        let FetchJob {
+            job_id,
+            pipeline_id,
+            params,
+        } = job;
+        let block_numbers = params.block_numbers()?;
+        ensure!(!block_numbers.is_empty(), "no block numbers to reproduce");
+
+        let fetch_msg = FetchMsg::ReproduceByList {
+            job_id,
+            pipeline_id,
+            block_numbers,
+        };
+
+        Ok(Self::Fetch(fetch_msg))
+    }
+}
+
+impl TryFrom<FetchJob<BackfillBlockRangeParams>> for BlockMsg {
+    type Error = Error;
+
+    fn try_from(job: FetchJob<BackfillBlockRangeParams>) -> Result<Self> {
+        let FetchJob {
+            job_id,
+            pipeline_id,
+            params,
+        } = job;
+        ensure!(
+            params.start_block_num <= params.end_block_num,
+            "start block number {} is greater than end block number {}",
+            params.start_block_num,
+            params.end_block_num,
+        );
+
+        let fetch_msg = FetchMsg::Backfill {
+            job_id,
+            pipeline_id,
+            start_block_number: params.start_block_num,
+            end_block_number: params.end_block_num,
+        };
+
+        Ok(Self::Fetch(fetch_msg))
+    }
+}
+
+impl From<FetchJob<EstimateBlockParams>> for BlockMsg {
+    fn from(job: FetchJob<EstimateBlockParams>) -> Self {
+        let FetchJob {
+            job_id,
+            pipeline_id,
+            params,
+        } = job;
+        let fetch_msg = FetchMsg::Estimate {
+            job_id,
+            pipeline_id,
             start_block_number: params.start_block_num,
             count: params.count.unwrap_or(DEFAULT_PARAM_COUNT),
         };
@@ -70,20 +317,236 @@ pub struct WatchMsg {
 }
 
 // fetch request message
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum FetchMsg {
     // fetch number of blocks starting from a specified block number
-    ProveFromStart { start_block_number: u64, count: u64 },
+    ProveFromStart {
+        job_id: JobId,
+        pipeline_id: PipelineId,
+        start_block_number: u64,
+        count: u64,
+    },
 
     // fetch number of latest blocks
-    ProveLatest { count: u64 },
+    ProveLatest {
+        job_id: JobId,
+        pipeline_id: PipelineId,
+        count: u64,
+    },
 
     // reproduce number of blocks starting from a specified block number
-    ReproduceFromStart { start_block_number: u64, count: u64 },
+    ReproduceFromStart {
+        job_id: JobId,
+        pipeline_id: PipelineId,
+        start_block_number: u64,
+        count: u64,
+    },
+
+    // reproduce number of the most recently dumped blocks
+    ReproduceLatest {
+        job_id: JobId,
+        pipeline_id: PipelineId,
+        count: u64,
+    },
+
+    // reproduce blocks of the specified block numbers
+    ReproduceByList {
+        job_id: JobId,
+        pipeline_id: PipelineId,
+        block_numbers: Vec<u64>,
+    },
+
+    // backfill a range of blocks with checkpointing, the end block number is inclusive
+    Backfill {
+        job_id: JobId,
+        pipeline_id: PipelineId,
+        start_block_number: u64,
+        end_block_number: u64,
+    },
+
+    // estimate number of blocks starting from a specified block number without proving
+    Estimate {
+        job_id: JobId,
+        pipeline_id: PipelineId,
+        start_block_number: u64,
+        count: u64,
+    },
+}
+
+impl FetchMsg {
+    // return the job id assigned by the fetch-service
+    pub fn job_id(&self) -> JobId {
+        match self {
+            Self::ProveFromStart { job_id, .. }
+            | Self::ProveLatest { job_id, .. }
+            | Self::ReproduceFromStart { job_id, .. }
+            | Self::ReproduceLatest { job_id, .. }
+            | Self::ReproduceByList { job_id, .. }
+            | Self::Backfill { job_id, .. }
+            | Self::Estimate { job_id, .. } => *job_id,
+        }
+    }
+
+    // return the id of the pipeline to fetch the blocks
+    pub fn pipeline_id(&self) -> PipelineId {
+        match self {
+            Self::ProveFromStart { pipeline_id, .. }
+            | Self::ProveLatest { pipeline_id, .. }
+            | Self::ReproduceFromStart { pipeline_id, .. }
+            | Self::ReproduceLatest { pipeline_id, .. }
+            | Self::ReproduceByList { pipeline_id, .. }
+            | Self::Backfill { pipeline_id, .. }
+            | Self::Estimate { pipeline_id, .. } => *pipeline_id,
+        }
+    }
+}
+
+// fetch request params with the job id assigned by the fetch-service, the job id is carried
+// through the fetch, proving and report messages for correlating a request across the threads
+#[derive(Constructor, Debug)]
+pub struct FetchJob<P> {
+    // job id of the request
+    pub job_id: JobId,
+
+    // id of the pipeline to fetch the blocks
+    pub pipeline_id: PipelineId,
+
+    // request params
+    pub params: P,
+}
+
+// pipeline control message sent by the admin requests, it's fanned out to the fetchers and
+// proving-clients of the all pipelines for maintenance windows on the proving cluster
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ControlMsg {
+    // stop fetching new blocks and dispatching them to the proving cluster, the block proving in
+    // progress is completed
+    Pause,
+
+    // continue fetching and dispatching blocks after a pause or drain
+    Resume,
+
+    // stop fetching new blocks but complete proving the blocks already fetched, the proving
+    // cluster is idle after the drain
+    Drain,
+}
+
+impl fmt::Display for ControlMsg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pause => write!(f, "pause"),
+            Self::Resume => write!(f, "resume"),
+            Self::Drain => write!(f, "drain"),
+        }
+    }
+}
+
+// block lifecycle status message emitted at each stage transition, it's forwarded to the
+// websocket watchers for showing the progress before the final block report
+#[derive(Clone, Constructor, Debug, Deserialize, Serialize)]
+pub struct StatusMsg {
+    // job id of the request
+    pub job_id: JobId,
+
+    // block number
+    pub block_number: u64,
+
+    // stage which the block enters
+    pub stage: BlockStage,
+
+    // trace id of the block, it's unknown if not propagated
+    #[serde(default)]
+    pub trace_id: TraceId,
+}
+
+// block failure message emitted by any stage instead of logging and dropping the failure, it's
+// counted by the scheduler and forwarded by the reporter to the websocket watchers
+#[derive(Clone, Constructor, Debug, Deserialize, Serialize)]
+pub struct ErrorMsg {
+    // job id of the request
+    pub job_id: JobId,
+
+    // block number
+    pub block_number: u64,
+
+    // stage where the block failed
+    pub stage: BlockStage,
+
+    // error details
+    pub error: String,
+
+    // identify if the block could succeed by retrying, e.g. a transient RPC or prover failure
+    pub retryable: bool,
+
+    // trace id of the block, it's unknown if not propagated
+    #[serde(default)]
+    pub trace_id: TraceId,
+}
+
+impl fmt::Display for ErrorMsg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "block {} of job {} failed in the {} stage (retryable: {}): {}",
+            self.block_number, self.job_id, self.stage, self.retryable, self.error,
+        )
+    }
+}
+
+// stage of a block in the pipeline
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockStage {
+    // waiting for the admission of the proving queue
+    Queued,
+
+    // fetching the block data and generating the proving inputs
+    Fetching,
+
+    // proving inputs are generated and measured
+    InputsReady,
+
+    // proving inputs are sent to the proving-client
+    Dispatched,
+
+    // proving inputs are sent to the proving cluster
+    Proving,
+
+    // a subblock is proved by the proving cluster
+    SubblockProved {
+        subblock_index: u32,
+        num_subblocks: u32,
+    },
+
+    // block is proved successfully
+    Proved,
+
+    // block is failed in any stage
+    Failed,
+}
+
+impl fmt::Display for BlockStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Queued => write!(f, "queued"),
+            Self::Fetching => write!(f, "fetching"),
+            Self::InputsReady => write!(f, "inputs-ready"),
+            Self::Dispatched => write!(f, "dispatched"),
+            Self::Proving => write!(f, "proving"),
+            Self::SubblockProved {
+                subblock_index,
+                num_subblocks,
+            } => write!(f, "subblock-proved {subblock_index}/{num_subblocks}"),
+            Self::Proved => write!(f, "proved"),
+            Self::Failed => write!(f, "failed"),
+        }
+    }
 }
 
 // proving request message
-#[derive(Clone, Constructor, Debug)]
+#[derive(Clone, Constructor, Debug, Deserialize, Serialize)]
 pub struct ProvingMsg {
     // block fetch report
     pub fetch_report: BlockProvingReport,
@@ -99,5 +562,15 @@ pub type BlockMsgSender = UnboundedSender<BlockMsg>;
 pub type BlockMsgReceiver = UnboundedReceiver<BlockMsg>;
 pub type BlockMsgEndpoint = DuplexUnboundedEndpoint<BlockMsg, BlockMsg>;
 
+pub type BoundedBlockMsgSender = Sender<BlockMsg>;
+pub type BoundedBlockMsgReceiver = Receiver<BlockMsg>;
+pub type BoundedBlockMsgEndpoint = DuplexBoundedEndpoint<BlockMsg, BlockMsg>;
+
+pub type BlockMsgChannelSender = ChannelSender<BlockMsg>;
+pub type BlockMsgChannelReceiver = ChannelReceiver<BlockMsg>;
+
 pub type FetchMsgSender = UnboundedSender<FetchMsg>;
 pub type FetchMsgReceiver = UnboundedReceiver<FetchMsg>;
+
+pub type PipelineErrorSender = UnboundedSender<PipelineError>;
+pub type PipelineErrorReceiver = UnboundedReceiver<PipelineError>;
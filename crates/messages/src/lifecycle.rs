@@ -0,0 +1,177 @@
+use crate::{BlockStage, JobId};
+use derive_more::Constructor;
+use std::{collections::HashMap, error::Error, fmt};
+
+// state of a block in its lifecycle, requested -> fetched -> dispatched -> proving -> proved or
+// failed; it's coarser than the stages reported to the watchers
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BlockState {
+    // block is requested and waiting for or in fetching
+    Requested,
+
+    // proving inputs of the block are generated
+    Fetched,
+
+    // proving inputs are sent to the proving-client
+    Dispatched,
+
+    // block is proving by the proving cluster
+    Proving,
+
+    // block is proved successfully
+    Proved,
+
+    // block is failed in any stage
+    Failed,
+}
+
+impl BlockState {
+    // return true if the block stays in the state once entered
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Proved | Self::Failed)
+    }
+
+    // return true if a block could move from this state to the next one, a block could fail in
+    // any non-terminal state, and the requested and proving states are re-entered by the queueing
+    // and subblock progress
+    pub fn can_transition_to(&self, next: Self) -> bool {
+        match (self, next) {
+            (Self::Requested, Self::Requested | Self::Fetched) => true,
+            (Self::Fetched, Self::Dispatched) => true,
+            (Self::Dispatched, Self::Proving) => true,
+            (Self::Proving, Self::Proving | Self::Proved) => true,
+            (state, Self::Failed) => !state.is_terminal(),
+            _ => false,
+        }
+    }
+}
+
+impl From<BlockStage> for BlockState {
+    fn from(stage: BlockStage) -> Self {
+        match stage {
+            BlockStage::Queued | BlockStage::Fetching => Self::Requested,
+            BlockStage::InputsReady => Self::Fetched,
+            BlockStage::Dispatched => Self::Dispatched,
+            BlockStage::Proving | BlockStage::SubblockProved { .. } => Self::Proving,
+            BlockStage::Proved => Self::Proved,
+            BlockStage::Failed => Self::Failed,
+        }
+    }
+}
+
+impl fmt::Display for BlockState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Requested => write!(f, "requested"),
+            Self::Fetched => write!(f, "fetched"),
+            Self::Dispatched => write!(f, "dispatched"),
+            Self::Proving => write!(f, "proving"),
+            Self::Proved => write!(f, "proved"),
+            Self::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+// error of a block moving to a state not allowed from its current one
+#[derive(Clone, Constructor, Debug)]
+pub struct InvalidTransition {
+    // job id of the block
+    pub job_id: JobId,
+
+    // block number
+    pub block_number: u64,
+
+    // current state of the block; none if the block is not tracked
+    pub from: Option<BlockState>,
+
+    // state the block is moving to
+    pub to: BlockState,
+}
+
+impl fmt::Display for InvalidTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid transition of block {} of job {} from ",
+            self.block_number, self.job_id,
+        )?;
+        match self.from {
+            Some(from) => write!(f, "{from}")?,
+            None => write!(f, "untracked")?,
+        }
+        write!(f, " to {}", self.to)
+    }
+}
+
+impl Error for InvalidTransition {}
+
+// lifecycle states of the blocks by job id and block number
+#[derive(Debug, Default)]
+pub struct BlockLifecycles {
+    // current state of each tracked block
+    states: HashMap<(JobId, u64), BlockState>,
+}
+
+impl BlockLifecycles {
+    // move a block to the state of the stage and return the new state, the state is kept if the
+    // transition is invalid; a block seen the first time starts from the state since its earlier
+    // stages may be not observed, and an unknown job id (e.g. not echoed back by the provers)
+    // matches the tracked block of any job
+    pub fn advance(
+        &mut self,
+        job_id: JobId,
+        block_number: u64,
+        stage: BlockStage,
+    ) -> Result<BlockState, InvalidTransition> {
+        let next = BlockState::from(stage);
+        let Some(key) = self.find(job_id, block_number) else {
+            self.states.insert((job_id, block_number), next);
+            return Ok(next);
+        };
+
+        let state = self.states[&key];
+        if !state.can_transition_to(next) {
+            return Err(InvalidTransition::new(
+                key.0,
+                block_number,
+                Some(state),
+                next,
+            ));
+        }
+        self.states.insert(key, next);
+
+        Ok(next)
+    }
+
+    // stop tracking a block once it's reported and return its last state
+    pub fn remove(&mut self, job_id: JobId, block_number: u64) -> Option<BlockState> {
+        let key = self.find(job_id, block_number)?;
+        self.states.remove(&key)
+    }
+
+    // return the number of the tracked blocks
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    // return true if no block is tracked
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    // find the key of a tracked block, the unknown job id matches any job
+    fn find(&self, job_id: JobId, block_number: u64) -> Option<(JobId, u64)> {
+        let key = (job_id, block_number);
+        if self.states.contains_key(&key) {
+            return Some(key);
+        }
+        if !job_id.is_unknown() {
+            return None;
+        }
+
+        self.states
+            .keys()
+            .find(|(_, tracked_block_number)| *tracked_block_number == block_number)
+            .copied()
+    }
+}
@@ -1,3 +1,10 @@
+use std::{env, path::PathBuf};
+
 fn main() {
-    tonic_build::compile_protos("proto/proof.proto").unwrap();
+    // save the file descriptor set for the grpc reflection service
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    tonic_build::configure()
+        .file_descriptor_set_path(out_dir.join("proof_descriptor.bin"))
+        .compile_protos(&["proto/proof.proto"], &["proto"])
+        .unwrap();
 }
@@ -1 +1,43 @@
+use tonic::{
+    Request,
+    metadata::{MetadataMap, MetadataValue},
+};
+
+pub mod traceparent;
+
 tonic::include_proto!("proof");
+
+// encoded file descriptor set of the protos for the grpc reflection service
+pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("proof_descriptor");
+
+// grpc metadata key of the shared token for authenticating the prover callbacks
+pub const AUTH_METADATA_KEY: &str = "authorization";
+
+// grpc metadata key of the block trace id, it's carried by the proving requests and should be
+// echoed back by the prover callbacks
+pub const TRACE_ID_METADATA_KEY: &str = "x-trace-id";
+
+// return the metadata value of the shared token
+pub fn bearer_token(token: &str) -> String {
+    format!("Bearer {token}")
+}
+
+// wrap a grpc message into a request carrying the trace id in the metadata, the trace id is
+// skipped if it's not a valid metadata value
+pub fn traced_request<T>(message: T, trace_id: &str) -> Request<T> {
+    let mut request = Request::new(message);
+    if let Ok(value) = MetadataValue::try_from(trace_id) {
+        request.metadata_mut().insert(TRACE_ID_METADATA_KEY, value);
+    }
+
+    request
+}
+
+// return the trace id carried in the grpc metadata, it's empty if not carried
+pub fn extract_trace_id(metadata: &MetadataMap) -> String {
+    metadata
+        .get(TRACE_ID_METADATA_KEY)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string()
+}
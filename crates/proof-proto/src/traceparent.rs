@@ -0,0 +1,162 @@
+use crate::{TRACE_ID_METADATA_KEY, extract_trace_id};
+use std::{
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hasher},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tonic::{
+    Request, Status,
+    metadata::MetadataValue,
+    service::{Interceptor, interceptor::InterceptedService},
+    transport::{Channel, Endpoint, Error},
+};
+
+// grpc metadata key of the W3C trace context
+pub const TRACEPARENT_METADATA_KEY: &str = "traceparent";
+
+// version of the W3C trace context sent by the interceptor
+const TRACEPARENT_VERSION: &str = "00";
+
+// invalid version of the W3C trace context
+const INVALID_TRACEPARENT_VERSION: &str = "ff";
+
+// trace flag of a sampled trace
+const SAMPLED_FLAG: u8 = 0x01;
+
+// sequence mixed into the generated span ids, so the ids generated at the same time differ
+static SPAN_ID_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+// grpc channel sending the W3C trace context of the requests
+pub type TracedChannel = InterceptedService<Channel, TraceParentInterceptor>;
+
+// W3C trace context of a grpc call, formatted as `00-<trace id>-<parent id>-<flags>`; the trace id
+// is the block trace id, so the traces of the orchestrator and the provers exported to the same
+// collector are joined by the block
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceParent {
+    // 32 lowercase hex digits of the trace id
+    pub trace_id: String,
+
+    // 16 lowercase hex digits of the span id of the caller
+    pub parent_id: String,
+
+    // trace flags, e.g. `01` if sampled
+    pub flags: u8,
+}
+
+impl TraceParent {
+    // create the trace context of a new call in a trace, it's none if the trace id is not valid,
+    // e.g. the unknown trace id of all zeros
+    pub fn new_call(trace_id: &str) -> Option<Self> {
+        is_hex_id(trace_id, 32).then(|| Self {
+            trace_id: trace_id.to_ascii_lowercase(),
+            parent_id: format!("{:016x}", generate_span_id()),
+            flags: SAMPLED_FLAG,
+        })
+    }
+
+    // parse a trace context, it's none if invalid and then ignored as the W3C spec requires; the
+    // fields appended by the later versions are skipped
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut fields = value.trim().split('-');
+        let version = fields.next()?;
+        let trace_id = fields.next()?;
+        let parent_id = fields.next()?;
+        let flags = fields.next()?;
+
+        let is_valid_version = version.len() == 2
+            && version.bytes().all(|byte| byte.is_ascii_hexdigit())
+            && !version.eq_ignore_ascii_case(INVALID_TRACEPARENT_VERSION)
+            && (version != TRACEPARENT_VERSION || fields.next().is_none());
+        if !is_valid_version
+            || !is_hex_id(trace_id, 32)
+            || !is_hex_id(parent_id, 16)
+            || flags.len() != 2
+        {
+            return None;
+        }
+
+        Some(Self {
+            trace_id: trace_id.to_ascii_lowercase(),
+            parent_id: parent_id.to_ascii_lowercase(),
+            flags: u8::from_str_radix(flags, 16).ok()?,
+        })
+    }
+}
+
+impl fmt::Display for TraceParent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{TRACEPARENT_VERSION}-{}-{}-{:02x}",
+            self.trace_id, self.parent_id, self.flags,
+        )
+    }
+}
+
+// interceptor of the grpc clients and servers converting between the block trace id and the W3C
+// trace context: a request carrying the trace id without a trace context is given the context of a
+// new call, e.g. the proving requests and prover callbacks, and a request carrying only a valid
+// trace context is given its trace id, e.g. from the provers propagating the W3C context only
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TraceParentInterceptor;
+
+impl Interceptor for TraceParentInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let metadata = request.metadata_mut();
+        let trace_id = extract_trace_id(metadata);
+        let trace_parent = metadata
+            .get(TRACEPARENT_METADATA_KEY)
+            .and_then(|value| value.to_str().ok())
+            .and_then(TraceParent::parse);
+
+        match trace_parent {
+            Some(trace_parent) if trace_id.is_empty() => {
+                if let Ok(value) = MetadataValue::try_from(trace_parent.trace_id.as_str()) {
+                    metadata.insert(TRACE_ID_METADATA_KEY, value);
+                }
+            }
+            None => {
+                if let Some(trace_parent) = TraceParent::new_call(&trace_id)
+                    && let Ok(value) = MetadataValue::try_from(trace_parent.to_string())
+                {
+                    metadata.insert(TRACEPARENT_METADATA_KEY, value);
+                }
+            }
+            Some(_) => {}
+        }
+
+        Ok(request)
+    }
+}
+
+// connect to a grpc endpoint by a channel sending the W3C trace context of the requests
+pub async fn connect_traced(url: String) -> Result<TracedChannel, Error> {
+    let channel = Endpoint::from_shared(url)?.connect().await?;
+
+    Ok(InterceptedService::new(channel, TraceParentInterceptor))
+}
+
+// identify if an id is the lowercase or uppercase hex digits of the length and not all zeros
+fn is_hex_id(id: &str, len: usize) -> bool {
+    id.len() == len
+        && id.bytes().all(|byte| byte.is_ascii_hexdigit())
+        && id.bytes().any(|byte| byte != b'0')
+}
+
+// generate a random non-zero span id
+fn generate_span_id() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or_default();
+    let sequence = SPAN_ID_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+
+    // the randomly seeded hasher spreads the time and sequence to the all bits
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(nanos);
+    hasher.write_u64(sequence);
+    hasher.finish().max(1)
+}
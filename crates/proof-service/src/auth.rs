@@ -0,0 +1,48 @@
+use proof_proto::{AUTH_METADATA_KEY, bearer_token};
+use tonic::{Request, Status, service::Interceptor};
+use tracing::warn;
+
+// interceptor of the prover callbacks, only the requests carrying the shared token of the proving
+// cluster are accepted; the all requests are accepted if the token is not configured
+#[derive(Clone, Debug)]
+pub struct TokenInterceptor {
+    // expected metadata value of the shared token
+    expected: Option<String>,
+}
+
+impl TokenInterceptor {
+    pub fn new(token: Option<&str>) -> Self {
+        Self {
+            expected: token.map(bearer_token),
+        }
+    }
+}
+
+impl Interceptor for TokenInterceptor {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        let Some(expected) = &self.expected else {
+            return Ok(request);
+        };
+
+        let actual = request
+            .metadata()
+            .get(AUTH_METADATA_KEY)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        if !constant_time_eq(actual.as_bytes(), expected.as_bytes()) {
+            warn!(
+                "proof-service: rejected an unauthenticated request from {:?}",
+                request.remote_addr(),
+            );
+            return Err(Status::unauthenticated("invalid proof service token"));
+        }
+
+        Ok(request)
+    }
+}
+
+// compare the bytes without returning early on the first mismatch, to avoid leaking the token by
+// the response time
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,57 @@
+use common::{ids::JobId, utils::PipelineId};
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+// window for detecting the duplicate proof results of a block (in seconds)
+const DUPLICATE_WINDOW_SECONDS: u64 = 600;
+
+// key of a completed block by the pipeline, job id and block number echoed from the provers
+type CompletedBlockKey = (PipelineId, JobId, u64);
+
+// recently completed blocks for dropping the duplicate proof results, e.g. a prover retries its
+// callback after the first one is received; the blocks are forgotten after the window, so a block
+// could be proved again by a later request
+#[derive(Debug, Default)]
+pub struct CompletedBlocks {
+    // completion times of the blocks in the window
+    completed_at: Mutex<HashMap<CompletedBlockKey, Instant>>,
+}
+
+impl CompletedBlocks {
+    // record a completed block, return false if it's already completed in the window
+    pub fn insert(&self, pipeline_id: PipelineId, job_id: JobId, block_number: u64) -> bool {
+        let now = Instant::now();
+        let window = Duration::from_secs(DUPLICATE_WINDOW_SECONDS);
+
+        let mut completed_at = self
+            .completed_at
+            .lock()
+            .expect("proof-service: completed blocks lock is poisoned");
+        completed_at.retain(|_, time| now.duration_since(*time) <= window);
+        completed_at
+            .insert((pipeline_id, job_id, block_number), now)
+            .is_none()
+    }
+
+    // forget a completed block, e.g. its result is failed to forward
+    pub fn remove(&self, pipeline_id: PipelineId, job_id: JobId, block_number: u64) {
+        self.completed_at
+            .lock()
+            .expect("proof-service: completed blocks lock is poisoned")
+            .remove(&(pipeline_id, job_id, block_number));
+    }
+
+    // return true if the block is completed in the window
+    pub fn contains(&self, pipeline_id: PipelineId, job_id: JobId, block_number: u64) -> bool {
+        let window = Duration::from_secs(DUPLICATE_WINDOW_SECONDS);
+
+        self.completed_at
+            .lock()
+            .expect("proof-service: completed blocks lock is poisoned")
+            .get(&(pipeline_id, job_id, block_number))
+            .is_some_and(|time| time.elapsed() <= window)
+    }
+}
@@ -1,12 +1,32 @@
 use derive_more::Constructor;
-use std::net::SocketAddr;
+use std::{net::SocketAddr, path::PathBuf};
 
 // proof grpc service configuration
-#[derive(Constructor, Debug)]
+#[derive(Clone, Constructor, Debug)]
 pub struct ProofServiceConfig {
     // proof grpc service address
     pub addr: SocketAddr,
 
     // maximum grpc message bytes
     pub max_msg_bytes: usize,
+
+    // maximum proof bytes uploaded in chunks by a proof stream
+    pub max_proof_bytes: usize,
+
+    // shared token required from the proving cluster callbacks; the callbacks are not
+    // authenticated if not specified
+    pub auth_token: Option<String>,
+
+    // tls certificate and key of the grpc server; the server is plaintext if not specified
+    pub tls: Option<ProofServiceTls>,
+}
+
+// tls configuration of the proof grpc service
+#[derive(Clone, Constructor, Debug)]
+pub struct ProofServiceTls {
+    // PEM encoded certificate chain file path
+    pub cert_path: PathBuf,
+
+    // PEM encoded private key file path
+    pub key_path: PathBuf,
 }
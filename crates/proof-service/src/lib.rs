@@ -1,2 +1,6 @@
+pub mod auth;
+pub mod completed_blocks;
 pub mod config;
+pub mod quarantine_store;
 pub mod service;
+pub mod verifier;
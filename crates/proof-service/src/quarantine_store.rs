@@ -0,0 +1,56 @@
+use anyhow::Result;
+use common::storage::{InputStorage, open_input_storage};
+use proof_proto::CompleteProvingRequest;
+use prost::Message;
+use std::{
+    fmt,
+    path::Path,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// store of the proof results received for unknown or unexpected blocks, which are not in flight
+// in any pipeline; the results are kept as the protobuf encoded requests for manual inspection,
+// e.g. `pipeline0/block23264565/job3-1756684800000.pb`
+pub struct QuarantineStore {
+    // base directory or object store URL of the quarantined results
+    location: String,
+
+    // storage for writing the quarantined result files
+    storage: Arc<dyn InputStorage>,
+}
+
+impl QuarantineStore {
+    // open the quarantine store by the location, it could be a local directory or an object store
+    // URL prefixed with `s3://` or `gs://`
+    pub fn open(location: &Path) -> Result<Self> {
+        let storage = open_input_storage(location)?;
+        let location = location.to_string_lossy().trim_end_matches('/').to_string();
+
+        Ok(Self { location, storage })
+    }
+
+    // write a quarantined proof result and return its storage reference, the results of the same
+    // block are distinguished by the receiving time
+    pub async fn save(&self, proved_msg: &CompleteProvingRequest) -> Result<String> {
+        let timestamp_milliseconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis())
+            .unwrap_or_default();
+        let key = format!(
+            "pipeline{}/block{}/job{}-{timestamp_milliseconds}.pb",
+            proved_msg.pipeline_id, proved_msg.block_number, proved_msg.job_id,
+        );
+        self.storage.write(&key, proved_msg.encode_to_vec()).await?;
+
+        Ok(format!("{}/{key}", self.location))
+    }
+}
+
+impl fmt::Debug for QuarantineStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QuarantineStore")
+            .field("location", &self.location)
+            .finish_non_exhaustive()
+    }
+}
@@ -1,37 +1,107 @@
-use crate::config::ProofServiceConfig;
-use derive_more::Constructor;
-use messages::{BlockMsg, BlockMsgSender};
+use crate::{
+    auth::TokenInterceptor,
+    completed_blocks::CompletedBlocks,
+    config::{ProofServiceConfig, ProofServiceTls},
+    quarantine_store::QuarantineStore,
+    verifier::ProofVerifier,
+};
+use anyhow::{Result, anyhow};
+use common::{
+    ids::JobId,
+    in_flight::InFlightBlocks,
+    metrics::MetricsRegistry,
+    proof_store::{ProofStore, proof_hash},
+    shutdown::{ShutdownPhase, shutdown_reached},
+    trace::{TraceId, block_span},
+};
+use messages::{BlockMsg, BlockMsgSender, BlockStage, ErrorMsg, StatusMsg};
 use proof_proto::{
-    CompleteProvingRequest,
+    CompleteProvingChunk, CompleteProvingRequest, FILE_DESCRIPTOR_SET, ProvingErrorCode,
+    ReportProgressRequest,
+    complete_proving_chunk::Content,
+    extract_trace_id,
     proof_server::{Proof, ProofServer},
+    traceparent::TraceParentInterceptor,
+};
+use std::{fs, sync::Arc};
+use tokio::{
+    spawn,
+    task::{JoinHandle, spawn_blocking},
 };
-use std::sync::Arc;
-use tokio::{signal::ctrl_c, spawn, task::JoinHandle};
 use tonic::{
-    Request, Response, Status, async_trait, codec::CompressionEncoding, service::LayerExt,
-    transport::Server,
+    Request, Response, Status, Streaming, async_trait,
+    codec::CompressionEncoding,
+    service::{LayerExt, interceptor::InterceptedService},
+    transport::{Identity, Server, ServerTlsConfig},
 };
+use tonic_health::server::health_reporter;
 use tonic_web::GrpcWebLayer;
 use tower::ServiceBuilder;
 use tower_http::cors::{Any, CorsLayer};
-use tracing::info;
+use tracing::{Instrument, error, info, warn};
+
+// counter name of the proof results quarantined for unknown or unexpected blocks
+const QUARANTINED_RESULTS_METRIC: &str = "proof_service_quarantined_results_total";
 
-#[derive(Constructor, Debug)]
+#[derive(Clone, Debug)]
 pub struct ProofService {
     // proof service configuration
     pub config: ProofServiceConfig,
 
     // communication sender for coordinating with the main scheduler
     pub comm_sender: Arc<BlockMsgSender>,
+
+    // verifier of the received proofs; the proofs are not verified if not specified
+    pub verifier: Option<Arc<ProofVerifier>>,
+
+    // store for persisting the received proofs; the proofs are carried by the reports if not
+    // specified
+    pub proof_store: Option<Arc<ProofStore>>,
+
+    // store for quarantining the proof results of unknown blocks; the results are dropped if not
+    // specified
+    pub quarantine_store: Option<Arc<QuarantineStore>>,
+
+    // registry of the blocks in flight shared with the proving-clients
+    pub in_flight_blocks: Arc<InFlightBlocks>,
+
+    // registry of the process metrics
+    pub metrics: Arc<MetricsRegistry>,
+
+    // recently completed blocks for dropping the duplicate proof results
+    completed_blocks: Arc<CompletedBlocks>,
 }
 
 impl ProofService {
+    pub fn new(
+        config: ProofServiceConfig,
+        comm_sender: Arc<BlockMsgSender>,
+        verifier: Option<Arc<ProofVerifier>>,
+        proof_store: Option<Arc<ProofStore>>,
+        quarantine_store: Option<Arc<QuarantineStore>>,
+        in_flight_blocks: Arc<InFlightBlocks>,
+        metrics: Arc<MetricsRegistry>,
+    ) -> Self {
+        Self {
+            config,
+            comm_sender,
+            verifier,
+            proof_store,
+            quarantine_store,
+            in_flight_blocks,
+            metrics,
+            completed_blocks: Arc::default(),
+        }
+    }
+
     pub fn run(self) -> JoinHandle<()> {
         info!("proof-service: start");
 
         spawn(async move {
             let addr = self.config.addr;
             let max_msg_bytes = self.config.max_msg_bytes;
+            let interceptor = TokenInterceptor::new(self.config.auth_token.as_deref());
+            let tls = self.config.tls.clone();
 
             // create the base grpc service
             let grpc = ProofServer::new(self)
@@ -40,6 +110,11 @@ impl ProofService {
                 .accept_compressed(CompressionEncoding::Zstd)
                 .send_compressed(CompressionEncoding::Zstd);
 
+            // authenticate the prover callbacks by the shared token, and carry the block trace id
+            // by the W3C trace context of the callbacks
+            let grpc = InterceptedService::new(grpc, interceptor);
+            let grpc = InterceptedService::new(grpc, TraceParentInterceptor);
+
             // add a web layer to the grpc service
             let service = ServiceBuilder::new()
                 .layer(
@@ -52,20 +127,239 @@ impl ProofService {
                 .into_inner()
                 .named_layer(grpc);
 
-            Server::builder()
+            // create the health and reflection services for probing and discovering the api
+            let (health_reporter, health_service) = health_reporter();
+            health_reporter
+                .set_serving::<ProofServer<ProofService>>()
+                .await;
+            let reflection_service = tonic_reflection::server::Builder::configure()
+                .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+                .build_v1()
+                .expect("proof-service: failed to build reflection service");
+
+            // enable tls if the certificate and key are specified
+            let mut server = Server::builder();
+            if let Some(tls) = tls {
+                info!(
+                    "proof-service: enabling tls by the certificate {:?}",
+                    tls.cert_path
+                );
+                server = server
+                    .tls_config(
+                        load_tls_config(&tls).expect("proof-service: failed to load tls config"),
+                    )
+                    .expect("proof-service: failed to apply tls config");
+            }
+
+            server
                 .accept_http1(true)
                 .add_service(service)
-                .serve_with_shutdown(addr, async {
-                    ctrl_c()
-                        .await
-                        .expect("proof-service: failed to wait for graceful shutdown");
-                })
+                .add_service(health_service)
+                .add_service(reflection_service)
+                .serve_with_shutdown(addr, shutdown_reached(ShutdownPhase::Flushing))
                 .await
                 .expect("proof-service: failed to start service");
 
             info!("proof-service: stopped");
         })
     }
+
+    // fill the trace id carried in the request metadata if the provers don't set it, and forward
+    // the proof result in the span of the block
+    async fn complete(
+        &self,
+        mut proved_msg: CompleteProvingRequest,
+        trace_id: String,
+    ) -> Result<Response<()>, Status> {
+        if proved_msg.trace_id.is_empty() {
+            proved_msg.trace_id = trace_id;
+        }
+        let span = block_span(
+            proved_msg.trace_id.parse().unwrap_or_default(),
+            proved_msg.job_id.into(),
+            proved_msg.block_number,
+        );
+
+        self.forward(proved_msg).instrument(span).await
+    }
+
+    // forward a received proof result to the proving-client
+    async fn forward(
+        &self,
+        mut proved_msg: CompleteProvingRequest,
+    ) -> Result<Response<()>, Status> {
+        let block_number = proved_msg.block_number;
+        let pipeline_id = proved_msg.pipeline_id;
+        let job_id = JobId::from(proved_msg.job_id);
+        info!("proof-service: received the proof result of block {block_number}");
+
+        // divert the result of a block not in flight to the quarantine, since the proving-client
+        // doesn't expect it; a recently completed block is a duplicate instead
+        if !self
+            .in_flight_blocks
+            .contains(pipeline_id, job_id, block_number)
+            && !self
+                .completed_blocks
+                .contains(pipeline_id, job_id, block_number)
+        {
+            self.quarantine(&proved_msg).await;
+            return Ok(Response::new(()));
+        }
+
+        // acknowledge a duplicate proof result without forwarding, since the proving-client no
+        // longer tracks the block
+        if self
+            .completed_blocks
+            .contains(pipeline_id, job_id, block_number)
+        {
+            warn!(
+                "proof-service: dropped a duplicate proof result of block {block_number} of job {}",
+                proved_msg.job_id,
+            );
+            return Ok(Response::new(()));
+        }
+
+        // a proof failed to verify is recorded as a proving failure instead of a success
+        let mut is_verification_failed = false;
+        if let Some(verifier) = self.verifier.clone()
+            && proved_msg.success
+        {
+            // a panic of the verifier on a malformed proof is a verification failure instead of
+            // crashing the handler
+            let proof = proved_msg.proof.clone().unwrap_or_default();
+            let result = spawn_blocking(move || verifier.verify(&proof))
+                .await
+                .unwrap_or_else(|e| Err(anyhow!("proof verification panicked: {e}")));
+            match result {
+                Ok(()) => info!("proof-service: verified the proof of block {block_number}"),
+                Err(e) => {
+                    error!(
+                        "proof-service: failed to verify the proof of block {block_number} {e:?}"
+                    );
+                    is_verification_failed = true;
+                    proved_msg.success = false;
+                    proved_msg.proof = None;
+                    proved_msg.set_error_code(ProvingErrorCode::VerificationFailure);
+                    proved_msg.error_message = Some(format!("{e:#}"));
+                }
+            }
+        }
+
+        // record the proof size and hash before the bytes are replaced by the storage reference
+        if let Some(proof) = &proved_msg.proof {
+            proved_msg.proof_bytes = proof.len() as u64;
+            proved_msg.proof_hash = proof_hash(proof);
+        }
+
+        // persist the proof and replace the bytes by the storage reference, the proof is kept in
+        // the message if it's failed to persist
+        if let Some(proof_store) = &self.proof_store
+            && let Some(proof) = proved_msg.proof.take()
+        {
+            match proof_store.save(job_id, block_number, proof.clone()).await {
+                Ok(proof_uri) => {
+                    info!(
+                        "proof-service: persisted the proof of block {block_number} to {proof_uri}"
+                    );
+                    proved_msg.proof_uri = Some(proof_uri);
+                }
+                Err(e) => {
+                    error!(
+                        "proof-service: failed to persist the proof of block {block_number} {e:?}"
+                    );
+                    proved_msg.proof = Some(proof);
+                }
+            }
+        }
+
+        // record the block as completed once the result is verified and the proof is written, a
+        // failed verification is not recorded so the valid proof of a retried callback is still
+        // accepted; a concurrent duplicate may complete the block during the verification or write
+        if !is_verification_failed
+            && !self
+                .completed_blocks
+                .insert(pipeline_id, job_id, block_number)
+        {
+            warn!(
+                "proof-service: dropped a duplicate proof result of block {block_number} of job {}",
+                proved_msg.job_id,
+            );
+            return Ok(Response::new(()));
+        }
+
+        // forget the completed block if the result is failed to forward, so the prover could retry
+        // the callback
+        let msg = BlockMsg::Proved(proved_msg);
+        if let Err(e) = self.comm_sender.send(msg) {
+            error!(
+                "proof-service: failed to forward the proof result of block {block_number} {e:?}"
+            );
+            self.completed_blocks
+                .remove(pipeline_id, job_id, block_number);
+            return Err(Status::unavailable(format!(
+                "failed to forward the proof result of block {block_number}"
+            )));
+        }
+
+        Ok(Response::new(()))
+    }
+
+    // quarantine a proof result of an unknown or unexpected block instead of forwarding it, an
+    // error message is sent if the result is dropped
+    async fn quarantine(&self, proved_msg: &CompleteProvingRequest) {
+        let block_number = proved_msg.block_number;
+        let pipeline_id = proved_msg.pipeline_id;
+        warn!(
+            "proof-service: received the proof result of block {block_number} of job {} which is not in flight in pipeline {pipeline_id}",
+            proved_msg.job_id,
+        );
+        self.metrics.inc_counter(
+            QUARANTINED_RESULTS_METRIC,
+            &[("pipeline", &pipeline_id.to_string())],
+        );
+
+        let Some(quarantine_store) = &self.quarantine_store else {
+            warn!("proof-service: dropped the unknown proof result of block {block_number}");
+            self.send_error(
+                proved_msg,
+                "dropped the proof result not in flight".to_string(),
+            );
+            return;
+        };
+        match quarantine_store.save(proved_msg).await {
+            Ok(uri) => {
+                warn!(
+                    "proof-service: quarantined the proof result of block {block_number} to {uri}"
+                )
+            }
+            Err(e) => {
+                error!(
+                    "proof-service: failed to quarantine the proof result of block {block_number} {e:?}"
+                );
+                self.send_error(
+                    proved_msg,
+                    format!("failed to quarantine the proof result not in flight: {e:#}"),
+                );
+            }
+        }
+    }
+
+    // send an error message of a dropped proof result, it's not retryable since the block is not
+    // expected by the proving-client
+    fn send_error(&self, proved_msg: &CompleteProvingRequest, error: String) {
+        let block_number = proved_msg.block_number;
+        let msg = BlockMsg::Error(ErrorMsg::new(
+            proved_msg.job_id.into(),
+            block_number,
+            BlockStage::Proved,
+            error,
+            false,
+            proved_msg.trace_id.parse().unwrap_or_default(),
+        ));
+        if let Err(e) = self.comm_sender.send(msg) {
+            warn!("proof-service: failed to send the error of block {block_number} {e:?}");
+        }
+    }
 }
 
 #[async_trait]
@@ -74,15 +368,89 @@ impl Proof for ProofService {
         &self,
         request: Request<CompleteProvingRequest>,
     ) -> Result<Response<()>, Status> {
-        // send the proved message
-        let proved_msg = request.into_inner();
-        let block_number = proved_msg.block_number;
-        info!("proof-service: received the proof result of block {block_number}");
-        let msg = BlockMsg::Proved(proved_msg);
-        self.comm_sender
-            .send(msg)
-            .expect("proof-service: failed to send a proved message of block {block_number}");
+        let trace_id = extract_trace_id(request.metadata());
+        self.complete(request.into_inner(), trace_id).await
+    }
+
+    async fn complete_proving_stream(
+        &self,
+        request: Request<Streaming<CompleteProvingChunk>>,
+    ) -> Result<Response<()>, Status> {
+        // assemble the proof from the chunks until the trailing metadata message
+        let trace_id = extract_trace_id(request.metadata());
+        let mut stream = request.into_inner();
+        let mut proof = vec![];
+        while let Some(chunk) = stream.message().await? {
+            match chunk.content {
+                Some(Content::ProofChunk(bytes)) => {
+                    // reject the stream before buffering the proof beyond the limit
+                    let max_proof_bytes = self.config.max_proof_bytes;
+                    if proof.len() + bytes.len() > max_proof_bytes {
+                        warn!(
+                            "proof-service: rejected a proof stream exceeding {max_proof_bytes} bytes"
+                        );
+                        return Err(Status::resource_exhausted(format!(
+                            "proof exceeds the maximum {max_proof_bytes} bytes"
+                        )));
+                    }
+                    proof.extend_from_slice(&bytes);
+                }
+                Some(Content::Metadata(mut proved_msg)) => {
+                    info!(
+                        "proof-service: received {} proof bytes of block {} by stream",
+                        proof.len(),
+                        proved_msg.block_number,
+                    );
+                    if !proof.is_empty() {
+                        proved_msg.proof = Some(proof);
+                    }
+                    return self.complete(proved_msg, trace_id).await;
+                }
+                None => return Err(Status::invalid_argument("received an empty proof chunk")),
+            }
+        }
+
+        Err(Status::invalid_argument(
+            "proof stream ended without the metadata message",
+        ))
+    }
+
+    async fn report_progress(
+        &self,
+        request: Request<ReportProgressRequest>,
+    ) -> Result<Response<()>, Status> {
+        // forward the progress as a status message to the proving-client and reporter
+        let trace_id: TraceId = extract_trace_id(request.metadata())
+            .parse()
+            .unwrap_or_default();
+        let progress = request.into_inner();
+        let job_id = JobId::from(progress.job_id);
+        let block_number = progress.block_number;
+        let stage = BlockStage::SubblockProved {
+            subblock_index: progress.subblock_index,
+            num_subblocks: progress.num_subblocks,
+        };
+        block_span(trace_id, job_id, block_number).in_scope(|| {
+            info!("proof-service: received the {stage} progress of block {block_number}")
+        });
+        let msg = BlockMsg::Status(StatusMsg::new(job_id, block_number, stage, trace_id));
+        if let Err(e) = self.comm_sender.send(msg) {
+            error!(
+                "proof-service: failed to forward the {stage} progress of block {block_number} {e:?}"
+            );
+            return Err(Status::unavailable(format!(
+                "failed to forward the {stage} progress of block {block_number}"
+            )));
+        }
 
         Ok(Response::new(()))
     }
 }
+
+// load the tls config by the PEM encoded certificate and key files
+fn load_tls_config(tls: &ProofServiceTls) -> Result<ServerTlsConfig> {
+    let cert = fs::read(&tls.cert_path)?;
+    let key = fs::read(&tls.key_path)?;
+
+    Ok(ServerTlsConfig::new().identity(Identity::from_pem(cert, key)))
+}
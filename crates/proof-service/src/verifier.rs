@@ -0,0 +1,92 @@
+use anyhow::{Context, Result, ensure};
+use pico_sdk::client::DefaultProverClient;
+use pico_vm::{
+    configs::stark_config::KoalaBearBn254Poseidon2, machine::proof::MetaProof,
+    proverchain::ProverChain,
+};
+use std::{fmt, fs, path::Path};
+
+// final proof of a block returned by the proving cluster, the riscv proof of the aggregator is
+// recursively converted, combined, compressed and embedded to the bn254 field
+pub type FinalProof = MetaProof<KoalaBearBn254Poseidon2>;
+
+// verifier of the final proofs returned by the proving cluster, the proofs are checked by the
+// embed stage verifier against the verification key of the aggregator elf before they are recorded
+// as a success
+pub struct ProofVerifier {
+    // prover client of the aggregator elf holding the verification key
+    agg_prover_client: DefaultProverClient,
+}
+
+impl ProofVerifier {
+    // create a verifier by the aggregator elf file
+    pub fn new(agg_elf_path: &Path) -> Result<Self> {
+        let agg_elf = fs::read(agg_elf_path)?;
+        let agg_prover_client = DefaultProverClient::new(&agg_elf);
+
+        Ok(Self { agg_prover_client })
+    }
+
+    // deserialize and verify a bincode serialized proof against the aggregator verification key
+    pub fn verify(&self, proof: &[u8]) -> Result<()> {
+        self.verify_proof(proof)?;
+
+        Ok(())
+    }
+
+    // verify a bincode serialized proof like `verify` and return its committed public values, e.g.
+    // the bincode serialized block hash committed by the aggregator
+    pub fn verify_public_values(&self, proof: &[u8]) -> Result<Vec<u8>> {
+        let proof = self.verify_proof(proof)?;
+
+        Ok(proof.pv_stream.unwrap_or_default())
+    }
+
+    // deserialize and verify a final proof, return the deserialized proof
+    fn verify_proof(&self, proof: &[u8]) -> Result<FinalProof> {
+        let proof: FinalProof =
+            bincode::deserialize(proof).context("failed to deserialize the proof")?;
+
+        let agg_vk = self.agg_prover_client.riscv_vk();
+        ensure!(
+            self.agg_prover_client.embed().verify(&proof, agg_vk),
+            "the proof is not valid for the aggregator verification key",
+        );
+
+        Ok(proof)
+    }
+}
+
+impl fmt::Debug for ProofVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProofVerifier").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, path::PathBuf};
+
+    // aggregator elf of the pinned pico revision
+    const AGG_ELF_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../data/aggregator-elf");
+
+    // the proof fixture is a final proof returned by the proving cluster for the aggregator elf,
+    // it's not committed for its size, so the path is specified by `AGG_PROOF_FIXTURE`
+    #[test]
+    #[ignore = "requires a final proof of the proving cluster specified by `AGG_PROOF_FIXTURE`"]
+    fn verify_cluster_proof() {
+        let fixture_path = PathBuf::from(env::var("AGG_PROOF_FIXTURE").unwrap());
+        let proof = fs::read(fixture_path).unwrap();
+        let verifier = ProofVerifier::new(Path::new(AGG_ELF_PATH)).unwrap();
+
+        let public_values = verifier.verify_public_values(&proof).unwrap();
+        assert!(!public_values.is_empty());
+
+        // a tampered proof is rejected
+        let mut tampered_proof = proof;
+        let index = tampered_proof.len() / 2;
+        tampered_proof[index] ^= 1;
+        assert!(verifier.verify(&tampered_proof).is_err());
+    }
+}
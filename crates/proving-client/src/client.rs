@@ -1,43 +1,88 @@
-use crate::config::ProvingClientConfig;
+use crate::{
+    config::ProvingClientConfig,
+    discovery::{DEFAULT_DISCOVERY_INTERVAL_SECONDS, SubblockDiscovery},
+    nats::{NatsDispatch, NatsDispatchConfig},
+};
 use aggregator_proto::{ProveAggregationRequest, aggregator_client::AggregatorClient};
-use common::inputs::ProvingInputs;
+use common::{
+    channel::WatchReceiver,
+    in_flight::InFlightBlocks,
+    inputs::ProvingInputs,
+    readiness::ReadinessCheck,
+    report::{BlockProvingReport, ProvedSubblock, ProvingErrorCode},
+    runtime::{ProvingUrls, RuntimeState},
+    shutdown::{ShutdownPhase, shutdown_reached},
+    trace::TraceId,
+};
 use derive_more::Constructor;
+use docker_control::control::DockerControl;
 use itertools::Itertools;
-use messages::{BlockMsg, BlockMsgEndpoint};
-use std::{collections::VecDeque, sync::Arc};
+use messages::{
+    BlockMsg, BlockStage, BoundedBlockMsgEndpoint, ControlMsg, ErrorMsg, JobId, ProvingMsg,
+    StatusMsg,
+    lifecycle::{BlockState, InvalidTransition},
+};
+use proof_proto::{
+    ProvingErrorCode as ProvedErrorCode, SubblockResult, traced_request,
+    traceparent::{TracedChannel, connect_traced},
+};
+use prost::Message;
+use reqwest::Url;
+use std::{
+    collections::{HashMap, VecDeque},
+    mem, slice,
+    sync::Arc,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
 use subblock_proto::{ProveSubblockRequest, subblock_client::SubblockClient};
 use tokio::{
-    process::Command,
     select, spawn,
     task::JoinHandle,
-    time::{Duration, sleep, timeout},
+    time::{Duration, MissedTickBehavior, interval, sleep},
 };
 use tokio_util::sync::CancellationToken;
-use tonic::{codec::CompressionEncoding, transport::Channel};
-use tracing::{error, info, warn};
-
-// maximum waiting time for proving complete
-const MAX_PROVING_WAITING_SECONDS: u64 = 120;
-
-// wait time after docker retry before reinitializing clients (in seconds)
-const DOCKER_RETRY_WAIT_SECONDS: u64 = 10;
+use tonic::codec::CompressionEncoding;
+use tracing::{Instrument, error, info, warn};
 
 // retry interval for client connection attempts (in seconds)
 const CLIENT_RETRY_INTERVAL_SECONDS: u64 = 2;
 
-// maximum number of retries for sending proving requests
-const MAX_PROVING_REQUEST_RETRIES: u32 = 50;
+// dispatcher of the proving inputs to the proving cluster
+enum Dispatcher {
+    // grpc clients of the aggregator and subblock services at the proving urls
+    Grpc {
+        proving_urls: ProvingUrls,
+        agg_client: AggregatorClient<TracedChannel>,
+        subblock_clients: Vec<SubblockClient<TracedChannel>>,
+    },
 
-// retry interval for proving request attempts (in seconds)
-const PROVING_REQUEST_RETRY_INTERVAL_SECONDS: u64 = 10;
+    // NATS JetStream subjects consumed by the prover workers
+    Nats(NatsDispatch),
+}
 
 #[derive(Constructor, Debug)]
 pub struct ProvingClient {
     // proving client configuration
     config: ProvingClientConfig,
 
-    // communication endpoint for coordinating with the main scheduler
-    comm_endpoint: Arc<BlockMsgEndpoint>,
+    // bounded communication endpoint for coordinating with the main scheduler, the sending waits
+    // for the capacity if the scheduler is behind
+    comm_endpoint: Arc<BoundedBlockMsgEndpoint>,
+
+    // registry of the blocks in flight shared with the proof-service for validating the results
+    in_flight_blocks: Arc<InFlightBlocks>,
+
+    // runtime configuration of the process, the changed proving timeout and urls of the pipeline
+    // are applied without restarting
+    runtime_state: WatchReceiver<RuntimeState>,
+
+    // readiness check passed once connected to the proving cluster, it's pending again while
+    // reconnecting
+    readiness: ReadinessCheck,
+
+    // control of the prover containers restarted on a proving timeout, the block is resent
+    // without restarting if not configured
+    docker_control: Option<Arc<DockerControl>>,
 }
 
 impl ProvingClient {
@@ -47,20 +92,41 @@ impl ProvingClient {
         let cancellation_token = CancellationToken::new();
         let token = cancellation_token.clone();
 
-        // Set up signal handling for graceful shutdown
+        // stop connecting the proving cluster once the process stops the proving services, the
+        // current proving block is drained before it
         let shutdown_token = token.clone();
         tokio::spawn(async move {
-            tokio::signal::ctrl_c()
-                .await
-                .expect("Failed to listen for ctrl+c");
-            info!("proving-client: received ctrl+c, initiating graceful shutdown");
+            shutdown_reached(ShutdownPhase::Flushing).await;
+            info!("proving-client: initiating graceful shutdown");
             shutdown_token.cancel();
         });
 
         spawn(async move {
+            let mut runtime_state = self.runtime_state.clone();
+            let state = runtime_state.latest();
+            let mut proving_timeout = self.proving_timeout(&state);
+            // the subblock urls are replaced by the discovered pods if the discovery is enabled
+            let mut discovered_urls = match &self.config.subblock_discovery {
+                Some(discovery) => Some(self.discover_subblock_urls(&token, discovery).await),
+                None => None,
+            };
+            let mut discovery_interval = interval(self.config.subblock_discovery.as_ref().map_or(
+                Duration::from_secs(DEFAULT_DISCOVERY_INTERVAL_SECONDS),
+                |discovery| discovery.interval,
+            ));
+            discovery_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            discovery_interval.reset();
+            self.check_docker_containers().await;
+
             info!("proving-client: initialize aggregator and subblock proving clients");
-            let mut agg_client = self.init_agg_proving_client(&token).await;
-            let mut subblock_clients = self.init_subblock_proving_clients(&token).await;
+            let mut dispatcher = match &self.config.nats_dispatch {
+                Some(config) => Dispatcher::Nats(self.init_nats_dispatch(&token, config).await),
+                None => {
+                    let proving_urls = self.proving_urls(&state, discovered_urls.as_deref());
+                    self.init_grpc_dispatcher(&token, proving_urls).await
+                }
+            };
+            self.readiness.pass();
 
             info!("proving-client: waiting for proving and proved messages");
             // variable for saving the block number proving in progress
@@ -69,33 +135,81 @@ impl ProvingClient {
             let mut last_proving_inputs: Option<ProvingInputs> = None;
             // queue for saving the pending messages when a block is proving
             let mut pending_msgs = VecDeque::new();
+            // identify if dispatching new blocks to the proving cluster is paused
+            let mut paused = false;
+            // identify if the pending blocks are being drained
+            let mut draining = false;
+            // time of the last observed progress of the proving block, the timeout is counted
+            // since it
+            let mut progress_at = Instant::now();
             loop {
-                // try to receive a proving or proved message with a timeout
-                let msg = timeout(
-                    Duration::from_secs(MAX_PROVING_WAITING_SECONDS),
-                    self.comm_endpoint.recv(),
-                )
-                .await;
-
-                match msg {
-                    Ok(Ok(BlockMsg::Proving(proving_msg))) => {
-                        if proving_block_report.is_none() {
-                            // send the proving inputs to aggregator and subblock grpc services
-                            send_proving_inputs(
-                                proving_msg.proving_inputs.clone(),
-                                &mut agg_client,
-                                &mut subblock_clients,
-                            )
-                            .await;
+                // try to receive a proving or proved message with a timeout, or apply the changed
+                // runtime configuration
+                let waiting_time = proving_timeout.saturating_sub(progress_at.elapsed());
+                let msg = select! {
+                    msg = self.comm_endpoint.recv_with_timeout(waiting_time) => msg,
+                    Some(state) = runtime_state.changed() => {
+                        proving_timeout = self.proving_timeout(&state);
 
-                            let report = proving_msg.fetch_report;
+                        // reconnect the proving cluster if the urls changed, the block proving in
+                        // progress is resent to the new cluster on timeout
+                        if let Dispatcher::Grpc { proving_urls, .. } = &dispatcher {
+                            let changed_urls =
+                                self.proving_urls(&state, discovered_urls.as_deref());
+                            if changed_urls != *proving_urls {
+                                info!(
+                                    "proving-client: reconnect the proving cluster at {}",
+                                    changed_urls.agg_url,
+                                );
+                                self.readiness.set_pending();
+                                dispatcher = self.init_grpc_dispatcher(&token, changed_urls).await;
+                                self.readiness.pass();
+                            }
+                        }
+                        continue;
+                    }
+                    _ = discovery_interval.tick(), if self.config.subblock_discovery.is_some() => {
+                        // the pods of a rescheduled or scaled prover are connected and the gone
+                        // ones dropped, the connections of the kept pods are reused
+                        let Some(changed_urls) = self.rediscover_subblock_urls().await else {
+                            continue;
+                        };
+                        if discovered_urls.as_ref() != Some(&changed_urls)
+                            && let Dispatcher::Grpc {
+                                proving_urls,
+                                subblock_clients,
+                                ..
+                            } = &mut dispatcher
+                        {
                             info!(
-                                "proving-client: save block {} as the current proving block in progress",
-                                report.block_number,
+                                "proving-client: discovered {} subblock provers {changed_urls:?}",
+                                changed_urls.len(),
                             );
-                            // save the proving inputs for potential retry on timeout
-                            last_proving_inputs = Some(proving_msg.proving_inputs);
+                            self.readiness.set_pending();
+                            *subblock_clients = self
+                                .update_subblock_proving_clients(
+                                    &token,
+                                    &proving_urls.subblock_urls,
+                                    mem::take(subblock_clients),
+                                    &changed_urls,
+                                )
+                                .await;
+                            self.readiness.pass();
+                            proving_urls.subblock_urls = changed_urls.clone();
+                            discovered_urls = Some(changed_urls);
+                        }
+                        continue;
+                    }
+                };
+
+                match msg {
+                    Ok(Some(BlockMsg::Proving(proving_msg))) => {
+                        if proving_block_report.is_none() && !paused {
+                            let (report, proving_inputs) =
+                                self.start_proving(proving_msg, &mut dispatcher).await;
+                            last_proving_inputs = Some(proving_inputs);
                             proving_block_report = Some(report);
+                            progress_at = Instant::now();
                         } else {
                             info!(
                                 "proving-client: save proving request of block {} to the pending queue",
@@ -104,115 +218,192 @@ impl ProvingClient {
                             pending_msgs.push_back(proving_msg);
                         }
                     }
-                    Ok(Ok(BlockMsg::Proved(proved_msg))) => {
-                        let mut report = proving_block_report.unwrap();
+                    Ok(Some(BlockMsg::Proved(proved_msg))) => {
+                        // only the block proving in progress could move to the proved state
+                        let Some(mut report) = proving_block_report
+                            .take_if(|report| report.block_number == proved_msg.block_number)
+                        else {
+                            let e = InvalidTransition::new(
+                                proved_msg.job_id.into(),
+                                proved_msg.block_number,
+                                None,
+                                BlockState::Proved,
+                            );
+                            error!("proving-client: ignored the proved message since {e}");
+                            continue;
+                        };
                         let block_number = report.block_number;
-                        proving_block_report = None;
-                        assert_eq!(
-                            block_number, proved_msg.block_number,
-                            "proving-client: the proved block is not consistent with the previous proving block",
-                        );
+                        self.in_flight_blocks
+                            .unregister(self.config.pipeline_id, block_number);
+
+                        // the job id is unknown if the prover doesn't echo it back
+                        if !report.job_id.matches(proved_msg.job_id.into()) {
+                            warn!(
+                                "proving-client: the proved job {} of block {block_number} is not consistent with the proving job {}",
+                                proved_msg.job_id, report.job_id,
+                            );
+                        }
 
                         // merge the proved result to the block report
                         if proved_msg.success {
+                            self.send_status(&report, BlockStage::Proved).await;
                             report.on_proving_success(
                                 proved_msg.cycles,
                                 proved_msg.proving_milliseconds,
-                                proved_msg.proof.unwrap(),
+                                proved_msg.proof,
+                                proved_msg.proof_uri,
+                                proved_msg.proof_bytes,
+                                (!proved_msg.proof_hash.is_empty())
+                                    .then_some(proved_msg.proof_hash),
+                            );
+                            report.on_subblocks_proved(
+                                proved_subblocks(
+                                    proved_msg.subblock_results,
+                                    proved_msg.subblock_cycles,
+                                ),
+                                proved_msg.aggregation_milliseconds,
                             );
                         } else {
-                            report.on_proving_failure();
+                            self.send_status(&report, BlockStage::Failed).await;
+                            let error_code = proving_error_code(proved_msg.error_code());
+                            warn!(
+                                "proving-client: failed to prove block {block_number} with error code {error_code:?}: {:?}",
+                                proved_msg.error_message,
+                            );
+                            self.send_error(
+                                &report,
+                                proved_msg.error_message.clone().unwrap_or_default(),
+                                error_code.is_some_and(|error_code| error_code.is_retryable()),
+                            )
+                            .await;
+                            report.on_proving_failure(error_code, proved_msg.error_message);
                         }
 
-                        info!("proving-client: send the report message of block {block_number}");
+                        report.span().in_scope(|| {
+                            info!(
+                                "proving-client: send the report message of block {block_number} of job {}",
+                                report.job_id,
+                            )
+                        });
                         let msg = BlockMsg::Report(report);
                         self.comm_endpoint
                             .send(msg)
+                            .await
                             .expect("proving-client: failed to send report message");
 
-                        // process the next pending block
-                        if let Some(proving_msg) = pending_msgs.pop_front() {
-                            // send the proving inputs to aggregator and subblock grpc services
-                            send_proving_inputs(
-                                proving_msg.proving_inputs.clone(),
-                                &mut agg_client,
-                                &mut subblock_clients,
-                            )
-                            .await;
+                        // process the next pending block unless paused
+                        if paused {
+                            info!("proving-client: paused after block {block_number}");
+                        } else if let Some(proving_msg) = pending_msgs.pop_front() {
+                            let (report, proving_inputs) =
+                                self.start_proving(proving_msg, &mut dispatcher).await;
+                            last_proving_inputs = Some(proving_inputs);
+                            proving_block_report = Some(report);
+                            progress_at = Instant::now();
+                        } else if draining {
+                            info!("proving-client: drained the all fetched blocks");
+                        }
+                    }
+                    Ok(Some(BlockMsg::Control(control_msg))) => {
+                        info!("proving-client: received a {control_msg} control message");
+                        match control_msg {
+                            ControlMsg::Pause => paused = true,
+                            ControlMsg::Resume | ControlMsg::Drain => {
+                                // a drain keeps proving the pending blocks until the queue is empty
+                                paused = false;
+                                draining = control_msg == ControlMsg::Drain;
 
-                            let report = proving_msg.fetch_report;
+                                if proving_block_report.is_none() {
+                                    if let Some(proving_msg) = pending_msgs.pop_front() {
+                                        let (report, proving_inputs) =
+                                            self.start_proving(proving_msg, &mut dispatcher).await;
+                                        last_proving_inputs = Some(proving_inputs);
+                                        proving_block_report = Some(report);
+                                        progress_at = Instant::now();
+                                    } else if draining {
+                                        info!("proving-client: drained the all fetched blocks");
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    Ok(Some(BlockMsg::Status(status_msg))) => {
+                        // the progress of the proving block reported by the proving cluster
+                        // resets the timeout, the other status messages are ignored
+                        let is_proving_block =
+                            proving_block_report.as_ref().is_some_and(|report| {
+                                report.block_number == status_msg.block_number
+                                    && report.job_id.matches(status_msg.job_id)
+                            });
+                        if is_proving_block
+                            && matches!(status_msg.stage, BlockStage::SubblockProved { .. })
+                        {
                             info!(
-                                "proving-client: save block {} as the current proving block in progress",
-                                report.block_number,
+                                "proving-client: observed the {} progress of block {}",
+                                status_msg.stage, status_msg.block_number,
                             );
-                            // save the proving inputs for potential retry on timeout
-                            last_proving_inputs = Some(proving_msg.proving_inputs);
-                            proving_block_report = Some(report);
+                            progress_at = Instant::now();
                         }
                     }
-                    Err(_) => {
-                        if let Some(_report) = &proving_block_report {
-                            let block_number = _report.block_number;
+                    Ok(None) => {
+                        progress_at = Instant::now();
+                        if let Some(report) = &proving_block_report {
+                            let block_number = report.block_number;
+                            let job_id = report.job_id;
+                            let trace_id = report.trace_id;
                             warn!("proving-client: proving timeout for block {block_number}");
-                            warn!(
-                                "proving-client: attempting to restart docker containers and retry"
-                            );
+                            self.readiness.set_pending();
 
-                            // Step 1: Restart docker containers using the retry script
-                            let retry_result = Command::new("./scripts/docker-multi-control.sh")
-                                .arg("retry")
-                                .status()
-                                .await;
-
-                            match retry_result {
-                                Ok(status) if status.success() => {
-                                    info!(
-                                        "proving-client: docker containers restarted successfully"
-                                    );
-                                }
-                                Ok(status) => {
-                                    error!(
-                                        "proving-client: docker retry script failed with exit code: {:?}",
-                                        status.code()
-                                    );
-                                    panic!(
-                                        "proving-client: cannot recover from docker restart failure - manual intervention required"
-                                    );
-                                }
-                                Err(e) => {
-                                    error!(
-                                        "proving-client: failed to execute docker retry script: {}",
-                                        e
-                                    );
-                                    panic!(
-                                        "proving-client: cannot recover from docker restart failure - manual intervention required"
+                            // Step 1: Restart the prover containers and wait for them to be healthy
+                            match &self.docker_control {
+                                Some(docker_control) => {
+                                    warn!(
+                                        "proving-client: attempting to restart docker containers and retry"
                                     );
+                                    match docker_control.restart_containers().await {
+                                        Ok(()) => info!(
+                                            "proving-client: docker containers restarted successfully"
+                                        ),
+                                        Err(e) => {
+                                            error!(
+                                                "proving-client: failed to restart docker containers {e:?}"
+                                            );
+                                            panic!(
+                                                "proving-client: cannot recover from docker restart failure - manual intervention required"
+                                            );
+                                        }
+                                    }
                                 }
+                                None => warn!(
+                                    "proving-client: docker control is not configured, retrying without restarting containers"
+                                ),
                             }
 
-                            // Step 2: Wait for containers to fully initialize
-                            info!(
-                                "proving-client: waiting {}s for docker containers to initialize",
-                                DOCKER_RETRY_WAIT_SECONDS
-                            );
-                            sleep(Duration::from_secs(DOCKER_RETRY_WAIT_SECONDS)).await;
-
-                            // Step 3: Reinitialize aggregator and subblock clients
-                            info!("proving-client: reinitializing aggregator and subblock clients");
-                            agg_client = self.init_agg_proving_client(&token).await;
-                            subblock_clients = self.init_subblock_proving_clients(&token).await;
+                            // Step 2: Reinitialize aggregator and subblock clients, the NATS
+                            // client reconnects by itself
+                            if let Dispatcher::Grpc { proving_urls, .. } = &dispatcher {
+                                info!(
+                                    "proving-client: reinitializing aggregator and subblock clients"
+                                );
+                                let proving_urls = proving_urls.clone();
+                                dispatcher = self.init_grpc_dispatcher(&token, proving_urls).await;
+                            }
+                            self.readiness.pass();
 
-                            // Step 4: Resend the last proving inputs to retry the failed block
+                            // Step 3: Resend the last proving inputs to retry the failed block
                             if let Some(ref inputs) = last_proving_inputs {
                                 info!(
                                     "proving-client: resending proving inputs for block {}",
                                     block_number
                                 );
                                 send_proving_inputs(
+                                    &self.config,
+                                    job_id,
+                                    trace_id,
                                     inputs.clone(),
-                                    &mut agg_client,
-                                    &mut subblock_clients,
+                                    &mut dispatcher,
                                 )
+                                .instrument(report.span())
                                 .await;
                                 info!(
                                     "proving-client: proving inputs resent, continuing to wait for proof"
@@ -233,13 +424,294 @@ impl ProvingClient {
         })
     }
 
+    // send the proving inputs of a block to the proving cluster, return the block report and the
+    // inputs saved for potential retry on timeout
+    async fn start_proving(
+        &self,
+        proving_msg: ProvingMsg,
+        dispatcher: &mut Dispatcher,
+    ) -> (BlockProvingReport, ProvingInputs) {
+        // register the block before sending, so a fast result is not taken as unknown
+        self.in_flight_blocks.register(
+            self.config.pipeline_id,
+            proving_msg.fetch_report.job_id,
+            proving_msg.fetch_report.block_number,
+        );
+        send_proving_inputs(
+            &self.config,
+            proving_msg.fetch_report.job_id,
+            proving_msg.fetch_report.trace_id,
+            proving_msg.proving_inputs.clone(),
+            dispatcher,
+        )
+        .instrument(proving_msg.fetch_report.span())
+        .await;
+
+        let report = proving_msg.fetch_report;
+        self.send_status(&report, BlockStage::Proving).await;
+        info!(
+            "proving-client: save block {} of job {} as the current proving block in progress",
+            report.block_number, report.job_id,
+        );
+
+        (report, proving_msg.proving_inputs)
+    }
+
+    // send a status message of a block entering the stage to the reporter
+    async fn send_status(&self, report: &BlockProvingReport, stage: BlockStage) {
+        let block_number = report.block_number;
+        let msg = BlockMsg::Status(StatusMsg::new(
+            report.job_id,
+            block_number,
+            stage,
+            report.trace_id,
+        ));
+        if let Err(e) = self.comm_endpoint.send(msg).await {
+            warn!(
+                "proving-client: failed to send the {stage} status of block {block_number} {e:?}"
+            );
+        }
+    }
+
+    // send an error message of a block failed in the proving cluster to the scheduler
+    async fn send_error(&self, report: &BlockProvingReport, error: String, retryable: bool) {
+        let block_number = report.block_number;
+        let msg = BlockMsg::Error(ErrorMsg::new(
+            report.job_id,
+            block_number,
+            BlockStage::Proving,
+            error,
+            retryable,
+            report.trace_id,
+        ));
+        if let Err(e) = self.comm_endpoint.send(msg).await {
+            warn!("proving-client: failed to send the error of block {block_number} {e:?}");
+        }
+    }
+
+    // list the prover containers restarted on a proving timeout, the missing or stopped ones are
+    // only warned since they may be started later
+    async fn check_docker_containers(&self) {
+        let Some(docker_control) = &self.docker_control else {
+            return;
+        };
+
+        match docker_control.list_containers().await {
+            Ok(statuses) => {
+                for status in statuses {
+                    match (&status.state, &status.status) {
+                        (Some(state), Some(detail)) if state == "running" => info!(
+                            "proving-client: docker container {} on {} is {detail}",
+                            status.name, status.host,
+                        ),
+                        (Some(state), _) => warn!(
+                            "proving-client: docker container {} on {} is {state}",
+                            status.name, status.host,
+                        ),
+                        (None, _) => warn!(
+                            "proving-client: docker container {} on {} doesn't exist",
+                            status.name, status.host,
+                        ),
+                    }
+                }
+            }
+            Err(e) => warn!("proving-client: failed to list docker containers {e:?}"),
+        }
+    }
+
+    // return the proving timeout of the runtime state, or the configured one if not set
+    fn proving_timeout(&self, state: &RuntimeState) -> Duration {
+        state
+            .proving_timeout_seconds
+            .map_or(self.config.proving_timeout, Duration::from_secs)
+    }
+
+    // return the proving urls of the pipeline in the runtime state, or the configured ones if not
+    // listed; the subblock urls are replaced by the discovered ones if any
+    fn proving_urls(
+        &self,
+        state: &RuntimeState,
+        discovered_urls: Option<&[String]>,
+    ) -> ProvingUrls {
+        let mut proving_urls = state
+            .proving_urls
+            .get(&self.config.pipeline_id)
+            .cloned()
+            .unwrap_or_else(|| {
+                let agg_url = self
+                    .config
+                    .agg_url
+                    .as_ref()
+                    .expect("proving-client: the aggregator url is required without NATS");
+                ProvingUrls::new(
+                    agg_url.to_string(),
+                    self.config
+                        .subblock_urls
+                        .iter()
+                        .map(Url::to_string)
+                        .collect(),
+                )
+            });
+        if let Some(discovered_urls) = discovered_urls {
+            proving_urls.subblock_urls = discovered_urls.to_vec();
+        }
+
+        proving_urls
+    }
+
+    // connect the grpc services at the proving urls
+    async fn init_grpc_dispatcher(
+        &self,
+        cancellation_token: &CancellationToken,
+        proving_urls: ProvingUrls,
+    ) -> Dispatcher {
+        let agg_client = self
+            .init_agg_proving_client(cancellation_token, &proving_urls.agg_url)
+            .await;
+        let subblock_clients = self
+            .init_subblock_proving_clients(cancellation_token, &proving_urls.subblock_urls)
+            .await;
+
+        Dispatcher::Grpc {
+            proving_urls,
+            agg_client,
+            subblock_clients,
+        }
+    }
+
+    // connect the NATS server and create the stream of the proving inputs
+    async fn init_nats_dispatch(
+        &self,
+        cancellation_token: &CancellationToken,
+        config: &NatsDispatchConfig,
+    ) -> NatsDispatch {
+        loop {
+            match NatsDispatch::connect(config).await {
+                Ok(nats_dispatch) => {
+                    info!(
+                        "proving-client: successfully connected to NATS at {} with stream {}",
+                        config.url, config.stream,
+                    );
+                    return nats_dispatch;
+                }
+                Err(e) => {
+                    warn!(
+                        "proving-client: failed to connect to NATS at {}: {e:?}",
+                        config.url
+                    );
+                    warn!(
+                        "proving-client: retrying in {}s",
+                        CLIENT_RETRY_INTERVAL_SECONDS
+                    );
+                }
+            }
+
+            select! {
+                _ = cancellation_token.cancelled() => {
+                    info!("proving-client: cancellation requested, stopping NATS initialization");
+                    panic!("proving-client: cancelled during NATS initialization");
+                }
+                _ = sleep(Duration::from_secs(CLIENT_RETRY_INTERVAL_SECONDS)) => {}
+            }
+        }
+    }
+
+    // resolve the subblock prover service until any pod is discovered
+    async fn discover_subblock_urls(
+        &self,
+        cancellation_token: &CancellationToken,
+        discovery: &SubblockDiscovery,
+    ) -> Vec<String> {
+        loop {
+            match discovery.resolve().await {
+                Ok(urls) if !urls.is_empty() => {
+                    info!(
+                        "proving-client: discovered {} subblock provers {urls:?} by {}",
+                        urls.len(),
+                        discovery.service,
+                    );
+                    return urls;
+                }
+                Ok(_) => warn!(
+                    "proving-client: no subblock prover is discovered by {}",
+                    discovery.service,
+                ),
+                Err(e) => warn!(
+                    "proving-client: failed to resolve the subblock provers by {}: {e}",
+                    discovery.service,
+                ),
+            }
+            warn!(
+                "proving-client: retrying in {}s",
+                CLIENT_RETRY_INTERVAL_SECONDS
+            );
+
+            select! {
+                _ = cancellation_token.cancelled() => {
+                    info!("proving-client: cancellation requested, stopping subblock discovery");
+                    panic!("proving-client: cancelled during subblock discovery");
+                }
+                _ = sleep(Duration::from_secs(CLIENT_RETRY_INTERVAL_SECONDS)) => {}
+            }
+        }
+    }
+
+    // resolve the subblock prover service again, it's none if failed or no pod is discovered so
+    // the current clients are kept, e.g. during a DNS outage or a rolling restart
+    async fn rediscover_subblock_urls(&self) -> Option<Vec<String>> {
+        let discovery = self.config.subblock_discovery.as_ref()?;
+        match discovery.resolve().await {
+            Ok(urls) if !urls.is_empty() => Some(urls),
+            Ok(_) => {
+                warn!(
+                    "proving-client: no subblock prover is discovered by {}, keeping the current ones",
+                    discovery.service,
+                );
+                None
+            }
+            Err(e) => {
+                warn!(
+                    "proving-client: failed to resolve the subblock provers by {}, keeping the current ones: {e}",
+                    discovery.service,
+                );
+                None
+            }
+        }
+    }
+
+    // update the subblock proving clients to the changed urls, the clients of the kept urls are
+    // reused and the new urls connected
+    async fn update_subblock_proving_clients(
+        &self,
+        cancellation_token: &CancellationToken,
+        subblock_urls: &[String],
+        subblock_clients: Vec<SubblockClient<TracedChannel>>,
+        changed_urls: &[String],
+    ) -> Vec<SubblockClient<TracedChannel>> {
+        let mut kept_clients: HashMap<&String, _> =
+            subblock_urls.iter().zip(subblock_clients).collect();
+        let mut updated_clients = Vec::with_capacity(changed_urls.len());
+        for url in changed_urls {
+            let client = match kept_clients.remove(url) {
+                Some(client) => client,
+                None => self
+                    .init_subblock_proving_clients(cancellation_token, slice::from_ref(url))
+                    .await
+                    .remove(0),
+            };
+            updated_clients.push(client);
+        }
+
+        updated_clients
+    }
+
     // initialize a aggregator proving client
     pub async fn init_agg_proving_client(
         &self,
         cancellation_token: &CancellationToken,
-    ) -> AggregatorClient<Channel> {
+        agg_url: &str,
+    ) -> AggregatorClient<TracedChannel> {
         let max_msg_bytes = self.config.max_msg_bytes;
-        let agg_url = self.config.agg_url.clone();
 
         loop {
             // Check for cancellation first
@@ -251,10 +723,10 @@ impl ProvingClient {
             }
 
             // Try to connect
-            match AggregatorClient::connect(agg_url.to_string()).await {
-                Ok(client) => {
+            match connect_traced(agg_url.to_string()).await {
+                Ok(channel) => {
                     info!("proving-client: successfully connected to aggregator at {agg_url}");
-                    return client
+                    return AggregatorClient::new(channel)
                         .max_encoding_message_size(max_msg_bytes)
                         .max_decoding_message_size(max_msg_bytes)
                         .accept_compressed(CompressionEncoding::Zstd)
@@ -286,9 +758,9 @@ impl ProvingClient {
     pub async fn init_subblock_proving_clients(
         &self,
         cancellation_token: &CancellationToken,
-    ) -> Vec<SubblockClient<Channel>> {
+        subblock_urls: &[String],
+    ) -> Vec<SubblockClient<TracedChannel>> {
         let max_msg_bytes = self.config.max_msg_bytes;
-        let subblock_urls = &self.config.subblock_urls;
         let mut subblock_clients = Vec::with_capacity(subblock_urls.len());
         for url in subblock_urls {
             let client = loop {
@@ -301,10 +773,10 @@ impl ProvingClient {
                 }
 
                 // Try to connect
-                match SubblockClient::connect(url.to_string()).await {
-                    Ok(client) => {
+                match connect_traced(url.to_string()).await {
+                    Ok(channel) => {
                         info!("proving-client: successfully connected to subblock at {url}");
-                        break client
+                        break SubblockClient::new(channel)
                             .max_encoding_message_size(max_msg_bytes)
                             .max_decoding_message_size(max_msg_bytes)
                             .accept_compressed(CompressionEncoding::Zstd)
@@ -338,10 +810,211 @@ impl ProvingClient {
     }
 }
 
+// convert the error code reported by the provers, it's none if unspecified
+fn proving_error_code(error_code: ProvedErrorCode) -> Option<ProvingErrorCode> {
+    match error_code {
+        ProvedErrorCode::Unspecified => None,
+        ProvedErrorCode::Oom => Some(ProvingErrorCode::Oom),
+        ProvedErrorCode::ConstraintFailure => Some(ProvingErrorCode::ConstraintFailure),
+        ProvedErrorCode::Timeout => Some(ProvingErrorCode::Timeout),
+        ProvedErrorCode::InputDecodeError => Some(ProvingErrorCode::InputDecodeError),
+        ProvedErrorCode::VerificationFailure => Some(ProvingErrorCode::VerificationFailure),
+    }
+}
+
+// convert the subblock results reported by the provers to the report, the legacy subblock cycles
+// are used if the provers don't report the results
+fn proved_subblocks(
+    subblock_results: Vec<SubblockResult>,
+    subblock_cycles: Vec<u64>,
+) -> Vec<ProvedSubblock> {
+    if subblock_results.is_empty() {
+        return subblock_cycles
+            .into_iter()
+            .enumerate()
+            .map(|(subblock_index, cycles)| ProvedSubblock {
+                subblock_index: subblock_index as u32,
+                cycles,
+                ..Default::default()
+            })
+            .collect();
+    }
+
+    subblock_results
+        .into_iter()
+        .map(|result| ProvedSubblock {
+            subblock_index: result.subblock_index,
+            cycles: result.cycles,
+            proving_milliseconds: result.proving_milliseconds,
+            prover_id: result.prover_id,
+        })
+        .collect()
+}
+
+// send the proving inputs of a block by the dispatcher
 async fn send_proving_inputs(
+    config: &ProvingClientConfig,
+    job_id: JobId,
+    trace_id: TraceId,
+    proving_inputs: ProvingInputs,
+    dispatcher: &mut Dispatcher,
+) {
+    match dispatcher {
+        Dispatcher::Grpc {
+            agg_client,
+            subblock_clients,
+            ..
+        } => {
+            send_grpc_proving_inputs(
+                config,
+                job_id,
+                trace_id,
+                proving_inputs,
+                agg_client,
+                subblock_clients,
+            )
+            .await
+        }
+        Dispatcher::Nats(nats_dispatch) => {
+            publish_proving_inputs(config, job_id, trace_id, proving_inputs, nats_dispatch).await
+        }
+    }
+}
+
+// publish the proving inputs of a block to the NATS subjects of the pipeline, each subblock input
+// is taken by one of the workers consuming the subject
+async fn publish_proving_inputs(
+    config: &ProvingClientConfig,
+    job_id: JobId,
+    trace_id: TraceId,
+    proving_inputs: ProvingInputs,
+    nats_dispatch: &NatsDispatch,
+) {
+    let block_number = proving_inputs.block_number;
+    let num_subblocks = proving_inputs.subblock_inputs.len();
+    assert!(num_subblocks > 0, "proving-client: no subblocks");
+    let num_subblocks = num_subblocks as u32;
+    let job_id = job_id.into();
+    let trace_id = trace_id.to_string();
+    let pipeline_id = config.pipeline_id;
+
+    // the message ids are unique per dispatch, so the retried publishes are deduplicated by the
+    // stream while a block resent on timeout is delivered again
+    let dispatched_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default();
+    let dispatch_id = format!("{pipeline_id}-{block_number}-{job_id}-{dispatched_at}");
+
+    info!("proving-client: publishing the aggregator input of block {block_number}");
+    let req = ProveAggregationRequest {
+        block_number,
+        job_id,
+        pipeline_id,
+        num_subblocks,
+        subblock_public_values: proving_inputs.subblock_public_values,
+        input: proving_inputs.agg_input,
+        input_digest: proving_inputs.agg_input_digest,
+    };
+    publish_with_retries(
+        config,
+        nats_dispatch,
+        nats_dispatch.aggregation_subject(pipeline_id),
+        format!("{dispatch_id}-aggregation"),
+        &trace_id,
+        &req,
+        "aggregator",
+    )
+    .await;
+
+    // unlike the grpc services, only the subblock inputs of the block are published since the
+    // workers are not bound to the subblock indexes
+    for (i, (input, input_digest)) in proving_inputs
+        .subblock_inputs
+        .into_iter()
+        .zip_eq(proving_inputs.subblock_input_digests)
+        .enumerate()
+    {
+        info!("proving-client: publishing the {i}-th subblock input of block {block_number}");
+        let req = ProveSubblockRequest {
+            block_number,
+            job_id,
+            pipeline_id,
+            num_subblocks,
+            subblock_index: i as u32,
+            input,
+            input_digest,
+        };
+        publish_with_retries(
+            config,
+            nats_dispatch,
+            nats_dispatch.subblock_subject(pipeline_id),
+            format!("{dispatch_id}-subblock-{i}"),
+            &trace_id,
+            &req,
+            &format!("subblock {i}"),
+        )
+        .await;
+    }
+}
+
+// publish a request to a NATS subject until stored by the stream, it panics after the maximum
+// retries like the grpc requests
+async fn publish_with_retries(
+    config: &ProvingClientConfig,
+    nats_dispatch: &NatsDispatch,
+    subject: String,
+    msg_id: String,
+    trace_id: &str,
+    req: &impl Message,
+    name: &str,
+) {
+    let max_retries = config.max_request_retries;
+    let retry_interval = config.request_retry_interval;
+    let payload = nats_dispatch
+        .encode(req)
+        .unwrap_or_else(|e| panic!("proving-client: failed to encode the {name} input: {e}"));
+
+    let mut retry_count = 0;
+    loop {
+        match nats_dispatch
+            .publish(subject.clone(), &msg_id, trace_id, payload.clone())
+            .await
+        {
+            Ok(()) => {
+                if retry_count > 0 {
+                    info!(
+                        "proving-client: {name} input published after {retry_count} retries to {subject}"
+                    );
+                }
+                break;
+            }
+            Err(e) => {
+                retry_count += 1;
+                if retry_count > max_retries {
+                    error!(
+                        "proving-client: failed to publish the {name} input after {max_retries} retries to {subject}: {e:?}"
+                    );
+                    panic!("proving-client: failed to publish the {name} input: {e}");
+                }
+                warn!(
+                    "proving-client: {name} input publish failed (attempt {retry_count}/{max_retries}) to {subject}: {e:?}"
+                );
+                warn!("proving-client: retrying in {}s", retry_interval.as_secs());
+                sleep(retry_interval).await;
+            }
+        }
+    }
+}
+
+// send the proving inputs of a block to the aggregator and subblock grpc services
+async fn send_grpc_proving_inputs(
+    config: &ProvingClientConfig,
+    job_id: JobId,
+    trace_id: TraceId,
     proving_inputs: ProvingInputs,
-    agg_client: &mut AggregatorClient<Channel>,
-    subblock_clients: &mut [SubblockClient<Channel>],
+    agg_client: &mut AggregatorClient<TracedChannel>,
+    subblock_clients: &mut [SubblockClient<TracedChannel>],
 ) {
     let block_number = proving_inputs.block_number;
     let num_subblocks = proving_inputs.subblock_inputs.len();
@@ -352,20 +1025,31 @@ async fn send_proving_inputs(
         "proving-client: insufficient subblock proving services",
     );
     let num_subblocks = num_subblocks as u32;
+    let job_id = job_id.into();
+    let trace_id = trace_id.to_string();
+    let pipeline_id = config.pipeline_id;
+    let max_retries = config.max_request_retries;
+    let retry_interval = config.request_retry_interval;
 
     // TODO: check if this could be changed to run futures in parallel
     info!("proving-client: requesting with the aggregator input of block {block_number}");
     let req = ProveAggregationRequest {
         block_number,
+        job_id,
+        pipeline_id,
         num_subblocks,
         subblock_public_values: proving_inputs.subblock_public_values,
         input: proving_inputs.agg_input,
+        input_digest: proving_inputs.agg_input_digest,
     };
 
     // Retry logic for aggregator request
     let mut retry_count = 0;
     loop {
-        match agg_client.prove_aggregation(req.clone()).await {
+        match agg_client
+            .prove_aggregation(traced_request(req.clone(), &trace_id))
+            .await
+        {
             Ok(_) => {
                 if retry_count > 0 {
                     info!(
@@ -376,20 +1060,17 @@ async fn send_proving_inputs(
             }
             Err(e) => {
                 retry_count += 1;
-                if retry_count > MAX_PROVING_REQUEST_RETRIES {
+                if retry_count > max_retries {
                     error!(
-                        "proving-client: failed to request with the aggregator input after {MAX_PROVING_REQUEST_RETRIES} retries: {e}"
+                        "proving-client: failed to request with the aggregator input after {max_retries} retries: {e}"
                     );
                     panic!("proving-client: failed to request with the aggregator input: {e}");
                 }
                 warn!(
-                    "proving-client: aggregator request failed (attempt {retry_count}/{MAX_PROVING_REQUEST_RETRIES}): {e}"
-                );
-                warn!(
-                    "proving-client: retrying in {}s",
-                    PROVING_REQUEST_RETRY_INTERVAL_SECONDS
+                    "proving-client: aggregator request failed (attempt {retry_count}/{max_retries}): {e}"
                 );
-                sleep(Duration::from_secs(PROVING_REQUEST_RETRY_INTERVAL_SECONDS)).await;
+                warn!("proving-client: retrying in {}s", retry_interval.as_secs());
+                sleep(retry_interval).await;
             }
         }
     }
@@ -397,28 +1078,37 @@ async fn send_proving_inputs(
     // TRICKY: aggregator service needs the all subblock services ready, even if the subblock
     // inputs are insufficient
     let mut subblock_inputs = proving_inputs.subblock_inputs;
+    let mut subblock_input_digests = proving_inputs.subblock_input_digests;
     if subblock_inputs.len() < subblock_client_len {
         let default_input = subblock_inputs[0].clone();
         subblock_inputs.resize(subblock_client_len, default_input);
+        let default_digest = subblock_input_digests[0].clone();
+        subblock_input_digests.resize(subblock_client_len, default_digest);
     }
 
-    for (i, (client, input)) in subblock_clients
+    for (i, (client, (input, input_digest))) in subblock_clients
         .iter_mut()
-        .zip_eq(subblock_inputs.into_iter())
+        .zip_eq(subblock_inputs.into_iter().zip_eq(subblock_input_digests))
         .enumerate()
     {
         info!("proving-client: requesting with the {i}-th subblock input of block {block_number}");
         let req = ProveSubblockRequest {
             block_number,
+            job_id,
+            pipeline_id,
             num_subblocks,
             subblock_index: i as u32,
             input,
+            input_digest,
         };
 
         // Retry logic for subblock request
         let mut retry_count = 0;
         loop {
-            match client.prove_subblock(req.clone()).await {
+            match client
+                .prove_subblock(traced_request(req.clone(), &trace_id))
+                .await
+            {
                 Ok(_) => {
                     if retry_count > 0 {
                         info!(
@@ -429,20 +1119,17 @@ async fn send_proving_inputs(
                 }
                 Err(e) => {
                     retry_count += 1;
-                    if retry_count > MAX_PROVING_REQUEST_RETRIES {
+                    if retry_count > max_retries {
                         error!(
-                            "proving-client: failed to request with the subblock {i} input after {MAX_PROVING_REQUEST_RETRIES} retries: {e}"
+                            "proving-client: failed to request with the subblock {i} input after {max_retries} retries: {e}"
                         );
                         panic!("proving-client: failed to request with the subblock input: {e}");
                     }
                     warn!(
-                        "proving-client: subblock {i} request failed (attempt {retry_count}/{MAX_PROVING_REQUEST_RETRIES}): {e}"
-                    );
-                    warn!(
-                        "proving-client: retrying in {}s",
-                        PROVING_REQUEST_RETRY_INTERVAL_SECONDS
+                        "proving-client: subblock {i} request failed (attempt {retry_count}/{max_retries}): {e}"
                     );
-                    sleep(Duration::from_secs(PROVING_REQUEST_RETRY_INTERVAL_SECONDS)).await;
+                    warn!("proving-client: retrying in {}s", retry_interval.as_secs());
+                    sleep(retry_interval).await;
                 }
             }
         }
@@ -1,15 +1,51 @@
+use crate::{discovery::SubblockDiscovery, nats::NatsDispatchConfig};
+use common::utils::PipelineId;
 use derive_more::Constructor;
 use reqwest::Url;
+use std::time::Duration;
+
+// default maximum waiting seconds for proving complete or any progress of the proving block
+pub const DEFAULT_PROVING_TIMEOUT_SECONDS: u64 = 120;
+
+// default maximum number of retries for sending proving requests
+pub const DEFAULT_MAX_PROVING_REQUEST_RETRIES: u32 = 50;
+
+// default retry interval seconds of the proving requests
+pub const DEFAULT_PROVING_REQUEST_RETRY_SECONDS: u64 = 10;
 
 // proving client configuration
 #[derive(Constructor, Debug)]
 pub struct ProvingClientConfig {
+    // id of the pipeline which the proving-client belongs to
+    pub pipeline_id: PipelineId,
+
     // maximum grpc message bytes
     pub max_msg_bytes: usize,
 
-    // aggregator proving grpc urls
-    pub agg_url: Url,
+    // aggregator proving grpc urls, it's replaced by the urls of the pipeline in the runtime state
+    // if listed; it's none if dispatched by NATS
+    pub agg_url: Option<Url>,
 
-    // subbblock proving grpc urls
+    // subbblock proving grpc urls, it's replaced by the urls of the pipeline in the runtime state
+    // if listed
     pub subblock_urls: Vec<Url>,
+
+    // discovery of the subblock provers replacing the subblock urls, the urls are used if not
+    // specified
+    pub subblock_discovery: Option<SubblockDiscovery>,
+
+    // dispatch of the proving inputs by NATS JetStream replacing the grpc urls, the grpc services
+    // are requested if not specified
+    pub nats_dispatch: Option<NatsDispatchConfig>,
+
+    // maximum waiting time for proving complete or any progress of the proving block, the block
+    // is resent to the proving cluster after it; it's replaced by the timeout of the runtime state
+    // if set
+    pub proving_timeout: Duration,
+
+    // maximum number of retries for sending proving requests
+    pub max_request_retries: u32,
+
+    // retry interval of the proving requests
+    pub request_retry_interval: Duration,
 }
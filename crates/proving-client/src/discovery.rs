@@ -0,0 +1,35 @@
+use derive_more::Constructor;
+use itertools::Itertools;
+use std::{io, time::Duration};
+use tokio::net::lookup_host;
+
+// default interval seconds of resolving the subblock prover service again
+pub const DEFAULT_DISCOVERY_INTERVAL_SECONDS: u64 = 10;
+
+// discovery of the subblock provers behind a headless kubernetes service, the service name is
+// resolved by the cluster DNS to the addresses of the ready pods, so the subblock proving clients
+// follow the pods as they're scaled or rescheduled
+#[derive(Clone, Constructor, Debug)]
+pub struct SubblockDiscovery {
+    // `<host>:<port>` of the headless service, e.g.
+    // `subblock-prover.pico.svc.cluster.local:50052`
+    pub service: String,
+
+    // interval of resolving the service again
+    pub interval: Duration,
+}
+
+impl SubblockDiscovery {
+    // resolve the service to the grpc urls of the ready pods, they're sorted so the subblock
+    // indexes of the pods keep stable across the resolutions
+    pub async fn resolve(&self) -> io::Result<Vec<String>> {
+        let urls = lookup_host(self.service.as_str())
+            .await?
+            .map(|addr| format!("http://{addr}"))
+            .sorted()
+            .dedup()
+            .collect();
+
+        Ok(urls)
+    }
+}
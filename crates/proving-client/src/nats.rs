@@ -0,0 +1,138 @@
+use anyhow::{Result, ensure};
+use async_nats::{
+    HeaderMap,
+    header::NATS_MESSAGE_ID,
+    jetstream::{
+        self, Context,
+        stream::{Config, RetentionPolicy},
+    },
+};
+use common::utils::PipelineId;
+use derive_more::Constructor;
+use proof_proto::{
+    TRACE_ID_METADATA_KEY,
+    traceparent::{TRACEPARENT_METADATA_KEY, TraceParent},
+};
+use prost::Message;
+use std::fmt;
+
+// default name of the JetStream stream of the proving inputs
+pub const DEFAULT_NATS_STREAM: &str = "PROVING";
+
+// default subject prefix of the proving inputs
+pub const DEFAULT_NATS_SUBJECT_PREFIX: &str = "proving";
+
+// header of the payload encoding, the requests are compressed by zstd
+const CONTENT_ENCODING_HEADER: &str = "Content-Encoding";
+
+// compression level of the published requests, the zstd default
+const PAYLOAD_COMPRESSION_LEVEL: i32 = 0;
+
+// config of dispatching the proving inputs by NATS JetStream instead of grpc
+#[derive(Clone, Constructor, Debug)]
+pub struct NatsDispatchConfig {
+    // NATS server URL, e.g. `nats://127.0.0.1:4222`
+    pub url: String,
+
+    // JetStream stream storing the proving inputs, it's created as a work queue if not existing
+    pub stream: String,
+
+    // subject prefix of the proving inputs, the requests of a pipeline are published to
+    // `<prefix>.<pipeline id>.aggregation` and `<prefix>.<pipeline id>.subblock`
+    pub subject_prefix: String,
+}
+
+// dispatch of the proving inputs to the prover workers by NATS JetStream; the requests are kept in
+// a work queue stream until acked by a worker, so a request is delivered at least once and the
+// subblock provers are scaled by adding workers to the same consumer
+#[derive(Clone)]
+pub struct NatsDispatch {
+    // JetStream context of the connected client
+    jetstream: Context,
+
+    // subject prefix of the proving inputs
+    subject_prefix: String,
+
+    // maximum payload bytes accepted by the NATS server
+    max_payload: usize,
+}
+
+impl NatsDispatch {
+    // connect the NATS server and create the stream of the proving inputs if not existing
+    pub async fn connect(config: &NatsDispatchConfig) -> Result<Self> {
+        let client = async_nats::connect(&config.url).await?;
+        let max_payload = client.server_info().max_payload;
+        let jetstream = jetstream::new(client);
+        jetstream
+            .get_or_create_stream(Config {
+                name: config.stream.clone(),
+                subjects: vec![format!("{}.>", config.subject_prefix)],
+                retention: RetentionPolicy::WorkQueue,
+                ..Default::default()
+            })
+            .await?;
+
+        Ok(Self {
+            jetstream,
+            subject_prefix: config.subject_prefix.clone(),
+            max_payload,
+        })
+    }
+
+    // return the subject of the aggregation inputs of a pipeline
+    pub fn aggregation_subject(&self, pipeline_id: PipelineId) -> String {
+        format!("{}.{pipeline_id}.aggregation", self.subject_prefix)
+    }
+
+    // return the subject of the subblock inputs of a pipeline
+    pub fn subblock_subject(&self, pipeline_id: PipelineId) -> String {
+        format!("{}.{pipeline_id}.subblock", self.subject_prefix)
+    }
+
+    // encode and compress a request, it's rejected if larger than the maximum payload of the
+    // server since it would never be accepted
+    pub fn encode(&self, request: &impl Message) -> Result<Vec<u8>> {
+        let payload = zstd::encode_all(&*request.encode_to_vec(), PAYLOAD_COMPRESSION_LEVEL)?;
+        ensure!(
+            payload.len() <= self.max_payload,
+            "compressed request of {} bytes exceeds `max_payload` {} of the NATS server",
+            payload.len(),
+            self.max_payload,
+        );
+
+        Ok(payload)
+    }
+
+    // publish an encoded request with the block trace id and wait for the stream to store it; the
+    // message id deduplicates the republishing of the same request by the retries
+    pub async fn publish(
+        &self,
+        subject: String,
+        msg_id: &str,
+        trace_id: &str,
+        payload: Vec<u8>,
+    ) -> Result<()> {
+        let mut headers = HeaderMap::new();
+        headers.insert(NATS_MESSAGE_ID, msg_id);
+        headers.insert(CONTENT_ENCODING_HEADER, "zstd");
+        headers.insert(TRACE_ID_METADATA_KEY, trace_id);
+        if let Some(trace_parent) = TraceParent::new_call(trace_id) {
+            headers.insert(TRACEPARENT_METADATA_KEY, trace_parent.to_string().as_str());
+        }
+        self.jetstream
+            .publish_with_headers(subject, headers, payload.into())
+            .await?
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for NatsDispatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NatsDispatch")
+            .field("subject_prefix", &self.subject_prefix)
+            .field("max_payload", &self.max_payload)
+            .finish_non_exhaustive()
+    }
+}
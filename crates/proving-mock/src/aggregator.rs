@@ -1,50 +1,76 @@
 use crate::{
+    arrivals::SubblockArrivals,
     config::{
-        MOCK_CYCLES, MOCK_PROOF, MOCK_PROVING_AGGREGATOR_ADDR, MOCK_PROVING_MILLISECONDS,
-        MockProvingServiceConfig,
+        MOCK_CYCLES, MOCK_PROOF, MOCK_PROVING_MILLISECONDS, MOCK_SUBBLOCK_ARRIVAL_TIMEOUT,
+        MOCK_SUBBLOCK_EMULATION_TIMEOUT, MockProvingServiceConfig,
     },
+    emulator::MockEmulator,
+    recorder::{ReceivedRequest, RequestRecorder},
+    replay::{RecordedProof, RecordedResult, load_recorded_proof},
+    scenario::ScenarioStep,
     service::MockProvingService,
+    validation::validate_aggregator_input,
 };
 use aggregator_proto::{
-    ProveAggregationRequest,
+    FILE_DESCRIPTOR_SET, ProveAggregationRequest,
     aggregator_server::{Aggregator, AggregatorServer},
 };
+use anyhow::{Result, anyhow};
+use common::{
+    inputs::input_digest,
+    shutdown::{ShutdownPhase, shutdown_reached},
+};
 use derive_more::Constructor;
-use proof_proto::{CompleteProvingRequest, proof_client::ProofClient};
-use std::{net::SocketAddr, sync::Arc};
-use tokio::{signal::ctrl_c, spawn, task::JoinHandle};
+use proof_proto::{
+    AUTH_METADATA_KEY, CompleteProvingRequest, ProvingErrorCode, bearer_token, extract_trace_id,
+    proof_client::ProofClient,
+    traced_request,
+    traceparent::{TraceParentInterceptor, connect_traced},
+};
+use std::{sync::Arc, time::Instant};
+use tokio::{
+    spawn,
+    task::{JoinHandle, spawn_blocking},
+    time::sleep,
+};
 use tonic::{
-    Request, Response, Status, async_trait, codec::CompressionEncoding, service::LayerExt,
+    Request, Response, Status, async_trait,
+    codec::CompressionEncoding,
+    service::{LayerExt, interceptor::InterceptedService},
     transport::Server,
 };
+use tonic_health::server::health_reporter;
 use tonic_web::GrpcWebLayer;
 use tower::ServiceBuilder;
 use tower_http::cors::{Any, CorsLayer};
-use tracing::info;
+use tracing::{error, info, warn};
 
 impl MockProvingService {
-    // return the mock aggregator grpc address
-    pub fn aggregator_addr(&self) -> SocketAddr {
-        MOCK_PROVING_AGGREGATOR_ADDR
-            .parse()
-            .expect("mock-proving-agg-service: failed to parse aggregator address")
-    }
-
     // start the mock aggregator grpc service
     pub fn run_aggregator_service(self: Arc<Self>) -> JoinHandle<()> {
-        info!("mock-proving-agg-service: start mock aggregator grpc service");
+        info!(
+            "mock-proving-agg-service: start mock aggregator grpc service on {}",
+            self.config.aggregator_addr(),
+        );
 
         spawn(async move {
             let max_msg_bytes = self.config.max_msg_bytes;
 
             // create the base grpc service
-            let mock_service = MockAggregatorService::new(self.config.clone());
+            let mock_service = MockAggregatorService::new(
+                self.config.clone(),
+                self.arrivals.clone(),
+                self.recorder.clone(),
+            );
             let grpc = AggregatorServer::new(mock_service)
                 .max_encoding_message_size(max_msg_bytes)
                 .max_decoding_message_size(max_msg_bytes)
                 .accept_compressed(CompressionEncoding::Zstd)
                 .send_compressed(CompressionEncoding::Zstd);
 
+            // carry the block trace id by the W3C trace context of the requests
+            let grpc = InterceptedService::new(grpc, TraceParentInterceptor);
+
             // add a web layer to the grpc service
             let service = ServiceBuilder::new()
                 .layer(
@@ -57,14 +83,25 @@ impl MockProvingService {
                 .into_inner()
                 .named_layer(grpc);
 
+            // create the health and reflection services for probing and discovering the api
+            let (health_reporter, health_service) = health_reporter();
+            health_reporter
+                .set_serving::<AggregatorServer<MockAggregatorService>>()
+                .await;
+            let reflection_service = tonic_reflection::server::Builder::configure()
+                .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+                .build_v1()
+                .expect("mock-proving-agg-service: failed to build reflection service");
+
             Server::builder()
                 .accept_http1(true)
                 .add_service(service)
-                .serve_with_shutdown(self.aggregator_addr(), async {
-                    ctrl_c()
-                        .await
-                        .expect("mock-proving-agg-service: failed to wait for graceful shutdown");
-                })
+                .add_service(health_service)
+                .add_service(reflection_service)
+                .serve_with_shutdown(
+                    self.config.aggregator_addr(),
+                    shutdown_reached(ShutdownPhase::Flushing),
+                )
                 .await
                 .expect("mock-proving-agg-service: failed to start service");
 
@@ -77,6 +114,12 @@ impl MockProvingService {
 #[derive(Constructor, Debug)]
 struct MockAggregatorService {
     config: Arc<MockProvingServiceConfig>,
+
+    // subblock requests received by the mock subblock services
+    arrivals: Arc<SubblockArrivals>,
+
+    // log of the received proving requests
+    recorder: Arc<RequestRecorder>,
 }
 
 #[async_trait]
@@ -85,39 +128,256 @@ impl Aggregator for MockAggregatorService {
         &self,
         request: Request<ProveAggregationRequest>,
     ) -> Result<Response<()>, Status> {
-        // get the request block number
-        let request = request.into_inner();
+        // get the request block number, the trace id is echoed back in the callback metadata
+        let trace_id = extract_trace_id(request.metadata());
+        let mut request = request.into_inner();
         let block_number = request.block_number;
         info!(
             "mock-proving-agg-service: received aggregation proving request of block {block_number}",
         );
 
-        // create a proof return grpc client
-        let max_msg_bytes = self.config.max_msg_bytes;
-        let proof_url = self.config.proof_service_url.clone();
-        let mut client = ProofClient::connect(proof_url.to_string())
-            .await
-            .expect("mock-proving-agg-service: failed to connect to proof return service {url}")
-            .max_encoding_message_size(max_msg_bytes)
-            .max_decoding_message_size(max_msg_bytes)
-            .accept_compressed(CompressionEncoding::Zstd)
-            .send_compressed(CompressionEncoding::Zstd);
+        // record the request as received before any check
+        self.recorder
+            .record(ReceivedRequest::aggregation(&request, &trace_id));
 
-        info!(
-            "mock-proving-agg-service: requesting to return the proving result of block {block_number}",
-        );
-        let req = CompleteProvingRequest {
-            success: true,
+        // verify the received input by its digest if sent
+        if !request.input_digest.is_empty() && input_digest(&request.input) != request.input_digest
+        {
+            return Err(Status::data_loss(format!(
+                "aggregator input of block {block_number} doesn't match its digest"
+            )));
+        }
+
+        // check the received input is a valid stdin builder consistent with the subblocks
+        validate_aggregator_input(
             block_number,
-            cycles: MOCK_CYCLES,
-            proving_milliseconds: MOCK_PROVING_MILLISECONDS,
-            proof: Some(MOCK_PROOF.to_vec()),
-        };
+            request.num_subblocks,
+            &request.subblock_public_values,
+            &request.input,
+        )?;
+
+        // return the proving result in the background after all the subblocks arrived and the
+        // simulated proving duration, so the request is acknowledged immediately like the proving
+        // cluster
+        let config = self.config.clone();
+        let arrivals = self.arrivals.clone();
+        let step = config.behavior.next_step(block_number);
+        if config.behavior.emulator.is_none() {
+            // the input is only kept for the emulation
+            request.input = vec![];
+        }
+        spawn(async move {
+            // the proving cluster never returns the result of a block with the missing subblocks
+            if !arrivals
+                .wait_all(
+                    request.job_id,
+                    block_number,
+                    request.num_subblocks,
+                    MOCK_SUBBLOCK_ARRIVAL_TIMEOUT,
+                )
+                .await
+            {
+                error!(
+                    "mock-proving-agg-service: not all {} subblocks of block {block_number} arrived, never returning the proving result",
+                    request.num_subblocks,
+                );
+                return;
+            }
+
+            let delay = step.delay(config.behavior.proving_delay);
+            if !delay.is_zero() {
+                sleep(delay).await;
+            }
+            if step.no_response {
+                warn!(
+                    "mock-proving-agg-service: never returning the proving result of block {block_number} by the scenario"
+                );
+                return;
+            }
+            return_proving_result(&config, &step, request, &trace_id).await;
+        });
+
+        Ok(Response::new(()))
+    }
+}
+
+// return the mock proving result of a block to the proof service, the block is failed if specified
+// by the scenario step or the config, and the result is returned repeatedly if the step specifies
+// the duplicate callbacks
+async fn return_proving_result(
+    config: &MockProvingServiceConfig,
+    step: &ScenarioStep,
+    request: ProveAggregationRequest,
+    trace_id: &str,
+) {
+    let block_number = request.block_number;
+    let (job_id, pipeline_id) = (request.job_id, request.pipeline_id);
+
+    // create a proof return grpc client
+    let max_msg_bytes = config.max_msg_bytes;
+    let proof_url = config.proof_service_url.clone();
+    let channel = connect_traced(proof_url.to_string())
+        .await
+        .expect("mock-proving-agg-service: failed to connect to proof return service {url}");
+    let mut client = ProofClient::new(channel)
+        .max_encoding_message_size(max_msg_bytes)
+        .max_decoding_message_size(max_msg_bytes)
+        .accept_compressed(CompressionEncoding::Zstd)
+        .send_compressed(CompressionEncoding::Zstd);
+
+    // the simulated proving duration is reported if specified
+    let behavior = &config.behavior;
+    let proving_milliseconds = match step.delay(behavior.proving_delay).as_millis() as u64 {
+        0 => MOCK_PROVING_MILLISECONDS,
+        milliseconds => milliseconds,
+    };
+    let error_code = match step.error_code() {
+        Some(error_code) => Some(error_code),
+        None if step.fail.is_none() && behavior.should_fail(block_number, job_id) => {
+            Some(ProvingErrorCode::Unspecified)
+        }
+        None => None,
+    };
+    let success = error_code.is_none();
+    if !success {
+        warn!("mock-proving-agg-service: simulating a proving failure of block {block_number}");
+    }
+
+    // replay the recorded proof and result of the block if found, the recorded proving time is
+    // reported if it's recorded
+    let recorded_proof = behavior
+        .replay_proof_dir
+        .as_deref()
+        .filter(|_| success)
+        .and_then(|dir| match load_recorded_proof(dir, block_number) {
+            Ok(Some(recorded_proof)) => {
+                info!("mock-proving-agg-service: replaying the recorded proof of block {block_number}");
+                Some(recorded_proof)
+            }
+            Ok(None) => {
+                warn!("mock-proving-agg-service: no recorded proof of block {block_number}, return the mock proof");
+                None
+            }
+            Err(e) => {
+                error!("mock-proving-agg-service: failed to load the recorded proof of block {block_number} {e:?}");
+                None
+            }
+        });
+    // otherwise emulate the inputs for the true cycles if enabled, the block is reported as proved
+    // without a proof
+    let emulated_result = match &behavior.emulator {
+        Some(emulator) if success && recorded_proof.is_none() => {
+            match emulate_block(emulator.clone(), request).await {
+                Ok(emulated_result) => Some(emulated_result),
+                Err(e) => {
+                    error!(
+                        "mock-proving-agg-service: failed to emulate block {block_number}, return the mock proof {e:?}"
+                    );
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+    let (proof, result) = match (recorded_proof, emulated_result) {
+        (Some(RecordedProof { proof, result }), _) => (Some(proof), result.unwrap_or_default()),
+        (None, Some(emulated_result)) => (None, emulated_result),
+        (None, None) => (
+            Some(MOCK_PROOF.to_vec()),
+            RecordedResult {
+                cycles: MOCK_CYCLES,
+                proving_milliseconds,
+                ..Default::default()
+            },
+        ),
+    };
+
+    info!(
+        "mock-proving-agg-service: requesting to return the proving result of block {block_number}",
+    );
+    let proving_result = CompleteProvingRequest {
+        success,
+        block_number,
+        cycles: if success { result.cycles } else { 0 },
+        proving_milliseconds: match result.proving_milliseconds {
+            0 => proving_milliseconds,
+            milliseconds => milliseconds,
+        },
+        proof: proof.filter(|_| success),
+        subblock_cycles: result.subblock_cycles,
+        job_id,
+        pipeline_id,
+        proof_uri: None,
+        error_code: error_code.unwrap_or(ProvingErrorCode::Unspecified).into(),
+        error_message: (!success).then(|| {
+            step.error_message
+                .clone()
+                .unwrap_or_else(|| "simulated mock proving failure".to_string())
+        }),
+        subblock_results: vec![],
+        aggregation_milliseconds: result.aggregation_milliseconds,
+        trace_id: String::new(),
+        proof_bytes: 0,
+        proof_hash: String::new(),
+    };
+    for i in 0..step.responses() {
+        if i > 0 {
+            warn!(
+                "mock-proving-agg-service: returning the proving result of block {block_number} again by the scenario"
+            );
+        }
+        let mut req = traced_request(proving_result.clone(), trace_id);
+        if let Some(token) = &config.proof_service_token {
+            let token = bearer_token(token)
+                .parse()
+                .expect("mock-proving-agg-service: invalid proof service token");
+            req.metadata_mut().insert(AUTH_METADATA_KEY, token);
+        }
         client
             .complete_proving(req)
             .await
             .expect("mock-proving-agg-service: failed to request to return the proving result");
-
-        Ok(Response::new(()))
     }
 }
+
+// emulate the aggregator input of a block and wait for the emulated subblocks, the reported cycles
+// are the total of the subblocks and the aggregator
+async fn emulate_block(
+    emulator: Arc<MockEmulator>,
+    request: ProveAggregationRequest,
+) -> Result<RecordedResult> {
+    let ProveAggregationRequest {
+        block_number,
+        job_id,
+        num_subblocks,
+        input,
+        ..
+    } = request;
+    info!("mock-proving-agg-service: emulating the aggregator input of block {block_number}");
+
+    let start_time = Instant::now();
+    let agg_emulator = emulator.clone();
+    let agg_cycles = spawn_blocking(move || agg_emulator.emulate_aggregator(&input)).await??;
+    let aggregation_milliseconds = start_time.elapsed().as_millis() as u64;
+
+    let subblock_cycles = emulator
+        .take_subblock_cycles(
+            job_id,
+            block_number,
+            num_subblocks,
+            MOCK_SUBBLOCK_EMULATION_TIMEOUT,
+        )
+        .await
+        .ok_or_else(|| anyhow!("subblocks of block {block_number} are not emulated in time"))?;
+    let cycles = subblock_cycles.iter().sum::<u64>() + agg_cycles;
+    info!(
+        "mock-proving-agg-service: emulated block {block_number} with subblock cycles {subblock_cycles:?} and aggregator cycles {agg_cycles}",
+    );
+
+    Ok(RecordedResult {
+        cycles,
+        proving_milliseconds: start_time.elapsed().as_millis() as u64,
+        aggregation_milliseconds,
+        subblock_cycles,
+    })
+}
@@ -0,0 +1,63 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+    time::Duration,
+};
+use tokio::{
+    sync::Notify,
+    time::{Instant, timeout_at},
+};
+
+// subblock requests received by the mock subblock services, the mock aggregator waits for all the
+// subblocks of a block before proving like the proving cluster
+#[derive(Debug, Default)]
+pub struct SubblockArrivals {
+    // received subblock indexes by job id and block number
+    received: Mutex<HashMap<(u64, u64), HashSet<u32>>>,
+
+    // notification of the received subblocks
+    arrived: Notify,
+}
+
+impl SubblockArrivals {
+    // record a received subblock request, the retried requests are counted once
+    pub fn record(&self, job_id: u64, block_number: u64, subblock_index: u32) {
+        self.received
+            .lock()
+            .expect("mock-subblock-arrivals: failed to lock the received subblocks")
+            .entry((job_id, block_number))
+            .or_default()
+            .insert(subblock_index);
+        self.arrived.notify_waiters();
+    }
+
+    // wait for all the subblocks of a block and stop tracking it, it returns false if not all
+    // received within the timeout
+    pub async fn wait_all(
+        &self,
+        job_id: u64,
+        block_number: u64,
+        num_subblocks: u32,
+        timeout: Duration,
+    ) -> bool {
+        let key = (job_id, block_number);
+        let deadline = Instant::now() + timeout;
+        loop {
+            // register the notification before checking, so an arrival in between isn't missed
+            let arrived = self.arrived.notified();
+            let is_all_received = self
+                .received
+                .lock()
+                .expect("mock-subblock-arrivals: failed to lock the received subblocks")
+                .get(&key)
+                .is_some_and(|indexes| indexes.len() >= num_subblocks as usize);
+            if is_all_received || timeout_at(deadline, arrived).await.is_err() {
+                self.received
+                    .lock()
+                    .expect("mock-subblock-arrivals: failed to lock the received subblocks")
+                    .remove(&key);
+                return is_all_received;
+            }
+        }
+    }
+}
@@ -1,12 +1,23 @@
+use crate::{
+    emulator::MockEmulator,
+    scenario::{MockScenario, ScenarioStep},
+};
 use common::utils::addr_to_url;
 use reqwest::Url;
-use std::{net::SocketAddr, sync::Arc};
+use std::{
+    collections::HashSet,
+    hash::{BuildHasher, RandomState},
+    net::{IpAddr, Ipv6Addr, SocketAddr},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
 
-// mock proving aggregator address
-pub const MOCK_PROVING_AGGREGATOR_ADDR: &str = "[::1]:55551";
+// default bind address of the mock proving services
+pub const DEFAULT_MOCK_BIND_IP: IpAddr = IpAddr::V6(Ipv6Addr::LOCALHOST);
 
-// mock proving subblock address (use the same address for multiple mock proving subblock services)
-pub const MOCK_PROVING_SUBBLOCK_ADDR: &str = "[::1]:55552";
+// default port of the mock aggregator, the mock subblock services listen on the following ports
+pub const DEFAULT_MOCK_BASE_PORT: u16 = 55551;
 
 // mock emulation cycles
 pub const MOCK_CYCLES: u64 = 1234;
@@ -14,6 +25,12 @@ pub const MOCK_CYCLES: u64 = 1234;
 // seconds of mock proving time
 pub const MOCK_PROVING_MILLISECONDS: u64 = 10_000;
 
+// maximum duration of waiting for the subblock requests of a block by the mock aggregator
+pub const MOCK_SUBBLOCK_ARRIVAL_TIMEOUT: Duration = Duration::from_secs(60);
+
+// maximum duration of waiting for the emulated subblocks of a block by the mock aggregator
+pub const MOCK_SUBBLOCK_EMULATION_TIMEOUT: Duration = Duration::from_secs(600);
+
 // mock proof bytes
 // TODO: read from dump file if necessary for verification
 pub const MOCK_PROOF: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
@@ -24,18 +41,133 @@ pub struct MockProvingServiceConfig {
     // maximum grpc message bytes
     pub max_msg_bytes: usize,
 
+    // number of the mock subblock proving services
+    pub num_subblocks: usize,
+
+    // bind address of the mock proving services
+    pub bind_ip: IpAddr,
+
+    // port of the mock aggregator, the mock subblock services listen on the following ports by
+    // subblock index
+    pub base_port: u16,
+
     // proof service grpc address for returning the mock proof
     pub proof_service_url: Url,
+
+    // shared token for authenticating the callbacks to the proof service
+    pub proof_service_token: Option<String>,
+
+    // simulated proving behaviors for testing
+    pub behavior: MockProvingBehavior,
 }
 
 impl MockProvingServiceConfig {
-    pub fn new(max_msg_bytes: usize, proof_service_addr: &SocketAddr) -> Arc<Self> {
+    pub fn new(
+        max_msg_bytes: usize,
+        num_subblocks: usize,
+        bind_ip: IpAddr,
+        base_port: u16,
+        proof_service_addr: &SocketAddr,
+        proof_service_token: Option<String>,
+        behavior: MockProvingBehavior,
+    ) -> Arc<Self> {
+        assert!(
+            base_port as usize + num_subblocks <= u16::MAX as usize,
+            "mock-proving-service: ports of {num_subblocks} subblocks from {base_port} overflow",
+        );
         let proof_service_url = addr_to_url(proof_service_addr, "http://");
 
         Self {
             max_msg_bytes,
+            num_subblocks,
+            bind_ip,
+            base_port,
             proof_service_url,
+            proof_service_token,
+            behavior,
         }
         .into()
     }
+
+    // return the mock aggregator grpc address
+    pub fn aggregator_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.bind_ip, self.base_port)
+    }
+
+    // return the mock subblock grpc addresses on the ports following the aggregator, one for each
+    // subblock
+    pub fn subblock_addrs(&self) -> Vec<SocketAddr> {
+        (1..=self.num_subblocks)
+            .map(|i| SocketAddr::new(self.bind_ip, self.base_port + i as u16))
+            .collect()
+    }
+}
+
+// simulated proving behaviors of the mock proving service, the scenario steps override the default
+// behaviors of the blocks in the scenario
+#[derive(Debug)]
+pub struct MockProvingBehavior {
+    // simulated proving duration before returning the proving result, the result is returned
+    // immediately if zero
+    pub proving_delay: Duration,
+
+    // probability of a block proved successfully, in the range of [0, 1]
+    pub success_rate: f64,
+
+    // block numbers always failed to prove
+    pub failed_block_numbers: HashSet<u64>,
+
+    // directory of the proofs recorded from the real proving runs keyed by block number, the
+    // recorded proof and result of a block are returned instead of the mock ones if found
+    pub replay_proof_dir: Option<PathBuf>,
+
+    // scenario of the per-block behaviors; nothing is scripted if not specified
+    pub scenario: Option<MockScenario>,
+
+    // emulator of the received inputs for reporting the true cycles without a proof; the mock
+    // cycles are reported if not specified
+    pub emulator: Option<Arc<MockEmulator>>,
+}
+
+impl MockProvingBehavior {
+    pub fn new(
+        proving_delay: Duration,
+        success_rate: f64,
+        failed_block_numbers: HashSet<u64>,
+        replay_proof_dir: Option<PathBuf>,
+        scenario: Option<MockScenario>,
+        emulator: Option<Arc<MockEmulator>>,
+    ) -> Self {
+        Self {
+            proving_delay,
+            success_rate: success_rate.clamp(0.0, 1.0),
+            failed_block_numbers,
+            replay_proof_dir,
+            scenario,
+            emulator,
+        }
+    }
+
+    // return the scripted step of the next request of a block, the default step is returned if the
+    // block is not in the scenario
+    pub fn next_step(&self, block_number: u64) -> ScenarioStep {
+        self.scenario
+            .as_ref()
+            .and_then(|scenario| scenario.next_step(block_number))
+            .unwrap_or_default()
+    }
+
+    // identify if a block should fail to prove, it's failed if specified or by the success rate
+    pub fn should_fail(&self, block_number: u64, job_id: u64) -> bool {
+        if self.failed_block_numbers.contains(&block_number) {
+            return true;
+        }
+        if self.success_rate >= 1.0 {
+            return false;
+        }
+
+        // a random sample in [0, 1) by the randomly keyed hasher
+        let sample = RandomState::new().hash_one((block_number, job_id)) as f64 / u64::MAX as f64;
+        sample >= self.success_rate
+    }
 }
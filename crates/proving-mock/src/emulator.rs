@@ -0,0 +1,122 @@
+use crate::validation::StdinBuilder;
+use anyhow::{Context, Result};
+use pico_sdk::client::DefaultProverClient;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    path::Path,
+    sync::Mutex,
+    time::Duration,
+};
+use tokio::{
+    sync::Notify,
+    time::{Instant, timeout_at},
+};
+
+// emulator of the received inputs for reporting the true cycles without the proving cluster, the
+// subblock cycles are collected by job id and block number until taken by the aggregator
+pub struct MockEmulator {
+    // prover client of the subblock elf
+    subblock_prover_client: DefaultProverClient,
+
+    // prover client of the aggregator elf
+    agg_prover_client: DefaultProverClient,
+
+    // emulated cycles of each subblock by job id and block number
+    subblock_cycles: Mutex<HashMap<(u64, u64), BTreeMap<u32, u64>>>,
+
+    // notification of the emulated subblocks
+    subblock_emulated: Notify,
+}
+
+impl MockEmulator {
+    // create an emulator by the subblock and aggregator elf files
+    pub fn new(subblock_elf_path: &Path, agg_elf_path: &Path) -> Result<Self> {
+        let subblock_elf = fs::read(subblock_elf_path)
+            .with_context(|| format!("failed to read the subblock elf {subblock_elf_path:?}"))?;
+        let agg_elf = fs::read(agg_elf_path)
+            .with_context(|| format!("failed to read the aggregator elf {agg_elf_path:?}"))?;
+
+        Ok(Self {
+            subblock_prover_client: DefaultProverClient::new(&subblock_elf),
+            agg_prover_client: DefaultProverClient::new(&agg_elf),
+            subblock_cycles: Mutex::default(),
+            subblock_emulated: Notify::new(),
+        })
+    }
+
+    // emulate a subblock input and record its cycles for the aggregator
+    pub fn emulate_subblock(
+        &self,
+        job_id: u64,
+        block_number: u64,
+        subblock_index: u32,
+        input: &[u8],
+    ) -> Result<u64> {
+        let stdin_builder: StdinBuilder = bincode::deserialize(input)?;
+        let (cycles, _) = self.subblock_prover_client.emulate(stdin_builder);
+
+        self.subblock_cycles
+            .lock()
+            .expect("mock-emulator: failed to lock the subblock cycles")
+            .entry((job_id, block_number))
+            .or_default()
+            .insert(subblock_index, cycles);
+        self.subblock_emulated.notify_waiters();
+
+        Ok(cycles)
+    }
+
+    // emulate an aggregator input and return its cycles
+    pub fn emulate_aggregator(&self, input: &[u8]) -> Result<u64> {
+        let stdin_builder: StdinBuilder = bincode::deserialize(input)?;
+        let (cycles, _) = self.agg_prover_client.emulate(stdin_builder);
+
+        Ok(cycles)
+    }
+
+    // wait for and take the emulated cycles of all the subblocks of a block in the subblock index
+    // order, it returns none if not all emulated within the timeout
+    pub async fn take_subblock_cycles(
+        &self,
+        job_id: u64,
+        block_number: u64,
+        num_subblocks: u32,
+        timeout: Duration,
+    ) -> Option<Vec<u64>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            // register the notification before checking, so an emulation in between isn't missed
+            let subblock_emulated = self.subblock_emulated.notified();
+            {
+                let mut subblock_cycles = self
+                    .subblock_cycles
+                    .lock()
+                    .expect("mock-emulator: failed to lock the subblock cycles");
+                let key = (job_id, block_number);
+                if subblock_cycles
+                    .get(&key)
+                    .is_some_and(|cycles| cycles.len() >= num_subblocks as usize)
+                {
+                    return subblock_cycles
+                        .remove(&key)
+                        .map(|cycles| cycles.into_values().collect());
+                }
+            }
+
+            if timeout_at(deadline, subblock_emulated).await.is_err() {
+                self.subblock_cycles
+                    .lock()
+                    .expect("mock-emulator: failed to lock the subblock cycles")
+                    .remove(&(job_id, block_number));
+                return None;
+            }
+        }
+    }
+}
+
+impl fmt::Debug for MockEmulator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockEmulator").finish_non_exhaustive()
+    }
+}
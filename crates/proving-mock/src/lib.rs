@@ -1,4 +1,10 @@
 pub mod aggregator;
+pub mod arrivals;
 pub mod config;
+pub mod emulator;
+pub mod recorder;
+pub mod replay;
+pub mod scenario;
 pub mod service;
 pub mod subblock;
+pub mod validation;
@@ -0,0 +1,180 @@
+use aggregator_proto::ProveAggregationRequest;
+use anyhow::Result;
+use common::inputs::input_digest;
+use serde::Serialize;
+use std::{
+    collections::VecDeque,
+    fs::{File, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::{Mutex, MutexGuard},
+};
+use subblock_proto::ProveSubblockRequest;
+use tracing::error;
+
+// maximum number of the recorded requests, the oldest ones are dropped beyond it
+pub const MAX_RECORDED_REQUESTS: usize = 10_000;
+
+// kind of a proving request received by the mock services
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestKind {
+    // `ProveAggregationRequest` received by the mock aggregator
+    Aggregation,
+
+    // `ProveSubblockRequest` received by a mock subblock service
+    Subblock,
+}
+
+// proving request received by the mock services, the input is recorded by its length and digest
+// instead of the bytes
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ReceivedRequest {
+    // kind of the request
+    pub kind: RequestKind,
+
+    // block number
+    pub block_number: u64,
+
+    // job id of the block
+    pub job_id: u64,
+
+    // pipeline id of the block
+    pub pipeline_id: u32,
+
+    // number of the subblocks of the block
+    pub num_subblocks: u32,
+
+    // subblock index, it's none for the aggregation request
+    pub subblock_index: Option<u32>,
+
+    // length of the input bytes
+    pub input_bytes: u64,
+
+    // digest of the received input
+    pub input_digest: String,
+
+    // digest sent with the input, it's empty if not sent
+    pub sent_input_digest: String,
+
+    // trace id of the request metadata, it's empty if not sent
+    pub trace_id: String,
+}
+
+impl ReceivedRequest {
+    // record a received aggregation request
+    pub fn aggregation(request: &ProveAggregationRequest, trace_id: &str) -> Self {
+        Self {
+            kind: RequestKind::Aggregation,
+            block_number: request.block_number,
+            job_id: request.job_id,
+            pipeline_id: request.pipeline_id,
+            num_subblocks: request.num_subblocks,
+            subblock_index: None,
+            input_bytes: request.input.len() as u64,
+            input_digest: input_digest(&request.input),
+            sent_input_digest: request.input_digest.clone(),
+            trace_id: trace_id.to_string(),
+        }
+    }
+
+    // record a received subblock request
+    pub fn subblock(request: &ProveSubblockRequest, trace_id: &str) -> Self {
+        Self {
+            kind: RequestKind::Subblock,
+            block_number: request.block_number,
+            job_id: request.job_id,
+            pipeline_id: request.pipeline_id,
+            num_subblocks: request.num_subblocks,
+            subblock_index: Some(request.subblock_index),
+            input_bytes: request.input.len() as u64,
+            input_digest: input_digest(&request.input),
+            sent_input_digest: request.input_digest.clone(),
+            trace_id: trace_id.to_string(),
+        }
+    }
+}
+
+// in-memory log of the proving requests received by the mock services in the received order, for
+// the integration tests to assert what the proving-client sent
+#[derive(Debug, Default)]
+pub struct RequestRecorder {
+    // recorded requests, the oldest first
+    requests: Mutex<VecDeque<ReceivedRequest>>,
+
+    // file of the json lines dump of every recorded request; nothing is dumped if not specified
+    log_file: Option<Mutex<File>>,
+}
+
+impl RequestRecorder {
+    // create a recorder dumping every recorded request to a json lines file if specified, the
+    // file is appended if it exists
+    pub fn new(log_path: Option<&Path>) -> Result<Self> {
+        let log_file = log_path
+            .map(|log_path| OpenOptions::new().create(true).append(true).open(log_path))
+            .transpose()?
+            .map(Mutex::new);
+
+        Ok(Self {
+            requests: Mutex::default(),
+            log_file,
+        })
+    }
+
+    // record a received request
+    pub fn record(&self, request: ReceivedRequest) {
+        if let Some(log_file) = &self.log_file
+            && let Err(e) = dump_request(log_file, &request)
+        {
+            error!("mock-request-recorder: failed to dump the received request {e:?}");
+        }
+
+        let mut requests = self.lock();
+        if requests.len() >= MAX_RECORDED_REQUESTS {
+            requests.pop_front();
+        }
+        requests.push_back(request);
+    }
+
+    // return the recorded requests in the received order
+    pub fn requests(&self) -> Vec<ReceivedRequest> {
+        self.lock().iter().cloned().collect()
+    }
+
+    // return the recorded requests of a block in the received order
+    pub fn block_requests(&self, block_number: u64) -> Vec<ReceivedRequest> {
+        self.lock()
+            .iter()
+            .filter(|request| request.block_number == block_number)
+            .cloned()
+            .collect()
+    }
+
+    // take and clear the recorded requests
+    pub fn take(&self) -> Vec<ReceivedRequest> {
+        self.lock().drain(..).collect()
+    }
+
+    // return the recorded requests as a json array
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&*self.lock())
+    }
+
+    // lock the recorded requests
+    fn lock(&self) -> MutexGuard<'_, VecDeque<ReceivedRequest>> {
+        self.requests
+            .lock()
+            .expect("mock-request-recorder: failed to lock the recorded requests")
+    }
+}
+
+// append a request to the json lines file
+fn dump_request(log_file: &Mutex<File>, request: &ReceivedRequest) -> Result<()> {
+    let line = serde_json::to_string(request)?;
+    let mut log_file = log_file
+        .lock()
+        .expect("mock-request-recorder: failed to lock the log file");
+    writeln!(log_file, "{line}")?;
+
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::{fs, io::ErrorKind, path::Path};
+
+// file name of the recorded proof of a block
+const PROOF_FILE: &str = "proof.bin";
+
+// file name of the recorded proving result of a block
+const RESULT_FILE: &str = "result.json";
+
+// proving result recorded with a proof, the missing fields are zero
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct RecordedResult {
+    // proving cycles
+    pub cycles: u64,
+
+    // milliseconds of proving time
+    pub proving_milliseconds: u64,
+
+    // milliseconds of aggregating the subblock proofs
+    pub aggregation_milliseconds: u64,
+
+    // proving cycles of each subblock
+    pub subblock_cycles: Vec<u64>,
+}
+
+// proof of a block recorded from a real proving run
+#[derive(Debug)]
+pub struct RecordedProof {
+    // bincode serialized proof bytes
+    pub proof: Vec<u8>,
+
+    // recorded proving result, it's none if not recorded with the proof
+    pub result: Option<RecordedResult>,
+}
+
+// load the recorded proof of a block from a directory keyed by block number, the proof is read from
+// `block{n}/proof.bin` or the latest `block{n}/job{id}.bin` persisted by the proof store, and the
+// proving result from an optional `block{n}/result.json`; it returns none if no proof is recorded
+pub fn load_recorded_proof(dir: &Path, block_number: u64) -> Result<Option<RecordedProof>> {
+    let block_dir = dir.join(format!("block{block_number}"));
+
+    let proof = match read_file(&block_dir.join(PROOF_FILE))? {
+        Some(proof) => proof,
+        None => match latest_job_proof_file(&block_dir)? {
+            Some(file_name) => fs::read(block_dir.join(file_name))?,
+            None => return Ok(None),
+        },
+    };
+    let result = read_file(&block_dir.join(RESULT_FILE))?
+        .map(|data| serde_json::from_slice(&data))
+        .transpose()?;
+
+    Ok(Some(RecordedProof { proof, result }))
+}
+
+// read a file, return `None` if it doesn't exist
+fn read_file(file_path: &Path) -> Result<Option<Vec<u8>>> {
+    match fs::read(file_path) {
+        Ok(data) => Ok(Some(data)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// return the file name of the proof with the largest job id in a block directory
+fn latest_job_proof_file(block_dir: &Path) -> Result<Option<String>> {
+    let entries = match fs::read_dir(block_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut latest: Option<(u64, String)> = None;
+    for entry in entries {
+        let file_name = entry?.file_name().to_string_lossy().to_string();
+        let Some(job_id) = file_name
+            .strip_prefix("job")
+            .and_then(|name| name.strip_suffix(".bin"))
+            .and_then(|job_id| job_id.parse::<u64>().ok())
+        else {
+            continue;
+        };
+        if latest
+            .as_ref()
+            .is_none_or(|(latest_job_id, _)| job_id > *latest_job_id)
+        {
+            latest = Some((job_id, file_name));
+        }
+    }
+
+    Ok(latest.map(|(_, file_name)| file_name))
+}
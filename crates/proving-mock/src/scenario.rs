@@ -0,0 +1,104 @@
+use anyhow::{Result, bail, ensure};
+use proof_proto::ProvingErrorCode;
+use serde::Deserialize;
+use std::{collections::HashMap, fs, path::Path, sync::Mutex, time::Duration};
+
+// behavior of the mock aggregator for a proving request of a block
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScenarioStep {
+    // milliseconds before returning the proving result, the default proving delay is used if not
+    // specified
+    pub delay_milliseconds: Option<u64>,
+
+    // error code of a simulated proving failure without the `PROVING_ERROR_CODE_` prefix, e.g.
+    // `oom` or `timeout`; the block is proved if not specified
+    pub fail: Option<String>,
+
+    // error message of the simulated proving failure
+    pub error_message: Option<String>,
+
+    // identify if the proving result is never returned, e.g. a hanging prover
+    pub no_response: bool,
+
+    // number of the returned proving results, more than one simulates the duplicate callbacks;
+    // one if not specified
+    pub responses: Option<u32>,
+}
+
+impl ScenarioStep {
+    // return the delay before returning the proving result, or the default one if not specified
+    pub fn delay(&self, default_delay: Duration) -> Duration {
+        self.delay_milliseconds
+            .map_or(default_delay, Duration::from_millis)
+    }
+
+    // return the error code of the simulated proving failure, it's none if the block is proved
+    pub fn error_code(&self) -> Option<ProvingErrorCode> {
+        self.fail.as_deref().map(|name| {
+            parse_error_code(name).expect("mock-proving-scenario: error code is checked on loading")
+        })
+    }
+
+    // return the number of the returned proving results
+    pub fn responses(&self) -> u32 {
+        self.responses.unwrap_or(1)
+    }
+}
+
+// scenario of the per-block behaviors of the mock proving service for reproducing the tricky
+// sequences deterministically, it's loaded from a JSON object of the steps by block number, e.g.
+// `{"23264565": [{"no_response": true}, {"delay_milliseconds": 5000}], "23264566": [{"fail":
+// "oom"}], "23264567": [{"responses": 2}]}`; the n-th request of a block takes the n-th step and
+// the last step is repeated, the blocks not in the scenario follow the default behavior
+#[derive(Debug)]
+pub struct MockScenario {
+    // steps of each block by block number
+    blocks: HashMap<u64, Vec<ScenarioStep>>,
+
+    // number of the received requests of each block
+    attempts: Mutex<HashMap<u64, usize>>,
+}
+
+impl MockScenario {
+    // load the scenario from a JSON file and check the steps
+    pub fn load(file_path: &Path) -> Result<Self> {
+        let blocks: HashMap<u64, Vec<ScenarioStep>> =
+            serde_json::from_slice(&fs::read(file_path)?)?;
+        for (block_number, steps) in &blocks {
+            ensure!(!steps.is_empty(), "no step of block {block_number}");
+            for step in steps {
+                if let Some(name) = &step.fail
+                    && parse_error_code(name).is_none()
+                {
+                    bail!("unknown error code `{name}` of block {block_number}");
+                }
+            }
+        }
+
+        Ok(Self {
+            blocks,
+            attempts: Mutex::default(),
+        })
+    }
+
+    // return the step of the next request of a block, it's none if the block is not in the
+    // scenario
+    pub fn next_step(&self, block_number: u64) -> Option<ScenarioStep> {
+        let steps = self.blocks.get(&block_number)?;
+        let mut attempts = self
+            .attempts
+            .lock()
+            .expect("mock-proving-scenario: failed to lock the attempts");
+        let attempt = attempts.entry(block_number).or_default();
+        let step = steps[(*attempt).min(steps.len() - 1)].clone();
+        *attempt += 1;
+
+        Some(step)
+    }
+}
+
+// parse an error code without the `PROVING_ERROR_CODE_` prefix, e.g. `oom`
+fn parse_error_code(name: &str) -> Option<ProvingErrorCode> {
+    ProvingErrorCode::from_str_name(&format!("PROVING_ERROR_CODE_{}", name.to_uppercase()))
+}
@@ -1,36 +1,61 @@
-use crate::config::MockProvingServiceConfig;
-use common::utils::{MAX_NUM_SUBBLOCKS, addr_to_url};
-use derive_more::Constructor;
+use crate::{
+    arrivals::SubblockArrivals,
+    config::MockProvingServiceConfig,
+    recorder::{ReceivedRequest, RequestRecorder},
+};
+use common::utils::addr_to_url;
 use reqwest::Url;
 use std::sync::Arc;
 use tokio::task::JoinHandle;
 use tracing::info;
 
 // fetch http and websocket service
-#[derive(Constructor, Debug)]
+#[derive(Debug)]
 pub struct MockProvingService {
     pub config: Arc<MockProvingServiceConfig>,
+
+    // subblock requests received by the mock subblock services, shared with the mock aggregator
+    pub arrivals: Arc<SubblockArrivals>,
+
+    // log of the proving requests received by the mock services
+    pub recorder: Arc<RequestRecorder>,
 }
 
 impl MockProvingService {
+    pub fn new(config: Arc<MockProvingServiceConfig>, recorder: RequestRecorder) -> Self {
+        Self {
+            config,
+            arrivals: Arc::default(),
+            recorder: recorder.into(),
+        }
+    }
+
+    // return the proving requests received by the mock services in the received order, for
+    // asserting what the proving-client sent
+    pub fn received_requests(&self) -> Vec<ReceivedRequest> {
+        self.recorder.requests()
+    }
+
     // return mock aggregator grpc url
     pub fn aggregator_url(&self) -> Url {
-        addr_to_url(&self.aggregator_addr(), "http://")
+        addr_to_url(&self.config.aggregator_addr(), "http://")
     }
 
-    // return mock subblock grpc urls
+    // return the distinct mock subblock grpc urls by subblock index
     pub fn subblock_urls(&self) -> Vec<Url> {
-        let url = addr_to_url(&self.subblock_addr(), "http://");
-
-        vec![url; MAX_NUM_SUBBLOCKS]
+        self.config
+            .subblock_addrs()
+            .iter()
+            .map(|addr| addr_to_url(addr, "http://"))
+            .collect()
     }
 
     pub fn run(self: Arc<Self>) -> Vec<JoinHandle<()>> {
         info!("mock-proving-service: start");
 
         let agg_handle = self.clone().run_aggregator_service();
-        let subblock_handle = self.run_subblock_service();
+        let subblock_handles = self.run_subblock_services();
 
-        vec![agg_handle, subblock_handle]
+        [agg_handle].into_iter().chain(subblock_handles).collect()
     }
 }
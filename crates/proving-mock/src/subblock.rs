@@ -1,42 +1,71 @@
-use crate::{config::MOCK_PROVING_SUBBLOCK_ADDR, service::MockProvingService};
+use crate::{
+    arrivals::SubblockArrivals,
+    emulator::MockEmulator,
+    recorder::{ReceivedRequest, RequestRecorder},
+    service::MockProvingService,
+    validation::validate_subblock_input,
+};
+use common::{
+    inputs::input_digest,
+    shutdown::{ShutdownPhase, shutdown_reached},
+};
 use derive_more::Constructor;
+use proof_proto::{extract_trace_id, traceparent::TraceParentInterceptor};
 use std::{net::SocketAddr, sync::Arc};
 use subblock_proto::{
-    ProveSubblockRequest,
+    FILE_DESCRIPTOR_SET, ProveSubblockRequest,
     subblock_server::{Subblock, SubblockServer},
 };
-use tokio::{signal::ctrl_c, spawn, task::JoinHandle};
+use tokio::{
+    spawn,
+    task::{JoinHandle, spawn_blocking},
+};
 use tonic::{
-    Request, Response, Status, async_trait, codec::CompressionEncoding, service::LayerExt,
+    Request, Response, Status, async_trait,
+    codec::CompressionEncoding,
+    service::{LayerExt, interceptor::InterceptedService},
     transport::Server,
 };
+use tonic_health::server::health_reporter;
 use tonic_web::GrpcWebLayer;
 use tower::ServiceBuilder;
 use tower_http::cors::{Any, CorsLayer};
-use tracing::info;
+use tracing::{error, info};
 
 impl MockProvingService {
-    // return the mcok subblock grpc address
-    pub fn subblock_addr(&self) -> SocketAddr {
-        MOCK_PROVING_SUBBLOCK_ADDR
-            .parse()
-            .expect("mock-proving-subblock-service: failed to parse subblock address")
+    // start the independent mock subblock grpc services, one for each subblock address
+    pub fn run_subblock_services(self: Arc<Self>) -> Vec<JoinHandle<()>> {
+        self.config
+            .subblock_addrs()
+            .into_iter()
+            .enumerate()
+            .map(|(i, addr)| self.clone().run_subblock_service(i as u32, addr))
+            .collect()
     }
 
-    // start the mock subblock grpc service
-    pub fn run_subblock_service(self: Arc<Self>) -> JoinHandle<()> {
-        info!("mock-proving-subblock-service: start mock subblock grpc service");
+    // start the mock subblock grpc service of an indexed subblock
+    fn run_subblock_service(self: Arc<Self>, index: u32, addr: SocketAddr) -> JoinHandle<()> {
+        info!("mock-proving-subblock-service: start mock subblock {index} grpc service on {addr}");
 
         spawn(async move {
             let max_msg_bytes = self.config.max_msg_bytes;
 
             // create the base grpc service
-            let grpc = SubblockServer::new(MockSubblockService)
+            let mock_service = MockSubblockService::new(
+                index,
+                self.arrivals.clone(),
+                self.recorder.clone(),
+                self.config.behavior.emulator.clone(),
+            );
+            let grpc = SubblockServer::new(mock_service)
                 .max_encoding_message_size(max_msg_bytes)
                 .max_decoding_message_size(max_msg_bytes)
                 .accept_compressed(CompressionEncoding::Zstd)
                 .send_compressed(CompressionEncoding::Zstd);
 
+            // carry the block trace id by the W3C trace context of the requests
+            let grpc = InterceptedService::new(grpc, TraceParentInterceptor);
+
             // add a web layer to the grpc service
             let service = ServiceBuilder::new()
                 .layer(
@@ -49,25 +78,45 @@ impl MockProvingService {
                 .into_inner()
                 .named_layer(grpc);
 
+            // create the health and reflection services for probing and discovering the api
+            let (health_reporter, health_service) = health_reporter();
+            health_reporter
+                .set_serving::<SubblockServer<MockSubblockService>>()
+                .await;
+            let reflection_service = tonic_reflection::server::Builder::configure()
+                .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+                .build_v1()
+                .expect("mock-proving-subblock-service: failed to build reflection service");
+
             Server::builder()
                 .accept_http1(true)
                 .add_service(service)
-                .serve_with_shutdown(self.subblock_addr(), async {
-                    ctrl_c().await.expect(
-                        "mock-proving-subblock-service: failed to wait for graceful shutdown",
-                    );
-                })
+                .add_service(health_service)
+                .add_service(reflection_service)
+                .serve_with_shutdown(addr, shutdown_reached(ShutdownPhase::Flushing))
                 .await
                 .expect("mock-proving-subblock-service: failed to start service");
 
-            info!("mock-proving-subblock-service: mock subblock grpc service stopped");
+            info!("mock-proving-subblock-service: mock subblock {index} grpc service stopped");
         })
     }
 }
 
-// mock subblock grpc service
+// mock subblock grpc service of an indexed subblock
 #[derive(Constructor, Debug)]
-struct MockSubblockService;
+struct MockSubblockService {
+    // index of the subblock served by this service
+    index: u32,
+
+    // subblock requests received by the mock subblock services
+    arrivals: Arc<SubblockArrivals>,
+
+    // log of the received proving requests
+    recorder: Arc<RequestRecorder>,
+
+    // emulator of the received inputs for the true cycles; nothing is emulated if not specified
+    emulator: Option<Arc<MockEmulator>>,
+}
 
 #[async_trait]
 impl Subblock for MockSubblockService {
@@ -75,12 +124,70 @@ impl Subblock for MockSubblockService {
         &self,
         request: Request<ProveSubblockRequest>,
     ) -> Result<Response<()>, Status> {
+        let trace_id = extract_trace_id(request.metadata());
         let request = request.into_inner();
         info!(
             "mock-proving-subblock-service: received subblock proving request of block {}, num_subblocks {}, subblock_index {}",
             request.block_number, request.num_subblocks, request.subblock_index,
         );
 
+        // record the request as received before any check
+        self.recorder
+            .record(ReceivedRequest::subblock(&request, &trace_id));
+
+        // each subblock service only accepts the requests of its own subblock
+        if request.subblock_index != self.index {
+            return Err(Status::invalid_argument(format!(
+                "subblock {} of block {} is sent to the service of subblock {}",
+                request.subblock_index, request.block_number, self.index,
+            )));
+        }
+
+        // verify the received input by its digest if sent
+        if !request.input_digest.is_empty() && input_digest(&request.input) != request.input_digest
+        {
+            return Err(Status::data_loss(format!(
+                "subblock input {} of block {} doesn't match its digest",
+                request.subblock_index, request.block_number,
+            )));
+        }
+
+        // check the received input is a valid stdin builder of the subblock
+        validate_subblock_input(
+            request.block_number,
+            request.num_subblocks,
+            request.subblock_index,
+            &request.input,
+        )?;
+
+        // the padded subblocks beyond the block are only for keeping all the services ready
+        if request.subblock_index >= request.num_subblocks {
+            return Ok(Response::new(()));
+        }
+        self.arrivals
+            .record(request.job_id, request.block_number, request.subblock_index);
+
+        // emulate the input in the background for the aggregator to report the true cycles
+        if let Some(emulator) = self.emulator.clone() {
+            spawn_blocking(move || {
+                let ProveSubblockRequest {
+                    block_number,
+                    subblock_index,
+                    job_id,
+                    input,
+                    ..
+                } = request;
+                match emulator.emulate_subblock(job_id, block_number, subblock_index, &input) {
+                    Ok(cycles) => info!(
+                        "mock-proving-subblock-service: emulated subblock {subblock_index} of block {block_number} with cycles {cycles}",
+                    ),
+                    Err(e) => error!(
+                        "mock-proving-subblock-service: failed to emulate subblock {subblock_index} of block {block_number} {e:?}",
+                    ),
+                }
+            });
+        }
+
         Ok(Response::new(()))
     }
 }
@@ -0,0 +1,76 @@
+use pico_vm::{configs::stark_config::KoalaBearPoseidon2, emulator::stdin::EmulatorStdinBuilder};
+use tonic::Status;
+
+// stdin builder serialized by the fetcher for both the subblock and aggregator inputs
+pub(crate) type StdinBuilder = EmulatorStdinBuilder<Vec<u8>, KoalaBearPoseidon2>;
+
+// check the received subblock input is a valid stdin builder, so the input serialization
+// regressions are caught by the mock path instead of only on real provers; the subblock index
+// could exceed the number of the subblocks since the proving-client pads the inputs for all the
+// subblock services
+pub fn validate_subblock_input(
+    block_number: u64,
+    num_subblocks: u32,
+    subblock_index: u32,
+    input: &[u8],
+) -> Result<(), Status> {
+    if block_number == 0 {
+        return Err(Status::invalid_argument(
+            "subblock input of the genesis block",
+        ));
+    }
+    if num_subblocks == 0 {
+        return Err(Status::invalid_argument(format!(
+            "no subblock of block {block_number}"
+        )));
+    }
+    deserialize_stdin_builder(input).map_err(|e| {
+        Status::invalid_argument(format!(
+            "failed to deserialize subblock input {subblock_index} of block {block_number}: {e}",
+        ))
+    })
+}
+
+// check the received aggregator input is a valid stdin builder, and the subblock public values
+// are consistent with the number of the subblocks
+pub fn validate_aggregator_input(
+    block_number: u64,
+    num_subblocks: u32,
+    subblock_public_values: &[u8],
+    input: &[u8],
+) -> Result<(), Status> {
+    if block_number == 0 {
+        return Err(Status::invalid_argument(
+            "aggregator input of the genesis block",
+        ));
+    }
+    if num_subblocks == 0 {
+        return Err(Status::invalid_argument(format!(
+            "no subblock of block {block_number}"
+        )));
+    }
+
+    let public_values: Vec<Vec<u8>> =
+        bincode::deserialize(subblock_public_values).map_err(|e| {
+            Status::invalid_argument(format!(
+                "failed to deserialize subblock public values of block {block_number}: {e}",
+            ))
+        })?;
+    if public_values.len() != num_subblocks as usize {
+        return Err(Status::invalid_argument(format!(
+            "{} subblock public values of block {block_number} mismatch {num_subblocks} subblocks",
+            public_values.len(),
+        )));
+    }
+
+    deserialize_stdin_builder(input).map_err(|e| {
+        Status::invalid_argument(format!(
+            "failed to deserialize aggregator input of block {block_number}: {e}",
+        ))
+    })
+}
+
+// bincode deserialize a stdin builder, the builder is dropped since only its encoding is checked
+fn deserialize_stdin_builder(input: &[u8]) -> bincode::Result<()> {
+    bincode::deserialize::<StdinBuilder>(input).map(|_| ())
+}
@@ -1,13 +1,43 @@
+use common::{
+    channel::BroadcastChannel,
+    report::{BlockProvingReport, ReportStore},
+    shutdown::{ShutdownPhase, flush_guard, shutdown_reached},
+    summary::ProvingCampaignSummary,
+};
 use derive_more::Constructor;
-use messages::{BlockMsg, BlockMsgReceiver, WatchMsg};
-use std::sync::Arc;
-use tokio::{spawn, sync::Mutex, task::JoinHandle};
-use tracing::{error, info};
+use ethproofs::submitter::EthproofsSubmitter;
+use futures::future::pending;
+use messages::{BlockMsg, BoundedBlockMsgReceiver, WatchMsg, lifecycle::BlockState};
+use std::{path::PathBuf, sync::Arc, time::Duration};
+use tokio::{
+    select, spawn,
+    sync::Mutex,
+    task::JoinHandle,
+    time::{Interval, interval},
+};
+use tracing::{debug, error, info};
 
 #[derive(Constructor, Debug)]
 pub struct BlockReporter {
-    // communication receiver for coordinating with the main scheduler
-    pub comm_receiver: Arc<Mutex<BlockMsgReceiver>>,
+    // bounded communication receiver for coordinating with the main scheduler
+    pub comm_receiver: Arc<Mutex<BoundedBlockMsgReceiver>>,
+
+    // broadcasting the block reports, status and error messages to the websocket watchers, a
+    // watcher falling behind the capacity skips the oldest messages
+    pub watcher_channel: BroadcastChannel<BlockMsg>,
+
+    // NDJSON file appending every block report for the log pipelines; disabled if not specified
+    pub report_ndjson_path: Option<PathBuf>,
+
+    // store of the block reports queried by the fetch-service history requests
+    pub report_store: Arc<dyn ReportStore>,
+
+    // interval of logging the summary of the blocks reported since the last summary; disabled if
+    // not specified
+    pub summary_interval: Option<Duration>,
+
+    // submitter of the block proving progress to ethproofs; disabled if not specified
+    pub ethproofs_submitter: Option<Arc<EthproofsSubmitter>>,
 }
 
 impl BlockReporter {
@@ -15,30 +45,136 @@ impl BlockReporter {
         info!("reporter: start");
 
         spawn(async move {
-            // saving the websocket watchers and will be removed as close if notification failed
-            let mut watchers = vec![];
+            // the queued reports are persisted before the process exits
+            let _flush_guard = flush_guard();
             let mut comm_receiver = self.comm_receiver.lock().await;
-            while let Some(msg) = comm_receiver.recv().await {
+            let mut summary_ticker = self.summary_interval.map(interval);
+            // reports since the last summary without the proof bytes
+            let mut summary_reports = vec![];
+            // identify if the process is shutting down, the queued messages are handled without
+            // waiting for new ones
+            let mut is_flushing = false;
+            loop {
+                let msg = if is_flushing {
+                    comm_receiver.try_recv().ok()
+                } else {
+                    select! {
+                        msg = comm_receiver.recv() => msg,
+                        _ = tick(&mut summary_ticker) => {
+                            log_summary(&mut summary_reports);
+                            continue;
+                        }
+                        () = shutdown_reached(ShutdownPhase::Flushing) => {
+                            info!("reporter: flushing the queued reports for shutdown");
+                            is_flushing = true;
+                            continue;
+                        }
+                    }
+                };
+                let Some(mut msg) = msg else {
+                    break;
+                };
+                if let BlockMsg::Report(report) = &mut msg {
+                    report.on_reported();
+                }
+
                 match &msg {
                     BlockMsg::Watch(WatchMsg { sender }) => {
-                        watchers.push(sender.clone());
+                        // forward the broadcast messages to the watcher until it's closed
+                        let mut subscriber = self.watcher_channel.subscribe();
+                        let sender = sender.clone();
+                        spawn(async move {
+                            while let Some(msg) = subscriber.recv().await {
+                                if sender.send(msg).is_err() {
+                                    break;
+                                }
+                            }
+                        });
                         info!(
                             "reporter: added a new websocket watcher, the current watcher number is {}",
-                            watchers.len(),
+                            self.watcher_channel.subscriber_count(),
                         );
                     }
                     BlockMsg::Report(report) => {
                         let block_number = report.block_number;
-                        watchers.retain(|watcher| watcher.send(msg.clone()).is_ok());
+                        if summary_ticker.is_some() {
+                            let mut report = report.clone();
+                            report.proof = None;
+                            summary_reports.push(report);
+                        }
+                        if let Err(e) = self.report_store.insert(report.clone()).await {
+                            error!(
+                                "reporter: failed to store the report of block {block_number} {e:?}"
+                            );
+                        }
+                        if let Some(ndjson_path) = &self.report_ndjson_path
+                            && let Err(e) = report.append_to_ndjson(ndjson_path)
+                        {
+                            error!(
+                                "reporter: failed to append the report of block {block_number} to {ndjson_path:?} {e:?}"
+                            );
+                        }
+                        if let Some(ethproofs_submitter) = &self.ethproofs_submitter {
+                            ethproofs_submitter.on_report(report).await;
+                        }
+                        let watcher_count = self.watcher_channel.send(msg.clone());
+                        report.span().in_scope(|| {
+                            info!(
+                                "reporter: notified the proved block {block_number} to watcher number {watcher_count}",
+                            )
+                        });
+                    }
+                    BlockMsg::Status(status_msg) => {
+                        if let Some(ethproofs_submitter) = &self.ethproofs_submitter {
+                            ethproofs_submitter.on_status(status_msg).await;
+                        }
+                        let watcher_count = self.watcher_channel.send(msg.clone());
+                        let state = BlockState::from(status_msg.stage);
+                        if state.is_terminal() {
+                            info!(
+                                "reporter: notified the {state} state of block {} to watcher number {watcher_count}",
+                                status_msg.block_number,
+                            );
+                        } else {
+                            debug!(
+                                "reporter: notified the {} stage of block {} to watcher number {watcher_count}",
+                                status_msg.stage, status_msg.block_number,
+                            );
+                        }
+                    }
+                    BlockMsg::Error(error_msg) => {
+                        let watcher_count = self.watcher_channel.send(msg.clone());
                         info!(
-                            "reporter: notified the proved block {block_number} to watcher number {}",
-                            watchers.len(),
+                            "reporter: notified the error of block {} to watcher number {watcher_count}",
+                            error_msg.block_number,
                         );
                     }
                     _ => error!("proving-client: received a wrong message {msg:?}"),
                 }
             }
+            log_summary(&mut summary_reports);
             info!("reporter: stopped");
         })
     }
 }
+
+// wait for the next tick of the summary interval, it never completes if disabled
+async fn tick(summary_ticker: &mut Option<Interval>) {
+    match summary_ticker {
+        Some(ticker) => {
+            ticker.tick().await;
+        }
+        None => pending().await,
+    }
+}
+
+// log the summary of the reports since the last summary, nothing is logged if no block is reported
+fn log_summary(reports: &mut Vec<BlockProvingReport>) {
+    if reports.is_empty() {
+        return;
+    }
+
+    let summary = ProvingCampaignSummary::from_reports(reports.iter());
+    info!("reporter: summary of the recently reported blocks {summary}");
+    reports.clear();
+}
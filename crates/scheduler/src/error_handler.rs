@@ -0,0 +1,45 @@
+use common::{metrics::MetricsRegistry, pipeline_error::PipelineErrorLog};
+use derive_more::Constructor;
+use messages::PipelineErrorReceiver;
+use std::sync::Arc;
+use tokio::{spawn, sync::Mutex, task::JoinHandle};
+use tracing::{info, warn};
+
+// counter name of the pipeline errors
+const PIPELINE_ERRORS_METRIC: &str = "pipeline_errors_total";
+
+// error handler for recording the pipeline errors produced by the scheduler, the errors are
+// counted in the metrics registry and kept in the error log for the admin api
+#[derive(Constructor)]
+pub struct PipelineErrorHandler {
+    // receiving the pipeline errors from the scheduler
+    error_receiver: Arc<Mutex<PipelineErrorReceiver>>,
+
+    // log of the recent pipeline errors
+    error_log: Arc<PipelineErrorLog>,
+
+    // registry of the process metrics
+    metrics: Arc<MetricsRegistry>,
+}
+
+impl PipelineErrorHandler {
+    pub fn run(self: Arc<Self>) -> JoinHandle<()> {
+        info!("pipeline-error-handler: start");
+
+        spawn(async move {
+            let mut error_receiver = self.error_receiver.lock().await;
+            while let Some(pipeline_error) = error_receiver.recv().await {
+                warn!("pipeline-error-handler: recorded pipeline error {pipeline_error}");
+
+                self.metrics.inc_counter(
+                    PIPELINE_ERRORS_METRIC,
+                    &[
+                        ("kind", &pipeline_error.kind.to_string()),
+                        ("source", &pipeline_error.source),
+                    ],
+                );
+                self.error_log.push(pipeline_error);
+            }
+        })
+    }
+}
@@ -0,0 +1,173 @@
+use anyhow::Result;
+use common::{report::FailureStage, utils::PipelineId};
+use messages::{BlockMsg, ControlMsg, ErrorMsg, FetchMsg, JobId, StatusMsg};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// entry of the message journal appended for each message routed by the scheduler
+#[derive(Debug, Deserialize, Serialize)]
+pub struct JournalEntry {
+    // unix timestamp when the scheduler received the message (in milliseconds)
+    pub timestamp_milliseconds: u64,
+
+    // name of the source thread
+    pub source: String,
+
+    // pipeline which the message is routed in
+    pub pipeline_id: PipelineId,
+
+    // summarized message payload
+    pub payload: JournalPayload,
+}
+
+// summarized payload of a journaled message, the fetch and control messages are kept entirely
+// for replaying, and the large inputs and proofs are recorded by sizes and SHA-256 hex digests
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JournalPayload {
+    Watch,
+    Fetch {
+        fetch_msg: FetchMsg,
+    },
+    Proving {
+        job_id: JobId,
+        block_number: u64,
+        agg_input_digest: String,
+        subblock_input_bytes: Vec<u64>,
+    },
+    Proved {
+        job_id: JobId,
+        block_number: u64,
+        success: bool,
+        cycles: u64,
+        proof_digest: Option<String>,
+    },
+    Report {
+        job_id: JobId,
+        block_number: u64,
+        success: bool,
+        failure_stage: Option<FailureStage>,
+    },
+    Control {
+        control_msg: ControlMsg,
+    },
+    Status {
+        status_msg: StatusMsg,
+    },
+    Error {
+        error_msg: ErrorMsg,
+    },
+}
+
+impl From<&BlockMsg> for JournalPayload {
+    fn from(msg: &BlockMsg) -> Self {
+        match msg {
+            BlockMsg::Watch(_) => Self::Watch,
+            BlockMsg::Fetch(fetch_msg) => Self::Fetch {
+                fetch_msg: fetch_msg.clone(),
+            },
+            BlockMsg::Proving(proving_msg) => {
+                let proving_inputs = &proving_msg.proving_inputs;
+                Self::Proving {
+                    job_id: proving_msg.fetch_report.job_id,
+                    block_number: proving_inputs.block_number,
+                    agg_input_digest: proving_inputs.agg_input_digest.clone(),
+                    subblock_input_bytes: proving_inputs
+                        .subblock_inputs
+                        .iter()
+                        .map(|input| input.len() as u64)
+                        .collect(),
+                }
+            }
+            BlockMsg::Proved(proved_msg) => Self::Proved {
+                job_id: proved_msg.job_id.into(),
+                block_number: proved_msg.block_number,
+                success: proved_msg.success,
+                cycles: proved_msg.cycles,
+                proof_digest: proved_msg.proof.as_deref().map(hex_digest),
+            },
+            BlockMsg::Report(report) => Self::Report {
+                job_id: report.job_id,
+                block_number: report.block_number,
+                success: report.success,
+                failure_stage: report.failure_stage,
+            },
+            BlockMsg::Control(control_msg) => Self::Control {
+                control_msg: *control_msg,
+            },
+            BlockMsg::Status(status_msg) => Self::Status {
+                status_msg: status_msg.clone(),
+            },
+            BlockMsg::Error(error_msg) => Self::Error {
+                error_msg: error_msg.clone(),
+            },
+        }
+    }
+}
+
+// journal of the routed messages for debugging the pipeline, each message is appended to the
+// journal file as a json line, and the fetch messages could be replayed by the `replay-journal`
+// client
+#[derive(Debug)]
+pub struct MessageJournal {
+    // journal file opened in append mode
+    file: Mutex<File>,
+}
+
+impl MessageJournal {
+    // open the journal file for appending, it's created if not exists
+    pub fn open(file_path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file_path)?;
+
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    // append a routed message to the journal
+    pub fn append(&self, source: &str, pipeline_id: PipelineId, msg: &BlockMsg) -> Result<()> {
+        let timestamp_milliseconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or_default();
+        let entry = JournalEntry {
+            timestamp_milliseconds,
+            source: source.to_string(),
+            pipeline_id,
+            payload: msg.into(),
+        };
+
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        self.file
+            .lock()
+            .expect("scheduler: journal lock is poisoned")
+            .write_all(&line)?;
+
+        Ok(())
+    }
+}
+
+// read the all entries of a journal file
+pub fn read_journal(file_path: &Path) -> Result<Vec<JournalEntry>> {
+    BufReader::new(File::open(file_path)?)
+        .lines()
+        .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|line| Ok(serde_json::from_str(&line?)?))
+        .collect()
+}
+
+// return the SHA-256 hex digest of the data
+fn hex_digest(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
@@ -0,0 +1,127 @@
+use messages::{FetchMsg, JobId};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+// seconds to keep a pending fetch request, the blocks of a dropped request are never reported, so
+// its pending entry is expired to bound the tracker
+const PENDING_FETCH_TTL_SECONDS: u64 = 24 * 60 * 60;
+
+// pending fetch request of a block range waiting for the reports
+#[derive(Debug)]
+struct PendingFetch {
+    // job id of the request
+    job_id: JobId,
+
+    // first block number of the request
+    start_block_number: u64,
+
+    // last block number of the request (inclusive)
+    end_block_number: u64,
+
+    // time when the scheduler received the request
+    received_at: Instant,
+
+    // number of blocks not reported yet
+    remaining: u64,
+}
+
+// tracker of the end-to-end latency from receiving a fetch request to emitting the block reports,
+// the requests of the latest blocks are not tracked since their block numbers are unknown until
+// fetched
+#[derive(Debug, Default)]
+pub struct LatencyTracker {
+    // pending fetch requests by ascending order of receiving
+    pending_fetches: Vec<PendingFetch>,
+}
+
+impl LatencyTracker {
+    // record the receiving time of the blocks in a fetch request
+    pub fn on_fetch_received(&mut self, fetch_msg: &FetchMsg) {
+        let received_at = Instant::now();
+        let ranges = match fetch_msg {
+            FetchMsg::ProveFromStart {
+                start_block_number,
+                count,
+                ..
+            }
+            | FetchMsg::ReproduceFromStart {
+                start_block_number,
+                count,
+                ..
+            }
+            | FetchMsg::Estimate {
+                start_block_number,
+                count,
+                ..
+            } if *count > 0 => start_block_number
+                .checked_add(count - 1)
+                .map(|end_block_number| (*start_block_number, end_block_number))
+                .into_iter()
+                .collect(),
+            FetchMsg::Backfill {
+                start_block_number,
+                end_block_number,
+                ..
+            } if start_block_number <= end_block_number => {
+                vec![(*start_block_number, *end_block_number)]
+            }
+            FetchMsg::ReproduceByList { block_numbers, .. } => block_numbers
+                .iter()
+                .map(|block_number| (*block_number, *block_number))
+                .collect(),
+            _ => vec![],
+        };
+
+        self.expire_pending_fetches();
+
+        let job_id = fetch_msg.job_id();
+        self.pending_fetches.extend(ranges.into_iter().map(
+            |(start_block_number, end_block_number)| PendingFetch {
+                job_id,
+                start_block_number,
+                end_block_number,
+                received_at,
+                remaining: (end_block_number - start_block_number).saturating_add(1),
+            },
+        ));
+    }
+
+    // return the latency of a reported block since the pending request of its job containing it,
+    // it's none if the block is not requested by a tracked request
+    pub fn on_report_emitted(&mut self, job_id: JobId, block_number: u64) -> Option<Duration> {
+        self.expire_pending_fetches();
+
+        let index = self.pending_fetches.iter().position(|pending| {
+            pending.job_id == job_id
+                && (pending.start_block_number..=pending.end_block_number).contains(&block_number)
+        })?;
+
+        let pending = &mut self.pending_fetches[index];
+        let latency = pending.received_at.elapsed();
+        pending.remaining = pending.remaining.saturating_sub(1);
+        if pending.remaining == 0 {
+            self.pending_fetches.remove(index);
+        }
+
+        Some(latency)
+    }
+
+    // remove the pending fetch requests which are not completed in the ttl
+    fn expire_pending_fetches(&mut self) {
+        let ttl = Duration::from_secs(PENDING_FETCH_TTL_SECONDS);
+        self.pending_fetches.retain(|pending| {
+            let is_expired = pending.received_at.elapsed() > ttl;
+            if is_expired {
+                warn!(
+                    "scheduler: expired the latency tracking of job {} blocks {}..={} with {} blocks not reported",
+                    pending.job_id,
+                    pending.start_block_number,
+                    pending.end_block_number,
+                    pending.remaining,
+                );
+            }
+
+            !is_expired
+        });
+    }
+}
@@ -1,8 +1,46 @@
+pub mod error_handler;
+pub mod journal;
+pub mod latency;
+pub mod rate_control;
+pub mod remote;
+pub mod routing;
+pub mod supervisor;
+
+use crate::{
+    journal::MessageJournal, latency::LatencyTracker, rate_control::RateController,
+    routing::RoutingTable,
+};
+use common::{
+    channel::WatchReceiver,
+    metrics::MetricsRegistry,
+    pipeline_error::{PipelineError, PipelineErrorKind},
+    runtime::RuntimeState,
+    utils::{DEFAULT_PIPELINE_ID, PipelineId},
+};
 use derive_more::Constructor;
-use messages::{BlockMsg, BlockMsgEndpoint, BlockMsgReceiver, BlockMsgSender};
-use std::sync::Arc;
-use tokio::{select, spawn, sync::Mutex, task::JoinHandle};
-use tracing::{error, info};
+use futures::future::select_all;
+use messages::{
+    BlockMsg, BlockMsgKind, ErrorMsg, JobId, PipelineErrorSender,
+    lifecycle::{BlockLifecycles, InvalidTransition},
+};
+use std::{fmt::Display, sync::Arc};
+use tokio::{select, spawn, task::JoinHandle};
+use tracing::{Instrument, Span, error, info, info_span, warn};
+
+// counter name of the messages routed by the scheduler
+const MESSAGES_METRIC: &str = "scheduler_messages_total";
+
+// summary name of the end-to-end latency from receiving a fetch request to emitting a block report
+const BLOCK_LATENCY_METRIC: &str = "scheduler_block_latency_seconds";
+
+// counter name of the fetch messages held by the rate controller
+const HELD_FETCHES_METRIC: &str = "scheduler_held_fetches_total";
+
+// counter name of the block failures emitted by the stages
+const BLOCK_ERRORS_METRIC: &str = "scheduler_block_errors_total";
+
+// counter name of the status messages moving a block to a state not allowed in its lifecycle
+const INVALID_TRANSITIONS_METRIC: &str = "scheduler_invalid_transitions_total";
 
 // main scheduler for coordinating multiple threads
 // the main process is:
@@ -11,94 +49,325 @@ use tracing::{error, info};
 // - fetch-service receives a http request and sends a FetchMsg to fetcher thread
 // - fetcher thread gets block data via rpc node, generates and sends subblock and aggregation
 //   inputs to proving-client thread, and sends fetch performance report to reporter thread
+// - for a dry-run estimation, fetcher thread emulates the inputs and sends the estimation report
+//   to reporter thread directly without proving
 // - proving-client thread sends subblock and aggregation inputs to proving-cluster
 // - after proving complete proving-cluster sends the proof result to proof-service by grpc
 // - proof-service sends the proof result to reporter thread
 // - reporter thread collects and calculates the final block proving report to each fetch-service
 //   websocket connection, each websocket connection receives the all proving results which should
 //   be filtered by the users
+// - the block reports are also sent back to the fetcher thread as a backpressure signal for
+//   prefetching blocks
+// - the routes are declared by the subsystems registered to the routing table, each message is
+//   delivered to the other subsystems consuming its kind
+// - the send failures, unroutable messages and closed channels are sent as pipeline errors to the
+//   error handler thread instead of crashing the scheduler
+// - the routed messages and the end-to-end latency of the blocks are recorded to the metrics
+// - multiple pipelines of a fetcher and a proving-client could run under one scheduler, e.g. one
+//   per chain or proving cluster, the fetch requests and proving results are routed to the
+//   pipeline carried by the messages, and the other messages stay in the pipeline of the source
+// - the new fetch messages of a pipeline are held when the blocks dispatched for proving but not
+//   reported reach the limit, and they are forwarded after the reports drain the backlog
+// - the routed messages are appended to the message journal if enabled for post-mortem analysis
+// - fetcher and proving-client threads send a status message at each stage transition of a block,
+//   which is forwarded by the reporter thread to the websocket connections as the progress
+// - the proving-client and reporter threads consume by bounded channels, the scheduler waits for
+//   the capacity when delivering to them, so a slow consumer holds back the producers instead of
+//   growing the queues unboundedly
+// - any stage sends an error message when a block fails, which is counted by stage and routed to
+//   the reporter thread for the websocket connections instead of only being logged
+// - the status messages drive the lifecycle state of each block, the transitions not allowed are
+//   counted and logged with the block and states, and the status messages are still forwarded
 #[derive(Constructor)]
 pub struct Scheduler {
-    // receiving and handling fetch requests from fetch-service
-    fetch_service_receiver: Arc<Mutex<BlockMsgReceiver>>,
+    // routing table of the registered subsystems
+    routing_table: RoutingTable,
 
-    // receiving and handling proving results
-    proof_service_receiver: Arc<Mutex<BlockMsgReceiver>>,
+    // sending the pipeline errors to the error handler thread
+    error_sender: Arc<PipelineErrorSender>,
 
-    // bidirectional endpoint for receiving the fetch requests and sending the proving requests
-    fetcher_endpoint: Arc<BlockMsgEndpoint>,
+    // registry of the process metrics
+    metrics: Arc<MetricsRegistry>,
 
-    // bidirectional endpoint for receiving the proving requests and sending the block reports
-    proving_client_endpoint: Arc<BlockMsgEndpoint>,
+    // runtime configuration changed by the admin requests, its maximum number of blocks
+    // dispatched for proving but not reported in a pipeline before holding the new fetch messages
+    // is applied on change; disabled if zero
+    runtime_state: WatchReceiver<RuntimeState>,
 
-    // sending the block reports to the reporter thread
-    reporter_sender: Arc<BlockMsgSender>,
+    // journal of the routed messages; disabled if none
+    journal: Option<MessageJournal>,
 }
 
 impl Scheduler {
     pub fn run(self: Arc<Self>) -> JoinHandle<()> {
         info!("scheduler: start");
 
-        let fetch_service_receiver = self.fetch_service_receiver.clone();
-        let proof_service_receiver = self.proof_service_receiver.clone();
-        let fetcher_endpoint = self.fetcher_endpoint.clone();
-        let proving_client_endpoint = self.proving_client_endpoint.clone();
-        let report_sender = self.reporter_sender.clone();
-
         spawn(async move {
-            let mut fetch_service_receiver = fetch_service_receiver.lock().await;
-            let mut proof_service_receiver = proof_service_receiver.lock().await;
-            loop {
-                select! {
-                    msg = fetch_service_receiver.recv() => {
-                        let msg = msg.expect("scheduler: received an error message from fetch-service");
-                        match msg {
-                            BlockMsg::Fetch(_) => {
-                                fetcher_endpoint.send(msg).expect("scheduler: failed to send a fetch message to fetcher thread");
-                            }
-                            BlockMsg::Watch(_) => {
-                                report_sender.send(msg).expect("scheduler: failed to send a watch message to reporter thread");
-                            }
-                            _ => {
-                                error!("scheduler: received a wrong message from fetch-service {msg:?}");
-                            }
-                        }
-                    }
-                    msg = proof_service_receiver.recv() => {
-                        let msg = msg.expect("scheduler: received an error message from proof-service");
-                        match msg {
-                            BlockMsg::Proved(_) => {
-                                proving_client_endpoint.send(msg).expect("scheduler: failed to send a proved message to proving-client thread");
-                            }
-                            _ => {
-                                error!("scheduler: received a wrong message from proof-service {msg:?}");
-                            }
+            // collect the receivers of the all producing subsystems
+            let (mut sources, mut receivers): (Vec<_>, Vec<_>) =
+                self.routing_table.producers().into_iter().unzip();
+
+            let mut latency_tracker = LatencyTracker::default();
+            let mut lifecycles = BlockLifecycles::default();
+            let mut runtime_state = self.runtime_state.clone();
+            let mut rate_controller =
+                RateController::new(runtime_state.latest().max_in_flight_blocks);
+            while !receivers.is_empty() {
+                // receive a message from any producing subsystem, or apply the changed runtime
+                // configuration
+                let (msg, index) = {
+                    let recv_futures = receivers.iter().map(|receiver| Box::pin(receiver.recv()));
+                    select! {
+                        (msg, index, _) = select_all(recv_futures) => (msg, index),
+                        Some(state) = runtime_state.changed() => {
+                            self.on_runtime_state_changed(&mut rate_controller, state).await;
+                            continue;
                         }
                     }
-                    msg = fetcher_endpoint.recv() => {
-                        let msg = msg.expect("scheduler: received an error message from fetcher thread");
-                        match msg {
-                            BlockMsg::Proving(_) => {
-                                proving_client_endpoint.send(msg).expect("scheduler: failed to send a proving message to proving-client thread");
-                            }
-                            _ => {
-                                error!("scheduler: received a wrong message from fetcher thread {msg:?}");
-                            }
-                        }
+                };
+
+                let source = &sources[index];
+                match msg {
+                    Some(msg) => {
+                        // route a block message in the span of its trace id
+                        let span = msg.trace_id().map_or_else(
+                            Span::none,
+                            |trace_id| info_span!("route", %trace_id, kind = %msg.kind()),
+                        );
+                        self.route(
+                            &mut latency_tracker,
+                            &mut rate_controller,
+                            &mut lifecycles,
+                            source,
+                            msg,
+                        )
+                        .instrument(span)
+                        .await
                     }
-                    msg = proving_client_endpoint.recv() => {
-                        let msg = msg.expect("scheduler: received an error message from proving-client thread");
-                        match msg {
-                            BlockMsg::Report(_) => {
-                                report_sender.send(msg).expect("scheduler: failed to send a report message to reporter thread");
-                            }
-                            _ => {
-                                error!("scheduler: received a wrong message from proving-client thread {msg:?}");
-                            }
-                        }
+                    None => {
+                        self.on_channel_closed(source);
+                        sources.remove(index);
+                        receivers.remove(index);
                     }
                 }
             }
         })
     }
+
+    // route a message from the source, the fetch messages are throttled by the rate controller
+    async fn route(
+        &self,
+        latency_tracker: &mut LatencyTracker,
+        rate_controller: &mut RateController,
+        lifecycles: &mut BlockLifecycles,
+        source: &str,
+        msg: BlockMsg,
+    ) {
+        let pipeline_id = msg
+            .pipeline_id()
+            .or_else(|| self.routing_table.pipeline_id(source))
+            .unwrap_or(DEFAULT_PIPELINE_ID);
+
+        if let Some(journal) = &self.journal {
+            if let Err(e) = journal.append(source, pipeline_id, &msg) {
+                error!(
+                    "scheduler: failed to append a {} message to the journal {e:?}",
+                    msg.kind()
+                );
+            }
+        }
+
+        match &msg {
+            BlockMsg::Fetch(fetch_msg) => {
+                latency_tracker.on_fetch_received(fetch_msg);
+
+                let Some(msg) = rate_controller.throttle(pipeline_id, source, msg) else {
+                    self.on_fetch_held(rate_controller, pipeline_id);
+                    return;
+                };
+                self.deliver(source, msg).await;
+            }
+            BlockMsg::Proving(_) => {
+                rate_controller.on_proving_dispatched(pipeline_id);
+                self.deliver(source, msg).await;
+            }
+            BlockMsg::Report(report) => {
+                self.on_report_emitted(latency_tracker, report.job_id, report.block_number);
+                lifecycles.remove(report.job_id, report.block_number);
+
+                let held_fetches = rate_controller.on_report_emitted(pipeline_id, report);
+                self.deliver(source, msg).await;
+                for (fetch_source, fetch_msg) in held_fetches {
+                    info!("scheduler: forwarding the held fetch message of pipeline {pipeline_id}");
+                    self.deliver(&fetch_source, fetch_msg).await;
+                }
+            }
+            BlockMsg::Error(error_msg) => {
+                self.on_block_error(pipeline_id, error_msg);
+                self.deliver(source, msg).await;
+            }
+            BlockMsg::Status(status_msg) => {
+                let (job_id, block_number) = (status_msg.job_id, status_msg.block_number);
+                if let Err(e) = lifecycles.advance(job_id, block_number, status_msg.stage) {
+                    self.on_invalid_transition(source, &e);
+                }
+                self.deliver(source, msg).await;
+            }
+            _ => self.deliver(source, msg).await,
+        }
+    }
+
+    // deliver a message from the source to the subsystems consuming its kind
+    async fn deliver(&self, source: &str, msg: BlockMsg) {
+        let kind = msg.kind();
+        let pipeline_id = msg.pipeline_id();
+        let consumers = self.routing_table.consumers(source, kind, pipeline_id);
+        let Some(((last_target, last_sender), targets)) = consumers.split_last() else {
+            self.on_unroutable(source, kind, pipeline_id);
+            return;
+        };
+
+        for (target, sender) in targets {
+            self.on_sent(source, target, kind, sender.send(msg.clone()).await);
+        }
+        self.on_sent(source, last_target, kind, last_sender.send(msg).await);
+    }
+
+    // count a message routed to the target thread, or send a pipeline error if it's failed to send
+    fn on_sent<E: Display>(
+        &self,
+        source: &str,
+        target: &str,
+        kind: BlockMsgKind,
+        result: Result<(), E>,
+    ) {
+        match result {
+            Ok(()) => self.metrics.inc_counter(
+                MESSAGES_METRIC,
+                &[
+                    ("variant", &kind.to_string()),
+                    ("source", source),
+                    ("target", target),
+                ],
+            ),
+            Err(e) => self.send_error(PipelineError::new(
+                PipelineErrorKind::SendFailed,
+                source,
+                Some(target),
+                e.to_string(),
+            )),
+        }
+    }
+
+    // record the end-to-end latency of a block when its report is emitted to the reporter
+    fn on_report_emitted(
+        &self,
+        latency_tracker: &mut LatencyTracker,
+        job_id: JobId,
+        block_number: u64,
+    ) {
+        if let Some(latency) = latency_tracker.on_report_emitted(job_id, block_number) {
+            info!("scheduler: block {block_number} is reported in {latency:?} since requested");
+            self.metrics
+                .observe(BLOCK_LATENCY_METRIC, &[], latency.as_secs_f64());
+        }
+    }
+
+    // apply the changed maximum number of blocks in flight, and forward the held fetch messages
+    // of the pipelines no longer saturated
+    async fn on_runtime_state_changed(
+        &self,
+        rate_controller: &mut RateController,
+        state: RuntimeState,
+    ) {
+        info!(
+            "scheduler: runtime state changed, pipeline state = {:?}, max in-flight blocks = {}",
+            state.pipeline_state, state.max_in_flight_blocks,
+        );
+
+        let held_fetches = rate_controller.set_max_in_flight_blocks(state.max_in_flight_blocks);
+        for (fetch_source, fetch_msg) in held_fetches {
+            info!("scheduler: forwarding the held fetch message from {fetch_source}");
+            self.deliver(&fetch_source, fetch_msg).await;
+        }
+    }
+
+    // count and log a fetch message held since the pipeline is saturated
+    fn on_fetch_held(&self, rate_controller: &RateController, pipeline_id: PipelineId) {
+        let (in_flight, held) = rate_controller.backlog(pipeline_id);
+        let (dispatch_rate, completion_rate) = rate_controller.rates(pipeline_id);
+        info!(
+            "scheduler: holding a fetch message of pipeline {pipeline_id}, in-flight blocks = {in_flight}, held fetches = {held}, dispatch rate = {dispatch_rate:.1}/min, completion rate = {completion_rate:.1}/min",
+        );
+        self.metrics.inc_counter(
+            HELD_FETCHES_METRIC,
+            &[("pipeline", &pipeline_id.to_string())],
+        );
+    }
+
+    // count and log a status message moving a block to a state not allowed
+    fn on_invalid_transition(&self, source: &str, e: &InvalidTransition) {
+        warn!("scheduler: {e} by the status message from {source}");
+        self.metrics.inc_counter(
+            INVALID_TRANSITIONS_METRIC,
+            &[
+                ("source", source),
+                (
+                    "from",
+                    &e.from
+                        .map_or("untracked".to_string(), |from| from.to_string()),
+                ),
+                ("to", &e.to.to_string()),
+            ],
+        );
+    }
+
+    // count and log a block failure emitted by a stage
+    fn on_block_error(&self, pipeline_id: PipelineId, error_msg: &ErrorMsg) {
+        warn!("scheduler: {error_msg} in pipeline {pipeline_id}");
+        self.metrics.inc_counter(
+            BLOCK_ERRORS_METRIC,
+            &[
+                ("pipeline", &pipeline_id.to_string()),
+                ("stage", &error_msg.stage.to_string()),
+                ("retryable", &error_msg.retryable.to_string()),
+            ],
+        );
+    }
+
+    // send a pipeline error for a message type which has no route from the source thread
+    fn on_unroutable(&self, source: &str, kind: BlockMsgKind, pipeline_id: Option<PipelineId>) {
+        let message = match pipeline_id {
+            Some(pipeline_id) => {
+                format!("received a wrong message of type {kind} for pipeline {pipeline_id}")
+            }
+            None => format!("received a wrong message of type {kind}"),
+        };
+        self.send_error(PipelineError::new(
+            PipelineErrorKind::Unroutable,
+            source,
+            None,
+            message,
+        ));
+    }
+
+    // send a pipeline error for a closed channel from the source thread, the source is no longer
+    // received since the messages could never be received again
+    fn on_channel_closed(&self, source: &str) {
+        self.send_error(PipelineError::new(
+            PipelineErrorKind::ChannelClosed,
+            source,
+            None,
+            "the receiving channel is closed".to_string(),
+        ));
+    }
+
+    // send a pipeline error to the error handler thread
+    fn send_error(&self, pipeline_error: PipelineError) {
+        error!("scheduler: {pipeline_error}");
+        if let Err(e) = self.error_sender.send(pipeline_error) {
+            error!("scheduler: failed to send a pipeline error {e}");
+        }
+    }
 }
@@ -0,0 +1,164 @@
+use common::{report::FailureStage, utils::PipelineId};
+use messages::{BlockMsg, ReportMsg};
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+// sliding window for measuring the dispatch and completion rates (in seconds)
+const RATE_WINDOW_SECONDS: u64 = 60;
+
+// fetch message held by the rate controller with its source thread
+pub type HeldFetch = (String, BlockMsg);
+
+// throughput of a pipeline measured by the scheduler
+#[derive(Debug, Default)]
+struct PipelineThroughput {
+    // times of the proving messages dispatched in the window
+    dispatched_at: VecDeque<Instant>,
+
+    // times of the proved blocks reported in the window
+    completed_at: VecDeque<Instant>,
+
+    // number of blocks dispatched for proving but not reported
+    in_flight: usize,
+
+    // fetch messages held by the order of receiving
+    held_fetches: VecDeque<HeldFetch>,
+}
+
+impl PipelineThroughput {
+    // remove the times out of the window
+    fn expire(&mut self, now: Instant) {
+        let window = Duration::from_secs(RATE_WINDOW_SECONDS);
+        for times in [&mut self.dispatched_at, &mut self.completed_at] {
+            while times
+                .front()
+                .is_some_and(|time| now.duration_since(*time) > window)
+            {
+                times.pop_front();
+            }
+        }
+    }
+}
+
+// rate controller of the fetch messages, it tracks the rate of the proving dispatches and the
+// report completions of each pipeline, and holds the new fetch messages when the pipeline is
+// saturated to smooth the bursts of large range requests
+#[derive(Debug)]
+pub struct RateController {
+    // maximum number of blocks dispatched for proving but not reported in a pipeline, the new
+    // fetch messages are held when it's reached; disabled if zero
+    max_in_flight_blocks: usize,
+
+    // throughput of each pipeline
+    pipelines: HashMap<PipelineId, PipelineThroughput>,
+}
+
+impl RateController {
+    pub fn new(max_in_flight_blocks: usize) -> Self {
+        Self {
+            max_in_flight_blocks,
+            pipelines: HashMap::new(),
+        }
+    }
+
+    // change the maximum number of blocks in flight and return the held fetch messages to forward
+    // of the pipelines no longer saturated
+    pub fn set_max_in_flight_blocks(&mut self, max_in_flight_blocks: usize) -> Vec<HeldFetch> {
+        self.max_in_flight_blocks = max_in_flight_blocks;
+
+        self.pipelines
+            .values_mut()
+            .filter(|throughput| {
+                max_in_flight_blocks == 0 || throughput.in_flight < max_in_flight_blocks
+            })
+            .flat_map(|throughput| throughput.held_fetches.drain(..))
+            .collect()
+    }
+
+    // record a proving message dispatched to the proving-client of the pipeline
+    pub fn on_proving_dispatched(&mut self, pipeline_id: PipelineId) {
+        let now = Instant::now();
+        let throughput = self.pipelines.entry(pipeline_id).or_default();
+        throughput.expire(now);
+        throughput.dispatched_at.push_back(now);
+        throughput.in_flight += 1;
+    }
+
+    // record a report of the pipeline and return the held fetch messages to forward if the
+    // pipeline is no longer saturated; the reports of the blocks failed before proving are not
+    // completions since they were never dispatched
+    pub fn on_report_emitted(
+        &mut self,
+        pipeline_id: PipelineId,
+        report: &ReportMsg,
+    ) -> Vec<HeldFetch> {
+        if matches!(
+            report.failure_stage,
+            Some(FailureStage::Fetching | FailureStage::Validation)
+        ) {
+            return vec![];
+        }
+
+        let now = Instant::now();
+        let max_in_flight_blocks = self.max_in_flight_blocks;
+        let throughput = self.pipelines.entry(pipeline_id).or_default();
+        throughput.expire(now);
+        throughput.completed_at.push_back(now);
+        throughput.in_flight = throughput.in_flight.saturating_sub(1);
+
+        if throughput.in_flight < max_in_flight_blocks {
+            throughput.held_fetches.drain(..).collect()
+        } else {
+            vec![]
+        }
+    }
+
+    // hold a fetch message if the pipeline is saturated, or return it back for forwarding; the
+    // fetch messages are kept in order, so a message is also held behind the held ones
+    pub fn throttle(
+        &mut self,
+        pipeline_id: PipelineId,
+        source: &str,
+        msg: BlockMsg,
+    ) -> Option<BlockMsg> {
+        if self.max_in_flight_blocks == 0 {
+            return Some(msg);
+        }
+
+        let max_in_flight_blocks = self.max_in_flight_blocks;
+        let throughput = self.pipelines.entry(pipeline_id).or_default();
+        if throughput.in_flight < max_in_flight_blocks && throughput.held_fetches.is_empty() {
+            return Some(msg);
+        }
+
+        throughput.held_fetches.push_back((source.to_string(), msg));
+        None
+    }
+
+    // return the number of the blocks in flight and the fetch messages held of the pipeline
+    pub fn backlog(&self, pipeline_id: PipelineId) -> (usize, usize) {
+        self.pipelines
+            .get(&pipeline_id)
+            .map_or((0, 0), |throughput| {
+                (throughput.in_flight, throughput.held_fetches.len())
+            })
+    }
+
+    // return the proving dispatch and report completion rates of the pipeline (blocks per
+    // minute) in the window
+    pub fn rates(&self, pipeline_id: PipelineId) -> (f64, f64) {
+        let Some(throughput) = self.pipelines.get(&pipeline_id) else {
+            return (0.0, 0.0);
+        };
+
+        let per_minute =
+            |times: &VecDeque<Instant>| times.len() as f64 * 60.0 / RATE_WINDOW_SECONDS as f64;
+
+        (
+            per_minute(&throughput.dispatched_at),
+            per_minute(&throughput.completed_at),
+        )
+    }
+}
@@ -0,0 +1,109 @@
+use crate::routing::Subsystem;
+use anyhow::Result;
+use common::{bus::MessageBus, channel::DuplexUnboundedChannel};
+use messages::{BlockMsg, BlockMsgChannelReceiver, BlockMsgChannelSender};
+use std::sync::Arc;
+use tokio::spawn;
+use tracing::{error, info, warn};
+
+// prefix of the message bus subjects of the components
+const SUBJECT_PREFIX: &str = "eth-proofs";
+
+// return the subject of the messages consumed by a component
+fn inbox_subject(name: &str) -> String {
+    format!("{SUBJECT_PREFIX}.{name}.inbox")
+}
+
+// return the subject of the messages produced by a component
+fn outbox_subject(name: &str) -> String {
+    format!("{SUBJECT_PREFIX}.{name}.outbox")
+}
+
+impl Subsystem {
+    // connect the subsystem to its component running on another host, the messages routed to the
+    // subsystem are published to the inbox subject of the component, and the messages produced by
+    // the component are subscribed from its outbox subject; the declared kinds are kept
+    pub async fn connect_remote(mut self, bus: &Arc<dyn MessageBus>) -> Result<Self> {
+        let channel = DuplexUnboundedChannel::default();
+        let (local_endpoint, bus_endpoint) = (channel.endpoint1(), channel.endpoint2());
+
+        if self.receiver.is_some() {
+            let subject = outbox_subject(&self.name);
+            forward_from_bus(bus.clone(), subject, bus_endpoint.sender().into()).await?;
+            self.receiver = Some(local_endpoint.receiver().into());
+        }
+        if self.sender.is_some() {
+            let subject = inbox_subject(&self.name);
+            forward_to_bus(bus.clone(), subject, bus_endpoint.receiver().into());
+            self.sender = Some(local_endpoint.sender().into());
+        }
+        info!("scheduler: connected the remote component {}", self.name);
+
+        Ok(self)
+    }
+
+    // serve the subsystem for the scheduler running on another host, the messages produced by
+    // the subsystem are published to its outbox subject, and the messages from its inbox subject
+    // are sent to the subsystem
+    pub async fn serve_remote(&self, bus: &Arc<dyn MessageBus>) -> Result<()> {
+        if let Some(receiver) = &self.receiver {
+            forward_to_bus(bus.clone(), outbox_subject(&self.name), receiver.clone());
+        }
+        if let Some(sender) = &self.sender {
+            forward_from_bus(bus.clone(), inbox_subject(&self.name), sender.clone()).await?;
+        }
+        info!(
+            "scheduler: serving the component {} by the message bus",
+            self.name
+        );
+
+        Ok(())
+    }
+}
+
+// publish the messages received from a local channel to a message bus subject, the messages not
+// transmittable are dropped
+fn forward_to_bus(bus: Arc<dyn MessageBus>, subject: String, receiver: BlockMsgChannelReceiver) {
+    spawn(async move {
+        while let Some(msg) = receiver.recv().await {
+            let kind = msg.kind();
+            let result = match msg.encode() {
+                Ok(payload) => bus.publish(&subject, payload).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                error!("scheduler: failed to publish a {kind} message to {subject} {e:?}");
+            }
+        }
+        warn!("scheduler: stopped publishing to {subject} since the channel is closed");
+    });
+}
+
+// send the messages subscribed from a message bus subject to a local channel
+async fn forward_from_bus(
+    bus: Arc<dyn MessageBus>,
+    subject: String,
+    sender: BlockMsgChannelSender,
+) -> Result<()> {
+    let mut subscription = bus.subscribe(&subject).await?;
+    spawn(async move {
+        while let Some(payload) = subscription.recv().await {
+            let msg = match BlockMsg::decode(&payload) {
+                Ok(msg) => msg,
+                Err(e) => {
+                    error!("scheduler: failed to decode a message from {subject} {e:?}");
+                    continue;
+                }
+            };
+            if let Err(e) = sender.send(msg).await {
+                error!(
+                    "scheduler: stopped subscribing {subject} since the channel is closed {e:?}"
+                );
+                return;
+            }
+        }
+        warn!("scheduler: the subscription of {subject} is closed");
+    });
+
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use common::utils::PipelineId;
+use messages::{BlockMsgChannelReceiver, BlockMsgChannelSender, BlockMsgKind};
+
+// subsystem registered to the scheduler, it declares the message kinds it produces and consumes
+pub struct Subsystem {
+    // subsystem name for logging and metrics, it should be unique in the routing table
+    pub(crate) name: String,
+
+    // id of the pipeline which the subsystem belongs to; none if it's shared by the all pipelines
+    pipeline_id: Option<PipelineId>,
+
+    // receiving the messages produced by the subsystem; none if it produces nothing
+    pub(crate) receiver: Option<BlockMsgChannelReceiver>,
+
+    // kinds of the messages produced by the subsystem
+    produces: Vec<BlockMsgKind>,
+
+    // sending the messages consumed by the subsystem, the scheduler waits for the capacity if
+    // the channel is bounded; none if it consumes nothing
+    pub(crate) sender: Option<BlockMsgChannelSender>,
+
+    // kinds of the messages consumed by the subsystem
+    consumes: Vec<BlockMsgKind>,
+}
+
+impl Subsystem {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            pipeline_id: None,
+            receiver: None,
+            produces: vec![],
+            sender: None,
+            consumes: vec![],
+        }
+    }
+
+    // return the subsystem name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // declare the pipeline which the subsystem belongs to
+    pub fn in_pipeline(mut self, pipeline_id: PipelineId) -> Self {
+        self.pipeline_id = Some(pipeline_id);
+        self
+    }
+
+    // declare the message kinds produced by the subsystem and the receiver of them
+    pub fn producing(
+        mut self,
+        receiver: impl Into<BlockMsgChannelReceiver>,
+        kinds: &[BlockMsgKind],
+    ) -> Self {
+        self.receiver = Some(receiver.into());
+        self.produces = kinds.to_vec();
+        self
+    }
+
+    // declare the message kinds consumed by the subsystem and the sender of them
+    pub fn consuming(
+        mut self,
+        sender: impl Into<BlockMsgChannelSender>,
+        kinds: &[BlockMsgKind],
+    ) -> Self {
+        self.sender = Some(sender.into());
+        self.consumes = kinds.to_vec();
+        self
+    }
+}
+
+// routing table of the subsystems registered to the scheduler, a message produced by a subsystem
+// is delivered to every other subsystem consuming its kind by the registration order, so a new
+// component could be inserted into the pipeline without editing the scheduler
+// - a message is delivered to the subsystems of its pipeline and the shared subsystems, the
+//   pipeline is carried by the message (e.g. a fetch request) or is the pipeline of the source
+//   subsystem
+#[derive(Default)]
+pub struct RoutingTable {
+    // registered subsystems by the registration order
+    subsystems: Vec<Subsystem>,
+}
+
+impl RoutingTable {
+    // register a subsystem
+    pub fn register(&mut self, subsystem: Subsystem) {
+        self.subsystems.push(subsystem);
+    }
+
+    // return the names and receivers of the producing subsystems
+    pub fn producers(&self) -> Vec<(String, BlockMsgChannelReceiver)> {
+        self.subsystems
+            .iter()
+            .filter_map(|subsystem| {
+                let receiver = subsystem.receiver.clone()?;
+                Some((subsystem.name.clone(), receiver))
+            })
+            .collect()
+    }
+
+    // return the pipeline of a subsystem, it's none if the subsystem is shared or not registered
+    pub fn pipeline_id(&self, name: &str) -> Option<PipelineId> {
+        self.subsystems
+            .iter()
+            .find(|subsystem| subsystem.name == name)
+            .and_then(|subsystem| subsystem.pipeline_id)
+    }
+
+    // return the names and senders of the subsystems consuming a message kind produced by the
+    // source in the pipeline, it's empty if the source doesn't declare producing the kind; a
+    // subsystem never receives its own messages
+    // - pipeline_id: pipeline carried by the message, the pipeline of the source is used if none
+    pub fn consumers(
+        &self,
+        source: &str,
+        kind: BlockMsgKind,
+        pipeline_id: Option<PipelineId>,
+    ) -> Vec<(String, BlockMsgChannelSender)> {
+        let Some(producer) = self
+            .subsystems
+            .iter()
+            .find(|subsystem| subsystem.name == source && subsystem.produces.contains(&kind))
+        else {
+            return vec![];
+        };
+        let pipeline_id = pipeline_id.or(producer.pipeline_id);
+
+        self.subsystems
+            .iter()
+            .filter(|subsystem| subsystem.name != source && subsystem.consumes.contains(&kind))
+            .filter(|subsystem| match (pipeline_id, subsystem.pipeline_id) {
+                (Some(pipeline_id), Some(consumer_pipeline_id)) => {
+                    pipeline_id == consumer_pipeline_id
+                }
+                _ => true,
+            })
+            .filter_map(|subsystem| {
+                let sender = subsystem.sender.clone()?;
+                Some((subsystem.name.clone(), sender))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,118 @@
+use common::utils::TaskStarter;
+use std::{process::exit, time::Instant};
+use tokio::{
+    spawn,
+    task::JoinHandle,
+    time::{Duration, sleep},
+};
+use tracing::{error, info, warn};
+
+// initial backoff for restarting a crashed task (in milliseconds)
+const INITIAL_RESTART_BACKOFF_MILLISECONDS: u64 = 500;
+
+// maximum backoff for restarting a crashed task (in seconds)
+const MAX_RESTART_BACKOFF_SECONDS: u64 = 60;
+
+// running time after which a restarted task is considered stable, the restart count and backoff
+// are reset if it crashes again (in seconds)
+const STABLE_RUNNING_SECONDS: u64 = 300;
+
+// supervisor for monitoring the pipeline tasks, a task crashed by a panic is logged and restarted
+// with an exponential backoff; the components keep their channels across restarts, so a restarted
+// task is re-wired to the same pipeline; the process exits if a critical task gives up restarting,
+// since the pipeline can't work without it and the service manager could restart the process
+pub struct Supervisor {
+    // maximum number of consecutive restarts of a task before giving up; unlimited if zero
+    max_restarts: usize,
+
+    // named starters of the supervised tasks and whether each task is critical
+    tasks: Vec<(String, TaskStarter, bool)>,
+}
+
+impl Supervisor {
+    pub fn new(max_restarts: usize) -> Self {
+        Self {
+            max_restarts,
+            tasks: vec![],
+        }
+    }
+
+    // add a named task to be supervised
+    pub fn supervise(&mut self, name: impl Into<String>, start: TaskStarter) {
+        self.tasks.push((name.into(), start, false));
+    }
+
+    // add a named critical task to be supervised, the process exits if it gives up restarting
+    pub fn supervise_critical(&mut self, name: impl Into<String>, start: TaskStarter) {
+        self.tasks.push((name.into(), start, true));
+    }
+
+    // add the named tasks of a component to be supervised
+    pub fn supervise_all<N: Into<String>>(
+        &mut self,
+        tasks: impl IntoIterator<Item = (N, TaskStarter)>,
+    ) {
+        self.tasks.extend(
+            tasks
+                .into_iter()
+                .map(|(name, start)| (name.into(), start, false)),
+        );
+    }
+
+    // start the all supervised tasks and return a monitoring handle of each task, a handle exits
+    // after the task exits normally or gives up restarting
+    pub fn run(self) -> Vec<JoinHandle<()>> {
+        info!("supervisor: start supervising {} tasks", self.tasks.len());
+
+        let max_restarts = self.max_restarts;
+        self.tasks
+            .into_iter()
+            .map(|(name, start, is_critical)| {
+                spawn(supervise_task(name, start, max_restarts, is_critical))
+            })
+            .collect()
+    }
+}
+
+// run a task and restart it after a panic until it exits normally or the restarts exceed the limit
+async fn supervise_task(name: String, start: TaskStarter, max_restarts: usize, is_critical: bool) {
+    let initial_backoff = Duration::from_millis(INITIAL_RESTART_BACKOFF_MILLISECONDS);
+    let mut backoff = initial_backoff;
+    let mut restarts = 0;
+
+    loop {
+        let start_time = Instant::now();
+        let e = match start().await {
+            Ok(()) => {
+                info!("supervisor: task {name} exited");
+                return;
+            }
+            Err(e) if e.is_cancelled() => {
+                warn!("supervisor: task {name} was cancelled");
+                return;
+            }
+            Err(e) => e,
+        };
+        error!("supervisor: task {name} crashed {e}");
+
+        // a task crashing after running stably is restarted as a fresh failure
+        if start_time.elapsed() >= Duration::from_secs(STABLE_RUNNING_SECONDS) {
+            backoff = initial_backoff;
+            restarts = 0;
+        }
+
+        restarts += 1;
+        if max_restarts > 0 && restarts > max_restarts {
+            error!("supervisor: task {name} crashed {max_restarts} times in a row, giving up");
+            if is_critical {
+                error!("supervisor: exiting the process since the critical task {name} is down");
+                exit(1);
+            }
+            return;
+        }
+
+        warn!("supervisor: restarting task {name} in {backoff:?} (restart {restarts})");
+        sleep(backoff).await;
+        backoff = (backoff * 2).min(Duration::from_secs(MAX_RESTART_BACKOFF_SECONDS));
+    }
+}
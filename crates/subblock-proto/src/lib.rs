@@ -1 +1,4 @@
 tonic::include_proto!("subblock");
+
+// encoded file descriptor set of the protos for the grpc reflection service
+pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("subblock_descriptor");